@@ -0,0 +1,322 @@
+/*
+ * Runs the same workloads on every heap in the crate, with std's BinaryHeap
+ * ("std BinaryHeap" in the table) as a baseline, and prints a table
+ * comparing them.
+ *
+ *   cargo bench --bench compare --features full,test-util
+ *
+ * Heaps behind features that are off are left out. The table is also
+ * written to compare.md in criterion's output directory.
+ *
+ * std's BinaryHeap has no decrease_key, so in workloads that decrease keys it
+ * runs under a LazyHeap, which reinserts instead. SkewHeap, MinMaxHeap,
+ * IntervalHeap, WeakHeap, BHeap and ExternalHeap can't decrease keys at all
+ * and only run the other workloads, with ExternalHeap spilling to the
+ * temporary directory.
+ * The persistent LeftistHeap and SkewBinomialHeap aren't `Heap`s, SoftHeap
+ * returns entries out of order, BucketQueue and VebHeap only take integer
+ * priorities and BoundedHeap drops entries beyond its capacity, so none of
+ * them are compared.
+ */
+
+extern crate criterion;
+extern crate rust_heaps;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use criterion::Criterion;
+use rust_heaps::fibonacci_heap::FibHeap;
+use rust_heaps::graph::{self, Graph};
+use rust_heaps::test_util::Rng;
+#[cfg(feature = "array-heap")]
+use rust_heaps::array_heap::ArrayHeap;
+#[cfg(feature = "b-heap")]
+use rust_heaps::b_heap::BHeap;
+#[cfg(feature = "binary-heap")]
+use rust_heaps::binary_heap::DaryHeap;
+#[cfg(feature = "calendar-queue")]
+use rust_heaps::calendar_queue::CalendarQueue;
+#[cfg(feature = "external-heap")]
+use rust_heaps::external_heap::ExternalHeap;
+#[cfg(feature = "interval-heap")]
+use rust_heaps::interval_heap::IntervalHeap;
+#[cfg(feature = "lazy-binomial-heap")]
+use rust_heaps::lazy_binomial_heap::LazyBinomialHeap;
+#[cfg(feature = "lazy-heap")]
+use rust_heaps::lazy_heap::LazyHeap;
+#[cfg(feature = "min-max-heap")]
+use rust_heaps::min_max_heap::MinMaxHeap;
+#[cfg(feature = "pairing-heap")]
+use rust_heaps::pairing_heap::PairingHeap;
+#[cfg(feature = "send-heap")]
+use rust_heaps::send_heap::SendFibHeap;
+#[cfg(feature = "skew-heap")]
+use rust_heaps::skew_heap::SkewHeap;
+#[cfg(feature = "slotmap")]
+use rust_heaps::slot_heap::SlotFibHeap;
+#[cfg(feature = "smooth-heap")]
+use rust_heaps::smooth_heap::SmoothHeap;
+#[cfg(feature = "strict-fib-heap")]
+use rust_heaps::strict_fib_heap::StrictFibHeap;
+#[cfg(feature = "treap")]
+use rust_heaps::treap::Treap;
+#[cfg(feature = "weak-heap")]
+use rust_heaps::weak_heap::WeakHeap;
+use rust_heaps::{Heap, DecreaseKey};
+
+// std's BinaryHeap as a min-heap, without decrease_key.
+struct StdHeap<K: Ord, V: Ord>(BinaryHeap<Reverse<(K, V)>>);
+
+impl<K: Ord + Clone, V: Ord + Clone> Heap<K, V> for StdHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        (self.0.peek().expect("heap is empty").0).clone()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        self.0.pop().expect("heap is empty").0
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.0.push(Reverse((key, value)));
+    }
+
+    fn empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn std_heap<K: Ord, V: Ord>() -> StdHeap<K, V> {
+    StdHeap(BinaryHeap::new())
+}
+
+// Entries in each workload, and the side of the grid for Dijkstra's.
+const SIZE: usize = 10000;
+const SIDE: usize = 100;
+
+// The workloads. Each takes a new, empty heap.
+
+// Inserts every key, then deletes them all.
+fn heapsort<H: Heap<u64, usize>>(mut heap: H, keys: &[u64]) -> u64 {
+    for (i, &k) in keys.iter().enumerate() {
+        heap.insert(k, i);
+    }
+    let mut sum = 0;
+    while !heap.empty() {
+        sum += heap.delete_min().0;
+    }
+    sum
+}
+
+// Fills the heap with the first half of the keys, then inserts the rest,
+// deleting the minimum after each.
+fn steady_state<H: Heap<u64, usize>>(mut heap: H, keys: &[u64]) -> u64 {
+    let half = keys.len() / 2;
+    for (i, &k) in keys[..half].iter().enumerate() {
+        heap.insert(k, i);
+    }
+    let mut sum = 0;
+    for (i, &k) in keys[half..].iter().enumerate() {
+        heap.insert(k, half + i);
+        sum += heap.delete_min().0;
+    }
+    sum
+}
+
+// Dijkstra's algorithm, which decreases keys as it finds shorter paths.
+fn dijkstra<H: DecreaseKey<u64, usize>>(mut heap: H, graph: &Graph<u64>) -> usize {
+    graph::shortest_path(&mut heap, graph, 0).distances.len()
+}
+
+// A grid with random edge weights, so shorter paths turn up often.
+fn grid(side: usize, rng: &mut Rng) -> Graph<u64> {
+    let mut graph = Graph::new(side * side);
+    for row in 0..side {
+        for col in 0..side {
+            let node = row * side + col;
+            if col + 1 < side {
+                graph.add_undirected_edge(node, node + 1, 1 + rng.below(100));
+            }
+            if row + 1 < side {
+                graph.add_undirected_edge(node, node + side, 1 + rng.below(100));
+            }
+        }
+    }
+    graph
+}
+
+// Benchmarks a workload on the given heaps and then every heap in the
+// crate, as one group. Returns the group and the names of its heaps.
+macro_rules! compare {
+    (@run $group:ident, $names:ident, $name:expr, $make:expr, $heap:ident, $workload:expr) => {
+        $group.bench_function($name, |b| b.iter(|| { let $heap = $make; $workload }));
+        $names.push($name);
+    };
+    ($c:expr, $group:expr, |$heap:ident| $workload:expr,
+     [$($(#[$attr:meta])* $name:literal => $make:expr),*]) => {{
+        let mut group = $c.benchmark_group($group);
+        let mut names = Vec::new();
+        $($(#[$attr])* compare!(@run group, names, $name, $make, $heap, $workload);)*
+        compare!(@run group, names, "FibHeap", FibHeap::new(), $heap, $workload);
+        #[cfg(feature = "send-heap")]
+        compare!(@run group, names, "SendFibHeap", SendFibHeap::new(), $heap, $workload);
+        #[cfg(feature = "slotmap")]
+        compare!(@run group, names, "SlotFibHeap", SlotFibHeap::new(), $heap, $workload);
+        #[cfg(feature = "lazy-heap")]
+        compare!(@run group, names, "LazyHeap", LazyHeap::new(), $heap, $workload);
+        #[cfg(feature = "pairing-heap")]
+        compare!(@run group, names, "PairingHeap", PairingHeap::new(), $heap, $workload);
+        #[cfg(feature = "lazy-binomial-heap")]
+        compare!(@run group, names, "LazyBinomialHeap", LazyBinomialHeap::new(), $heap, $workload);
+        #[cfg(feature = "smooth-heap")]
+        compare!(@run group, names, "SmoothHeap", SmoothHeap::new(), $heap, $workload);
+        #[cfg(feature = "strict-fib-heap")]
+        compare!(@run group, names, "StrictFibHeap", StrictFibHeap::new(), $heap, $workload);
+        #[cfg(feature = "calendar-queue")]
+        compare!(@run group, names, "CalendarQueue", CalendarQueue::new(), $heap, $workload);
+        #[cfg(feature = "treap")]
+        compare!(@run group, names, "Treap", Treap::new(), $heap, $workload);
+        #[cfg(feature = "binary-heap")]
+        compare!(@run group, names, "BinaryHeap", rust_heaps::binary_heap::BinaryHeap::new(), $heap, $workload);
+        #[cfg(feature = "binary-heap")]
+        compare!(@run group, names, "4-ary DaryHeap", DaryHeap::<_, _, 4>::new(), $heap, $workload);
+        #[cfg(feature = "binary-heap")]
+        compare!(@run group, names, "8-ary DaryHeap", DaryHeap::<_, _, 8>::new(), $heap, $workload);
+        #[cfg(feature = "array-heap")]
+        compare!(@run group, names, "ArrayHeap", ArrayHeap::<_, _, SIZE>::new(), $heap, $workload);
+        group.finish();
+        ($group, names)
+    }}
+}
+
+fn benches(c: &mut Criterion) -> Vec<(&'static str, Vec<&'static str>)> {
+    let mut rng = Rng::new(1);
+    let keys: Vec<u64> = (0..SIZE).map(|_| rng.below(1 << 32)).collect();
+    let keys = &keys[..];
+    let graph = &grid(SIDE, &mut rng);
+    let mut groups = Vec::new();
+    #[cfg(feature = "external-heap")]
+    let dir = &env::temp_dir();
+    groups.push(compare!(c, "heapsort", |heap| heapsort(heap, black_box(keys)),
+                         ["std BinaryHeap" => std_heap(),
+                          #[cfg(feature = "skew-heap")]
+                          "SkewHeap" => SkewHeap::new(),
+                          #[cfg(feature = "min-max-heap")]
+                          "MinMaxHeap" => MinMaxHeap::new(),
+                          #[cfg(feature = "interval-heap")]
+                          "IntervalHeap" => IntervalHeap::new(),
+                          #[cfg(feature = "weak-heap")]
+                          "WeakHeap" => WeakHeap::new(),
+                          #[cfg(feature = "b-heap")]
+                          "BHeap" => BHeap::new(),
+                          #[cfg(feature = "external-heap")]
+                          "ExternalHeap" => ExternalHeap::new(dir, SIZE / 8)]));
+    groups.push(compare!(c, "steady_state", |heap| steady_state(heap, black_box(keys)),
+                         ["std BinaryHeap" => std_heap(),
+                          #[cfg(feature = "skew-heap")]
+                          "SkewHeap" => SkewHeap::new(),
+                          #[cfg(feature = "min-max-heap")]
+                          "MinMaxHeap" => MinMaxHeap::new(),
+                          #[cfg(feature = "interval-heap")]
+                          "IntervalHeap" => IntervalHeap::new(),
+                          #[cfg(feature = "weak-heap")]
+                          "WeakHeap" => WeakHeap::new(),
+                          #[cfg(feature = "b-heap")]
+                          "BHeap" => BHeap::new(),
+                          #[cfg(feature = "external-heap")]
+                          "ExternalHeap" => ExternalHeap::new(dir, SIZE / 8)]));
+    #[cfg(feature = "lazy-heap")]
+    groups.push(compare!(c, "dijkstra", |heap| dijkstra(heap, black_box(graph)),
+                         ["std BinaryHeap" => LazyHeap::with_heap(std_heap())]));
+    #[cfg(not(feature = "lazy-heap"))]
+    groups.push(compare!(c, "dijkstra", |heap| dijkstra(heap, black_box(graph)), []));
+    groups
+}
+
+// Where criterion puts its results, found the way it finds it but without
+// asking cargo.
+fn output_directory() -> PathBuf {
+    if let Some(home) = env::var_os("CRITERION_HOME") {
+        PathBuf::from(home)
+    } else if let Some(target) = env::var_os("CARGO_TARGET_DIR") {
+        Path::new(&target).join("criterion")
+    } else {
+        PathBuf::from("target/criterion")
+    }
+}
+
+// The mean time in nanoseconds from a benchmark's estimates.json.
+fn mean(dir: &Path, group: &str, name: &str) -> Option<f64> {
+    let path = dir.join(group).join(name).join("new/estimates.json");
+    let text = fs::read_to_string(path).ok()?;
+    let mean = &text[text.find("\"mean\"")?..];
+    let value = &mean[mean.find("\"point_estimate\":")? + 17..];
+    let end = value.find(&[',', '}'][..])?;
+    value[..end].trim().parse().ok()
+}
+
+fn format_time(ns: f64) -> String {
+    if ns >= 1e6 {
+        format!("{:.2} ms", ns / 1e6)
+    } else {
+        format!("{:.1} µs", ns / 1e3)
+    }
+}
+
+// A Markdown table with a row per heap and a column per workload, giving
+// each heap's mean time and how many times slower it was than the fastest.
+fn table(dir: &Path, groups: &[(&str, Vec<&str>)]) -> Option<String> {
+    let mut heaps: Vec<&str> = Vec::new();
+    for (_, names) in groups.iter() {
+        for name in names.iter() {
+            if !heaps.contains(name) {
+                heaps.push(name);
+            }
+        }
+    }
+    let times: Vec<Vec<Option<f64>>> = groups.iter()
+        .map(|&(group, _)| heaps.iter().map(|name| mean(dir, group, name)).collect())
+        .collect();
+    if times.iter().all(|row| row.iter().all(|t| t.is_none())) {
+        return None
+    }
+    let mut out = String::from("| heap |");
+    for &(group, _) in groups.iter() {
+        out.push_str(&format!(" {} |", group));
+    }
+    out.push_str("\n|---|");
+    out.push_str(&"---|".repeat(groups.len()));
+    for (i, heap) in heaps.iter().enumerate() {
+        out.push_str(&format!("\n| {} |", heap));
+        for row in times.iter() {
+            let fastest = row.iter().filter_map(|&t| t).fold(f64::INFINITY, f64::min);
+            match row[i] {
+                Some(t) => out.push_str(&format!(" {} ({:.2}x) |", format_time(t), t / fastest)),
+                None => out.push_str(" - |"),
+            }
+        }
+    }
+    out.push('\n');
+    Some(out)
+}
+
+fn main() {
+    let dir = output_directory();
+    let mut c = Criterion::default().output_directory(&dir).configure_from_args();
+    let groups = benches(&mut c);
+    c.final_summary();
+    // Only `cargo bench` measures; under `cargo test` the estimates would
+    // be left over from an earlier run.
+    if !env::args().any(|arg| arg == "--bench") {
+        return
+    }
+    if let Some(table) = table(&dir, &groups) {
+        println!("\n{}", table);
+        let _ = fs::write(dir.join("compare.md"), &table);
+    }
+}
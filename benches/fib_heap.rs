@@ -0,0 +1,60 @@
+/*
+ * Micro-benchmarks of single FibHeap operations on small heaps.
+ *
+ *   cargo bench --bench fib_heap
+ */
+
+extern crate criterion;
+extern crate rust_heaps;
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rust_heaps::fibonacci_heap::FibHeap;
+use rust_heaps::{Heap, DecreaseKey, HeapExt};
+
+fn filled(keys: &[u64]) -> FibHeap<u64, u64> {
+    let mut fheap = FibHeap::new();
+    for &k in keys {
+        fheap.insert(k, k);
+    }
+    fheap
+}
+
+fn bench_new(c: &mut Criterion) {
+    c.bench_function("new", |b| b.iter(|| {
+        let fheap: FibHeap<u64, u64> = black_box(FibHeap::new());
+        assert!(fheap.empty());
+    }));
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let mut fheap = FibHeap::new();
+    let mut n = 0u64;
+    c.bench_function("insert", |b| b.iter(|| {
+        fheap.insert(n, n);
+        n += 1;
+    }));
+}
+
+fn bench_merge(c: &mut Criterion) {
+    let fheap = filled(&[1, 4, 0, 5, 2, 6, 3, 11]);
+    let fheap1 = filled(&[7, 10]);
+    c.bench_function("merge", |b| b.iter(|| fheap.clone().merge(fheap1.clone())));
+}
+
+fn bench_delete_min(c: &mut Criterion) {
+    let mut fheap = filled(&[1, 4, 0, 5, 2, 6, 3, 12, 11, 13, 14, 15, 16, 17]);
+    c.bench_function("delete_min", |b| b.iter(|| {
+        fheap.delete_min();
+        fheap.insert(0, 0);
+    }));
+}
+
+fn bench_decrease_key(c: &mut Criterion) {
+    let mut fheap = filled(&[1, 4, 0, 5, 2, 6, 3, 11, 13, 14, 15, 16, 17, 10]);
+    let entry = fheap.insert(u64::MAX, 10);
+    c.bench_function("decrease_key", |b| b.iter(|| fheap.decrease_key(&entry, 1)));
+}
+
+criterion_group!(benches, bench_new, bench_insert, bench_merge, bench_delete_min, bench_decrease_key);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+// Sets `nightly` when the compiler is a nightly or dev build, so that
+// `fallible-alloc`, which needs unstable `Rc` APIs, can refuse stable ones
+// with a clear error.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(nightly)");
+    println!("cargo::rerun-if-env-changed=RUSTC");
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let version = Command::new(rustc).arg("--version").output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default();
+    if version.contains("-nightly") || version.contains("-dev") {
+        println!("cargo::rustc-cfg=nightly");
+    }
+}
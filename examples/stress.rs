@@ -0,0 +1,220 @@
+/*
+ * Randomized stress test for FibHeap.
+ *
+ * Runs a seeded stream of operations, checking every result against a
+ * BTreeSet, and prints the throughput. A failing seed reproduces exactly.
+ *
+ *   cargo run --release --example stress --features test-util -- --ops 5000000 --seed 7 \
+ *       --mix insert=40,pop=30,decrease=20,delete=8,meld=2
+ *
+ * `--no-check` skips the oracle to time the heap alone.
+ */
+
+extern crate rust_heaps;
+use std::collections::BTreeSet;
+use std::env;
+use std::mem;
+use std::process;
+use std::time::Instant;
+use rust_heaps::fibonacci_heap::FibHeap;
+use rust_heaps::test_util::Rng;
+use rust_heaps::{Heap, DecreaseKey, HeapExt, HeapDelete};
+
+type Entry = <FibHeap<u64, u64> as Heap<u64, u64>>::HeapEntry;
+
+struct Config {
+    ops: u64,
+    seed: u64,
+    // Weights of insert, pop, decrease, delete and meld.
+    mix: [u64; 5],
+    check: bool,
+}
+
+const OPS: [&str; 5] = ["insert", "pop", "decrease", "delete", "meld"];
+
+fn usage(err: &str) -> ! {
+    eprintln!("{}", err);
+    eprintln!("usage: stress [--ops N] [--seed S] [--mix insert=W,pop=W,decrease=W,delete=W,meld=W] [--no-check]");
+    process::exit(2)
+}
+
+fn parse_args() -> Config {
+    let mut config = Config { ops: 1000000, seed: 1, mix: [40, 30, 20, 8, 2], check: true };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage(&format!("{} needs a value", arg)));
+        match &arg[..] {
+            "--ops" => config.ops = value().parse().unwrap_or_else(|_| usage("bad --ops")),
+            "--seed" => config.seed = value().parse().unwrap_or_else(|_| usage("bad --seed")),
+            "--mix" => {
+                config.mix = [0; 5];
+                for part in value().split(',') {
+                    let mut kv = part.splitn(2, '=');
+                    let (name, weight) = (kv.next().unwrap(), kv.next().unwrap_or(""));
+                    let i = OPS.iter().position(|op| *op == name)
+                        .unwrap_or_else(|| usage(&format!("unknown operation {}", name)));
+                    config.mix[i] = weight.parse().unwrap_or_else(|_| usage(&format!("bad weight for {}", name)));
+                }
+                if config.mix.iter().sum::<u64>() == 0 {
+                    usage("the mix needs a nonzero weight");
+                }
+            }
+            "--no-check" => config.check = false,
+            _ => usage(&format!("unknown argument {}", arg))
+        }
+    }
+    config
+}
+
+// The heap under test, its live entries, and the oracle.
+struct Stress {
+    heap: FibHeap<u64, u64>,
+    // Handle and current key of every entry, by id; None once removed.
+    entries: Vec<Option<(Entry, u64)>>,
+    // Ids of live entries, and each live id's index in it.
+    live: Vec<u64>,
+    slots: Vec<usize>,
+    oracle: BTreeSet<(u64, u64)>,
+    check: bool,
+    step: u64,
+    seed: u64,
+}
+
+impl Stress {
+    fn fail(&self, msg: &str) -> ! {
+        eprintln!("seed {}, operation {}: {}", self.seed, self.step, msg);
+        process::exit(1)
+    }
+
+    fn add(&mut self, entry: Entry, key: u64) {
+        let id = self.entries.len() as u64;
+        self.entries.push(Some((entry, key)));
+        self.slots.push(self.live.len());
+        self.live.push(id);
+        if self.check {
+            self.oracle.insert((key, id));
+        }
+    }
+
+    fn removed(&mut self, key: u64, id: u64) {
+        if self.entries[id as usize].as_ref().map(|e| e.1) != Some(key) {
+            self.fail(&format!("removed ({}, {}), which isn't a live entry", key, id));
+        }
+        self.entries[id as usize] = None;
+        let slot = self.slots[id as usize];
+        self.live.swap_remove(slot);
+        if slot < self.live.len() {
+            self.slots[self.live[slot] as usize] = slot;
+        }
+        if self.check && !self.oracle.remove(&(key, id)) {
+            self.fail(&format!("({}, {}) wasn't expected in the heap", key, id));
+        }
+    }
+
+    fn pop(&mut self) {
+        let (k, id) = self.heap.delete_min();
+        if self.check {
+            let min = self.oracle.iter().next().unwrap().0;
+            if k != min {
+                self.fail(&format!("delete_min gave {} but the minimum is {}", k, min));
+            }
+        }
+        self.removed(k, id);
+    }
+
+    fn decrease(&mut self, id: u64, delta: u64) {
+        let (entry, key) = self.entries[id as usize].clone().unwrap();
+        self.heap.decrease_key(&entry, delta).unwrap();
+        self.entries[id as usize] = Some((entry, key - delta));
+        if self.check {
+            self.oracle.remove(&(key, id));
+            self.oracle.insert((key - delta, id));
+        }
+    }
+
+    fn delete(&mut self, id: u64) {
+        let entry = self.entries[id as usize].as_ref().unwrap().0.clone();
+        let (k, v) = self.heap.delete(entry).unwrap();
+        if v != id {
+            self.fail(&format!("delete of entry {} removed entry {}", id, v));
+        }
+        self.removed(k, v);
+    }
+}
+
+fn main() {
+    let config = parse_args();
+    let mut rng = Rng::new(config.seed);
+    let mut stress = Stress {
+        heap: FibHeap::new(),
+        entries: Vec::new(),
+        live: Vec::new(),
+        slots: Vec::new(),
+        oracle: BTreeSet::new(),
+        check: config.check,
+        step: 0,
+        seed: config.seed,
+    };
+    let total: u64 = config.mix.iter().sum();
+    let mut counts = [0u64; 5];
+    let start = Instant::now();
+    for step in 0..config.ops {
+        stress.step = step;
+        let mut pick = rng.below(total);
+        let mut op = 0;
+        while pick >= config.mix[op] {
+            pick -= config.mix[op];
+            op += 1;
+        }
+        // Operations on entries fall back to inserting into an empty heap.
+        if op != 0 && op != 4 && stress.live.is_empty() {
+            op = 0;
+        }
+        counts[op] += 1;
+        match op {
+            0 => {
+                let key = rng.below(1 << 32);
+                let id = stress.entries.len() as u64;
+                let entry = stress.heap.insert(key, id);
+                stress.add(entry, key);
+            }
+            1 => stress.pop(),
+            2 => {
+                let id = stress.live[rng.below(stress.live.len() as u64) as usize];
+                let key = stress.entries[id as usize].as_ref().unwrap().1;
+                let delta = rng.below(key + 1);
+                stress.decrease(id, delta);
+            }
+            3 => {
+                let id = stress.live[rng.below(stress.live.len() as u64) as usize];
+                stress.delete(id);
+            }
+            _ => {
+                // Builds a small heap and melds it in; its handles stay valid.
+                let mut other = FibHeap::new();
+                let mut added = Vec::new();
+                for _ in 0..1 + rng.below(8) {
+                    let key = rng.below(1 << 32);
+                    let id = (stress.entries.len() + added.len()) as u64;
+                    added.push((other.insert(key, id), key));
+                }
+                let heap = mem::replace(&mut stress.heap, FibHeap::new());
+                stress.heap = heap.merge(other);
+                for (entry, key) in added.into_iter() {
+                    stress.add(entry, key);
+                }
+            }
+        }
+        if stress.check && stress.heap.len() != stress.live.len() {
+            stress.fail(&format!("len is {} but {} entries are live", stress.heap.len(), stress.live.len()));
+        }
+    }
+    let elapsed = start.elapsed();
+    let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+    println!("seed {}: {} operations in {:.3}s, {:.0} ops/s{}", config.seed, config.ops, secs,
+             config.ops as f64 / secs, if config.check { " (checked)" } else { "" });
+    for (name, count) in OPS.iter().zip(counts.iter()) {
+        println!("  {:>8}: {}", name, count);
+    }
+    println!("  {} entries left", stress.heap.len());
+}
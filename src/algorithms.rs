@@ -0,0 +1,152 @@
+use loser_tree::LoserTree;
+use {Heap, DoubleEndedHeap};
+
+/// Merges iterators that each yield items in ascending order into a single
+/// ascending iterator. Equal items are yielded in the order of their
+/// iterators in `iters`.
+pub fn kmerge<I>(mut iters: Vec<I>) -> KMerge<I> where I: Iterator, I::Item: Ord {
+    let leaves = iters.iter_mut().map(|iter| iter.next().map(|item| (item, ()))).collect();
+    KMerge { iters: iters, tree: LoserTree::new(leaves) }
+}
+
+pub struct KMerge<I: Iterator> {
+    iters: Vec<I>,
+    // The next item of each iterator, in the leaf of the same index.
+    tree: LoserTree<I::Item, ()>,
+}
+
+impl<I> Iterator for KMerge<I> where I: Iterator, I::Item: Ord {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let i = self.tree.winner()?;
+        let next = self.iters[i].next().map(|item| (item, ()));
+        Some(self.tree.replace_min(next).0)
+    }
+}
+
+/// Sorts `slice` in ascending order by pushing every element through
+/// `heap`, which should be empty.
+pub fn heapsort<T: Clone, H: Heap<T, ()>>(heap: &mut H, slice: &mut [T]) {
+    for item in slice.iter() {
+        heap.insert(item.clone(), ());
+    }
+    for item in slice.iter_mut() {
+        *item = heap.delete_min().0;
+    }
+}
+
+/// Like `heapsort`, but fills `slice` from both ends at once with
+/// `DoubleEndedHeap::delete_both`.
+pub fn heapsort_both_ends<T: Clone, H: DoubleEndedHeap<T, ()>>(heap: &mut H, slice: &mut [T]) {
+    for item in slice.iter() {
+        heap.insert(item.clone(), ());
+    }
+    let (mut low, mut high) = (0, slice.len());
+    while low < high {
+        let (min, max) = heap.delete_both();
+        slice[low] = min.0;
+        low += 1;
+        if let Some((max, _)) = max {
+            high -= 1;
+            slice[high] = max;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_heap::{FibHeap};
+    use algorithms::{kmerge, heapsort};
+
+    // A small linear congruential generator, so tests don't need a rand
+    // dependency.
+    fn pseudo_random(n: usize, mut seed: u64) -> Vec<u32> {
+        (0..n).map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 40) as u32
+        }).collect()
+    }
+
+    #[test]
+    fn algorithms_kmerge() {
+        let merged: Vec<u32> = kmerge(vec!(vec!(1, 4, 9).into_iter(),
+                                           vec!().into_iter(),
+                                           vec!(2, 3, 10, 11).into_iter(),
+                                           vec!(0, 4).into_iter())).collect();
+        assert_eq!(merged, vec!(0, 1, 2, 3, 4, 4, 9, 10, 11));
+    }
+
+    #[test]
+    fn algorithms_kmerge_stable() {
+        let a = vec!((1, "a"), (3, "a")).into_iter();
+        let b = vec!((1, "b"), (2, "b")).into_iter();
+        let merged: Vec<(u8, &str)> = kmerge(vec!(a, b)).collect();
+        assert_eq!(merged, vec!((1, "a"), (1, "b"), (2, "b"), (3, "a")));
+    }
+
+    #[test]
+    fn algorithms_kmerge_strings() {
+        let logs = vec!(vec!("10:00 start", "10:05 stop").into_iter(),
+                        vec!("10:01 connect", "10:02 request").into_iter());
+        let merged: Vec<&str> = kmerge(logs).collect();
+        assert_eq!(merged, vec!("10:00 start", "10:01 connect", "10:02 request", "10:05 stop"));
+    }
+
+    #[test]
+    fn algorithms_kmerge_moves_items() {
+        // `Record` isn't `Clone`, so the merge can only move items.
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Record(Box<u32>);
+        let a = vec!(Record(Box::new(1)), Record(Box::new(4))).into_iter();
+        let b = vec!(Record(Box::new(2))).into_iter();
+        let merged: Vec<u32> = kmerge(vec!(a, b)).map(|r| *r.0).collect();
+        assert_eq!(merged, vec!(1, 2, 4));
+    }
+
+    #[test]
+    fn algorithms_heapsort() {
+        let mut items = vec!(5u8, 3, 9, 0, 3, 7, 1);
+        heapsort(&mut FibHeap::new(), &mut items);
+        assert_eq!(items, vec!(0, 1, 3, 3, 5, 7, 9));
+    }
+
+    #[test]
+    fn algorithms_heapsort_large() {
+        let mut items = pseudo_random(10000, 42);
+        let mut expected = items.clone();
+        expected.sort();
+        heapsort(&mut FibHeap::new(), &mut items);
+        assert_eq!(items, expected);
+    }
+
+    #[cfg(feature = "slotmap")]
+    #[test]
+    fn algorithms_heapsort_slot_heap() {
+        use slot_heap::SlotFibHeap;
+        let mut items = pseudo_random(10000, 7);
+        let mut expected = items.clone();
+        expected.sort();
+        heapsort(&mut SlotFibHeap::new(), &mut items);
+        assert_eq!(items, expected);
+    }
+
+    #[cfg(all(feature = "min-max-heap", feature = "interval-heap"))]
+    #[test]
+    fn algorithms_heapsort_both_ends() {
+        use algorithms::heapsort_both_ends;
+        use interval_heap::IntervalHeap;
+        use min_max_heap::MinMaxHeap;
+        for n in 0..50 {
+            let items = pseudo_random(n, n as u64);
+            let mut expected = items.clone();
+            expected.sort();
+            let mut sorted = items.clone();
+            heapsort_both_ends(&mut MinMaxHeap::new(), &mut sorted);
+            assert_eq!(sorted, expected);
+            let mut sorted = items.clone();
+            heapsort_both_ends(&mut IntervalHeap::new(), &mut sorted);
+            assert_eq!(sorted, expected);
+        }
+    }
+}
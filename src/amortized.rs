@@ -0,0 +1,228 @@
+//! Workloads that check a `FibHeap`'s operation counts against its amortized
+//! bounds, to catch changes that keep the heap correct but make it slow.
+
+use std::fmt;
+use fibonacci_heap::{FibHeap};
+use metrics::Metrics;
+use test_util::Rng;
+use {Heap, DecreaseKey};
+
+type Entry = <FibHeap<u64, usize> as Heap<u64, usize>>::HeapEntry;
+
+/// The operations a workload made and the work the heap did for them.
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub workload: &'static str,
+    pub inserts: u64,
+    pub delete_mins: u64,
+    pub decrease_keys: u64,
+    /// The most entries the heap held at once.
+    pub max_len: u64,
+    pub metrics: Metrics,
+}
+
+impl Report {
+    /// The most children a node can have in a heap of `max_len` entries,
+    /// which is below log base phi of the size.
+    pub fn max_rank(&self) -> u64 {
+        ((self.max_len.max(1) as f64).ln() / 1.618_f64.ln()) as u64 + 1
+    }
+
+    /// Every link joins two trees. Trees are made by inserts and cuts, and
+    /// by the children of each deleted minimum.
+    pub fn link_bound(&self) -> u64 {
+        self.inserts + self.metrics.cuts + self.delete_mins * self.max_rank()
+    }
+
+    /// A decrease marks at most one node, and each cascading cut unmarks
+    /// one, so there are at most two cuts per decrease.
+    pub fn cut_bound(&self) -> u64 {
+        2 * self.decrease_keys
+    }
+
+    /// Comparisons are O(1) amortized for inserts, decreases and links, and
+    /// O(log n) for deleting the minimum.
+    pub fn comparison_bound(&self) -> u64 {
+        self.inserts + self.decrease_keys + self.metrics.links + self.delete_mins * self.max_rank()
+    }
+
+    /// Checks every count against its bound times `slack`, describing the
+    /// first one that is over.
+    pub fn check(&self, slack: f64) -> Result<(), String> {
+        let checks = [("links", self.metrics.links, self.link_bound()),
+                      ("cuts", self.metrics.cuts, self.cut_bound()),
+                      ("comparisons", self.metrics.comparisons, self.comparison_bound())];
+        for &(name, count, bound) in checks.iter() {
+            let limit = (bound as f64 * slack) as u64;
+            if count > limit {
+                return Err(format!("{}: {} {} over the limit of {} ({}x the bound of {})",
+                                   self.workload, count, name, limit, slack, bound))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} inserts, {} delete_mins, {} decrease_keys -> {} links (bound {}), \
+                   {} cuts (bound {}), {} comparisons (bound {})",
+               self.workload, self.inserts, self.delete_mins, self.decrease_keys,
+               self.metrics.links, self.link_bound(), self.metrics.cuts, self.cut_bound(),
+               self.metrics.comparisons, self.comparison_bound())
+    }
+}
+
+// Drives a heap, tracking live entries so they can be picked at random.
+struct Driver {
+    heap: FibHeap<u64, usize>,
+    entries: Vec<Option<(Entry, u64)>>,
+    // Ids of live entries, and each live entry's index in it.
+    live: Vec<usize>,
+    slots: Vec<usize>,
+    rng: Rng,
+    report: Report,
+}
+
+impl Driver {
+    fn new(workload: &'static str, seed: u64) -> Driver {
+        Driver {
+            heap: FibHeap::new(),
+            entries: Vec::new(),
+            live: Vec::new(),
+            slots: Vec::new(),
+            rng: Rng::new(seed),
+            report: Report {
+                workload: workload,
+                inserts: 0,
+                delete_mins: 0,
+                decrease_keys: 0,
+                max_len: 0,
+                metrics: Metrics::default(),
+            },
+        }
+    }
+
+    fn insert(&mut self, key: u64) {
+        let id = self.entries.len();
+        let entry = self.heap.insert(key, id);
+        self.entries.push(Some((entry, key)));
+        self.slots.push(self.live.len());
+        self.live.push(id);
+        self.report.inserts += 1;
+        self.report.max_len = self.report.max_len.max(self.live.len() as u64);
+    }
+
+    fn delete_min(&mut self) {
+        let (_, id) = self.heap.delete_min();
+        self.entries[id] = None;
+        let slot = self.slots[id];
+        self.live.swap_remove(slot);
+        if slot < self.live.len() {
+            self.slots[self.live[slot]] = slot;
+        }
+        self.report.delete_mins += 1;
+    }
+
+    // Lowers a random live entry's key by up to `max_delta`.
+    fn decrease_random(&mut self, max_delta: u64) {
+        let id = self.live[self.rng.below(self.live.len() as u64) as usize];
+        let delta = self.rng.below(max_delta + 1);
+        let &mut (ref entry, ref mut key) = self.entries[id].as_mut().unwrap();
+        let delta = delta.min(*key);
+        self.heap.decrease_key(entry, delta).unwrap();
+        *key -= delta;
+        self.report.decrease_keys += 1;
+    }
+
+    fn finish(mut self) -> Report {
+        self.report.metrics = self.heap.metrics();
+        self.report
+    }
+}
+
+/// Inserts `n` random keys and then deletes them all.
+pub fn heapsort(n: usize, seed: u64) -> Report {
+    let mut driver = Driver::new("heapsort", seed);
+    for _ in 0..n {
+        let key = driver.rng.below(1 << 32);
+        driver.insert(key);
+    }
+    for _ in 0..n {
+        driver.delete_min();
+    }
+    driver.finish()
+}
+
+/// Fills the heap to `n` entries, then alternates random inserts and
+/// deletes so it stays near that size.
+pub fn steady_state(n: usize, seed: u64) -> Report {
+    let mut driver = Driver::new("steady_state", seed);
+    for _ in 0..n {
+        let key = driver.rng.below(1 << 32);
+        driver.insert(key);
+    }
+    for _ in 0..4 * n {
+        if driver.rng.below(2) == 0 {
+            let key = driver.rng.below(1 << 32);
+            driver.insert(key);
+        } else {
+            driver.delete_min();
+        }
+    }
+    driver.finish()
+}
+
+/// Like Dijkstra's algorithm: every deleted minimum is followed by a few
+/// decreases of random entries, which cut nodes and cascade.
+pub fn decrease_heavy(n: usize, seed: u64) -> Report {
+    let mut driver = Driver::new("decrease_heavy", seed);
+    for _ in 0..n {
+        let key = driver.rng.below(1 << 32);
+        driver.insert(key);
+    }
+    while driver.live.len() > 1 {
+        driver.delete_min();
+        for _ in 0..4 {
+            driver.decrease_random(1 << 30);
+        }
+    }
+    driver.finish()
+}
+
+/// Runs every workload on heaps of `n` entries.
+pub fn run_all(n: usize, seed: u64) -> Vec<Report> {
+    vec!(heapsort(n, seed), steady_state(n, seed), decrease_heavy(n, seed))
+}
+
+/// Runs every workload, panicking with the reports if any count exceeds its
+/// bound times `slack`.
+pub fn assert_amortized_bounds(n: usize, seed: u64, slack: f64) {
+    let reports = run_all(n, seed);
+    for report in reports.iter() {
+        if let Err(err) = report.check(slack) {
+            let all: Vec<String> = reports.iter().map(|r| r.to_string()).collect();
+            panic!("{}\n{}", err, all.join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amortized::{assert_amortized_bounds, heapsort};
+
+    #[test]
+    fn amortized_bounds_hold() {
+        assert_amortized_bounds(20000, 1, 1.0);
+        assert_amortized_bounds(1000, 2, 1.0);
+    }
+
+    #[test]
+    fn amortized_check_reports_overruns() {
+        let mut report = heapsort(1000, 3);
+        report.metrics.links = 10 * report.link_bound();
+        let err = report.check(2.0).unwrap_err();
+        assert!(err.starts_with("heapsort: "));
+        assert!(err.contains(" links over the limit"));
+    }
+}
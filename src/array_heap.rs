@@ -0,0 +1,359 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Sub;
+use slab::{Generation, SlotKey};
+use {Heap, DecreaseKey, HeapDelete, AddressableHeap, StaleEntry};
+
+/// Handle to an entry of a `FixedHeap`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ArrayEntry(SlotKey);
+
+/// The entry `FixedHeap::try_insert` had no room for.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Full<K, V>(pub K, pub V);
+
+impl<K, V> fmt::Display for Full<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ArrayHeap is full")
+    }
+}
+
+/// Room for one entry of a `FixedHeap`.
+pub struct Slot<K, V> {
+    entry: Option<(K, V)>,
+    generation: Generation,
+    // The slot at this index in heap order. The free slots are the ones
+    // past the heap's length.
+    order: usize,
+    // This slot's index in heap order.
+    position: usize,
+}
+
+impl<K, V> Slot<K, V> {
+    /// An empty slot. This is a `const fn`, so storage for a `SliceHeap` can
+    /// be a `static`, e.g. `[const { Slot::new() }; 64]`.
+    pub const fn new() -> Slot<K, V> {
+        Slot { entry: None, generation: Generation::new(), order: 0, position: 0 }
+    }
+}
+
+/// A binary heap in a fixed number of slots, which never allocates.
+///
+/// Entries stay in the slot they were inserted into while the heap orders
+/// slot numbers, so handles stay valid as entries move. `try_insert` hands
+/// the entry back when the heap is full; `Heap::insert` panics.
+///
+/// `S` is the storage: an array for `ArrayHeap`, or a slice the caller
+/// provides for `SliceHeap`.
+pub struct FixedHeap<K, V, S> {
+    slots: S,
+    len: usize,
+    entries: PhantomData<(K, V)>,
+}
+
+/// A `FixedHeap` of at most `N` entries, stored inline.
+pub type ArrayHeap<K, V, const N: usize> = FixedHeap<K, V, [Slot<K, V>; N]>;
+
+/// A `FixedHeap` in storage the caller provides, e.g. a statically
+/// allocated buffer in firmware without an allocator.
+pub type SliceHeap<'a, K, V> = FixedHeap<K, V, &'a mut [Slot<K, V>]>;
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, const N: usize> ArrayHeap<K, V, N> {
+    /// An empty heap. This is a `const fn`, so the heap can be a `static`
+    /// initialized at compile time.
+    pub const fn new() -> ArrayHeap<K, V, N> {
+        let mut slots = [const { Slot::new() }; N];
+        let mut i = 0;
+        while i < N {
+            slots[i].order = i;
+            slots[i].position = i;
+            i += 1;
+        }
+        FixedHeap { slots: slots, len: 0, entries: PhantomData }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]>> FixedHeap<K, V, S> {
+    /// A heap in `storage`, with one entry per slot. Anything left in the
+    /// slots is dropped.
+    pub fn with_storage(mut storage: S) -> FixedHeap<K, V, S> {
+        for (i, slot) in storage.as_mut().iter_mut().enumerate() {
+            if slot.entry.take().is_some() {
+                slot.generation.bump();
+            }
+            slot.order = i;
+            slot.position = i;
+        }
+        FixedHeap { slots: storage, len: 0, entries: PhantomData }
+    }
+
+    /// Empties the heap and gives back its storage.
+    pub fn into_storage(mut self) -> S {
+        self.clear();
+        self.slots
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.as_ref().len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    pub fn contains(&self, entry: ArrayEntry) -> bool {
+        match self.slots.as_ref().get(entry.0.index()) {
+            Some(slot) => slot.entry.is_some() && entry.0.is_current(slot.generation),
+            None => false
+        }
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        while self.len > 0 {
+            self.remove(self.len - 1);
+        }
+    }
+
+    /// Inserts the entry, or returns it if the heap is full.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<ArrayEntry, Full<K, V>> {
+        if self.is_full() {
+            return Err(Full(key, value))
+        }
+        let slot = self.slots.as_ref()[self.len].order;
+        self.slots.as_mut()[slot].entry = Some((key, value));
+        self.len += 1;
+        self.sift_up(self.len - 1);
+        Ok(ArrayEntry(SlotKey::new(slot, self.slots.as_ref()[slot].generation)))
+    }
+
+    /// Inserts entries until the heap is full, returning the first one that
+    /// didn't fit. Pass `&mut iter` to resume with the ones after it.
+    pub fn try_extend<I: IntoIterator<Item=(K, V)>>(&mut self, entries: I) -> Result<(), Full<K, V>> {
+        for (k, v) in entries {
+            self.try_insert(k, v)?;
+        }
+        Ok(())
+    }
+
+    // The entries in heap order.
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn entries(&self) -> impl Iterator<Item=&(K, V)> {
+        let slots = self.slots.as_ref();
+        slots[..self.len].iter().map(move |s| slots[s.order].entry.as_ref().unwrap())
+    }
+
+    fn key(&self, position: usize) -> &K {
+        let slots = self.slots.as_ref();
+        &slots[slots[position].order].entry.as_ref().unwrap().0
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        let slots = self.slots.as_mut();
+        let (slot_a, slot_b) = (slots[a].order, slots[b].order);
+        slots[a].order = slot_b;
+        slots[b].order = slot_a;
+        slots[slot_b].position = a;
+        slots[slot_a].position = b;
+    }
+
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self.key(parent) <= self.key(position) {
+                break
+            }
+            self.swap(parent, position);
+            position = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let mut least = position;
+            for child in [2 * position + 1, 2 * position + 2].iter() {
+                if *child < self.len && self.key(*child) < self.key(least) {
+                    least = *child;
+                }
+            }
+            if least == position {
+                break
+            }
+            self.swap(position, least);
+            position = least;
+        }
+    }
+
+    // Removes the entry at `position`, leaving its slot free past `len`.
+    fn remove(&mut self, position: usize) -> (K, V) {
+        self.len -= 1;
+        let last = self.len;
+        self.swap(position, last);
+        if position < last {
+            self.sift_down(position);
+            self.sift_up(position);
+        }
+        let slots = self.slots.as_mut();
+        let slot = &mut slots[slots[last].order];
+        slot.generation.bump();
+        slot.entry.take().unwrap()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]>> Heap<K, V>
+for FixedHeap<K, V, S> {
+    type HeapEntry = ArrayEntry;
+
+    fn find_min(&self) -> (K, V) {
+        if self.len == 0 {
+            panic!("ArrayHeap is empty");
+        }
+        let slots = self.slots.as_ref();
+        slots[slots[0].order].entry.clone().unwrap()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        if self.len == 0 {
+            panic!("ArrayHeap is empty");
+        }
+        self.remove(0)
+    }
+
+    // Panics if the heap is full.
+    fn insert(&mut self, key: K, value: V) -> ArrayEntry {
+        match self.try_insert(key, value) {
+            Ok(entry) => entry,
+            Err(full) => panic!("{}", full)
+        }
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]>> DecreaseKey<K, V>
+for FixedHeap<K, V, S> {
+    fn decrease_key(&mut self, entry: &ArrayEntry, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        let position = {
+            let slot = &mut self.slots.as_mut()[entry.0.index()];
+            let key = &mut slot.entry.as_mut().unwrap().0;
+            *key = key.clone() - delta;
+            slot.position
+        };
+        self.sift_up(position);
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]>> HeapDelete<K, V>
+for FixedHeap<K, V, S> {
+    type HeapEntry = ArrayEntry;
+
+    fn delete(&mut self, entry: ArrayEntry) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        let position = self.slots.as_ref()[entry.0.index()].position;
+        Ok(self.remove(position))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]>> AddressableHeap<K, V>
+for FixedHeap<K, V, S> {
+    fn is_valid(&self, entry: &ArrayEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use array_heap::{ArrayHeap, Full, Slot, SliceHeap};
+    use {Heap, DecreaseKey, HeapDelete, StaleEntry};
+
+    #[test]
+    fn array_heap_operations() {
+        let mut heap: ArrayHeap<u32, u32, 16> = ArrayHeap::new();
+        let entries: Vec<_> = (0..16).map(|i| heap.insert(100 + (i * 7) % 16, i)).collect();
+        assert!(heap.is_full());
+        assert_eq!(heap.try_insert(1, 99), Err(Full(1, 99)));
+        heap.decrease_key(&entries[5], 50).unwrap();
+        assert_eq!(heap.find_min(), (53, 5));
+        assert_eq!(heap.delete(entries[9]), Ok((115, 9)));
+        assert!(!heap.contains(entries[9]));
+        let a = heap.insert(100, 16);
+        heap.decrease_key(&a, 100).unwrap();
+        let mut keys = Vec::new();
+        while !heap.empty() {
+            keys.push(heap.delete_min().0);
+        }
+        let mut expected = vec!(0, 53);
+        expected.extend((100..115).filter(|&k| k != 103));
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn array_heap_try_extend() {
+        let mut heap: ArrayHeap<u32, char, 3> = ArrayHeap::new();
+        let mut entries = vec!((3, 'c'), (1, 'a'), (2, 'b'), (4, 'd'), (5, 'e')).into_iter();
+        assert_eq!(heap.try_extend(&mut entries), Err(Full(4, 'd')));
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap.delete_min(), (1, 'a'));
+        assert_eq!(heap.try_extend(&mut entries), Ok(()));
+        assert_eq!(heap.len(), 3);
+    }
+
+    #[test]
+    fn array_heap_macro() {
+        let mut heap = heap![ArrayHeap::<_, _, 4>::new(); 3 => 'c', 1 => 'a', 2 => 'b'];
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap.delete_min(), (1, 'a'));
+        let empty = heap![ArrayHeap::<u8, u8, 4>::new();];
+        assert!(empty.empty());
+    }
+
+    #[test]
+    fn slice_heap_storage() {
+        let mut storage: Vec<Slot<i32, char>> = (0..3).map(|_| Slot::new()).collect();
+        let (b, c) = {
+            let mut heap = SliceHeap::with_storage(&mut storage[..]);
+            let c = heap.insert(3, 'c');
+            let b = heap.insert(2, 'b');
+            heap.insert(4, 'a');
+            assert!(heap.is_full());
+            heap.decrease_key(&b, 2).unwrap();
+            assert_eq!(heap.delete_min(), (0, 'b'));
+            heap.insert(1, 'd');
+            assert_eq!(heap.find_min(), (1, 'd'));
+            (b, c)
+        };
+        // New heaps in the same storage start empty, and old handles stay
+        // stale.
+        let mut heap = SliceHeap::with_storage(&mut storage[..]);
+        assert!(heap.empty());
+        assert!(!heap.contains(b));
+        heap.insert(7, 'e');
+        assert!(!heap.contains(c));
+        assert_eq!(heap.into_storage().iter().filter(|s| s.entry.is_some()).count(), 0);
+    }
+
+    #[test]
+    fn array_heap_stale_handle() {
+        let mut heap: ArrayHeap<i32, char, 2> = ArrayHeap::new();
+        let a = heap.insert(1, 'a');
+        heap.delete_min();
+        // The new entry reuses a's slot.
+        heap.insert(2, 'b');
+        assert_eq!(heap.decrease_key(&a, 1), Err(StaleEntry));
+        assert_eq!(heap.find_min(), (2, 'b'));
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::ops::Sub;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use owned_heap::OwnedHeap;
+
+struct State<K, V> {
+    heap: OwnedHeap<K, V>,
+    waiters: Vec<Waker>,
+}
+
+/// A heap shared between async tasks, whose `pop` waits for an element
+/// instead of returning `None`.
+///
+/// Clones refer to the same heap. It doesn't depend on any runtime, so it
+/// works under tokio, async-std or a hand-rolled executor alike.
+pub struct AsyncHeap<K, V> {
+    state: Arc<Mutex<State<K, V>>>,
+}
+
+impl<K, V> Clone for AsyncHeap<K, V> {
+    fn clone(&self) -> AsyncHeap<K, V> {
+        AsyncHeap { state: self.state.clone() }
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> AsyncHeap<K, V> {
+    pub fn new() -> AsyncHeap<K, V> {
+        AsyncHeap {
+            state: Arc::new(Mutex::new(State {
+                heap: OwnedHeap::new(),
+                waiters: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().heap.len()
+    }
+
+    /// Inserts an element and wakes the tasks waiting in `pop`.
+    pub fn push(&self, key: K, value: V) {
+        let waiters = {
+            let mut state = self.state.lock().unwrap();
+            state.heap.insert(key, value);
+            // Every waiter is woken rather than just one, since a woken
+            // future may be dropped before it is polled again.
+            state.waiters.split_off(0)
+        };
+        for waker in waiters.into_iter() {
+            waker.wake();
+        }
+    }
+
+    /// Removes the minimum if there is one, without waiting.
+    pub fn try_pop(&self) -> Option<(K, V)> {
+        self.state.lock().unwrap().heap.delete_min()
+    }
+
+    /// Returns a future resolving to the minimum once the heap is
+    /// non-empty.
+    pub fn pop(&self) -> Pop<'_, K, V> {
+        Pop { state: &self.state }
+    }
+}
+
+/// The future returned by `AsyncHeap::pop`.
+pub struct Pop<'a, K: 'a, V: 'a> {
+    state: &'a Mutex<State<K, V>>,
+}
+
+impl<'a, K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> Future for Pop<'a, K, V> {
+    type Output = (K, V);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<(K, V)> {
+        let mut state = self.state.lock().unwrap();
+        match state.heap.delete_min() {
+            Some(min) => Poll::Ready(min),
+            None => {
+                if !state.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.waiters.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+    use async_heap::AsyncHeap;
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(out) => return out,
+                Poll::Pending => thread::park()
+            }
+        }
+    }
+
+    #[test]
+    fn async_heap_pops_in_order() {
+        let heap = AsyncHeap::new();
+        heap.push(3, 'c');
+        heap.push(1, 'a');
+        heap.push(2, 'b');
+        assert_eq!(heap.len(), 3);
+        assert_eq!(block_on(heap.pop()), (1, 'a'));
+        assert_eq!(block_on(heap.pop()), (2, 'b'));
+        assert_eq!(heap.try_pop(), Some((3, 'c')));
+        assert_eq!(heap.try_pop(), None);
+    }
+
+    #[test]
+    fn async_heap_pop_waits_for_push() {
+        let heap = AsyncHeap::new();
+        let producer = heap.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.push(7u32, 70u32);
+            producer.push(5, 50);
+        });
+        let first = block_on(heap.pop());
+        handle.join().unwrap();
+        let second = block_on(heap.pop());
+        assert!(first == (7, 70) || first == (5, 50));
+        assert!(second != first);
+        assert_eq!(heap.len(), 0);
+    }
+}
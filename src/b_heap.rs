@@ -0,0 +1,240 @@
+use std::alloc::{self, Layout};
+use std::mem;
+use std::ops::Sub;
+use std::ptr::{self, NonNull};
+use Heap;
+
+const PAGE_BYTES: usize = 4096;
+
+// The largest power of two, and at least 4, with that many entries of
+// `size` bytes fitting in a page.
+const fn entries_per_page(size: usize) -> usize {
+    let fit = match PAGE_BYTES.checked_div(size) {
+        Some(fit) => fit,
+        None => PAGE_BYTES
+    };
+    let mut entries = 4;
+    while entries * 2 <= fit {
+        entries *= 2;
+    }
+    entries
+}
+
+/// A B-heap (Kamp): a binary heap laid out in page-aligned blocks, each
+/// holding a complete subtree, so that a path from the root to a leaf
+/// touches about log n / log B pages instead of log n.
+///
+/// With `B` entries to a page, a page holds a subtree of B - 1 entries and
+/// its B / 2 leaves have their children at the roots of B other pages.
+/// Pages fill one after the other, so memory grows a page at a time and
+/// stays as dense as a plain array. For heaps much larger than the caches,
+/// sifting then misses the cache and TLB far less often than in a binary
+/// heap or a pointer-based one, though it's a little slower for small
+/// heaps.
+pub struct BHeap<K, V> {
+    pages: Vec<NonNull<(K, V)>>,
+    len: usize,
+}
+
+unsafe impl<K: Send, V: Send> Send for BHeap<K, V> {}
+
+impl<K, V> BHeap<K, V> {
+    // Entries a page has room for, one more than it holds.
+    const PAGE: usize = entries_per_page(mem::size_of::<(K, V)>());
+    const USED: usize = BHeap::<K, V>::PAGE - 1;
+
+    fn layout() -> Layout {
+        let size = (BHeap::<K, V>::USED * mem::size_of::<(K, V)>()).max(1);
+        Layout::from_size_align(size, PAGE_BYTES.max(mem::align_of::<(K, V)>())).unwrap()
+    }
+
+    fn slot(&self, i: usize) -> *mut (K, V) {
+        unsafe { self.pages[i / BHeap::<K, V>::USED].as_ptr().add(i % BHeap::<K, V>::USED) }
+    }
+
+    fn entry(&self, i: usize) -> &(K, V) {
+        unsafe { &*self.slot(i) }
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        unsafe { ptr::swap(self.slot(i), self.slot(j)) }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> BHeap<K, V> {
+    pub const fn new() -> BHeap<K, V> {
+        BHeap { pages: Vec::new(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Entries per page, which holds one fewer: a power of two that fits
+    /// the entries in 4096 bytes, or 4 for entries too large for that.
+    pub fn page_entries() -> usize {
+        BHeap::<K, V>::PAGE
+    }
+
+    fn parent(i: usize) -> usize {
+        let (page, node) = (i / BHeap::<K, V>::USED, i % BHeap::<K, V>::USED + 1);
+        if node > 1 {
+            i + node / 2 - node
+        } else {
+            // A page's root hangs off a leaf of its parent page.
+            let (parent, index) = ((page - 1) / BHeap::<K, V>::PAGE, (page - 1) % BHeap::<K, V>::PAGE);
+            parent * BHeap::<K, V>::USED + BHeap::<K, V>::PAGE / 2 + index / 2 - 1
+        }
+    }
+
+    fn children(i: usize) -> (usize, usize) {
+        let (page, node) = (i / BHeap::<K, V>::USED, i % BHeap::<K, V>::USED + 1);
+        if node < BHeap::<K, V>::PAGE / 2 {
+            let left = i + node;
+            (left, left + 1)
+        } else {
+            let child = page * BHeap::<K, V>::PAGE + 1 + 2 * (node - BHeap::<K, V>::PAGE / 2);
+            (child * BHeap::<K, V>::USED, (child + 1) * BHeap::<K, V>::USED)
+        }
+    }
+
+    fn less(&self, i: usize, j: usize) -> bool {
+        self.entry(i).0 < self.entry(j).0
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = BHeap::<K, V>::parent(i);
+            if !self.less(i, parent) {
+                break
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let (left, right) = BHeap::<K, V>::children(i);
+            if left >= self.len {
+                return
+            }
+            let child = if right < self.len && self.less(right, left) { right } else { left };
+            if !self.less(child, i) {
+                return
+            }
+            self.swap(i, child);
+            i = child;
+        }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for BHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        if self.len == 0 {
+            panic!("BHeap is empty");
+        }
+        self.entry(0).clone()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        if self.len == 0 {
+            panic!("BHeap is empty");
+        }
+        self.len -= 1;
+        let last = self.len;
+        self.swap(0, last);
+        let entry = unsafe { ptr::read(self.slot(last)) };
+        if last > 0 {
+            self.sift_down(0);
+        }
+        entry
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        let i = self.len;
+        if i / BHeap::<K, V>::USED == self.pages.len() {
+            let layout = BHeap::<K, V>::layout();
+            let page = unsafe { alloc::alloc(layout) } as *mut (K, V);
+            self.pages.push(NonNull::new(page).unwrap_or_else(|| alloc::handle_alloc_error(layout)));
+        }
+        unsafe { ptr::write(self.slot(i), (key, value)) };
+        self.len += 1;
+        self.sift_up(i);
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K, V> Drop for BHeap<K, V> {
+    fn drop(&mut self) {
+        for i in 0..self.len {
+            unsafe { ptr::drop_in_place(self.slot(i)) };
+        }
+        for page in self.pages.drain(..) {
+            unsafe { alloc::dealloc(page.as_ptr() as *mut u8, BHeap::<K, V>::layout()) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use b_heap::BHeap;
+    use Heap;
+
+    fn sorts<V: Clone>(value: V) {
+        let mut heap = BHeap::new();
+        for i in 0..5000u32 {
+            heap.insert((i * 7919) % 5000 + 5000, value.clone());
+        }
+        for k in 5000..7500 {
+            assert_eq!(heap.delete_min().0, k);
+        }
+        // Each new minimum climbs from the last page to the root.
+        for k in (0..2500).rev() {
+            heap.insert(k, value.clone());
+            assert_eq!(heap.find_min().0, k);
+        }
+        for k in (0..2500).chain(7500..10000) {
+            assert_eq!(heap.delete_min().0, k);
+        }
+        assert!(heap.empty());
+    }
+
+    #[test]
+    fn b_heap_sorts() {
+        assert_eq!(BHeap::<u32, u32>::page_entries(), 512);
+        sorts(0u32);
+        // Large entries make small pages, so paths cross many of them.
+        assert_eq!(BHeap::<u32, [u8; 1000]>::page_entries(), 4);
+        sorts([7u8; 1000]);
+    }
+
+    #[test]
+    fn b_heap_pages_are_aligned() {
+        let mut heap = BHeap::new();
+        for i in 0..2000u64 {
+            heap.insert(i, i);
+        }
+        assert_eq!(heap.pages.len(), 8);
+        assert!(heap.pages.iter().all(|p| (p.as_ptr() as usize).is_multiple_of(4096)));
+    }
+
+    #[test]
+    fn b_heap_drops_entries() {
+        let value = Rc::new(());
+        let mut heap = BHeap::new();
+        for i in 0..1000u32 {
+            heap.insert(i, value.clone());
+        }
+        heap.delete_min();
+        assert_eq!(Rc::strong_count(&value), 1000);
+        drop(heap);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,253 @@
+use std::ops::Sub;
+use slab::{Slab, SlotKey};
+use {Heap, DecreaseKey, HeapDelete, AddressableHeap, StaleEntry};
+
+/// Handle to an entry of a `DaryHeap`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BinaryEntry(SlotKey);
+
+struct Slot<V> {
+    value: V,
+    // The entry's index in `heap`.
+    position: usize,
+}
+
+/// An implicit heap in a growable array where each entry has `D` children.
+///
+/// Keys are kept in the array itself, next to the number of the slot
+/// holding the value, so sifting touches one contiguous array. The slots
+/// record where their entry is, which lets handles decrease keys and delete
+/// entries.
+///
+/// A larger `D` makes the heap shallower, so decreasing a key sifts up
+/// through fewer levels, while deleting the minimum compares more children
+/// at each level. Workloads with many more decreases than deletions, like
+/// Dijkstra's algorithm on dense graphs, tend to be fastest with `D` of 4
+/// or 8.
+pub struct DaryHeap<K, V, const D: usize> {
+    heap: Vec<(K, usize)>,
+    slots: Slab<Slot<V>>,
+}
+
+/// A `DaryHeap` with two children per entry, as a cache-friendly baseline
+/// for the pointer-based heaps.
+pub type BinaryHeap<K, V> = DaryHeap<K, V, 2>;
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, const D: usize> DaryHeap<K, V, D> {
+    pub const fn new() -> DaryHeap<K, V, D> {
+        const { assert!(D >= 2, "a DaryHeap needs at least two children per entry") };
+        DaryHeap { heap: Vec::new(), slots: Slab::new() }
+    }
+
+    /// An empty heap with room for `capacity` entries before it reallocates.
+    pub fn with_capacity(capacity: usize) -> DaryHeap<K, V, D> {
+        let mut heap = DaryHeap::new();
+        heap.heap.reserve(capacity);
+        heap.slots.reserve(capacity);
+        heap
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn contains(&self, entry: BinaryEntry) -> bool {
+        self.slots.contains(entry.0)
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.slots[self.heap[a].1].position = a;
+        self.slots[self.heap[b].1].position = b;
+    }
+
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / D;
+            if self.heap[parent].0 <= self.heap[position].0 {
+                break
+            }
+            self.swap(parent, position);
+            position = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        let len = self.heap.len();
+        loop {
+            let mut least = position;
+            let first = D * position + 1;
+            for child in first..len.min(first + D) {
+                if self.heap[child].0 < self.heap[least].0 {
+                    least = child;
+                }
+            }
+            if least == position {
+                break
+            }
+            self.swap(position, least);
+            position = least;
+        }
+    }
+
+    // Removes the entry at `position`, freeing its slot.
+    fn remove(&mut self, position: usize) -> (K, V) {
+        let last = self.heap.len() - 1;
+        self.swap(position, last);
+        let (key, slot) = self.heap.pop().unwrap();
+        if position < last {
+            self.sift_down(position);
+            self.sift_up(position);
+        }
+        (key, self.slots.remove(slot).value)
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, const D: usize> Heap<K, V> for DaryHeap<K, V, D> {
+    type HeapEntry = BinaryEntry;
+
+    fn find_min(&self) -> (K, V) {
+        match self.heap.first() {
+            Some(&(ref key, slot)) => (key.clone(), self.slots[slot].value.clone()),
+            None => panic!("DaryHeap is empty")
+        }
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        if self.heap.is_empty() {
+            panic!("DaryHeap is empty");
+        }
+        self.remove(0)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> BinaryEntry {
+        let position = self.heap.len();
+        let slot = self.slots.insert(Slot { value: value, position: position });
+        self.heap.push((key, slot.index()));
+        self.sift_up(position);
+        BinaryEntry(slot)
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, const D: usize> DecreaseKey<K, V> for DaryHeap<K, V, D> {
+    fn decrease_key(&mut self, entry: &BinaryEntry, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        let position = self.slots[entry.0.index()].position;
+        let key = &mut self.heap[position].0;
+        *key = key.clone() - delta;
+        self.sift_up(position);
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, const D: usize> HeapDelete<K, V> for DaryHeap<K, V, D> {
+    type HeapEntry = BinaryEntry;
+
+    fn delete(&mut self, entry: BinaryEntry) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        let position = self.slots[entry.0.index()].position;
+        Ok(self.remove(position))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, const D: usize> AddressableHeap<K, V> for DaryHeap<K, V, D> {
+    fn is_valid(&self, entry: &BinaryEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use binary_heap::{BinaryHeap, DaryHeap};
+    use {Heap, DecreaseKey, HeapDelete, AddressableHeap, StaleEntry};
+
+    #[test]
+    fn binary_heap_stale_handles() {
+        let mut heap = BinaryHeap::with_capacity(4);
+        let a = heap.insert(3u32, 'a');
+        let b = heap.insert(6, 'b');
+        assert_eq!(heap.delete(a), Ok((3, 'a')));
+        // The slot is reused, but the old handle stays stale.
+        let c = heap.insert(4, 'c');
+        assert!(!heap.is_valid(&a) && heap.is_valid(&c));
+        assert_eq!(heap.decrease_key(&a, 1), Err(StaleEntry));
+        assert_eq!(heap.decrease_key(&b, 5), Ok(()));
+        assert_eq!(heap.delete_min(), (1, 'b'));
+    }
+
+    #[test]
+    fn binary_heap_operations() {
+        let mut heap = BinaryHeap::with_capacity(16);
+        let entries: Vec<_> = (0..40u32).map(|i| heap.insert(100 + (i * 7) % 40, i)).collect();
+        assert_eq!(heap.len(), 40);
+        heap.decrease_key(&entries[5], 50).unwrap();
+        assert_eq!(heap.find_min(), (85, 5));
+        assert_eq!(heap.delete(entries[9]), Ok((123, 9)));
+        assert!(!heap.contains(entries[9]));
+        // Reuses the slot of entries[9], whose handle stays stale.
+        let a = heap.insert(100, 40);
+        assert!(!heap.contains(entries[9]));
+        heap.decrease_key(&a, 100).unwrap();
+        let mut keys = Vec::new();
+        while !heap.empty() {
+            keys.push(heap.delete_min().0);
+        }
+        let mut expected = vec!(0, 85);
+        expected.extend((100..140).filter(|&k| k != 123 && k != 135));
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn binary_heap_handles_follow_sifts() {
+        let mut heap = BinaryHeap::new();
+        let entries: Vec<_> = (0..500u32).map(|i| heap.insert(10000 + (i * 211) % 500, i)).collect();
+        for round in 0..100u32 {
+            heap.delete_min();
+            heap.insert(20000 + round, 500 + round);
+        }
+        // Each live handle still finds its own entry, wherever it's moved.
+        for (i, entry) in entries.iter().enumerate() {
+            if !heap.contains(*entry) {
+                continue
+            }
+            heap.decrease_key(entry, 10000).unwrap();
+            assert_eq!(heap.delete(*entry), Ok(((i as u32 * 211) % 500, i as u32)));
+        }
+        assert_eq!(heap.len(), 100);
+    }
+
+    #[test]
+    fn dary_heap_arities() {
+        fn sorted<H: DecreaseKey<u32, u32>>(mut heap: H) -> Vec<u32> {
+            let entries: Vec<_> = (0..200).map(|i| (heap.insert(1000 + (i * 37) % 200, i), i)).collect();
+            for &(ref entry, i) in entries.iter().filter(|&&(_, i)| i % 3 == 0) {
+                heap.decrease_key(entry, 1000 - i).unwrap();
+            }
+            (0..200).map(|_| heap.delete_min().0).collect()
+        }
+        let expected = sorted(BinaryHeap::new());
+        assert!(expected.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(sorted(DaryHeap::<_, _, 3>::new()), expected);
+        assert_eq!(sorted(DaryHeap::<_, _, 4>::new()), expected);
+        assert_eq!(sorted(DaryHeap::<_, _, 8>::new()), expected);
+    }
+
+    #[test]
+    fn binary_heap_stale_handle() {
+        let mut heap = BinaryHeap::new();
+        let a = heap.insert(1, 'a');
+        heap.delete_min();
+        heap.insert(2, 'b');
+        assert_eq!(heap.decrease_key(&a, 1), Err(StaleEntry));
+        assert_eq!(heap.find_min(), (2, 'b'));
+    }
+}
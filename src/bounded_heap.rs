@@ -0,0 +1,142 @@
+use std::ops::Sub;
+use min_max_heap::MinMaxHeap;
+use {Heap, DoubleEndedHeap};
+
+/// A heap holding at most `capacity` entries, which keeps the ones with the
+/// smallest keys: once full, an entry only goes in if its key is below the
+/// largest, which it evicts. That's the usual way of finding the top k of a
+/// stream; wrap keys in `Rev` to keep the largest instead.
+///
+/// Entries sit in a `MinMaxHeap`, so both the smallest and the largest are
+/// at hand and inserting takes O(log k).
+pub struct BoundedHeap<K, V> {
+    heap: MinMaxHeap<K, V>,
+    capacity: usize,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> BoundedHeap<K, V> {
+    pub fn new(capacity: usize) -> BoundedHeap<K, V> {
+        BoundedHeap { heap: MinMaxHeap::with_capacity(capacity), capacity: capacity }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.heap.len() >= self.capacity
+    }
+
+    /// Offers an entry, returning the one left out: the largest held entry
+    /// if the new one evicted it, or the new entry itself if its key isn't
+    /// below every held key of a full heap.
+    pub fn push(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if !self.is_full() {
+            self.heap.insert(key, value);
+            return None
+        }
+        if self.capacity == 0 || key >= self.heap.find_max().0 {
+            return Some((key, value))
+        }
+        let evicted = self.heap.delete_max();
+        self.heap.insert(key, value);
+        Some(evicted)
+    }
+
+    /// The held entries, smallest key first.
+    pub fn into_sorted_vec(mut self) -> Vec<(K, V)> {
+        let mut entries = Vec::with_capacity(self.heap.len());
+        while !self.heap.empty() {
+            entries.push(self.heap.delete_min());
+        }
+        entries
+    }
+
+    fn check(&self) {
+        if self.heap.empty() {
+            panic!("BoundedHeap is empty");
+        }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for BoundedHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        self.check();
+        self.heap.find_min()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        self.check();
+        self.heap.delete_min()
+    }
+
+    // Offers an entry like `push`, dropping whichever entry is left out.
+    fn insert(&mut self, key: K, value: V) {
+        self.push(key, value);
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DoubleEndedHeap<K, V> for BoundedHeap<K, V> {
+    fn find_max(&self) -> (K, V) {
+        self.check();
+        self.heap.find_max()
+    }
+
+    fn delete_max(&mut self) -> (K, V) {
+        self.check();
+        self.heap.delete_max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bounded_heap::BoundedHeap;
+    use rev::Rev;
+    use {Heap, DoubleEndedHeap};
+
+    #[test]
+    fn bounded_heap_keeps_smallest() {
+        let mut heap = BoundedHeap::new(3);
+        assert_eq!(heap.push(5, 'e'), None);
+        assert_eq!(heap.push(1, 'a'), None);
+        assert_eq!(heap.push(9, 'i'), None);
+        assert!(heap.is_full());
+        assert_eq!(heap.push(3, 'c'), Some((9, 'i')));
+        assert_eq!(heap.push(5, 'x'), Some((5, 'x')));
+        assert_eq!(heap.push(7, 'g'), Some((7, 'g')));
+        heap.insert(2, 'b');
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap.find_max(), (3, 'c'));
+        assert_eq!(heap.delete_min(), (1, 'a'));
+        assert_eq!(heap.push(4, 'd'), None);
+        assert_eq!(heap.into_sorted_vec(), vec!((2, 'b'), (3, 'c'), (4, 'd')));
+    }
+
+    #[test]
+    fn bounded_heap_top_k_largest() {
+        let mut heap = BoundedHeap::new(10);
+        for i in 0..1000u32 {
+            heap.push(Rev((i * 7919) % 1000), i);
+        }
+        let keys: Vec<u32> = heap.into_sorted_vec().into_iter().map(|(Rev(k), _)| k).collect();
+        assert_eq!(keys, (990..1000).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn bounded_heap_zero_capacity() {
+        let mut heap = BoundedHeap::new(0);
+        assert_eq!(heap.push(1, ()), Some((1, ())));
+        assert!(heap.empty());
+        assert!(heap.is_full());
+    }
+}
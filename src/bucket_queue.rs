@@ -0,0 +1,245 @@
+use slab::{Slab, SlotKey};
+use {Heap, DecreaseKey, HeapDelete, AddressableHeap, MonotoneHeap, StaleEntry};
+
+const NONE: usize = usize::MAX;
+
+/// Handle to an entry of a `BucketQueue`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BucketEntry(SlotKey);
+
+struct Slot<V> {
+    value: V,
+    priority: usize,
+    // Neighbours in the bucket's list.
+    prev: usize,
+    next: usize,
+}
+
+/// A priority queue for integer priorities below `C`, with a FIFO list per
+/// priority, e.g. for a scheduler with a fixed number of levels.
+///
+/// Inserting, decreasing a priority and deleting an entry take constant
+/// time. Finding the minimum scans a bitmap of non-empty buckets, a word
+/// for every 64 priorities, so it's constant time for small `C`. Entries
+/// of equal priority come out in the order they went in.
+///
+/// It's a `MonotoneHeap`: in debug builds, inserting or decreasing a
+/// priority below the last one deleted panics.
+pub struct BucketQueue<V, const C: usize> {
+    slots: Slab<Slot<V>>,
+    heads: Vec<usize>,
+    tails: Vec<usize>,
+    occupied: Vec<u64>,
+    len: usize,
+    last: Option<usize>,
+}
+
+impl<V: Clone, const C: usize> BucketQueue<V, C> {
+    pub fn new() -> BucketQueue<V, C> {
+        BucketQueue {
+            slots: Slab::new(),
+            heads: vec!(NONE; C),
+            tails: vec!(NONE; C),
+            occupied: vec!(0; C.div_ceil(64)),
+            len: 0,
+            last: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn contains(&self, entry: BucketEntry) -> bool {
+        self.slots.contains(entry.0)
+    }
+
+    /// The lowest priority with an entry.
+    pub fn min_priority(&self) -> Option<usize> {
+        self.occupied.iter().position(|&w| w != 0)
+            .map(|i| i * 64 + self.occupied[i].trailing_zeros() as usize)
+    }
+
+    // Appends the slot to its priority's bucket.
+    fn link(&mut self, slot: usize) {
+        let priority = self.slots[slot].priority;
+        let tail = self.tails[priority];
+        self.slots[slot].prev = tail;
+        self.slots[slot].next = NONE;
+        if tail == NONE {
+            self.heads[priority] = slot;
+            self.occupied[priority / 64] |= 1 << (priority % 64);
+        } else {
+            self.slots[tail].next = slot;
+        }
+        self.tails[priority] = slot;
+    }
+
+    // Takes the slot out of its priority's bucket.
+    fn unlink(&mut self, slot: usize) {
+        let (priority, prev, next) = {
+            let s = &self.slots[slot];
+            (s.priority, s.prev, s.next)
+        };
+        if prev == NONE {
+            self.heads[priority] = next;
+        } else {
+            self.slots[prev].next = next;
+        }
+        if next == NONE {
+            self.tails[priority] = prev;
+        } else {
+            self.slots[next].prev = prev;
+        }
+        if self.heads[priority] == NONE {
+            self.occupied[priority / 64] &= !(1 << (priority % 64));
+        }
+    }
+
+    fn remove(&mut self, slot: usize) -> (usize, V) {
+        self.unlink(slot);
+        self.len -= 1;
+        let s = self.slots.remove(slot);
+        (s.priority, s.value)
+    }
+}
+
+impl<V: Clone, const C: usize> Heap<usize, V> for BucketQueue<V, C> {
+    type HeapEntry = BucketEntry;
+
+    fn find_min(&self) -> (usize, V) {
+        let priority = self.min_priority().expect("BucketQueue is empty");
+        let slot = &self.slots[self.heads[priority]];
+        (priority, slot.value.clone())
+    }
+
+    fn delete_min(&mut self) -> (usize, V) {
+        let priority = self.min_priority().expect("BucketQueue is empty");
+        let head = self.heads[priority];
+        self.last = Some(priority);
+        self.remove(head)
+    }
+
+    // Panics unless `priority` is below `C`.
+    fn insert(&mut self, priority: usize, value: V) -> BucketEntry {
+        if priority >= C {
+            panic!("priority {} is out of range for a BucketQueue of {} levels", priority, C);
+        }
+        if cfg!(debug_assertions) && !self.is_monotone(&priority) {
+            panic!("priority inserted below the last deleted priority");
+        }
+        let slot = self.slots.insert(Slot { value: value, priority: priority, prev: NONE, next: NONE });
+        self.link(slot.index());
+        self.len += 1;
+        BucketEntry(slot)
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<V: Clone, const C: usize> DecreaseKey<usize, V> for BucketQueue<V, C> {
+    // The entry goes to the back of its new priority's list.
+    fn decrease_key(&mut self, entry: &BucketEntry, delta: usize) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        let priority = self.slots[entry.0.index()].priority.checked_sub(delta)
+            .expect("priority decreased below zero");
+        if cfg!(debug_assertions) && !self.is_monotone(&priority) {
+            panic!("priority decreased below the last deleted priority");
+        }
+        self.unlink(entry.0.index());
+        self.slots[entry.0.index()].priority = priority;
+        self.link(entry.0.index());
+        Ok(())
+    }
+}
+
+impl<V: Clone, const C: usize> HeapDelete<usize, V> for BucketQueue<V, C> {
+    type HeapEntry = BucketEntry;
+
+    fn delete(&mut self, entry: BucketEntry) -> Result<(usize, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        Ok(self.remove(entry.0.index()))
+    }
+}
+
+impl<V: Clone, const C: usize> MonotoneHeap<usize, V> for BucketQueue<V, C> {
+    fn last_deleted(&self) -> Option<usize> {
+        self.last
+    }
+}
+
+impl<V: Clone, const C: usize> AddressableHeap<usize, V> for BucketQueue<V, C> {
+    fn is_valid(&self, entry: &BucketEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use bucket_queue::BucketQueue;
+    use {Heap, DecreaseKey, HeapDelete, MonotoneHeap};
+
+    #[test]
+    fn bucket_queue_levels() {
+        let mut queue: BucketQueue<&str, 200> = BucketQueue::new();
+        let idle = queue.insert(199, "idle");
+        queue.insert(70, "batch");
+        let a = queue.insert(70, "a");
+        let b = queue.insert(70, "b");
+        let c = queue.insert(130, "c");
+        assert_eq!(queue.min_priority(), Some(70));
+        assert_eq!(queue.delete(a), Ok((70, "a")));
+        queue.decrease_key(&c, 60).unwrap();
+        queue.decrease_key(&idle, 199).unwrap();
+        assert_eq!(queue.delete_min(), (0, "idle"));
+        assert_eq!(queue.delete_min(), (70, "batch"));
+        assert_eq!(queue.delete_min(), (70, "b"));
+        assert_eq!(queue.last_deleted(), Some(70));
+        assert!(!queue.contains(idle));
+        // Reuses b's slot.
+        let d = queue.insert(75, "d");
+        assert_eq!(d.0.index(), b.0.index());
+        assert!(!queue.contains(b));
+        assert_eq!(queue.delete_min(), (70, "c"));
+        assert_eq!(queue.delete_min(), (75, "d"));
+        assert!(queue.empty());
+        assert_eq!(queue.min_priority(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn bucket_queue_priority_range() {
+        let mut queue: BucketQueue<(), 8> = BucketQueue::new();
+        queue.insert(8, ());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "priority inserted below the last deleted priority")]
+    fn bucket_queue_insert_below() {
+        let mut queue: BucketQueue<(), 8> = BucketQueue::new();
+        queue.insert(5, ());
+        queue.delete_min();
+        queue.insert(4, ());
+    }
+
+    #[test]
+    fn bucket_queue_bad_decrease_keeps_entry() {
+        let mut queue: BucketQueue<char, 8> = BucketQueue::new();
+        let a = queue.insert(2, 'a');
+        queue.insert(3, 'b');
+        let decreased = panic::catch_unwind(AssertUnwindSafe(|| queue.decrease_key(&a, 5)));
+        assert!(decreased.is_err());
+        assert_eq!(queue.min_priority(), Some(2));
+        assert_eq!(queue.delete_min(), (2, 'a'));
+        assert_eq!(queue.delete_min(), (3, 'b'));
+    }
+}
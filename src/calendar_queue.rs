@@ -0,0 +1,383 @@
+use slab::{Slab, SlotKey};
+use {Heap, DecreaseKey, HeapDelete, AddressableHeap, MonotoneHeap, StaleEntry};
+
+// The queue never shrinks below this many buckets.
+const MIN_BUCKETS: usize = 2;
+
+// How many of the earliest timestamps a resize looks at to pick the width.
+const SAMPLE: usize = 25;
+
+/// Timestamps a `CalendarQueue` can be keyed by.
+pub trait Timestamp: Copy + PartialOrd {
+    fn as_f64(self) -> f64;
+
+    /// The timestamp `delta` earlier.
+    fn decrease(self, delta: Self) -> Self;
+}
+
+impl Timestamp for u64 {
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn decrease(self, delta: u64) -> u64 {
+        self.checked_sub(delta).expect("key decreased below zero")
+    }
+}
+
+impl Timestamp for f64 {
+    fn as_f64(self) -> f64 {
+        self
+    }
+
+    fn decrease(self, delta: f64) -> f64 {
+        self - delta
+    }
+}
+
+/// Handle to an entry of a `CalendarQueue`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CalendarEntry(SlotKey);
+
+struct Slot<K, V> {
+    value: V,
+    key: K,
+}
+
+/// A calendar queue (Brown) for pending events of a discrete event
+/// simulation, keyed by `u64` or `f64` timestamps.
+///
+/// Events are hashed by time into a ring of buckets a fixed width apart,
+/// like days of a year on a calendar, and the queue walks the ring from the
+/// last event it took out. The number of buckets doubles or halves to stay
+/// within a factor of two of the number of events, and each time the width
+/// is re-estimated from the spacing of the earliest ones, so that inserting
+/// and taking out the next event both take O(1) expected time on the
+/// usual simulation workloads, where comparison heaps need O(log n).
+/// Events with equal timestamps come out in the order they went in. NaN
+/// timestamps panic, and negative ones all share a bucket.
+///
+/// It's a `MonotoneHeap`: in debug builds, scheduling an event, or moving
+/// one, before the last event taken out panics.
+pub struct CalendarQueue<K, V> {
+    // Slots of each bucket's events, latest first.
+    buckets: Vec<Vec<usize>>,
+    width: f64,
+    // No event is in an earlier virtual bucket than this one.
+    current: u64,
+    slots: Slab<Slot<K, V>>,
+    len: usize,
+    last: Option<K>,
+}
+
+impl<K: Timestamp, V: Clone> CalendarQueue<K, V> {
+    pub fn new() -> CalendarQueue<K, V> {
+        CalendarQueue {
+            buckets: vec!(Vec::new(); MIN_BUCKETS),
+            width: 1.0,
+            current: 0,
+            slots: Slab::new(),
+            len: 0,
+            last: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn contains(&self, entry: CalendarEntry) -> bool {
+        self.slots.contains(entry.0)
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn bucket_width(&self) -> f64 {
+        self.width
+    }
+
+    // The bucket `key` falls in counting from time zero, which only grows
+    // with the key; the ring holds it at this modulo the bucket count.
+    fn virtual_bucket(&self, key: K) -> u64 {
+        (key.as_f64() / self.width).floor() as u64
+    }
+
+    fn check_key(key: K) {
+        if key.partial_cmp(&key).is_none() {
+            panic!("NaN key in CalendarQueue");
+        }
+    }
+
+    // Whether `key` isn't before the last event taken out. Timestamps are
+    // only `PartialOrd`, so this can't be `MonotoneHeap::is_monotone`.
+    fn after_last(&self, key: K) -> bool {
+        self.last.is_none_or(|last| key >= last)
+    }
+
+    // Puts the slot in its bucket, after any events with the same
+    // timestamp.
+    fn file(&mut self, slot: usize) {
+        let key = self.slots[slot].key;
+        CalendarQueue::<K, V>::check_key(key);
+        let vb = self.virtual_bucket(key);
+        self.current = self.current.min(vb);
+        let index = (vb % self.buckets.len() as u64) as usize;
+        let slots = &self.slots;
+        let bucket = &mut self.buckets[index];
+        let at = bucket.partition_point(|&s| slots[s].key > key);
+        bucket.insert(at, slot);
+    }
+
+    fn unfile(&mut self, slot: usize) {
+        let index = self.virtual_bucket(self.slots[slot].key) % self.buckets.len() as u64;
+        let bucket = &mut self.buckets[index as usize];
+        let at = bucket.iter().position(|&s| s == slot).unwrap();
+        bucket.remove(at);
+    }
+
+    // The bucket holding the earliest event. Walks the ring from the
+    // current bucket for an event due in it, and failing a whole lap,
+    // falls back to comparing the earliest event of every bucket.
+    fn head(&self) -> usize {
+        if self.len == 0 {
+            panic!("CalendarQueue is empty");
+        }
+        let n = self.buckets.len() as u64;
+        for i in 0..n {
+            let vb = match self.current.checked_add(i) {
+                Some(vb) => vb,
+                None => break
+            };
+            let bucket = (vb % n) as usize;
+            if let Some(&slot) = self.buckets[bucket].last() {
+                if self.virtual_bucket(self.slots[slot].key) == vb {
+                    return bucket
+                }
+            }
+        }
+        (0..self.buckets.len())
+            .filter(|&b| !self.buckets[b].is_empty())
+            .min_by(|&a, &b| {
+                let (a, b) = (self.buckets[a].last().unwrap(), self.buckets[b].last().unwrap());
+                self.slots[*a].key.partial_cmp(&self.slots[*b].key).unwrap()
+            })
+            .unwrap()
+    }
+
+    // Rebuilds the ring with `count` buckets, sized for the spacing of the
+    // earliest events.
+    fn resize(&mut self, count: usize) {
+        // Each bucket is reversed so events with equal timestamps keep
+        // their order when filed again.
+        let slots: Vec<usize> = self.buckets.drain(..).flat_map(|b| b.into_iter().rev()).collect();
+        let mut times: Vec<f64> = slots.iter().map(|&s| self.slots[s].key.as_f64()).collect();
+        if times.len() > SAMPLE {
+            times.select_nth_unstable_by(SAMPLE, |a, b| a.partial_cmp(b).unwrap());
+            times.truncate(SAMPLE);
+        }
+        times.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        if let Some(width) = CalendarQueue::<K, V>::estimate_width(&times) {
+            self.width = width;
+        }
+        self.buckets = vec!(Vec::new(); count);
+        self.current = u64::MAX;
+        for slot in slots {
+            self.file(slot);
+        }
+    }
+
+    // Three times the average gap between the sorted `times`, leaving out
+    // gaps over twice the average, which would stretch the buckets over
+    // sparse stretches of time.
+    fn estimate_width(times: &[f64]) -> Option<f64> {
+        let gaps: Vec<f64> = times.windows(2).map(|w| w[1] - w[0]).collect();
+        if gaps.is_empty() {
+            return None
+        }
+        let average = gaps.iter().sum::<f64>() / gaps.len() as f64;
+        let close: Vec<f64> = gaps.into_iter().filter(|&g| g <= 2.0 * average).collect();
+        let width = 3.0 * close.iter().sum::<f64>() / close.len() as f64;
+        if width > 0.0 && width.is_finite() { Some(width) } else { None }
+    }
+
+    fn remove(&mut self, slot: usize) -> (K, V) {
+        self.len -= 1;
+        let s = self.slots.remove(slot);
+        let entry = (s.key, s.value);
+        if self.buckets.len() > MIN_BUCKETS && self.len < self.buckets.len() / 2 {
+            let count = self.buckets.len() / 2;
+            self.resize(count);
+        }
+        entry
+    }
+}
+
+impl<K: Timestamp, V: Clone> Heap<K, V> for CalendarQueue<K, V> {
+    type HeapEntry = CalendarEntry;
+
+    fn find_min(&self) -> (K, V) {
+        let slot = &self.slots[*self.buckets[self.head()].last().unwrap()];
+        (slot.key, slot.value.clone())
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        let head = self.head();
+        let slot = self.buckets[head].pop().unwrap();
+        self.current = self.virtual_bucket(self.slots[slot].key);
+        self.last = Some(self.slots[slot].key);
+        self.remove(slot)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> CalendarEntry {
+        if cfg!(debug_assertions) && !self.after_last(key) {
+            panic!("key inserted below the last deleted key");
+        }
+        let slot = self.slots.insert(Slot { value: value, key: key });
+        self.file(slot.index());
+        self.len += 1;
+        if self.len > 2 * self.buckets.len() {
+            let count = 2 * self.buckets.len();
+            self.resize(count);
+        }
+        CalendarEntry(slot)
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K: Timestamp, V: Clone> DecreaseKey<K, V> for CalendarQueue<K, V> {
+    // The entry goes after any others with its new timestamp.
+    fn decrease_key(&mut self, entry: &CalendarEntry, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        let key = self.slots[entry.0.index()].key.decrease(delta);
+        CalendarQueue::<K, V>::check_key(key);
+        if cfg!(debug_assertions) && !self.after_last(key) {
+            panic!("key decreased below the last deleted key");
+        }
+        self.unfile(entry.0.index());
+        self.slots[entry.0.index()].key = key;
+        self.file(entry.0.index());
+        Ok(())
+    }
+}
+
+impl<K: Timestamp, V: Clone> HeapDelete<K, V> for CalendarQueue<K, V> {
+    type HeapEntry = CalendarEntry;
+
+    fn delete(&mut self, entry: CalendarEntry) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        self.unfile(entry.0.index());
+        Ok(self.remove(entry.0.index()))
+    }
+}
+
+impl<K: Timestamp, V: Clone> MonotoneHeap<K, V> for CalendarQueue<K, V> {
+    fn last_deleted(&self) -> Option<K> {
+        self.last
+    }
+}
+
+impl<K: Timestamp, V: Clone> AddressableHeap<K, V> for CalendarQueue<K, V> {
+    fn is_valid(&self, entry: &CalendarEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use calendar_queue::CalendarQueue;
+    use rng::Rng;
+    use test_model;
+    use {Heap, DecreaseKey, HeapDelete, MonotoneHeap};
+
+    #[test]
+    fn calendar_queue_against_model() {
+        let mut queue = CalendarQueue::new();
+        // Bunched timestamps with the odd far-off one, none before the last
+        // event taken out.
+        let key = |queue: &CalendarQueue<u64, usize>, step, x| {
+            queue.last_deleted().unwrap_or(0) + if step % 50 == 0 { x >> 4 } else { 1000 + x % 5000 }
+        };
+        test_model::against_model(&mut queue, 20000, key, Some(|queue, entry| queue.delete(entry)),
+                                  |queue, entries, _| {
+            assert_eq!(queue.len(), entries.len());
+            assert!(queue.bucket_count() <= 2 * queue.len().max(1) + 2);
+        });
+    }
+
+    #[test]
+    fn calendar_queue_hold_model() {
+        // The classic benchmark: take out the next event and schedule one
+        // a random time after it.
+        let mut queue = CalendarQueue::new();
+        let mut rng = Rng::new(2);
+        let mut random = || rng.below(1 << 53) as f64 / (1u64 << 53) as f64;
+        for i in 0..1000 {
+            queue.insert(random() * 100.0, i);
+        }
+        let mut now = 0.0;
+        for _ in 0..100000 {
+            let (time, event) = queue.delete_min();
+            assert!(time >= now);
+            now = time;
+            assert_eq!(queue.last_deleted(), Some(now));
+            queue.insert(now + random() * 100.0, event);
+        }
+        assert_eq!(queue.len(), 1000);
+        assert!(queue.bucket_count() >= 512);
+        // Events are about 0.1 apart, so buckets should hold a few each.
+        assert!(queue.bucket_width() > 0.05 && queue.bucket_width() < 1.0);
+    }
+
+    #[test]
+    fn calendar_queue_ties_keep_order() {
+        let mut queue = CalendarQueue::new();
+        let a = queue.insert(5u64, 'a');
+        for (i, v) in "bcdefghij".chars().enumerate() {
+            queue.insert(if i % 2 == 0 { 5 } else { 3 }, v);
+        }
+        queue.decrease_key(&a, 2).unwrap();
+        let order: String = (0..10).map(|_| queue.delete_min().1).collect();
+        assert_eq!(order, "cegiabdfhj");
+        assert!(!queue.contains(a));
+        assert!(queue.empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN key in CalendarQueue")]
+    fn calendar_queue_rejects_nan() {
+        CalendarQueue::new().insert(f64::NAN, ());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "key decreased below the last deleted key")]
+    fn calendar_queue_decrease_below() {
+        let mut queue = CalendarQueue::new();
+        queue.insert(5u64, ());
+        let entry = queue.insert(8, ());
+        queue.delete_min();
+        queue.decrease_key(&entry, 4).unwrap();
+    }
+
+    #[test]
+    fn calendar_queue_bad_decrease_keeps_entry() {
+        let mut queue = CalendarQueue::new();
+        let a = queue.insert(2.0, 'a');
+        queue.insert(3.0, 'b');
+        let decreased = panic::catch_unwind(AssertUnwindSafe(|| queue.decrease_key(&a, f64::NAN)));
+        assert!(decreased.is_err());
+        assert_eq!(queue.delete(a), Ok((2.0, 'a')));
+        assert_eq!(queue.delete_min(), (3.0, 'b'));
+    }
+}
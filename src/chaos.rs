@@ -0,0 +1,188 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Sub;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use rng::Rng;
+use {Heap, DecreaseKey, HeapDelete, StaleEntry};
+
+/// A `FibHeap` that restructures itself at random after every operation,
+/// then checks its invariants and its answers against a simple model.
+///
+/// The restructuring (`FibHeap::perturb`) never changes the contents, so
+/// any failure points to code relying on a particular tree shape. Runs are
+/// reproducible from the seed, which failures report along with the
+/// operation count. Every check is O(n), so this is for tests only.
+pub struct ChaosHeap<K, V> {
+    heap: FibHeap<K, V>,
+    // Every (key, value) pair in the heap.
+    model: Vec<(K, V)>,
+    seed: u64,
+    rng: Rng,
+    ops: u64,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> ChaosHeap<K, V> {
+    pub fn new(seed: u64) -> ChaosHeap<K, V> {
+        ChaosHeap {
+            heap: FibHeap::new(),
+            model: Vec::new(),
+            seed: seed,
+            rng: Rng::new(seed),
+            ops: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn heap(&self) -> &FibHeap<K, V> {
+        &self.heap
+    }
+
+    fn next(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    // Perturbs the heap up to three times, then checks it.
+    fn shake(&mut self) {
+        self.ops += 1;
+        for _ in 0..self.next() % 4 {
+            let choice = self.next();
+            self.heap.perturb(choice);
+        }
+        if let Err(err) = self.heap.check_invariants() {
+            self.fail(&err);
+        }
+        if self.heap.len() != self.model.len() {
+            let err = format!("len is {} but {} entries were expected", self.heap.len(), self.model.len());
+            self.fail(&err);
+        }
+        if !self.heap.empty() {
+            let (k, _) = self.heap.find_min();
+            let expected = self.model_min();
+            if k != self.model[expected].0 {
+                let err = format!("minimum is {:?} but {:?} was expected", k, self.model[expected].0);
+                self.fail(&err);
+            }
+        }
+    }
+
+    fn fail(&self, err: &str) -> ! {
+        panic!("chaos seed {}, operation {}: {}", self.seed, self.ops, err)
+    }
+
+    fn model_min(&self) -> usize {
+        (0..self.model.len()).min_by(|&a, &b| self.model[a].0.cmp(&self.model[b].0)).unwrap()
+    }
+
+    fn model_remove(&mut self, k: &K, v: &V) {
+        match self.model.iter().position(|(mk, mv)| mk == k && mv == v) {
+            Some(i) => { self.model.swap_remove(i); }
+            None => {
+                let err = format!("removed ({:?}, {:?}), which wasn't in the heap", k, v);
+                self.fail(&err)
+            }
+        }
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> Heap<K, V>
+for ChaosHeap<K, V> {
+    type HeapEntry = Rc<FibNode<K, V>>;
+
+    fn find_min(&self) -> (K, V) {
+        self.heap.find_min()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        let expected = self.model_min();
+        let (k, v) = self.heap.delete_min();
+        if k != self.model[expected].0 {
+            let err = format!("delete_min gave {:?} but {:?} was expected", k, self.model[expected].0);
+            self.fail(&err);
+        }
+        self.model_remove(&k, &v);
+        self.shake();
+        (k, v)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Rc<FibNode<K, V>> {
+        self.model.push((key.clone(), value.clone()));
+        let entry = self.heap.insert(key, value);
+        self.shake();
+        entry
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> DecreaseKey<K, V>
+for ChaosHeap<K, V> {
+    fn decrease_key(&mut self, entry: &Rc<FibNode<K, V>>, delta: K) -> Result<(), StaleEntry> {
+        let (k, v) = (entry.get_key().clone(), entry.get_value().clone());
+        self.heap.decrease_key(entry, delta)?;
+        self.model_remove(&k, &v);
+        self.model.push((entry.get_key().clone(), v));
+        self.shake();
+        Ok(())
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Hash + Clone> HeapDelete<K, V>
+for ChaosHeap<K, V> {
+    type HeapEntry = Rc<FibNode<K, V>>;
+
+    fn delete(&mut self, entry: Rc<FibNode<K, V>>) -> Result<(K, V), StaleEntry> {
+        let (k, v) = self.heap.delete(entry)?;
+        self.model_remove(&k, &v);
+        self.shake();
+        Ok((k, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chaos::ChaosHeap;
+    use {Heap, DecreaseKey, HeapDelete};
+
+    #[test]
+    fn chaos_operations() {
+        for seed in 0..20 {
+            let mut heap = ChaosHeap::new(seed);
+            let mut entries = Vec::new();
+            for i in 0..200u32 {
+                entries.push(heap.insert((i * 37) % 101 + 100, i));
+            }
+            for i in 0..50 {
+                heap.decrease_key(&entries[i * 3], 90).unwrap();
+                heap.delete_min();
+            }
+            for i in 0..20 {
+                heap.delete(entries[i * 3 + 1].clone()).unwrap();
+            }
+            let mut last = 0;
+            while !heap.empty() {
+                let (k, _) = heap.delete_min();
+                assert!(k >= last);
+                last = k;
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "chaos seed 7, operation 3: ")]
+    fn chaos_reports_broken_heaps() {
+        let mut heap = ChaosHeap::new(7);
+        heap.insert(1i32, 'b');
+        let a = heap.insert(5, 'a');
+        // Changing a key behind the heap's back leaves the minimum out of
+        // place.
+        a.set_key(0);
+        heap.insert(7, 'c');
+    }
+}
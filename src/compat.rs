@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::collections::hash_map;
+use std::hash::Hash;
+use std::fmt::Debug;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use rev::Rev;
+use {Heap, DecreaseKey, HeapDelete};
+
+type Entry<P> = Rc<FibNode<Rev<P>, u64>>;
+
+/// A max-priority queue with the API of the `priority-queue` crate's
+/// `PriorityQueue`, backed by a `FibHeap`.
+///
+/// Raising an item's priority is a decrease_key on the underlying heap.
+/// Lowering it removes and reinserts the item. Unlike the `priority-queue`
+/// crate, priorities must support `+` and `-` to compute key deltas.
+pub struct PriorityQueue<I, P> {
+    heap: FibHeap<Rev<P>, u64>,
+    ids: HashMap<I, u64>,
+    entries: HashMap<u64, (I, Entry<P>)>,
+    next_id: u64,
+}
+
+impl<I, P> PriorityQueue<I, P>
+where I: Hash + Eq + Clone,
+      P: Ord + Debug + Clone + Add<P, Output=P> + Sub<P, Output=P> {
+    pub fn new() -> PriorityQueue<I, P> {
+        PriorityQueue {
+            heap: FibHeap::new(),
+            ids: HashMap::new(),
+            entries: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Inserts `item`, or changes its priority if it is already queued, in
+    /// which case the old priority is returned.
+    pub fn push(&mut self, item: I, priority: P) -> Option<P> {
+        if self.ids.contains_key(&item) {
+            return self.change_priority(&item, priority)
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(Rev(priority), id);
+        self.ids.insert(item.clone(), id);
+        self.entries.insert(id, (item, entry));
+        None
+    }
+
+    /// Like `push`, but only ever raises the priority of a queued item.
+    pub fn push_increase(&mut self, item: I, priority: P) -> Option<P> {
+        match self.get_priority(&item).cloned() {
+            Some(old) => {
+                if priority > old {
+                    self.change_priority(&item, priority)
+                } else {
+                    Some(priority)
+                }
+            }
+            None => self.push(item, priority)
+        }
+    }
+
+    /// Sets the priority of a queued item, returning the old priority, or
+    /// `None` if the item is not queued.
+    pub fn change_priority(&mut self, item: &I, priority: P) -> Option<P> {
+        let id = {
+            let id = self.ids.get(item)?;
+            *id
+        };
+        let old = self.entries[&id].1.get_key().0.clone();
+        if priority >= old {
+            self.heap.decrease_key(&self.entries[&id].1, Rev(priority - old.clone())).unwrap();
+        } else {
+            let (item, entry) = self.entries.remove(&id).unwrap();
+            self.heap.delete(entry).unwrap();
+            let entry = self.heap.insert(Rev(priority), id);
+            self.entries.insert(id, (item, entry));
+        }
+        Some(old)
+    }
+
+    pub fn get_priority(&self, item: &I) -> Option<&P> {
+        self.ids.get(item).map(|id| &self.entries[id].1.get_key().0)
+    }
+
+    pub fn get(&self, item: &I) -> Option<(&I, &P)> {
+        self.ids.get(item).map(|id| {
+            let (i, entry) = &self.entries[id];
+            (i, &entry.get_key().0)
+        })
+    }
+
+    /// Returns the item with the highest priority.
+    pub fn peek(&self) -> Option<(&I, &P)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (_, id) = self.heap.find_min();
+        let (item, entry) = &self.entries[&id];
+        Some((item, &entry.get_key().0))
+    }
+
+    /// Removes the item with the highest priority.
+    pub fn pop(&mut self) -> Option<(I, P)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (Rev(priority), id) = self.heap.delete_min();
+        let (item, _) = self.entries.remove(&id).unwrap();
+        self.ids.remove(&item);
+        Some((item, priority))
+    }
+
+    pub fn remove(&mut self, item: &I) -> Option<(I, P)> {
+        self.ids.remove(item).map(|id| {
+            let (item, entry) = self.entries.remove(&id).unwrap();
+            let (Rev(priority), _) = self.heap.delete(entry).unwrap();
+            (item, priority)
+        })
+    }
+
+    /// Iterates over the queued items and their priorities in arbitrary
+    /// order.
+    pub fn iter(&self) -> Iter<'_, I, P> {
+        Iter { inner: self.entries.values() }
+    }
+}
+
+pub struct Iter<'a, I: 'a, P: 'a> {
+    inner: hash_map::Values<'a, u64, (I, Entry<P>)>,
+}
+
+impl<'a, I, P> Iterator for Iter<'a, I, P>
+where P: Ord + Debug + Clone + Add<P, Output=P> {
+    type Item = (&'a I, &'a P);
+
+    fn next(&mut self) -> Option<(&'a I, &'a P)> {
+        self.inner.next().map(|(item, entry)| (item, &entry.get_key().0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use compat::PriorityQueue;
+
+    #[test]
+    fn compat_push_pop() {
+        let mut pq = PriorityQueue::new();
+        assert_eq!(pq.push("a", 3u32), None);
+        assert_eq!(pq.push("b", 7), None);
+        assert_eq!(pq.push("c", 5), None);
+        assert_eq!(pq.len(), 3);
+        assert_eq!(pq.peek(), Some((&"b", &7)));
+        assert_eq!(pq.pop(), Some(("b", 7)));
+        assert_eq!(pq.pop(), Some(("c", 5)));
+        assert_eq!(pq.pop(), Some(("a", 3)));
+        assert_eq!(pq.pop(), None);
+        assert!(pq.is_empty());
+    }
+
+    #[test]
+    fn compat_change_priority() {
+        let mut pq = PriorityQueue::new();
+        pq.push("a", 3u32);
+        pq.push("b", 7);
+        pq.push("c", 5);
+        pq.push("d", 1);
+        pq.pop();
+        assert_eq!(pq.push("a", 10), Some(3));
+        assert_eq!(pq.get_priority(&"a"), Some(&10));
+        assert_eq!(pq.change_priority(&"a", 0), Some(10));
+        assert_eq!(pq.change_priority(&"b", 1), None);
+        assert_eq!(pq.push_increase("d", 0), Some(0));
+        assert_eq!(pq.push_increase("d", 6), Some(1));
+        assert_eq!(pq.get(&"d"), Some((&"d", &6)));
+
+        let mut all: Vec<(&str, u32)> = pq.iter().map(|(i, p)| (*i, *p)).collect();
+        all.sort();
+        assert_eq!(all, vec!(("a", 0), ("c", 5), ("d", 6)));
+
+        assert_eq!(pq.remove(&"c"), Some(("c", 5)));
+        assert_eq!(pq.remove(&"c"), None);
+        assert_eq!(pq.pop(), Some(("d", 6)));
+        assert_eq!(pq.pop(), Some(("a", 0)));
+        assert!(pq.is_empty());
+    }
+}
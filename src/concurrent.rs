@@ -0,0 +1,825 @@
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::ops::Sub;
+use std::sync::{Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
+use std::thread;
+use std::time::{Duration, Instant};
+use ordered::Ordered;
+use owned_heap::OwnedHeap;
+#[cfg(feature = "persistent")]
+use persistent::LeftistHeap;
+use rev::Rev;
+use rng::Rng;
+
+struct Shard<K, V> {
+    heap: Mutex<OwnedHeap<K, V>>,
+    // A copy of the shard's minimum key, only written with `heap` locked, so
+    // readers can find the best shard without waiting on busy heaps.
+    min: Mutex<Option<K>>,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> Shard<K, V> {
+    fn sync_min(&self, heap: &OwnedHeap<K, V>) {
+        *self.min.lock().unwrap() = heap.find_min().map(|(k, _)| k);
+    }
+}
+
+/// A heap that can be shared between threads, split into shards that each
+/// have their own lock.
+///
+/// Each thread inserts into the shard its id hashes to, so concurrent
+/// inserts from different threads rarely contend. `delete_min` picks the
+/// shard whose cached minimum is smallest and only locks that one, so under
+/// concurrent updates it may return an element that is not quite the
+/// smallest. `delete_min_exact` locks every shard and is always exact.
+pub struct ShardedHeap<K, V> {
+    shards: Vec<Shard<K, V>>,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K> + Send,
+     V: Eq + PartialOrd + Debug + Clone + Send> ShardedHeap<K, V> {
+    pub fn new(shards: usize) -> ShardedHeap<K, V> {
+        assert!(shards > 0, "ShardedHeap needs at least one shard");
+        ShardedHeap {
+            shards: (0..shards).map(|_| Shard {
+                heap: Mutex::new(OwnedHeap::new()),
+                min: Mutex::new(None),
+            }).collect(),
+        }
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.heap.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|s| s.heap.lock().unwrap().empty())
+    }
+
+    /// Inserts into the calling thread's shard.
+    pub fn insert(&self, key: K, value: V) {
+        let mut hasher = DefaultHasher::new();
+        thread::current().id().hash(&mut hasher);
+        let shard = (hasher.finish() % self.shards.len() as u64) as usize;
+        self.insert_into(shard, key, value);
+    }
+
+    /// Inserts into a particular shard, for callers that partition the work
+    /// themselves.
+    pub fn insert_into(&self, shard: usize, key: K, value: V) {
+        let shard = &self.shards[shard];
+        let mut heap = shard.heap.lock().unwrap();
+        heap.insert(key, value);
+        shard.sync_min(&heap);
+    }
+
+    /// Removes the minimum of the shard whose cached minimum is smallest.
+    /// This is exact when no other thread is updating the heap at the same
+    /// time.
+    pub fn delete_min(&self) -> Option<(K, V)> {
+        loop {
+            let mut best: Option<(usize, K)> = None;
+            for (i, shard) in self.shards.iter().enumerate() {
+                if let Some(ref k) = *shard.min.lock().unwrap() {
+                    if best.as_ref().is_none_or(|(_, b)| k < b) {
+                        best = Some((i, k.clone()));
+                    }
+                }
+            }
+            let shard = match best {
+                Some((i, _)) => &self.shards[i],
+                None => return None
+            };
+            let mut heap = shard.heap.lock().unwrap();
+            let min = heap.delete_min();
+            shard.sync_min(&heap);
+            // Another thread may have emptied the shard since its minimum
+            // was read, in which case look again.
+            if min.is_some() {
+                return min
+            }
+        }
+    }
+
+    /// Removes the overall minimum, locking every shard to find it.
+    pub fn delete_min_exact(&self) -> Option<(K, V)> {
+        // Shards are always locked in index order, so two callers can't
+        // deadlock.
+        let mut heaps: Vec<_> = self.shards.iter().map(|s| s.heap.lock().unwrap()).collect();
+        let mut best: Option<(usize, K)> = None;
+        for (i, heap) in heaps.iter().enumerate() {
+            if let Some((k, _)) = heap.find_min() {
+                if best.as_ref().is_none_or(|(_, b)| k < *b) {
+                    best = Some((i, k));
+                }
+            }
+        }
+        best.and_then(|(i, _)| {
+            let min = heaps[i].delete_min();
+            self.shards[i].sync_min(&heaps[i]);
+            min
+        })
+    }
+}
+
+/// A heap behind a mutex whose consumers can sleep until an element
+/// arrives, instead of polling.
+pub struct SyncHeap<K, V> {
+    heap: Mutex<OwnedHeap<K, V>>,
+    ready: Condvar,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K> + Send,
+     V: Eq + PartialOrd + Debug + Clone + Send> SyncHeap<K, V> {
+    pub fn new() -> SyncHeap<K, V> {
+        SyncHeap { heap: Mutex::new(OwnedHeap::new()), ready: Condvar::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.lock().unwrap().empty()
+    }
+
+    /// Inserts an element, waking one thread waiting in `pop_blocking`.
+    pub fn push(&self, key: K, value: V) {
+        self.heap.lock().unwrap().insert(key, value);
+        self.ready.notify_one();
+    }
+
+    pub fn try_pop(&self) -> Option<(K, V)> {
+        self.heap.lock().unwrap().delete_min()
+    }
+
+    /// Removes the minimum, waiting up to `timeout` for one to arrive.
+    pub fn pop_blocking(&self, timeout: Duration) -> Option<(K, V)> {
+        let deadline = Instant::now() + timeout;
+        let mut heap = self.heap.lock().unwrap();
+        loop {
+            if let Some(min) = heap.delete_min() {
+                return Some(min)
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None
+            }
+            heap = self.ready.wait_timeout(heap, deadline - now).unwrap().0;
+        }
+    }
+}
+
+thread_local! {
+    // Per-thread generator for picking queues, seeded lazily from the
+    // thread id.
+    static RNG: Cell<Option<Rng>> = const { Cell::new(None) };
+}
+
+fn random_below(n: usize) -> usize {
+    RNG.with(|rng| {
+        let mut r = rng.get().unwrap_or_else(|| {
+            let mut hasher = DefaultHasher::new();
+            thread::current().id().hash(&mut hasher);
+            Rng::new(hasher.finish())
+        });
+        let i = r.below(n as u64) as usize;
+        rng.set(Some(r));
+        i
+    })
+}
+
+/// A relaxed concurrent priority queue after Rihani, Sanders and Dementiev's
+/// MultiQueue.
+///
+/// Elements are spread over several heaps, each behind its own lock.
+/// `insert` uses a random heap that isn't locked, and `delete_min` takes the
+/// smaller minimum of two random heaps, so threads rarely wait for each
+/// other. The element removed is not always the smallest, but is expected to
+/// rank among the smallest few times the number of heaps.
+pub struct MultiQueue<K, V> {
+    queues: Vec<Shard<K, V>>,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K> + Send,
+     V: Eq + PartialOrd + Debug + Clone + Send> MultiQueue<K, V> {
+    /// A queue with `c` heaps per thread, for `threads` threads. A `c` of 2
+    /// to 4 is typical.
+    pub fn new(threads: usize, c: usize) -> MultiQueue<K, V> {
+        let n = if threads * c > 2 { threads * c } else { 2 };
+        MultiQueue {
+            queues: (0..n).map(|_| Shard {
+                heap: Mutex::new(OwnedHeap::new()),
+                min: Mutex::new(None),
+            }).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.iter().map(|q| q.heap.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(|q| q.min.lock().unwrap().is_none())
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        loop {
+            let queue = &self.queues[random_below(self.queues.len())];
+            if let Ok(mut heap) = queue.heap.try_lock() {
+                heap.insert(key, value);
+                queue.sync_min(&heap);
+                return
+            }
+        }
+    }
+
+    /// Removes an element with a small key, or returns `None` if every heap
+    /// is empty.
+    pub fn delete_min(&self) -> Option<(K, V)> {
+        loop {
+            let i = random_below(self.queues.len());
+            let j = random_below(self.queues.len());
+            let best = {
+                let a = self.queues[i].min.lock().unwrap().clone();
+                let b = self.queues[j].min.lock().unwrap().clone();
+                match (a, b) {
+                    (Some(a), Some(b)) => if b < a { j } else { i },
+                    (Some(_), None) => i,
+                    (None, Some(_)) => j,
+                    (None, None) => {
+                        if self.is_empty() {
+                            return None
+                        }
+                        continue
+                    }
+                }
+            };
+            let queue = &self.queues[best];
+            if let Ok(mut heap) = queue.heap.try_lock() {
+                let min = heap.delete_min();
+                queue.sync_min(&heap);
+                if min.is_some() {
+                    return min
+                }
+            }
+        }
+    }
+}
+
+struct Jobs<P, T> {
+    // Keyed by priority, highest first, then by push order.
+    heap: OwnedHeap<Ordered<(Rev<P>, u64)>, u64>,
+    items: HashMap<u64, T>,
+    // Tickets of the consumers waiting in `pop`, in arrival order.
+    waiting: VecDeque<u64>,
+    next_seq: u64,
+    next_ticket: u64,
+    closed: bool,
+}
+
+/// A multi-producer, multi-consumer job queue handing out the job with the
+/// highest priority first, and jobs of equal priority in the order they
+/// were pushed.
+///
+/// Consumers blocked in `pop` are served strictly in the order they
+/// started waiting, so a consumer can't be starved by others that keep
+/// coming back for more. `try_pop` never takes a job while any consumer is
+/// waiting.
+pub struct FairQueue<P, T> {
+    jobs: Mutex<Jobs<P, T>>,
+    changed: Condvar,
+}
+
+impl<P: Ord + Clone + Send, T: Send> FairQueue<P, T> {
+    pub fn new() -> FairQueue<P, T> {
+        FairQueue {
+            jobs: Mutex::new(Jobs {
+                heap: OwnedHeap::new(),
+                items: HashMap::new(),
+                waiting: VecDeque::new(),
+                next_seq: 0,
+                next_ticket: 0,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// The number of queued jobs.
+    pub fn len(&self) -> usize {
+        self.jobs.lock().unwrap().items.len()
+    }
+
+    /// Queues a job, or gives it back if the queue has been closed.
+    pub fn push(&self, priority: P, job: T) -> Result<(), T> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.closed {
+            return Err(job)
+        }
+        let seq = jobs.next_seq;
+        jobs.next_seq += 1;
+        jobs.heap.insert(Ordered((Rev(priority), seq)), seq);
+        jobs.items.insert(seq, job);
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// Stops accepting jobs. Consumers still get the jobs already queued,
+    /// after which `pop` returns `None`.
+    pub fn close(&self) {
+        self.jobs.lock().unwrap().closed = true;
+        self.changed.notify_all();
+    }
+
+    /// Waits for the next job, or returns `None` once the queue is closed
+    /// and empty.
+    pub fn pop(&self) -> Option<(P, T)> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let ticket = jobs.next_ticket;
+        jobs.next_ticket += 1;
+        jobs.waiting.push_back(ticket);
+        loop {
+            if jobs.waiting.front() == Some(&ticket) {
+                if let Some(job) = FairQueue::take(&mut jobs) {
+                    jobs.waiting.pop_front();
+                    self.changed.notify_all();
+                    return Some(job)
+                }
+                if jobs.closed {
+                    jobs.waiting.pop_front();
+                    self.changed.notify_all();
+                    return None
+                }
+            }
+            jobs = self.changed.wait(jobs).unwrap();
+        }
+    }
+
+    /// Takes the next job if one is queued and no consumer is waiting.
+    pub fn try_pop(&self) -> Option<(P, T)> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.waiting.is_empty() { FairQueue::take(&mut jobs) } else { None }
+    }
+
+    fn take(jobs: &mut Jobs<P, T>) -> Option<(P, T)> {
+        jobs.heap.delete_min().map(|(Ordered((Rev(priority), _)), seq)| {
+            (priority, jobs.items.remove(&seq).unwrap())
+        })
+    }
+}
+
+/// A heap shared between threads that can hand out consistent snapshots
+/// of its contents without blocking its writers for long.
+///
+/// The contents are a persistent `LeftistHeap`, so each update swaps in a
+/// new version under the lock, and `snapshot` just takes another reference
+/// to the current one. A monitoring thread can then iterate over the
+/// snapshot at leisure while workers keep pushing and popping.
+#[cfg(feature = "persistent")]
+pub struct SnapshotHeap<K, V> {
+    current: Mutex<LeftistHeap<K, V>>,
+}
+
+#[cfg(feature = "persistent")]
+impl<K: Ord + Clone + Send + Sync, V: Clone + Send + Sync> SnapshotHeap<K, V> {
+    pub fn new() -> SnapshotHeap<K, V> {
+        SnapshotHeap { current: Mutex::new(LeftistHeap::new()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.current.lock().unwrap().len()
+    }
+
+    pub fn push(&self, key: K, value: V) {
+        let mut current = self.current.lock().unwrap();
+        *current = current.insert(key, value);
+    }
+
+    pub fn pop(&self) -> Option<(K, V)> {
+        let mut current = self.current.lock().unwrap();
+        current.delete_min().map(|(min, rest)| {
+            *current = rest;
+            min
+        })
+    }
+
+    /// The contents as of now, unaffected by later updates. Takes O(1).
+    pub fn snapshot(&self) -> LeftistHeap<K, V> {
+        self.current.lock().unwrap().clone()
+    }
+}
+
+struct Local<P, T> {
+    // Keyed by priority, then by spawn order.
+    heap: OwnedHeap<Ordered<(P, u64)>, u64>,
+    tasks: HashMap<u64, T>,
+}
+
+struct Slot<P, T> {
+    local: Mutex<Local<P, T>>,
+    // The best priority waiting here, only written with `local` locked.
+    min: Mutex<Option<P>>,
+}
+
+impl<P: Ord + Clone, T> Slot<P, T> {
+    fn sync_min(&self, local: &Local<P, T>) {
+        *self.min.lock().unwrap() = local.heap.find_min().map(|(Ordered((p, _)), _)| p);
+    }
+
+    fn pop(&self) -> Option<(P, T)> {
+        let mut local = self.local.lock().unwrap();
+        let popped = local.heap.delete_min().map(|(Ordered((p, _)), id)| {
+            (p, local.tasks.remove(&id).unwrap())
+        });
+        self.sync_min(&local);
+        popped
+    }
+}
+
+// Marks a task as finished even if its handler panics, so the other workers
+// still stop once the remaining work is done.
+struct Finish<'a>(&'a AtomicUsize);
+
+impl<'a> Drop for Finish<'a> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, AtomicOrdering::SeqCst);
+    }
+}
+
+/// A pool of workers that each own a queue of tasks, handing out the task
+/// with the smallest priority value first.
+///
+/// A worker runs tasks from its own queue, and only when that is empty
+/// steals the best task waiting anywhere else. Tasks spawned by a running
+/// task go to the worker running it, which keeps related work together, as
+/// when exploring a branch-and-bound tree.
+pub struct WorkStealingScheduler<P, T> {
+    slots: Vec<Slot<P, T>>,
+    next_id: AtomicU64,
+    next_slot: AtomicUsize,
+    // Tasks spawned but not yet finished, including running ones.
+    pending: AtomicUsize,
+}
+
+/// A worker of a `WorkStealingScheduler`, passed to the task handler.
+pub struct Worker<'a, P: 'a, T: 'a> {
+    scheduler: &'a WorkStealingScheduler<P, T>,
+    index: usize,
+}
+
+impl<'a, P: Ord + Clone + Send, T: Send> Worker<'a, P, T> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Spawns a task onto this worker's own queue.
+    pub fn spawn(&self, priority: P, task: T) {
+        self.scheduler.push(self.index, priority, task);
+    }
+}
+
+impl<P: Ord + Clone + Send, T: Send> WorkStealingScheduler<P, T> {
+    pub fn new(workers: usize) -> WorkStealingScheduler<P, T> {
+        assert!(workers > 0, "WorkStealingScheduler needs at least one worker");
+        WorkStealingScheduler {
+            slots: (0..workers).map(|_| Slot {
+                local: Mutex::new(Local { heap: OwnedHeap::new(), tasks: HashMap::new() }),
+                min: Mutex::new(None),
+            }).collect(),
+            next_id: AtomicU64::new(0),
+            next_slot: AtomicUsize::new(0),
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// The number of tasks waiting to run.
+    pub fn len(&self) -> usize {
+        self.slots.iter().map(|s| s.local.lock().unwrap().tasks.len()).sum()
+    }
+
+    /// Spawns a task, spreading tasks spawned from outside the workers
+    /// across their queues in turn.
+    pub fn spawn(&self, priority: P, task: T) {
+        let slot = self.next_slot.fetch_add(1, AtomicOrdering::Relaxed) % self.slots.len();
+        self.push(slot, priority, task);
+    }
+
+    /// Runs every task on one thread per worker, returning once no tasks
+    /// are waiting or running. The handler gets the worker running the
+    /// task, through which it can spawn more.
+    pub fn run<F>(&self, handler: F) where F: Fn(&Worker<P, T>, P, T) + Sync {
+        let handler = &handler;
+        thread::scope(|scope| {
+            for index in 0..self.slots.len() {
+                scope.spawn(move || {
+                    let worker = Worker { scheduler: self, index: index };
+                    loop {
+                        match self.slots[index].pop().or_else(|| self.steal(index)) {
+                            Some((priority, task)) => {
+                                let _finish = Finish(&self.pending);
+                                handler(&worker, priority, task);
+                            }
+                            None if self.pending.load(AtomicOrdering::SeqCst) == 0 => break,
+                            None => thread::yield_now()
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    fn push(&self, slot: usize, priority: P, task: T) {
+        let id = self.next_id.fetch_add(1, AtomicOrdering::Relaxed);
+        self.pending.fetch_add(1, AtomicOrdering::SeqCst);
+        let slot = &self.slots[slot];
+        let mut local = slot.local.lock().unwrap();
+        local.heap.insert(Ordered((priority, id)), id);
+        local.tasks.insert(id, task);
+        slot.sync_min(&local);
+    }
+
+    // Takes the best task waiting on another worker.
+    fn steal(&self, thief: usize) -> Option<(P, T)> {
+        loop {
+            let mut best: Option<(usize, P)> = None;
+            for (i, slot) in self.slots.iter().enumerate() {
+                if i == thief {
+                    continue
+                }
+                if let Some(ref p) = *slot.min.lock().unwrap() {
+                    if best.as_ref().is_none_or(|(_, b)| p < b) {
+                        best = Some((i, p.clone()));
+                    }
+                }
+            }
+            match best {
+                Some((i, _)) => {
+                    if let Some(stolen) = self.slots[i].pop() {
+                        return Some(stolen)
+                    }
+                }
+                None => return None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use concurrent::{FairQueue, MultiQueue, ShardedHeap, SyncHeap, WorkStealingScheduler};
+    #[cfg(feature = "persistent")]
+    use concurrent::SnapshotHeap;
+
+    #[test]
+    fn sharded_heap_exact_order() {
+        let heap = ShardedHeap::new(3);
+        for (i, &k) in [5u32, 1, 9, 3, 7, 2].iter().enumerate() {
+            heap.insert_into(i % 3, k, i);
+        }
+        assert_eq!(heap.len(), 6);
+        assert_eq!(heap.delete_min_exact(), Some((1, 1)));
+        assert_eq!(heap.delete_min(), Some((2, 5)));
+        let rest: Vec<u32> = (0..4).map(|_| heap.delete_min_exact().unwrap().0).collect();
+        assert_eq!(rest, vec!(3, 5, 7, 9));
+        assert!(heap.is_empty());
+        assert_eq!(heap.delete_min(), None);
+        assert_eq!(heap.delete_min_exact(), None);
+    }
+
+    #[test]
+    fn sharded_heap_concurrent_inserts() {
+        let heap = Arc::new(ShardedHeap::new(4));
+        let workers: Vec<_> = (0..4u32).map(|t| {
+            let heap = heap.clone();
+            thread::spawn(move || {
+                for i in 0..250u32 {
+                    heap.insert(i * 4 + t, t);
+                }
+            })
+        }).collect();
+        for w in workers.into_iter() {
+            w.join().unwrap();
+        }
+        assert_eq!(heap.len(), 1000);
+        // With no concurrent updates the approximate path is exact too.
+        let keys: Vec<u32> = (0..1000).map(|_| heap.delete_min().unwrap().0).collect();
+        assert_eq!(keys, (0..1000).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn sharded_heap_concurrent_deletes() {
+        let heap = Arc::new(ShardedHeap::new(4));
+        for i in 0..400u32 {
+            heap.insert_into(i as usize % 4, i, ());
+        }
+        let workers: Vec<_> = (0..4).map(|_| {
+            let heap = heap.clone();
+            thread::spawn(move || {
+                let mut got = Vec::new();
+                while let Some((k, _)) = heap.delete_min() {
+                    got.push(k);
+                }
+                got
+            })
+        }).collect();
+        let mut all: Vec<u32> = Vec::new();
+        for w in workers.into_iter() {
+            all.extend(w.join().unwrap());
+        }
+        all.sort();
+        assert_eq!(all, (0..400).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn work_stealing_priority_order() {
+        let scheduler = WorkStealingScheduler::new(1);
+        for &p in [4u32, 1, 3, 1, 2].iter() {
+            scheduler.spawn(p, p * 10);
+        }
+        assert_eq!(scheduler.len(), 5);
+        let order = Mutex::new(Vec::new());
+        scheduler.run(|_, p, task| order.lock().unwrap().push((p, task)));
+        assert_eq!(order.into_inner().unwrap(), vec!((1, 10), (1, 10), (2, 20), (3, 30), (4, 40)));
+        assert_eq!(scheduler.len(), 0);
+    }
+
+    #[test]
+    fn work_stealing_runs_spawned_tasks() {
+        // Expands a binary tree of depth 10 from a single root, so the other
+        // workers only get work by stealing.
+        let scheduler = WorkStealingScheduler::new(4);
+        scheduler.spawn(0u32, 0u32);
+        let done = AtomicUsize::new(0);
+        let workers = Mutex::new(vec!(0; 4));
+        scheduler.run(|worker, depth, _| {
+            done.fetch_add(1, Ordering::SeqCst);
+            workers.lock().unwrap()[worker.index()] += 1;
+            if depth < 10 {
+                worker.spawn(depth + 1, 0);
+                worker.spawn(depth + 1, 1);
+            }
+        });
+        assert_eq!(done.load(Ordering::SeqCst), (1 << 11) - 1);
+        assert_eq!(workers.into_inner().unwrap().iter().sum::<usize>(), (1 << 11) - 1);
+    }
+
+    #[test]
+    fn sync_heap_pop_blocking() {
+        let heap = Arc::new(SyncHeap::new());
+        let start = Instant::now();
+        assert_eq!(heap.pop_blocking(Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let producer = heap.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.push(2u32, 'b');
+        });
+        assert_eq!(heap.pop_blocking(Duration::from_secs(10)), Some((2, 'b')));
+        handle.join().unwrap();
+
+        heap.push(5, 'e');
+        heap.push(1, 'a');
+        assert_eq!(heap.len(), 2);
+        assert_eq!(heap.pop_blocking(Duration::from_millis(0)), Some((1, 'a')));
+        assert_eq!(heap.try_pop(), Some((5, 'e')));
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn fair_queue_order_and_close() {
+        let queue = FairQueue::new();
+        queue.push(1, "low").unwrap();
+        queue.push(5, "first").unwrap();
+        queue.push(5, "second").unwrap();
+        queue.push(3, "mid").unwrap();
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.pop(), Some((5, "first")));
+        assert_eq!(queue.try_pop(), Some((5, "second")));
+        queue.close();
+        assert_eq!(queue.push(9, "late"), Err("late"));
+        assert_eq!(queue.pop(), Some((3, "mid")));
+        assert_eq!(queue.pop(), Some((1, "low")));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn fair_queue_serves_waiting_consumers() {
+        let queue = Arc::new(FairQueue::new());
+        let consumers: Vec<_> = (0..4).map(|_| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut got = Vec::new();
+                while let Some((_, job)) = queue.pop() {
+                    got.push(job);
+                }
+                got
+            })
+        }).collect();
+        for i in 0..400u32 {
+            queue.push(i % 3, i).unwrap();
+        }
+        queue.close();
+        let mut all: Vec<u32> = Vec::new();
+        for c in consumers.into_iter() {
+            let got = c.join().unwrap();
+            // Each consumer sees jobs of equal priority in push order.
+            for p in 0..3 {
+                let same: Vec<&u32> = got.iter().filter(|&&j| j % 3 == p).collect();
+                assert!(same.windows(2).all(|w| w[0] < w[1]));
+            }
+            all.extend(got);
+        }
+        all.sort();
+        assert_eq!(all, (0..400).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn multi_queue_relaxed_order() {
+        let queue = MultiQueue::new(2, 4);
+        for i in 0..1000u32 {
+            queue.insert(i, ());
+        }
+        assert_eq!(queue.len(), 1000);
+        let keys: Vec<u32> = (0..1000).map(|_| queue.delete_min().unwrap().0).collect();
+        assert!(keys[0] < 100);
+        assert_eq!(queue.delete_min(), None);
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..1000).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn multi_queue_concurrent() {
+        let queue = Arc::new(MultiQueue::new(4, 2));
+        let workers: Vec<_> = (0..4u32).map(|t| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut got = Vec::new();
+                for i in 0..250u32 {
+                    queue.insert(i * 4 + t, t);
+                    if i % 2 == 0 {
+                        got.extend(queue.delete_min().map(|(k, _)| k));
+                    }
+                }
+                got
+            })
+        }).collect();
+        let mut all: Vec<u32> = Vec::new();
+        for w in workers.into_iter() {
+            all.extend(w.join().unwrap());
+        }
+        while let Some((k, _)) = queue.delete_min() {
+            all.push(k);
+        }
+        all.sort();
+        assert_eq!(all, (0..1000).collect::<Vec<u32>>());
+    }
+
+    #[cfg(feature = "persistent")]
+    #[test]
+    fn snapshot_heap_consistent_reads() {
+        let heap = Arc::new(SnapshotHeap::new());
+        for i in 0..100u32 {
+            heap.push(i, i);
+        }
+        let snapshot = heap.snapshot();
+        let workers: Vec<_> = (0..2).map(|_| {
+            let heap = heap.clone();
+            thread::spawn(move || {
+                for i in 100..200u32 {
+                    heap.pop();
+                    heap.push(i, i);
+                }
+            })
+        }).collect();
+        // Reading the snapshot sees exactly the entries at the time it was
+        // taken, whatever the workers are doing.
+        let mut keys: Vec<u32> = snapshot.iter().map(|(k, _)| *k).collect();
+        keys.sort();
+        assert_eq!(keys, (0..100).collect::<Vec<u32>>());
+        for w in workers.into_iter() {
+            w.join().unwrap();
+        }
+        assert_eq!(heap.len(), 100);
+        assert_eq!(snapshot.len(), 100);
+        assert!(heap.pop().unwrap().0 >= 100);
+    }
+}
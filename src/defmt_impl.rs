@@ -0,0 +1,227 @@
+#[cfg(feature = "std")]
+use core::fmt::Debug;
+use core::ops::Sub;
+use defmt::{Format, Formatter};
+#[cfg(feature = "array-heap")]
+use array_heap::{FixedHeap, Slot};
+#[cfg(feature = "std")]
+use fibonacci_heap::{FibHeap};
+#[cfg(feature = "b-heap")]
+use b_heap::BHeap;
+#[cfg(feature = "binary-heap")]
+use binary_heap::DaryHeap;
+#[cfg(feature = "bounded-heap")]
+use bounded_heap::BoundedHeap;
+#[cfg(feature = "bucket-queue")]
+use bucket_queue::BucketQueue;
+#[cfg(feature = "calendar-queue")]
+use calendar_queue::{CalendarQueue, Timestamp};
+#[cfg(feature = "interval-heap")]
+use interval_heap::IntervalHeap;
+#[cfg(feature = "interrupt")]
+use interrupt_heap::InterruptHeap;
+#[cfg(feature = "lazy-binomial-heap")]
+use lazy_binomial_heap::LazyBinomialHeap;
+#[cfg(feature = "lazy-heap")]
+use lazy_heap::LazyHeap;
+#[cfg(feature = "min-max-heap")]
+use min_max_heap::MinMaxHeap;
+#[cfg(feature = "pairing-heap")]
+use pairing_heap::PairingHeap;
+#[cfg(feature = "send-heap")]
+use send_heap::SendFibHeap;
+#[cfg(feature = "skew-heap")]
+use skew_heap::SkewHeap;
+#[cfg(feature = "smooth-heap")]
+use smooth_heap::SmoothHeap;
+#[cfg(feature = "soft-heap")]
+use soft_heap::SoftHeap;
+#[cfg(feature = "strict-fib-heap")]
+use strict_fib_heap::StrictFibHeap;
+#[cfg(feature = "treap")]
+use treap::Treap;
+#[cfg(feature = "veb-heap")]
+use veb_heap::VebHeap;
+#[cfg(feature = "weak-heap")]
+use weak_heap::WeakHeap;
+use Heap;
+
+// Heaps print their size and minimum rather than every entry, which keeps
+// the log line short and the same size whatever the heap holds.
+fn write_heap<K: Format, V: Format, H: Heap<K, V>>(f: Formatter, name: &str, len: usize, heap: &H) {
+    if heap.empty() {
+        defmt::write!(f, "{=str} {{ len: 0 }}", name)
+    } else {
+        let (k, v) = heap.find_min();
+        defmt::write!(f, "{=str} {{ len: {=usize}, min: ({}, {}) }}", name, len, k, v)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V> Format for FibHeap<K, V>
+where K: Format + Ord + Debug + Clone + Sub<K, Output=K>,
+      V: Format + Eq + PartialOrd + Debug + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "FibHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "send-heap")]
+impl<K, V> Format for SendFibHeap<K, V>
+where K: Format + Ord + Debug + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "SendFibHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "lazy-heap")]
+impl<K, V, H> Format for LazyHeap<K, V, H>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone, H: Heap<K, u64> {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "LazyHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "pairing-heap")]
+impl<K, V> Format for PairingHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "PairingHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "binary-heap")]
+impl<K, V, const D: usize> Format for DaryHeap<K, V, D>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "DaryHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "skew-heap")]
+impl<K, V> Format for SkewHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "SkewHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "min-max-heap")]
+impl<K, V> Format for MinMaxHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "MinMaxHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "interval-heap")]
+impl<K, V> Format for IntervalHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "IntervalHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "soft-heap")]
+impl<K, V> Format for SoftHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "SoftHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "bucket-queue")]
+impl<V: Format + Clone, const C: usize> Format for BucketQueue<V, C> {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "BucketQueue", self.len(), self)
+    }
+}
+
+#[cfg(feature = "strict-fib-heap")]
+impl<K, V> Format for StrictFibHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "StrictFibHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "weak-heap")]
+impl<K, V> Format for WeakHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "WeakHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "smooth-heap")]
+impl<K, V> Format for SmoothHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "SmoothHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "b-heap")]
+impl<K, V> Format for BHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "BHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "bounded-heap")]
+impl<K, V> Format for BoundedHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "BoundedHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "veb-heap")]
+impl<V: Format + Clone> Format for VebHeap<V> {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "VebHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "calendar-queue")]
+impl<K: Format + Timestamp, V: Format + Clone> Format for CalendarQueue<K, V> {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "CalendarQueue", self.len(), self)
+    }
+}
+
+#[cfg(feature = "lazy-binomial-heap")]
+impl<K, V> Format for LazyBinomialHeap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "LazyBinomialHeap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "treap")]
+impl<K, V> Format for Treap<K, V>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "Treap", self.len(), self)
+    }
+}
+
+#[cfg(feature = "array-heap")]
+impl<K, V, S> Format for FixedHeap<K, V, S>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone,
+      S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]> {
+    fn format(&self, f: Formatter) {
+        write_heap(f, "FixedHeap", self.len(), self)
+    }
+}
+
+// Reading the heap takes a critical section, which is safe to nest, so this
+// works from interrupt handlers too.
+#[cfg(feature = "interrupt")]
+impl<K, V, const N: usize> Format for InterruptHeap<K, V, N>
+where K: Format + Ord + Clone + Sub<K, Output=K>, V: Format + Clone {
+    fn format(&self, f: Formatter) {
+        self.with(|heap| write_heap(f, "InterruptHeap", heap.len(), heap))
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{self, Instant, Sleep};
+use send_heap::{EntryId, SendFibHeap};
+use {Heap, DecreaseKey, HeapDelete};
+
+/// Handle to an item in a `DelayHeap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DelayKey(u64);
+
+/// A queue of items that each become available at a deadline, for use from
+/// tokio tasks.
+///
+/// `next().await` waits for the earliest deadline and yields its item.
+/// Deadlines are kept as offsets from when the queue was created, so moving
+/// one earlier is a `decrease_key` and moving it later reinserts the item.
+pub struct DelayHeap<T> {
+    heap: SendFibHeap<Duration, u64>,
+    items: HashMap<u64, (EntryId, T)>,
+    start: Instant,
+    // Created on first poll, since a timer can only be made inside a runtime.
+    sleep: Option<Pin<Box<Sleep>>>,
+    next_id: u64,
+}
+
+impl<T> DelayHeap<T> {
+    pub fn new() -> DelayHeap<T> {
+        DelayHeap {
+            heap: SendFibHeap::new(),
+            items: HashMap::new(),
+            start: Instant::now(),
+            sleep: None,
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Queues an item that becomes available after `timeout`.
+    pub fn insert(&mut self, item: T, timeout: Duration) -> DelayKey {
+        self.insert_at(item, Instant::now() + timeout)
+    }
+
+    pub fn insert_at(&mut self, item: T, deadline: Instant) -> DelayKey {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(self.offset(deadline), id);
+        self.items.insert(id, (entry, item));
+        DelayKey(id)
+    }
+
+    pub fn deadline(&self, key: &DelayKey) -> Option<Instant> {
+        self.items.get(&key.0).map(|&(entry, _)| self.start + *self.heap.get(entry).unwrap().0)
+    }
+
+    /// Removes an item before its deadline.
+    pub fn remove(&mut self, key: &DelayKey) -> Option<T> {
+        self.items.remove(&key.0).map(|(entry, item)| {
+            self.heap.delete(entry).unwrap();
+            item
+        })
+    }
+
+    /// Moves an item's deadline to `timeout` from now. Returns false if the
+    /// item is no longer queued.
+    pub fn reset(&mut self, key: &DelayKey, timeout: Duration) -> bool {
+        self.reset_at(key, Instant::now() + timeout)
+    }
+
+    pub fn reset_at(&mut self, key: &DelayKey, deadline: Instant) -> bool {
+        let offset = self.offset(deadline);
+        let entry = match self.items.get(&key.0) {
+            Some(&(entry, _)) => entry,
+            None => return false
+        };
+        let current = *self.heap.get(entry).unwrap().0;
+        if offset < current {
+            self.heap.decrease_key(&entry, current - offset).unwrap();
+        } else {
+            self.heap.delete(entry).unwrap();
+            let entry = self.heap.insert(offset, key.0);
+            self.items.get_mut(&key.0).unwrap().0 = entry;
+        }
+        true
+    }
+
+    /// Polls for the next expired item. Gives `None` once the queue is
+    /// empty.
+    pub fn poll_expired(&mut self, cx: &mut Context) -> Poll<Option<T>> {
+        loop {
+            if self.heap.empty() {
+                return Poll::Ready(None)
+            }
+            let (offset, id) = self.heap.find_min();
+            let deadline = self.start + offset;
+            if deadline <= Instant::now() {
+                self.heap.delete_min();
+                return Poll::Ready(Some(self.items.remove(&id).unwrap().1))
+            }
+            let sleep = self.sleep.get_or_insert_with(|| Box::pin(time::sleep_until(deadline)));
+            sleep.as_mut().reset(deadline);
+            if sleep.as_mut().poll(cx).is_pending() {
+                return Poll::Pending
+            }
+        }
+    }
+
+    /// Waits for the next item to expire, or gives `None` if the queue is
+    /// empty.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Next<'_, T> {
+        Next { heap: self }
+    }
+
+    fn offset(&self, deadline: Instant) -> Duration {
+        deadline.saturating_duration_since(self.start)
+    }
+}
+
+/// The future returned by `DelayHeap::next`.
+pub struct Next<'a, T: 'a> {
+    heap: &'a mut DelayHeap<T>,
+}
+
+impl<'a, T> Future for Next<'a, T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        self.heap.poll_expired(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use tokio::runtime::{Builder, Runtime};
+    use tokio::time::Instant;
+    use delay_heap::DelayHeap;
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().enable_time().build().unwrap()
+    }
+
+    #[test]
+    fn delay_heap_yields_in_deadline_order() {
+        let rt = runtime();
+        let mut delays = DelayHeap::new();
+        delays.insert("c", Duration::from_millis(30));
+        delays.insert("a", Duration::from_millis(10));
+        delays.insert("b", Duration::from_millis(20));
+        let start = Instant::now();
+        assert_eq!(rt.block_on(delays.next()), Some("a"));
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert_eq!(rt.block_on(delays.next()), Some("b"));
+        assert_eq!(rt.block_on(delays.next()), Some("c"));
+        assert_eq!(rt.block_on(delays.next()), None);
+    }
+
+    #[test]
+    fn delay_heap_reset_and_remove() {
+        let rt = runtime();
+        let mut delays = DelayHeap::new();
+        let a = delays.insert('a', Duration::from_millis(10));
+        let b = delays.insert('b', Duration::from_millis(40));
+        let c = delays.insert('c', Duration::from_millis(20));
+        assert!(delays.reset(&b, Duration::from_millis(0)));
+        assert!(delays.reset(&a, Duration::from_millis(30)));
+        assert_eq!(delays.remove(&c), Some('c'));
+        assert!(delays.deadline(&a).unwrap() > delays.deadline(&b).unwrap());
+        assert_eq!(delays.len(), 2);
+        assert_eq!(rt.block_on(delays.next()), Some('b'));
+        assert_eq!(rt.block_on(delays.next()), Some('a'));
+        assert!(!delays.reset(&a, Duration::from_millis(5)));
+        assert_eq!(delays.remove(&a), None);
+        assert!(delays.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete};
+
+/// Handle to a released job in an `EdfQueue`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EdfHandle(u64);
+
+/// An earliest-deadline-first dispatch queue.
+///
+/// Jobs are released with absolute deadlines and the one with the earliest
+/// deadline runs first. Periodic tasks can be admitted up front: EDF meets
+/// every deadline of a set of periodic tasks with deadlines equal to their
+/// periods exactly when their total utilization, the sum of
+/// `wcet / period`, is at most 1. Utilization is tracked as an exact
+/// fraction.
+pub struct EdfQueue<T> {
+    heap: FibHeap<u64, u64>,
+    jobs: HashMap<u64, (Rc<FibNode<u64, u64>>, T)>,
+    next_id: u64,
+    // Total utilization of admitted tasks as a reduced fraction.
+    util_num: u128,
+    util_den: u128,
+}
+
+impl<T> EdfQueue<T> {
+    pub fn new() -> EdfQueue<T> {
+        EdfQueue {
+            heap: FibHeap::new(),
+            jobs: HashMap::new(),
+            next_id: 0,
+            util_num: 0,
+            util_den: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// The total utilization of admitted tasks, as a fraction.
+    pub fn utilization(&self) -> (u128, u128) {
+        (self.util_num, self.util_den)
+    }
+
+    /// Whether a periodic task taking at most `wcet` time every `period`
+    /// could be added to the admitted tasks with every deadline still met.
+    pub fn schedulable(&self, wcet: u64, period: u64) -> bool {
+        let (num, den) = self.with_task(wcet, period);
+        num <= den
+    }
+
+    /// Admits a periodic task if it is schedulable, returning whether it was
+    /// admitted.
+    pub fn admit(&mut self, wcet: u64, period: u64) -> bool {
+        let (num, den) = self.with_task(wcet, period);
+        if num > den {
+            return false
+        }
+        self.util_num = num;
+        self.util_den = den;
+        true
+    }
+
+    /// Removes a previously admitted periodic task from the utilization.
+    pub fn withdraw(&mut self, wcet: u64, period: u64) {
+        let (wcet, period) = (wcet as u128, period as u128);
+        let den = self.util_den / gcd(self.util_den, period) * period;
+        let num = self.util_num * (den / self.util_den);
+        let removed = wcet * (den / period);
+        assert!(removed <= num, "Task was not admitted");
+        let g = gcd(num - removed, den);
+        self.util_num = (num - removed) / g;
+        self.util_den = den / g;
+    }
+
+    /// Releases a job that must complete by `deadline`.
+    pub fn release(&mut self, deadline: u64, task: T) -> EdfHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(deadline, id);
+        self.jobs.insert(id, (entry, task));
+        EdfHandle(id)
+    }
+
+    /// The job to run now: the one with the earliest deadline.
+    pub fn current(&self) -> Option<(EdfHandle, u64, &T)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (deadline, id) = self.heap.find_min();
+        Some((EdfHandle(id), deadline, &self.jobs[&id].1))
+    }
+
+    /// Removes a completed job, returning it, or `None` if it was already
+    /// completed.
+    pub fn complete(&mut self, handle: EdfHandle) -> Option<T> {
+        self.jobs.remove(&handle.0).map(|(entry, task)| {
+            self.heap.delete(entry).unwrap();
+            task
+        })
+    }
+
+    /// Moves the deadline of a released job. Returns whether the job was
+    /// still queued.
+    pub fn set_deadline(&mut self, handle: EdfHandle, deadline: u64) -> bool {
+        let id = handle.0;
+        let old = match self.jobs.get(&id) {
+            Some((entry, _)) => *entry.get_key(),
+            None => return false
+        };
+        if deadline <= old {
+            self.heap.decrease_key(&self.jobs[&id].0, old - deadline).unwrap();
+        } else {
+            let (entry, task) = self.jobs.remove(&id).unwrap();
+            self.heap.delete(entry).unwrap();
+            let entry = self.heap.insert(deadline, id);
+            self.jobs.insert(id, (entry, task));
+        }
+        true
+    }
+
+    /// Jobs whose deadline is before `now`, earliest first. They stay queued.
+    pub fn missed(&self, now: u64) -> Vec<EdfHandle> {
+        let mut missed: Vec<(u64, u64)> = self.heap.iter().filter(|&(d, _)| d < now).collect();
+        missed.sort();
+        missed.into_iter().map(|(_, id)| EdfHandle(id)).collect()
+    }
+
+    fn with_task(&self, wcet: u64, period: u64) -> (u128, u128) {
+        assert!(period > 0, "Period must be positive");
+        add_fraction(self.util_num, self.util_den, wcet as u128, period as u128)
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn add_fraction(a: u128, b: u128, c: u128, d: u128) -> (u128, u128) {
+    let den = b / gcd(b, d) * d;
+    let num = a * (den / b) + c * (den / d);
+    let g = gcd(num, den);
+    (num / g, den / g)
+}
+
+#[cfg(test)]
+mod tests {
+    use edf::EdfQueue;
+
+    #[test]
+    fn edf_admission() {
+        let mut edf: EdfQueue<()> = EdfQueue::new();
+        assert!(edf.admit(1, 3));
+        assert!(edf.admit(1, 4));
+        assert!(edf.admit(2, 6));
+        assert_eq!(edf.utilization(), (11, 12));
+        assert!(edf.schedulable(1, 12));
+        assert!(!edf.schedulable(1, 11));
+        assert!(!edf.admit(1, 6));
+        assert!(edf.admit(1, 12));
+        assert_eq!(edf.utilization(), (1, 1));
+        edf.withdraw(1, 4);
+        assert_eq!(edf.utilization(), (3, 4));
+    }
+
+    #[test]
+    fn edf_dispatch_order() {
+        let mut edf = EdfQueue::new();
+        let a = edf.release(30, "a");
+        let b = edf.release(10, "b");
+        let c = edf.release(20, "c");
+        assert_eq!(edf.current(), Some((b, 10, &"b")));
+        assert!(edf.set_deadline(a, 5));
+        assert_eq!(edf.current(), Some((a, 5, &"a")));
+        assert!(edf.set_deadline(a, 25));
+        assert_eq!(edf.missed(21), vec!(b, c));
+        assert_eq!(edf.complete(b), Some("b"));
+        assert_eq!(edf.complete(b), None);
+        assert!(!edf.set_deadline(b, 1));
+        assert_eq!(edf.current(), Some((c, 20, &"c")));
+        edf.complete(c);
+        assert_eq!(edf.current(), Some((a, 25, &"a")));
+        assert_eq!(edf.len(), 1);
+    }
+}
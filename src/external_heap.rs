@@ -0,0 +1,386 @@
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use Heap;
+
+/// A fixed-size binary encoding, for the keys and values an `ExternalHeap`
+/// writes to disk.
+pub trait Record: Sized {
+    const SIZE: usize;
+
+    /// Writes the record into `out`, which is exactly `SIZE` bytes.
+    fn write(&self, out: &mut [u8]);
+    /// Reads a record from `bytes`, which are exactly `SIZE` bytes.
+    fn read(bytes: &[u8]) -> Self;
+}
+
+macro_rules! int_record {
+    ($($t:ty),*) => {$(
+        impl Record for $t {
+            const SIZE: usize = mem::size_of::<$t>();
+
+            fn write(&self, out: &mut [u8]) {
+                out.copy_from_slice(&self.to_le_bytes());
+            }
+
+            fn read(bytes: &[u8]) -> $t {
+                let mut buf = [0; mem::size_of::<$t>()];
+                buf.copy_from_slice(bytes);
+                <$t>::from_le_bytes(buf)
+            }
+        }
+    )*}
+}
+
+int_record!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Record for () {
+    const SIZE: usize = 0;
+
+    fn write(&self, _: &mut [u8]) {}
+
+    fn read(_: &[u8]) {}
+}
+
+impl<A: Record, B: Record> Record for (A, B) {
+    const SIZE: usize = A::SIZE + B::SIZE;
+
+    fn write(&self, out: &mut [u8]) {
+        self.0.write(&mut out[..A::SIZE]);
+        self.1.write(&mut out[A::SIZE..]);
+    }
+
+    fn read(bytes: &[u8]) -> (A, B) {
+        (A::read(&bytes[..A::SIZE]), B::read(&bytes[A::SIZE..]))
+    }
+}
+
+// Numbers the heaps in this process, so that heaps sharing a directory
+// don't share run files.
+static NEXT_HEAP: AtomicUsize = AtomicUsize::new(0);
+
+enum Source {
+    File(BufReader<File>),
+    #[cfg(feature = "mmap")]
+    Mapped(Mmap, usize),
+}
+
+// A sorted run spilled to disk, read from the front.
+struct Run {
+    path: PathBuf,
+    source: Source,
+    remaining: u64,
+}
+
+impl Run {
+    fn next<K: Record, V: Record>(&mut self, buf: &mut [u8]) -> io::Result<Option<(K, V)>> {
+        if self.remaining == 0 {
+            return Ok(None)
+        }
+        let bytes = match self.source {
+            Source::File(ref mut reader) => {
+                reader.read_exact(buf)?;
+                &buf[..]
+            }
+            #[cfg(feature = "mmap")]
+            Source::Mapped(ref map, ref mut offset) => {
+                *offset += buf.len();
+                &map[*offset - buf.len()..*offset]
+            }
+        };
+        self.remaining -= 1;
+        Ok(Some((K::read(&bytes[..K::SIZE]), V::read(&bytes[K::SIZE..]))))
+    }
+}
+
+/// A priority queue for more entries than fit in memory.
+///
+/// Inserts go to an in-memory buffer of at most `memory` entries. When it
+/// fills up, its entries are written out in order as a run file in the
+/// heap's directory. Deleting the minimum merges the buffer with the
+/// smallest unread entry of each run, reading runs in blocks of `block`
+/// entries, so memory use is about `memory + runs * block` entries.
+///
+/// Run files are deleted once they've been read, and when the heap is
+/// dropped. The `Heap` methods panic on I/O errors; `push` and `pop` return
+/// them. Keys can't be decreased.
+pub struct ExternalHeap<K, V> {
+    dir: PathBuf,
+    id: usize,
+    memory: usize,
+    block: usize,
+    mapped: bool,
+    buffer: FibHeap<Ordered<K>, V>,
+    // The next entry of each run that has one, with the run's index.
+    heads: FibHeap<Ordered<K>, (V, usize)>,
+    runs: Vec<Option<Run>>,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Record, V: Eq + PartialOrd + Debug + Clone + Record> ExternalHeap<K, V> {
+    /// A heap that keeps at most `memory` entries in its buffer and writes
+    /// runs to `dir`, which must exist.
+    pub fn new(dir: &Path, memory: usize) -> ExternalHeap<K, V> {
+        assert!(memory > 0, "ExternalHeap needs room for an entry");
+        ExternalHeap {
+            dir: dir.to_path_buf(),
+            id: NEXT_HEAP.fetch_add(1, Ordering::Relaxed),
+            memory: memory,
+            block: 4096,
+            mapped: false,
+            buffer: FibHeap::new(),
+            heads: FibHeap::new(),
+            runs: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Sets how many entries are read from a run at a time. Applies to runs
+    /// written from now on.
+    pub fn block_size(mut self, block: usize) -> ExternalHeap<K, V> {
+        self.block = block.max(1);
+        self
+    }
+
+    /// Reads runs written from now on through memory maps instead of
+    /// buffered reads, leaving the caching to the operating system.
+    #[cfg(feature = "mmap")]
+    pub fn memory_mapped(mut self) -> ExternalHeap<K, V> {
+        self.mapped = true;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of runs on disk that still have entries.
+    pub fn runs(&self) -> usize {
+        self.runs.iter().filter(|r| r.is_some()).count()
+    }
+
+    pub fn push(&mut self, key: K, value: V) -> io::Result<()> {
+        if self.buffer.len() == self.memory {
+            self.spill()?;
+        }
+        self.buffer.insert(Ordered(key), value);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> io::Result<Option<(K, V)>> {
+        let from_buffer = match (self.buffer.empty(), self.heads.empty()) {
+            (true, true) => return Ok(None),
+            (false, false) => self.buffer.find_min().0 <= self.heads.find_min().0,
+            (buffer_empty, _) => !buffer_empty,
+        };
+        if from_buffer {
+            self.len -= 1;
+            let (Ordered(k), v) = self.buffer.delete_min();
+            return Ok(Some((k, v)))
+        }
+        let (Ordered(k), (v, run)) = self.heads.delete_min();
+        // On a read error the entry goes back, so a retry can return it.
+        if let Err(e) = self.advance(run) {
+            self.heads.insert(Ordered(k), (v, run));
+            return Err(e)
+        }
+        self.len -= 1;
+        Ok(Some((k, v)))
+    }
+
+    // Writes the buffer out as a new run.
+    fn spill(&mut self) -> io::Result<()> {
+        let path = self.dir.join(format!("heap-{}-{}-run-{}.bin", process::id(), self.id, self.runs.len()));
+        let count = self.buffer.len() as u64;
+        {
+            let mut writer = BufWriter::new(File::create(&path)?);
+            let mut buf = vec!(0; K::SIZE + V::SIZE);
+            while !self.buffer.empty() {
+                let (Ordered(k), v) = self.buffer.delete_min();
+                k.write(&mut buf[..K::SIZE]);
+                v.write(&mut buf[K::SIZE..]);
+                writer.write_all(&buf)?;
+            }
+            writer.flush()?;
+        }
+        let file = File::open(&path)?;
+        let source = if self.mapped { self.map(file)? } else {
+            Source::File(BufReader::with_capacity(self.block * (K::SIZE + V::SIZE), file))
+        };
+        self.runs.push(Some(Run { path: path, source: source, remaining: count }));
+        let run = self.runs.len() - 1;
+        self.advance(run)
+    }
+
+    #[cfg(feature = "mmap")]
+    fn map(&self, file: File) -> io::Result<Source> {
+        // The file is private to this heap, which never writes to it again.
+        Ok(Source::Mapped(unsafe { Mmap::map(&file)? }, 0))
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn map(&self, _: File) -> io::Result<Source> {
+        unreachable!()
+    }
+
+    // Moves the next entry of a run into `heads`, or deletes the run if it
+    // has been read.
+    fn advance(&mut self, run: usize) -> io::Result<()> {
+        let mut buf = vec!(0; K::SIZE + V::SIZE);
+        let next = self.runs[run].as_mut().unwrap().next::<K, V>(&mut buf)?;
+        match next {
+            Some((k, v)) => { self.heads.insert(Ordered(k), (v, run)); }
+            None => fs::remove_file(self.runs[run].take().unwrap().path)?,
+        }
+        Ok(())
+    }
+}
+
+impl<K, V> Drop for ExternalHeap<K, V> {
+    fn drop(&mut self) {
+        for run in self.runs.drain(..).flatten() {
+            let _ = fs::remove_file(&run.path);
+        }
+    }
+}
+
+impl<K: Ord + Clone + Record, V: Eq + PartialOrd + Debug + Clone + Record> Heap<K, V> for ExternalHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        let buffered = if self.buffer.empty() { None } else { Some(self.buffer.find_min()) };
+        let spilled = if self.heads.empty() { None } else { Some(self.heads.find_min()) };
+        match (buffered, spilled) {
+            (Some((Ordered(k), v)), None) => (k, v),
+            (None, Some((Ordered(k), (v, _)))) => (k, v),
+            (Some((Ordered(k), v)), Some((Ordered(sk), (sv, _)))) => if k <= sk { (k, v) } else { (sk, sv) },
+            (None, None) => panic!("ExternalHeap is empty"),
+        }
+    }
+
+    // Panics on I/O errors.
+    fn delete_min(&mut self) -> (K, V) {
+        match self.pop() {
+            Ok(Some(entry)) => entry,
+            Ok(None) => panic!("ExternalHeap is empty"),
+            Err(err) => panic!("ExternalHeap couldn't read a run: {}", err),
+        }
+    }
+
+    // Panics on I/O errors.
+    fn insert(&mut self, key: K, value: V) {
+        if let Err(err) = self.push(key, value) {
+            panic!("ExternalHeap couldn't write a run: {}", err);
+        }
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, OpenOptions};
+    use std::path::PathBuf;
+    use external_heap::ExternalHeap;
+    use Heap;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    fn keys() -> Vec<u64> {
+        let mut x = 12345u64;
+        (0..5000).map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            x >> 33
+        }).collect()
+    }
+
+    #[test]
+    fn external_heap_spills_and_merges() {
+        let dir = dir("rust_heaps_external_test");
+        {
+            let mut heap = ExternalHeap::new(&dir, 300).block_size(16);
+            let keys = keys();
+            for (i, &k) in keys.iter().enumerate() {
+                heap.insert(k, i as u32);
+            }
+            assert_eq!(heap.runs(), 16);
+            assert_eq!(fs::read_dir(&dir).unwrap().count(), 16);
+            let mut sorted = keys.clone();
+            sorted.sort();
+            // Inserting while popping mixes the buffer with the runs.
+            for &key in &sorted[..1000] {
+                assert_eq!(heap.delete_min().0, key);
+                heap.insert(key, 0);
+                assert_eq!(heap.delete_min().0, key);
+            }
+            assert_eq!(heap.len(), 4000);
+            let mut out = Vec::new();
+            while !heap.empty() {
+                let (k, v) = heap.delete_min();
+                assert_eq!(keys[v as usize], k);
+                out.push(k);
+            }
+            assert_eq!(&out[..], &sorted[1000..]);
+            assert_eq!(heap.runs(), 0);
+            heap.insert(3, 0);
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn external_heap_read_error_keeps_entry() {
+        let dir = dir("rust_heaps_external_error_test");
+        let mut heap: ExternalHeap<u64, u32> = ExternalHeap::new(&dir, 2).block_size(1);
+        heap.push(1, 10).unwrap();
+        heap.push(2, 20).unwrap();
+        heap.push(5, 50).unwrap();
+        // Cut the run short after the entry already read, so reading the
+        // next one fails.
+        let run = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        OpenOptions::new().write(true).open(&run).unwrap().set_len(12).unwrap();
+        assert!(heap.pop().is_err());
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap.find_min(), (1, 10));
+        assert!(heap.pop().is_err());
+        assert_eq!(heap.len(), 3);
+        drop(heap);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn external_heap_memory_mapped() {
+        let dir = dir("rust_heaps_external_mmap_test");
+        let mut heap = ExternalHeap::new(&dir, 100).memory_mapped();
+        let keys = keys();
+        for &k in keys.iter() {
+            heap.push(k, (k as u8, ())).unwrap();
+        }
+        let mut last = 0;
+        while let Some((k, (v, ()))) = heap.pop().unwrap() {
+            assert!(k >= last);
+            assert_eq!(v, k as u8);
+            last = k;
+        }
+        drop(heap);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
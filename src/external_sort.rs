@@ -0,0 +1,77 @@
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use Heap;
+
+/// Splits `input` into sorted runs with replacement selection, holding at
+/// most `memory` items at once.
+///
+/// The items come out tagged with the index of their run, run by run, so
+/// each run can be streamed to its own file and merged later (e.g. with
+/// `algorithms::kmerge`). On random input runs average about twice
+/// `memory` items; already sorted input comes out as a single run.
+pub fn run_generator<I>(input: I, memory: usize) -> RunGenerator<I>
+where I: Iterator, I::Item: Ord + Clone {
+    assert!(memory > 0, "Replacement selection needs room for an item");
+    let mut runs = RunGenerator { input: input, heap: FibHeap::new() };
+    for _ in 0..memory {
+        match runs.input.next() {
+            Some(item) => { runs.heap.insert(Ordered((0, item)), ()); }
+            None => break
+        }
+    }
+    runs
+}
+
+pub struct RunGenerator<I: Iterator> {
+    input: I,
+    // Items are keyed by the run they belong to first, so items too small
+    // for the current run wait behind all of its items.
+    heap: FibHeap<Ordered<(usize, I::Item)>, ()>,
+}
+
+impl<I> Iterator for RunGenerator<I> where I: Iterator, I::Item: Ord + Clone {
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<(usize, I::Item)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (Ordered((run, item)), _) = self.heap.delete_min();
+        if let Some(next) = self.input.next() {
+            let next_run = if next < item { run + 1 } else { run };
+            self.heap.insert(Ordered((next_run, next)), ());
+        }
+        Some((run, item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use external_sort::run_generator;
+
+    #[test]
+    fn external_sort_runs_are_sorted() {
+        let input = vec!(5, 1, 9, 3, 7, 2, 8, 0, 6, 4, 3);
+        let out: Vec<(usize, u32)> = run_generator(input.into_iter(), 3).collect();
+        assert_eq!(out, vec!((0, 1), (0, 3), (0, 5), (0, 7), (0, 8), (0, 9),
+                             (1, 0), (1, 2), (1, 3), (1, 4), (1, 6)));
+    }
+
+    #[test]
+    fn external_sort_run_lengths() {
+        let sorted: Vec<_> = run_generator(0..100, 4).collect();
+        assert!(sorted.iter().all(|&(run, _)| run == 0));
+
+        // A linear congruential sequence stands in for random input.
+        let mut x = 12345u64;
+        let input: Vec<u64> = (0..10000).map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            x >> 33
+        }).collect();
+        let out: Vec<_> = run_generator(input.into_iter(), 100).collect();
+        assert_eq!(out.len(), 10000);
+        assert!(out.windows(2).all(|w| w[0] <= w[1]));
+        let runs = out.last().unwrap().0 + 1;
+        assert!((40..=60).contains(&runs), "{} runs", runs);
+    }
+}
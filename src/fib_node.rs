@@ -1,33 +1,51 @@
-use std::fmt::{Debug};
+use std::fmt::{self, Debug};
 use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 use std::rc::{Rc, Weak};
 use std::cell::UnsafeCell;
 use std::collections::VecDeque;
-use std::collections::vec_deque::Drain;
+use std::collections::vec_deque::{Drain, Iter};
 
 pub struct FibNode<K, V> {
     inner: UnsafeCell<Inner<K, V>>,
 }
 
-impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> Ord for FibNode<K, V> {
-    fn cmp(&self, other: &FibNode<K, V>) -> Ordering {
-        unsafe { (*(self.inner.get())).cmp(&*other.inner.get()) }
+// Nodes are only equal to themselves, so handles can key a `HashMap` even
+// when entries share a key. They have no ordering; heaps compare their keys.
+impl<K, V> PartialEq for FibNode<K, V> {
+    fn eq(&self, other: &FibNode<K, V>) -> bool {
+        std::ptr::eq(self, other)
     }
 }
 
-impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> PartialOrd for FibNode<K, V> {
-    fn partial_cmp(&self, other: &FibNode<K, V>) -> Option<Ordering> {
-        unsafe { (*(self.inner.get())).partial_cmp(&*other.inner.get()) }
+impl<K, V> Eq for FibNode<K, V> {}
+
+impl<K, V> Hash for FibNode<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self as *const FibNode<K, V>).hash(state)
     }
 }
 
-impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> PartialEq for FibNode<K, V> {
-    fn eq(&self, other: &FibNode<K, V>) -> bool {
-        unsafe { (*(self.inner.get())).eq(&*other.inner.get()) }
+// Shows what a handle refers to: the current key and value, and whether the
+// entry is still in the heap.
+impl<K: Debug, V: Debug> Debug for FibNode<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = unsafe { &*self.inner.get() };
+        f.debug_struct("FibNode")
+            .field("key", &inner.key)
+            .field("value", &inner.value)
+            .field("live", &!inner.removed)
+            .finish()
     }
 }
 
-impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> Eq for FibNode<K, V> {}
+#[cfg(feature = "defmt")]
+impl<K: defmt::Format, V: defmt::Format> defmt::Format for FibNode<K, V> {
+    fn format(&self, f: defmt::Formatter) {
+        let inner = unsafe { &*self.inner.get() };
+        defmt::write!(f, "FibNode {{ key: {}, value: {}, live: {=bool} }}", inner.key, inner.value, !inner.removed)
+    }
+}
 
 #[derive(Clone)]
 pub struct Inner<K,V> {
@@ -35,6 +53,8 @@ pub struct Inner<K,V> {
     children: VecDeque<Rc<FibNode<K, V>>>,
     // Rank is the length of children
     marked: bool,
+    // Set once the entry has been taken out of the heap.
+    removed: bool,
     key: K,
     value: V,
 }
@@ -47,7 +67,7 @@ impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> Ord for Inner<K
 
 impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> PartialOrd for Inner<K, V> {
     fn partial_cmp(&self, other: &Inner<K, V>) -> Option<Ordering> {
-        self.key.partial_cmp(&other.key)
+        Some(self.cmp(other))
     }
 }
 
@@ -65,6 +85,20 @@ impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> FibNode<K,V> {
         Rc::new(FibNode { inner: inner })
     }
 
+    /// Like `new`, but hands back the key and value if the node can't be
+    /// allocated.
+    #[cfg(all(feature = "fallible-alloc", nightly))]
+    pub fn try_new(key: K, value: V) -> Result<Rc<FibNode<K,V>>, (K, V)> {
+        match Rc::<FibNode<K,V>>::try_new_uninit() {
+            Ok(mut node) => {
+                let inner = UnsafeCell::new(Inner::new(key, value));
+                Rc::get_mut(&mut node).unwrap().write(FibNode { inner: inner });
+                Ok(unsafe { node.assume_init() })
+            }
+            Err(_) => Err((key, value))
+        }
+    }
+
     pub fn rank(&self) -> usize {
         unsafe { (*self.inner.get()).rank() }
     }
@@ -98,18 +132,46 @@ impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> FibNode<K,V> {
         unsafe { (*self.inner.get()).get_parent() }
     }
 
-    pub fn drain_children(&self) -> Drain<Rc<FibNode<K,V>>> {
+    pub fn drain_children(&self) -> Drain<'_, Rc<FibNode<K,V>>> {
         unsafe { (*self.inner.get()).drain_children() }
     }
 
+    pub fn children(&self) -> Iter<'_, Rc<FibNode<K,V>>> {
+        unsafe { (*self.inner.get()).children() }
+    }
+
+    pub fn children_capacity(&self) -> usize {
+        unsafe { (*self.inner.get()).children_capacity() }
+    }
+
+    // Copies this node and its whole subtree, so the copy shares no nodes
+    // with the original.
+    pub fn deep_clone(&self) -> Rc<FibNode<K,V>> {
+        let node = FibNode::new(self.get_key().clone(), self.get_value().clone());
+        node.set_marked(self.get_marked());
+        for c in self.children() {
+            let child = c.deep_clone();
+            child.set_parent(Some(Rc::downgrade(&node)));
+            node.add_child(child);
+        }
+        node
+    }
+
     // Do this better, don't clone the thing.
+    #[allow(clippy::wrong_self_convention)]
     pub fn into_inner(&self) -> (K, V) {
         unsafe {
             let n = (*self.inner.get()).clone();
+            (*self.inner.get()).removed = true;
             n.into_inner()
         }
     }
 
+    // Whether the node's entry has been taken out of its heap.
+    pub fn is_removed(&self) -> bool {
+        unsafe { (*self.inner.get()).removed }
+    }
+
     pub fn get_value(&self) -> &V {
         unsafe { (*self.inner.get()).get_value() }
     }
@@ -125,6 +187,7 @@ impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> Inner<K,V> {
             parent: None,
             children: VecDeque::new(),
             marked: false,
+            removed: false,
             key: key,
             value: value,
         }
@@ -141,14 +204,15 @@ impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> Inner<K,V> {
     // XXX: Better way to do this?
     pub fn remove_child(&mut self, child: Rc<FibNode<K,V>>)
         -> Result<Rc<FibNode<K,V>>, String> {
-            for _ in (0..self.children.len()) {
-                if *self.children.front().unwrap() == child {
+            for _ in 0..self.children.len() {
+                // Siblings can have equal keys, so match the node itself.
+                if Rc::ptr_eq(self.children.front().unwrap(), &child) {
                     return Ok(self.children.pop_front().unwrap())
                 }
                 let front = self.children.pop_front().unwrap();
                 self.children.push_back(front);
             }
-            Err(String::from_str("Could not find child {:?} in children"))
+            Err(String::from("Could not find child {:?} in children"))
         }
 
     pub fn set_marked(&mut self, mark: bool) {
@@ -171,8 +235,16 @@ impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> Inner<K,V> {
         self.parent.clone()
     }
 
-    pub fn drain_children(&mut self) -> Drain<Rc<FibNode<K,V>>> {
-        self.children.drain()
+    pub fn drain_children(&mut self) -> Drain<'_, Rc<FibNode<K,V>>> {
+        self.children.drain(..)
+    }
+
+    pub fn children(&self) -> Iter<'_, Rc<FibNode<K,V>>> {
+        self.children.iter()
+    }
+
+    pub fn children_capacity(&self) -> usize {
+        self.children.capacity()
     }
 
     pub fn into_inner(self) -> (K, V) {
@@ -192,6 +264,7 @@ impl<K: Clone + Ord + Debug, V: Eq + Clone + PartialOrd + Debug> Inner<K,V> {
 
 #[cfg(test)]
 mod test {
+    use std::rc::Rc;
     use fib_node::{FibNode};
 
     #[test]
@@ -202,9 +275,9 @@ mod test {
         assert_eq!(node.get_key(), &0u8);
         assert_eq!(node.get_value(), &0u8);
         assert_eq!(node.get_value(), &0u8);
-        assert_eq!(node.get_marked(), false);
+        assert!(!node.get_marked());
         node.set_marked(true);
-        assert_eq!(node.get_marked(), true);
+        assert!(node.get_marked());
         assert_eq!(node.rank(), 0);
         node.add_child(child);
         assert_eq!(node.rank(), 1);
@@ -215,7 +288,7 @@ mod test {
         let node = FibNode::new(1u8, 1u8);
         let root = node.clone();
         let child = FibNode::new(2u8, 2u8);
-        child.set_parent(Some(root.clone().downgrade()));
+        child.set_parent(Some(Rc::downgrade(&root)));
 
         node.set_key(10u8);
         node.set_marked(true);
@@ -224,7 +297,7 @@ mod test {
 
         assert!(root == parent);
         assert_eq!(root.get_key(), &10u8);
-        assert_eq!(parent.get_marked(), true);
+        assert!(parent.get_marked());
         assert_eq!(child.get_key(), &2u8);
     }
 
@@ -254,4 +327,39 @@ mod test {
         let res = node.remove_child(child5);
         assert!(res.is_err());
     }
+
+    #[test]
+    fn remove_child_equal_keys() {
+        let node = FibNode::new(0u8, 0u8);
+        let first = FibNode::new(1u8, 1u8);
+        let second = FibNode::new(1u8, 2u8);
+        node.add_child(first.clone());
+        node.add_child(second.clone());
+
+        let removed = node.remove_child(second).unwrap();
+        assert_eq!(removed.get_value(), &2u8);
+        assert_eq!(node.rank(), 1);
+        assert!(Rc::ptr_eq(node.children().next().unwrap(), &first));
+    }
+
+    #[test]
+    fn deep_clone_test() {
+        let node = FibNode::new(0u8, 0u8);
+        let child = FibNode::new(1u8, 1u8);
+        child.set_parent(Some(Rc::downgrade(&node)));
+        child.set_marked(true);
+        node.add_child(child);
+
+        let copy = node.deep_clone();
+        assert!(copy.get_parent().is_none());
+        assert_eq!(copy.rank(), 1);
+        node.set_key(5u8);
+        assert_eq!(copy.get_key(), &0u8);
+
+        let copied_child = copy.children().next().unwrap().clone();
+        assert_eq!(copied_child.get_key(), &1u8);
+        assert!(copied_child.get_marked());
+        let parent = copied_child.get_parent().unwrap().upgrade().unwrap();
+        assert_eq!(parent.get_key(), &0u8);
+    }
 }
@@ -1,16 +1,106 @@
 use std::ops::Sub;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::collections::LinkedList;
+use std::mem;
 use std::rc::{Rc, Weak};
 use std::hash::Hash;
+#[cfg(feature = "rayon")]
+use rayon::{self, iter::{IntoParallelIterator, ParallelIterator}};
+#[cfg(feature = "metrics")]
+use std::cell::Cell;
 use fib_node::{FibNode};
-use {Heap, HeapExt, HeapDelete};
+#[cfg(feature = "metrics")]
+use metrics::Metrics;
+use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
+
+/// The entry `FibHeap::try_insert` couldn't allocate a node for.
+#[cfg(all(feature = "fallible-alloc", nightly))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllocFailed<K, V>(pub K, pub V);
+
+#[cfg(all(feature = "fallible-alloc", nightly))]
+impl<K, V> fmt::Display for AllocFailed<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "out of memory for a FibHeap node")
+    }
+}
 
-#[derive(Clone)]
 pub struct FibHeap<K,V> {
     // The minimum element is always contained at the top of the first root.
     roots: LinkedList<Rc<FibNode<K, V>>>,
-    total: u32
+    total: u32,
+    tally: Tally,
+}
+
+// Operation counts, which are only kept with the `metrics` feature. Without
+// it every method is a no-op and the struct is empty.
+#[derive(Clone)]
+struct Tally {
+    #[cfg(feature = "metrics")]
+    counts: Cell<Metrics>,
+}
+
+impl Tally {
+    // Spelled out, since `Metrics::default` can't be called from a const fn.
+    const fn new() -> Tally {
+        Tally {
+            #[cfg(feature = "metrics")]
+            counts: Cell::new(Metrics { comparisons: 0, links: 0, cuts: 0, cascading_cuts: 0, consolidations: 0 }),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn bump<F: FnOnce(&mut Metrics)>(&self, f: F) {
+        let mut counts = self.counts.get();
+        f(&mut counts);
+        self.counts.set(counts);
+    }
+
+    fn comparison(&self) {
+        #[cfg(feature = "metrics")]
+        self.bump(|m| m.comparisons += 1);
+    }
+
+    fn link(&self) {
+        #[cfg(feature = "metrics")]
+        self.bump(|m| m.links += 1);
+    }
+
+    fn cut(&self) {
+        #[cfg(feature = "metrics")]
+        self.bump(|m| m.cuts += 1);
+    }
+
+    fn cascading_cut(&self) {
+        #[cfg(feature = "metrics")]
+        self.bump(|m| m.cascading_cuts += 1);
+    }
+
+    fn consolidation(&self) {
+        #[cfg(feature = "metrics")]
+        self.bump(|m| m.consolidations += 1);
+    }
+
+    // Adds the counts of another tally, e.g. one kept on a worker thread.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn absorb(&self, other: &Tally) {
+        #[cfg(feature = "metrics")]
+        self.bump(|m| *m = *m + other.counts.get());
+    }
+}
+
+// Cloning copies every node, so the clone can be mutated without affecting
+// the original. Entries handed out by the original heap do not refer to the
+// clone.
+impl<K: Ord + Debug + Clone, V: Eq + PartialOrd + Debug + Clone> Clone for FibHeap<K, V> {
+    fn clone(&self) -> FibHeap<K, V> {
+        let mut roots = LinkedList::new();
+        for r in self.roots.iter() {
+            roots.push_back(r.deep_clone());
+        }
+        FibHeap { roots: roots, total: self.total, tally: self.tally.clone() }
+    }
 }
 
 impl<K: Ord + Debug + Clone + Sub<K, Output=K>,
@@ -46,41 +136,39 @@ for FibHeap<K, V> {
                 // Linking Step
                 self.consolidate();
 
-                self.total = self.total - 1;
+                self.total -= 1;
                 min_entry.into_inner()
             }
         }
     }
 
-    fn decrease_key(&mut self, node: &Rc<FibNode<K, V>>, delta: K) {
+    fn empty(&self) -> bool {
+        self.total == 0
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>,
+V: Eq + PartialOrd + Debug + Clone> DecreaseKey<K, V>
+for FibHeap<K, V> {
+    fn decrease_key(&mut self, node: &Rc<FibNode<K, V>>, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(node) {
+            return Err(StaleEntry)
+        }
         // TODO: Figure out how to do this better.
         let new_node = node.clone();
         let key = new_node.get_key().clone();
         let new_key: K = key - delta;
         new_node.set_key(new_key);
         self.decreased_node(new_node);
-    }
-
-    fn empty(&self) -> bool {
-        self.total == 0
+        Ok(())
     }
 }
 
 impl<K: Ord + Debug + Clone + Sub<K, Output=K>,
 V: Eq + PartialOrd + Debug + Hash + Clone> HeapExt for FibHeap<K, V> {
-    fn merge(mut self, mut other: FibHeap<K,V>) -> FibHeap<K, V> {
-        let (smin, _) = self.find_min();
-        let (omin, _) = other.find_min();
-
-        if smin < omin {
-            self.roots.append(&mut other.roots);
-            self.total += other.total;
-            self
-        } else {
-            other.roots.append(&mut self.roots);
-            other.total += self.total;
-            other
-        }
+    fn merge(mut self, other: FibHeap<K,V>) -> FibHeap<K, V> {
+        self.meld(other);
+        self
     }
 }
 
@@ -89,28 +177,275 @@ V: Eq + PartialOrd + Debug + Hash + Clone> HeapDelete<K, V>
 for FibHeap<K, V> {
     type HeapEntry = Rc<FibNode<K, V>>;
 
-    // This will essentially zero out the given value's key.
-    // It is undefined behaviour if there is another zero value in the Heap.
-    // TODO: Fix this and do it better
-    fn delete(&mut self, node: Rc<FibNode<K, V>>) -> (K, V) {
-        {
-            let key = node.get_key().clone();
-            self.decrease_key(&node, key);
+    // The node is detached from its parent, or from the root list, and its
+    // children become roots before the roots are consolidated.
+    fn delete(&mut self, node: Rc<FibNode<K, V>>) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&node) {
+            return Err(StaleEntry)
+        }
+        match node.get_parent() {
+            Some(parent) => {
+                self.cut(parent.clone(), node.clone());
+                self.cascading_cut(parent);
+            }
+            None => self.remove_root(&node)
+        }
+        for c in node.drain_children() {
+            c.set_parent(None);
+            self.insert_root(c);
         }
-        self.delete_min()
+        self.consolidate();
+
+        self.total -= 1;
+        Ok(node.into_inner())
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>,
+V: Eq + PartialOrd + Debug + Clone> AddressableHeap<K, V>
+for FibHeap<K, V> {
+    fn is_valid(&self, entry: &Rc<FibNode<K, V>>) -> bool {
+        !entry.is_removed()
     }
 }
 
 impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> FibHeap<K, V> {
-    pub fn new() -> FibHeap<K,V> {
-        FibHeap { roots: LinkedList::new(), total: 0 }
+    /// An empty heap. This is a `const fn`, so a heap can be the initial
+    /// value of a `thread_local!` or of a static cell.
+    pub const fn new() -> FibHeap<K,V> {
+        FibHeap { roots: LinkedList::new(), total: 0, tally: Tally::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.total as usize
+    }
+
+    /// Lowers the key of `node` to `key` itself rather than by a delta, for
+    /// keys such as floats whose subtraction rounds or can give NaN. Panics
+    /// if `key` is above the node's current key.
+    pub fn decrease_key_to(&mut self, node: &Rc<FibNode<K, V>>, key: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(node) {
+            return Err(StaleEntry)
+        }
+        if key > *node.get_key() {
+            panic!("new key is above the current key");
+        }
+        node.set_key(key);
+        self.decreased_node(node.clone());
+        Ok(())
+    }
+
+    /// Like `insert`, but returns the entry instead of aborting if its node
+    /// can't be allocated. Only the node's allocation is fallible: linking it
+    /// into the root list still allocates infallibly, as do the rank buffer
+    /// and child lists of a later consolidation. Those are small next to the
+    /// node, but a heap that must never abort needs room for them too.
+    #[cfg(all(feature = "fallible-alloc", nightly))]
+    pub fn try_insert(&mut self, k: K, v: V) -> Result<Rc<FibNode<K, V>>, AllocFailed<K, V>> {
+        let node = FibNode::try_new(k, v).map_err(|(k, v)| AllocFailed(k, v))?;
+        let ret = node.clone();
+        self.total += 1;
+        self.insert_root(node);
+        Ok(ret)
+    }
+
+    /// Inserts entries until one can't be allocated, which is returned. The
+    /// entries before it stay in the heap; pass `&mut iter` to resume with
+    /// the ones after it.
+    #[cfg(all(feature = "fallible-alloc", nightly))]
+    pub fn try_extend<I: IntoIterator<Item=(K, V)>>(&mut self, entries: I) -> Result<(), AllocFailed<K, V>> {
+        for (k, v) in entries {
+            self.try_insert(k, v)?;
+        }
+        Ok(())
+    }
+
+    /// Counts of the work this heap has done since it was created or the
+    /// counts were last reset.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Metrics {
+        self.tally.counts.get()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&mut self) {
+        self.tally = Tally::new();
+    }
+
+    /// Iterates over copies of every (key, value) pair in the heap, in no
+    /// particular order.
+    pub fn iter(&self) -> Iter<K, V> {
+        Iter { stack: self.roots.iter().cloned().collect() }
+    }
+
+    /// An estimate of the bytes this heap uses: the heap itself, its root
+    /// list and, for every node, the node with its reference counts and its
+    /// child list's buffer. Memory that keys and values own elsewhere is not
+    /// included; see `memory_footprint_with`.
+    pub fn memory_footprint(&self) -> usize {
+        self.memory_footprint_with(|_, _| 0)
+    }
+
+    /// Like `memory_footprint`, adding `owned(key, value)` for every entry,
+    /// e.g. the capacity of a `String` value.
+    pub fn memory_footprint_with<F: Fn(&K, &V) -> usize>(&self, owned: F) -> usize {
+        let rc = mem::size_of::<Rc<FibNode<K, V>>>();
+        // A reference counted allocation holds a strong and a weak count.
+        let node = 2 * mem::size_of::<usize>() + mem::size_of::<FibNode<K, V>>();
+        // Linked list nodes hold next and previous pointers.
+        let mut bytes = mem::size_of::<FibHeap<K, V>>()
+            + self.roots.len() * (rc + 2 * mem::size_of::<usize>());
+        let mut stack: Vec<&Rc<FibNode<K, V>>> = self.roots.iter().collect();
+        while let Some(n) = stack.pop() {
+            bytes += node + n.children_capacity() * rc + owned(n.get_key(), n.get_value());
+            stack.extend(n.children());
+        }
+        bytes
+    }
+
+    /// Checks the heap's structure: the first root holds the minimum, every
+    /// child's key is at least its parent's, parent links match the trees,
+    /// and the size matches the number of nodes. Describes the first problem
+    /// found.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let mut count = 0;
+        if let Some(min) = self.roots.front() {
+            if let Some(r) = self.roots.iter().find(|r| r.get_key() < min.get_key()) {
+                return Err(format!("root {:?} is less than the first root {:?}", r.get_key(), min.get_key()))
+            }
+        }
+        for root in self.roots.iter() {
+            if root.get_parent().is_some() {
+                return Err(format!("root {:?} has a parent", root.get_key()))
+            }
+            let mut stack = vec!(root.clone());
+            while let Some(node) = stack.pop() {
+                count += 1;
+                for child in node.children() {
+                    if child.get_key() < node.get_key() {
+                        return Err(format!("child {:?} is less than its parent {:?}",
+                                           child.get_key(), node.get_key()))
+                    }
+                    match child.get_parent().and_then(|p| p.upgrade()) {
+                        Some(ref p) if Rc::ptr_eq(p, &node) => {}
+                        _ => return Err(format!("child {:?} of {:?} doesn't link back to it",
+                                                child.get_key(), node.get_key()))
+                    }
+                    stack.push(child.clone());
+                }
+            }
+        }
+        if count != self.total {
+            return Err(format!("the heap holds {} nodes but counts {}", count, self.total))
+        }
+        Ok(())
+    }
+
+    /// Restructures the heap without changing its contents, for testing
+    /// that nothing depends on incidental structure. Depending on `choice`
+    /// this consolidates the roots, cuts a node from its parent (cascading
+    /// as a decrease would), or rotates the roots after the first.
+    #[cfg(feature = "chaos")]
+    pub fn perturb(&mut self, choice: u64) {
+        if self.roots.is_empty() {
+            return
+        }
+        match choice % 3 {
+            0 => self.consolidate(),
+            1 => {
+                let mut nodes: Vec<Rc<FibNode<K, V>>> = Vec::new();
+                let mut stack: Vec<Rc<FibNode<K, V>>> = self.roots.iter().cloned().collect();
+                while let Some(node) = stack.pop() {
+                    stack.extend(node.children().cloned());
+                    if node.get_parent().is_some() {
+                        nodes.push(node);
+                    }
+                }
+                if !nodes.is_empty() {
+                    let node = nodes.swap_remove((choice / 3) as usize % nodes.len());
+                    let parent = node.get_parent().unwrap();
+                    let root = self.cut(parent.clone(), node);
+                    self.insert_root(root);
+                    self.cascading_cut(parent);
+                }
+            }
+            _ => {
+                let min = self.roots.pop_front().unwrap();
+                let n = self.roots.len();
+                if n > 0 {
+                    let mut rest = self.roots.split_off(0);
+                    let mut tail = rest.split_off((choice / 3) as usize % n);
+                    tail.append(&mut rest);
+                    self.roots = tail;
+                }
+                self.roots.push_front(min);
+            }
+        }
+    }
+
+    /// The keys and marks of the heap's trees, in root list order. The first
+    /// tree holds the minimum.
+    pub fn trees(&self) -> Vec<Tree<K>> {
+        self.roots.iter().map(|r| Tree::of(r)).collect()
+    }
+
+    /// The heap's trees as a Graphviz digraph. Roots share a rank, with the
+    /// minimum drawn with a double border, and marked nodes are shaded. Node
+    /// ids come from node addresses, so a node keeps its id while it lives.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n    node [shape=circle];\n");
+        let mut stack: Vec<Rc<FibNode<K, V>>> = Vec::new();
+        for (i, root) in self.roots.iter().enumerate() {
+            out.push_str(&format!("    {} [label=\"{}\"{}];\n", dot_id(root),
+                                  dot_label(root.get_key()), if i == 0 { ", peripheries=2" } else { "" }));
+            stack.push(root.clone());
+        }
+        if self.roots.len() > 1 {
+            let ids: Vec<String> = self.roots.iter().map(|r| dot_id(r)).collect();
+            out.push_str(&format!("    {{ rank=same; {}; }}\n", ids.join("; ")));
+            out.push_str(&format!("    {} [style=dashed, arrowhead=none];\n", ids.join(" -> ")));
+        }
+        while let Some(node) = stack.pop() {
+            for child in node.children() {
+                out.push_str(&format!("    {} [label=\"{}\"{}];\n", dot_id(child),
+                                      dot_label(child.get_key()),
+                                      if child.get_marked() { ", style=filled, fillcolor=gray" } else { "" }));
+                out.push_str(&format!("    {} -> {};\n", dot_id(&node), dot_id(child)));
+                stack.push(child.clone());
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    // Moves every root of `other` into this heap, keeping the smaller
+    // minimum at the front.
+    fn meld(&mut self, mut other: FibHeap<K, V>) {
+        self.tally.absorb(&other.tally);
+        if other.empty() {
+            return
+        }
+        if !self.empty() {
+            self.tally.comparison();
+        }
+        #[cfg(feature = "tracing")]
+        trace!(total = self.total, other_total = other.total,
+               roots = self.roots.len(), other_roots = other.roots.len(), "meld");
+        if !self.empty() && self.roots.front().unwrap().get_key() < other.roots.front().unwrap().get_key() {
+            self.roots.append(&mut other.roots);
+        } else {
+            other.roots.append(&mut self.roots);
+            self.roots = other.roots;
+        }
+        self.total += other.total;
     }
 
     fn decreased_node(&mut self, node: Rc<FibNode<K, V>>) {
         match node.get_parent() {
             Some(parent) => {
                 let p = parent.clone().upgrade().expect("Parent has already been destroyed");
-                if node < p {
+                self.tally.comparison();
+                if node.get_key() < p.get_key() {
                     let root = self.cut(parent.clone(), node);
                     self.insert_root(root);
                     self.cascading_cut(parent);
@@ -118,19 +453,30 @@ impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clo
             }
             None => {
                 self.sort_roots();
-                return
             }
         }
     }
 
     fn insert_root(&mut self, root: Rc<FibNode<K, V>>) {
-        if self.roots.len() == 0 || *self.roots.front().unwrap() < root {
+        if !self.roots.is_empty() {
+            self.tally.comparison();
+        }
+        if self.roots.is_empty() || self.roots.front().unwrap().get_key() < root.get_key() {
             self.roots.push_back(root);
         } else {
             self.roots.push_front(root);
         }
     }
 
+    fn remove_root(&mut self, root: &Rc<FibNode<K, V>>) {
+        let r = self.roots.split_off(0);
+        for n in r.into_iter() {
+            if !std::ptr::eq(&*n, &**root) {
+                self.roots.push_back(n);
+            }
+        }
+    }
+
     // TODO: This is horrible and inefficient.
     fn sort_roots(&mut self) {
         let r = self.roots.split_off(0);
@@ -141,6 +487,7 @@ impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clo
 
     fn cut(&self, p: Weak<FibNode<K, V>>, child: Rc<FibNode<K, V>>) -> Rc<FibNode<K, V>> {
         let parent = p.upgrade().expect("Parent was already destroyed");
+        self.tally.cut();
         let res = parent.remove_child(child.clone());
         assert!(res.is_ok());
         child.set_parent(None);
@@ -149,56 +496,74 @@ impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clo
     }
 
     fn cascading_cut(&mut self, n: Weak<FibNode<K, V>>) {
+        #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+        let chain = self.cut_marked(n);
+        #[cfg(feature = "tracing")]
+        {
+            if chain > 0 {
+                debug!(chain = chain, roots = self.roots.len(), "cascading cut");
+            }
+        }
+    }
+
+    // Cuts marked ancestors until reaching an unmarked one, which is marked,
+    // or a root. Returns the number of nodes cut.
+    fn cut_marked(&mut self, n: Weak<FibNode<K, V>>) -> usize {
         let node = n.upgrade().expect("Node was already destroyed");
         match node.get_parent() {
             Some(parent) => {
                 if node.get_marked() {
+                    self.tally.cascading_cut();
                     let root = self.cut(parent.clone(), node);
                     self.insert_root(root);
-                    self.cascading_cut(parent);
+                    1 + self.cut_marked(parent)
                 } else {
                     node.set_marked(true);
+                    0
                 }
             }
-            None => {
-                return
-            }
+            None => 0
         }
     }
 
     fn consolidate(&mut self) {
+        self.tally.consolidation();
+        #[cfg(feature = "tracing")]
+        let roots_before = self.roots.len();
         // The maximum rank of a FibHeap is O(log n).
         let log_n = (self.total as f64).log2() as u64 + 1;
         let mut rank_vec = vec!(None);
         rank_vec.resize(log_n as usize, None);
-        loop {
-            match self.roots.pop_front() {
-                Some(node) => {
-                    self.insert_by_rank(&mut rank_vec, node);
-                }
-                None => break
-            }
+        while let Some(node) = self.roots.pop_front() {
+            FibHeap::insert_by_rank(&self.tally, &mut rank_vec, node);
         }
-        for n in rank_vec.into_iter() {
-            if n.is_some() {
-                self.insert_root(n.unwrap());
-            }
+        for node in rank_vec.into_iter().flatten() {
+            self.insert_root(node);
         }
+        #[cfg(feature = "tracing")]
+        debug!(total = self.total, roots_before = roots_before, roots_after = self.roots.len(),
+               "consolidate");
     }
 
-    fn link_and_insert(&self, rank_vec: &mut Vec<Option<Rc<FibNode<K, V>>>>,
+    fn link_and_insert(tally: &Tally, rank_vec: &mut Vec<Option<Rc<FibNode<K, V>>>>,
                        root: Rc<FibNode<K, V>>, child: Rc<FibNode<K, V>>) {
+        tally.link();
         // We are only linking FibHeap roots, so they don't have parents.
-        child.set_parent(Some(root.clone().downgrade()));
+        child.set_parent(Some(Rc::downgrade(&root)));
         child.set_marked(false);
 
         root.add_child(child);
-        self.insert_by_rank(rank_vec, root);
+        FibHeap::insert_by_rank(tally, rank_vec, root);
     }
 
-    fn insert_by_rank(&self, rank_vec: &mut Vec<Option<Rc<FibNode<K, V>>>>,
+    fn insert_by_rank(tally: &Tally, rank_vec: &mut Vec<Option<Rc<FibNode<K, V>>>>,
                       node: Rc<FibNode<K, V>>) {
         let rank = node.rank();
+        // Ranks are below log base phi of the size, which can exceed the
+        // log base 2 that `consolidate` sizes the table for.
+        if rank >= rank_vec.len() {
+            rank_vec.resize(rank + 1, None);
+        }
         if rank_vec[rank].is_none() {
             rank_vec[rank] = Some(node);
             return
@@ -207,20 +572,178 @@ impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clo
         rank_vec.push(None);
         let other = rank_vec.swap_remove(rank).unwrap();
 
-        if node < other {
-            self.link_and_insert(rank_vec, node, other);
+        tally.comparison();
+        if node.get_key() < other.get_key() {
+            FibHeap::link_and_insert(tally, rank_vec, node, other);
         } else {
-            self.link_and_insert(rank_vec, other, node);
+            FibHeap::link_and_insert(tally, rank_vec, other, node);
+        }
+    }
+}
+
+// A sub-heap built on one of rayon's worker threads. Its handles are dropped
+// as soon as they are returned, so all of its nodes are owned by the heap
+// and it can be moved to whichever thread melds it.
+#[cfg(feature = "rayon")]
+struct Part<K, V>(FibHeap<K, V>);
+
+#[cfg(feature = "rayon")]
+unsafe impl<K: Send, V: Send> Send for Part<K, V> {}
+
+// A share of a heap's roots, linked on a worker thread by
+// `par_consolidate`. The trees are disjoint, so no two threads touch the
+// same reference counts, and any handles to their nodes belong to the
+// thread blocked in `par_consolidate` until the workers are done.
+#[cfg(feature = "rayon")]
+struct Roots<K, V>(Vec<Rc<FibNode<K, V>>>);
+
+#[cfg(feature = "rayon")]
+unsafe impl<K: Send, V: Send> Send for Roots<K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<K: Ord + Debug + Clone + Sub<K, Output=K> + Send,
+V: Eq + PartialOrd + Debug + Clone + Send> FibHeap<K, V> {
+    /// Builds a heap from `items`, inserting into separate sub-heaps in
+    /// parallel and melding them. No handles are returned.
+    pub fn par_heapify<I>(items: I) -> FibHeap<K, V>
+    where I: IntoParallelIterator<Item=(K, V)> {
+        items.into_par_iter()
+            .fold(|| Part(FibHeap::new()), |mut part, (k, v)| {
+                part.0.insert(k, v);
+                part
+            })
+            .reduce(|| Part(FibHeap::new()), |mut a, b| {
+                a.0.meld(b.0);
+                a
+            })
+            .0
+    }
+
+    /// Adds `items` to this heap, building them into a heap in parallel
+    /// first.
+    pub fn par_extend<I>(&mut self, items: I)
+    where I: IntoParallelIterator<Item=(K, V)> {
+        self.meld(FibHeap::par_heapify(items));
+    }
+
+    /// Links the roots together until no two share a rank, as `delete_min`
+    /// does, but splits the roots between rayon's threads first and only
+    /// links the per-thread results on this thread. Worth calling after
+    /// bulk inserts or melds leave a very long root list.
+    pub fn par_consolidate(&mut self) {
+        let threads = rayon::current_num_threads();
+        if self.roots.len() < 2 * threads {
+            self.consolidate();
+            return
+        }
+        // No tree can have a rank above 1.44 log2(n).
+        let slots = 2 * (self.total as f64).log2() as usize + 2;
+        let chunk = self.roots.len().div_ceil(threads);
+        let mut parts = Vec::new();
+        while !self.roots.is_empty() {
+            let rest = if self.roots.len() > chunk { self.roots.split_off(chunk) } else { LinkedList::new() };
+            parts.push(Roots(self.roots.split_off(0).into_iter().collect()));
+            self.roots = rest;
+        }
+        let linked: Vec<(Roots<K, V>, Tally)> = parts.into_par_iter().map(|Roots(nodes)| {
+            let tally = Tally::new();
+            let mut rank_vec = vec!(None);
+            rank_vec.resize(slots, None);
+            for node in nodes.into_iter() {
+                FibHeap::insert_by_rank(&tally, &mut rank_vec, node);
+            }
+            (Roots(rank_vec.into_iter().flatten().collect()), tally)
+        }).collect();
+        for (Roots(nodes), tally) in linked.into_iter() {
+            self.tally.absorb(&tally);
+            for node in nodes.into_iter() {
+                self.insert_root(node);
+            }
         }
+        self.consolidate();
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> Debug
+for FibHeap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The shape of one tree of a `FibHeap`, from `FibHeap::trees`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tree<K> {
+    pub key: K,
+    pub marked: bool,
+    pub children: Vec<Tree<K>>,
+}
+
+impl<K> Tree<K> {
+    pub fn rank(&self) -> usize {
+        self.children.len()
+    }
+}
+
+impl<K: Ord + Debug + Clone> Tree<K> {
+    fn of<V: Eq + PartialOrd + Debug + Clone>(node: &Rc<FibNode<K, V>>) -> Tree<K> {
+        Tree {
+            key: node.get_key().clone(),
+            marked: node.get_marked(),
+            children: node.children().map(|c| Tree::of(c)).collect(),
+        }
+    }
+}
+
+fn dot_id<K, V>(node: &Rc<FibNode<K, V>>) -> String {
+    format!("n{:x}", &**node as *const FibNode<K, V> as usize)
+}
+
+fn dot_label<K: Debug>(key: &K) -> String {
+    format!("{:?}", key).replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub struct Iter<K, V> {
+    stack: Vec<Rc<FibNode<K, V>>>,
+}
+
+impl<K: Ord + Debug + Clone, V: Eq + PartialOrd + Debug + Clone> Iterator for Iter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.stack.pop().map(|node| {
+            self.stack.extend(node.children().cloned());
+            (node.get_key().clone(), node.get_value().clone())
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use test::Bencher;
-    use {Heap, HeapExt, HeapDelete};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::mem;
+    use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
     use fibonacci_heap::{FibHeap};
 
+    #[test]
+    fn fib_heap_stale_handles() {
+        let mut heap = FibHeap::new();
+        let a = heap.insert(5u32, 'a');
+        let b = heap.insert(7, 'b');
+        let c = heap.insert(9, 'c');
+        assert!(heap.is_valid(&a));
+        assert_eq!(heap.delete_min(), (5, 'a'));
+        assert!(!heap.is_valid(&a));
+        assert_eq!(heap.decrease_key(&a, 3), Err(StaleEntry));
+        assert_eq!(heap.delete(a), Err(StaleEntry));
+        assert_eq!(heap.decrease_key(&c, 4), Ok(()));
+        assert_eq!(heap.delete(b.clone()), Ok((7, 'b')));
+        assert_eq!(heap.delete(b), Err(StaleEntry));
+        assert_eq!(heap.delete_min(), (5, 'c'));
+        assert!(heap.empty());
+    }
+
     #[test]
     fn fheap_insert() {
         let mut fheap: FibHeap<u8, u8> = FibHeap::new();
@@ -285,11 +808,11 @@ mod tests {
         let five = fheap.insert(5, 5);
         fheap.delete_min();
         assert_eq!(fheap.roots.len(), 2);
-        fheap.decrease_key(&four.clone(), 3);
+        fheap.decrease_key(&four.clone(), 3).unwrap();
         assert_eq!(four.clone().get_key(), &1);
         assert!(four.get_parent().is_none());
         assert_eq!(fheap.roots.len(), 3);
-        fheap.decrease_key(&five, 5);
+        fheap.decrease_key(&five, 5).unwrap();
         assert_eq!(fheap.roots.len(), 3);
         assert_eq!(fheap.find_min(), (0, 5));
     }
@@ -301,7 +824,7 @@ mod tests {
         fheap.insert(0, 0);
         fheap.delete_min();
         assert_eq!(fheap.roots.len(), 1);
-        fheap.decrease_key(&four, 2);
+        fheap.decrease_key(&four, 2).unwrap();
         assert_eq!(four.get_key(), &2);
         assert!(four.get_parent().is_none());
     }
@@ -324,12 +847,71 @@ mod tests {
         fheap.delete_min();
         assert_eq!(fheap.find_min(), (1, 1));
         assert_eq!(fheap.roots.len(), 3);
-        fheap.decrease_key(&six, 4);
+        fheap.decrease_key(&six, 4).unwrap();
         assert_eq!(fheap.roots.len(), 4);
-        fheap.decrease_key(&seven, 7);
+        fheap.decrease_key(&seven, 7).unwrap();
         assert_eq!(fheap.roots.len(), 6);
     }
 
+    #[test]
+    fn test_fheap_convert_into() {
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        fheap.insert(3, 3);
+        fheap.insert(1, 1);
+        fheap.insert(2, 2);
+        let mut other = fheap.convert_into(FibHeap::new());
+        assert_eq!(other.len(), 3);
+        assert_eq!(other.delete_min(), (1, 1));
+        assert_eq!(other.delete_min(), (2, 2));
+        assert_eq!(other.delete_min(), (3, 3));
+    }
+
+    #[test]
+    fn test_fheap_convert_with_handles() {
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        fheap.insert(0, 0);
+        let four = fheap.insert(4, 4);
+        fheap.insert(2, 2);
+        let six = fheap.insert(6, 6);
+        fheap.delete_min();
+        let (mut other, handles) = fheap.convert_with_handles(FibHeap::new(), vec!(six, four));
+        assert_eq!(other.len(), 3);
+        assert_eq!(handles[0].get_key(), &6);
+        other.decrease_key(&handles[0], 5).unwrap();
+        other.decrease_key(&handles[1], 1).unwrap();
+        assert_eq!(other.delete_min(), (1, 6));
+        assert_eq!(other.delete_min(), (2, 2));
+        assert_eq!(other.delete_min(), (3, 4));
+    }
+
+    #[test]
+    fn test_fheap_iter() {
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        fheap.insert(3, 3);
+        fheap.insert(1, 1);
+        fheap.insert(0, 0);
+        fheap.insert(2, 2);
+        fheap.delete_min();
+        let mut entries: Vec<(u8, u8)> = fheap.iter().collect();
+        entries.sort();
+        assert_eq!(entries, vec!((1, 1), (2, 2), (3, 3)));
+    }
+
+    #[test]
+    fn test_fheap_clone() {
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        fheap.insert(3, 3);
+        fheap.insert(1, 1);
+        fheap.insert(0, 0);
+        fheap.delete_min();
+        let mut copy = fheap.clone();
+        assert_eq!(copy.delete_min(), (1, 1));
+        assert_eq!(copy.delete_min(), (3, 3));
+        assert!(copy.empty());
+        assert_eq!(fheap.delete_min(), (1, 1));
+        assert_eq!(fheap.delete_min(), (3, 3));
+    }
+
     #[test]
     fn test_fheap_delete() {
         let mut fheap: FibHeap<u8, u8> = FibHeap::new();
@@ -338,98 +920,285 @@ mod tests {
         fheap.insert(0, 0);
         let five = fheap.insert(5, 5);
         fheap.delete_min();
-        fheap.delete(five);
+        fheap.delete(five).unwrap();
         assert_eq!(fheap.roots.len(), 1);
-        fheap.delete(one);
+        fheap.delete(one).unwrap();
         assert_eq!(fheap.roots.len(), 1);
         assert_eq!(fheap.find_min(), (4, 4))
     }
 
-    #[bench]
-    fn bench_new(b: &mut Bencher) {
-        b.iter(|| {
-            let fheap: FibHeap<u8, u8> = FibHeap::new();
-            assert_eq!(fheap.roots.len(), 0);
-            assert!(fheap.empty());
-        });
+    #[test]
+    fn test_fheap_delete_keeps_keys() {
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        fheap.insert(0, 0);
+        let one = fheap.insert(1, 1);
+        let two = fheap.insert(2, 2);
+        fheap.insert(3, 3);
+        fheap.insert(4, 4);
+        let five = fheap.insert(5, 5);
+        fheap.delete_min();
+        assert!(two.get_parent().is_some());
+        assert_eq!(fheap.delete(two), Ok((2, 2)));
+        assert_eq!(fheap.delete(five), Ok((5, 5)));
+        assert_eq!(fheap.delete(one), Ok((1, 1)));
+        assert_eq!(fheap.len(), 2);
+        assert_eq!(fheap.delete_min(), (3, 3));
+        assert_eq!(fheap.delete_min(), (4, 4));
+        assert!(fheap.empty());
     }
 
-    #[bench]
-    fn bench_insert(b: &mut Bencher) {
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_fheap_metrics() {
         let mut fheap: FibHeap<u8, u8> = FibHeap::new();
-        let mut n = 0;
-        b.iter(|| {
-            fheap.insert(n,n);
-            n += 1;
-        });
+        for &k in [0, 1, 4, 5, 2, 3].iter() {
+            fheap.insert(k, k);
+        }
+        let six = fheap.insert(6, 6);
+        let seven = fheap.insert(7, 7);
+        for &k in [18, 9, 11, 15].iter() {
+            fheap.insert(k, k);
+        }
+        fheap.delete_min();
+        let metrics = fheap.metrics();
+        assert_eq!(metrics.consolidations, 1);
+        // Eleven nodes end up in three trees.
+        assert_eq!(metrics.links, 8);
+        assert!(metrics.comparisons >= metrics.links);
+        fheap.decrease_key(&six, 4).unwrap();
+        fheap.decrease_key(&seven, 7).unwrap();
+        let metrics = fheap.metrics();
+        assert_eq!(metrics.cuts, 3);
+        assert_eq!(metrics.cascading_cuts, 1);
+        fheap.reset_metrics();
+        assert_eq!(fheap.metrics(), Default::default());
     }
 
-    #[bench]
-    fn bench_merge(b: &mut Bencher) {
-        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
-        fheap.insert(1, 1);
-        fheap.insert(4, 4);
-        fheap.insert(0, 0);
-        fheap.insert(5, 5);
-        fheap.insert(2, 2);
-        fheap.insert(6, 6);
-        fheap.insert(3, 3);
-        fheap.insert(11, 11);
-        let mut fheap1: FibHeap<u8, u8> = FibHeap::new();
-        fheap1.insert(7, 7);
-        fheap1.insert(10, 10);
+    #[test]
+    fn test_fheap_memory_footprint() {
+        let mut fheap: FibHeap<u32, String> = FibHeap::new();
+        let empty = fheap.memory_footprint();
+        for i in 0..100u32 {
+            fheap.insert(i, i.to_string());
+        }
+        let full = fheap.memory_footprint();
+        assert!(full > empty + 100 * mem::size_of::<(u32, String)>());
+        fheap.delete_min();
+        assert!(fheap.memory_footprint() < full);
+        let owned = fheap.memory_footprint_with(|_, v| v.capacity());
+        assert_eq!(owned, fheap.memory_footprint() + 9 + 90 * 2);
+    }
 
-        // TODO: How to do this better?
-        b.iter(move || {
-            fheap.clone().merge(fheap1.clone());
+    #[test]
+    #[cfg(all(feature = "fallible-alloc", nightly))]
+    fn test_fheap_try_insert() {
+        let mut fheap = FibHeap::new();
+        let a = fheap.try_insert(5, 'a').unwrap();
+        fheap.try_extend(vec!((3, 'b'), (4, 'c'))).unwrap();
+        fheap.decrease_key(&a, 3).unwrap();
+        assert_eq!(fheap.len(), 3);
+        assert_eq!(fheap.delete_min(), (2, 'a'));
+        assert_eq!(fheap.delete_min(), (3, 'b'));
+    }
+
+    #[test]
+    fn test_fheap_const_new() {
+        thread_local! {
+            static HEAP: RefCell<FibHeap<u32, char>> = const { RefCell::new(FibHeap::new()) };
+        }
+        HEAP.with(|heap| {
+            heap.borrow_mut().insert(2, 'b');
+            heap.borrow_mut().insert(1, 'a');
         });
+        assert_eq!(HEAP.with(|heap| heap.borrow_mut().delete_min()), (1, 'a'));
+    }
+
+    #[test]
+    fn test_fheap_cut_equal_keys() {
+        let mut fheap: FibHeap<u32, u32> = FibHeap::new();
+        let keys = [1, 1, 2, 1, 2, 2, 2, 3, 3, 1, 2, 2, 2, 3, 2, 3];
+        let entries: Vec<_> = (0..16).map(|i| fheap.insert(keys[i], i as u32)).collect();
+        fheap.insert(0, 99);
+        fheap.delete_min();
+        // Cascading cuts detach marked nodes that can have siblings with
+        // the same key.
+        for &i in [10, 8, 11, 1, 5, 13, 14, 6, 3, 0, 9, 2, 4, 7, 12, 15].iter() {
+            fheap.decrease_key(&entries[i], keys[i]).unwrap();
+            fheap.delete_min();
+        }
+        assert!(fheap.empty());
+    }
+
+    #[test]
+    fn test_fheap_rank_above_log2() {
+        let mut fheap: FibHeap<u32, u32> = FibHeap::new();
+        let entries: Vec<_> = (1..18).map(|k| fheap.insert(k, k)).collect();
+        fheap.delete_min();
+        // The other 16 entries are one tree of rank 4. Deleting a leaf below
+        // two of the root's children leaves the root's rank alone, so the
+        // next consolidation has a rank 4 root among only 15 entries.
+        let root = entries[1].clone();
+        assert_eq!(root.rank(), 4);
+        let leaves: Vec<_> = root.children()
+            .filter(|child| child.rank() == 1 || child.rank() == 2)
+            .map(|child| child.children().find(|c| c.rank() == 0).unwrap().clone())
+            .collect();
+        for leaf in leaves {
+            fheap.delete(leaf).unwrap();
+        }
+        assert_eq!(root.rank(), 4);
+        assert_eq!(fheap.len(), 14);
+        let mut last = 0;
+        while !fheap.empty() {
+            let (k, _) = fheap.delete_min();
+            assert!(k > last);
+            last = k;
+        }
     }
 
-    #[bench]
-    fn bench_delete_min(b: &mut Bencher) {
+    #[test]
+    fn test_fheap_merge_empty() {
         let mut fheap: FibHeap<u8, u8> = FibHeap::new();
         fheap.insert(1, 1);
-        fheap.insert(4, 4);
-        fheap.insert(0, 0);
-        fheap.insert(5, 5);
-        fheap.insert(2, 2);
-        fheap.insert(6, 6);
-        fheap.insert(3, 3);
-        fheap.insert(12, 12);
-        fheap.insert(11, 11);
-        fheap.insert(13, 13);
-        fheap.insert(14, 14);
-        fheap.insert(15, 15);
-        fheap.insert(16, 16);
-        fheap.insert(17, 17);
+        fheap = fheap.merge(FibHeap::new());
+        fheap = FibHeap::new().merge(fheap);
+        assert_eq!(fheap.len(), 1);
+        assert_eq!(fheap.find_min(), (1, 1));
+    }
 
-        b.iter(|| {
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_fheap_tracing() {
+        use std::fmt;
+        use std::sync::{Arc, Mutex};
+        use tracing::{self, Event, Metadata, Subscriber};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+
+        struct Messages(Vec<String>);
+
+        impl Visit for Messages {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0.push(format!("{:?}", value));
+                }
+            }
+        }
+
+        struct Collect(Arc<Mutex<Vec<String>>>);
+
+        impl Subscriber for Collect {
+            fn enabled(&self, _: &Metadata) -> bool { true }
+            fn new_span(&self, _: &Attributes) -> Id { Id::from_u64(1) }
+            fn record(&self, _: &Id, _: &Record) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event) {
+                let mut messages = Messages(Vec::new());
+                event.record(&mut messages);
+                self.0.lock().unwrap().extend(messages.0);
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Collect(events.clone()), || {
+            let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+            for &k in [0, 1, 4, 5, 2, 3].iter() {
+                fheap.insert(k, k);
+            }
+            let six = fheap.insert(6, 6);
+            let seven = fheap.insert(7, 7);
+            for &k in [18, 9, 11, 15].iter() {
+                fheap.insert(k, k);
+            }
             fheap.delete_min();
-            fheap.insert(0, 0);
+            fheap.decrease_key(&six, 4).unwrap();
+            fheap.decrease_key(&seven, 7).unwrap();
+            let mut other = FibHeap::new();
+            other.insert(10, 10);
+            fheap.merge(other);
         });
+        assert_eq!(*events.lock().unwrap(), vec!("consolidate", "cascading cut", "meld"));
     }
 
-    #[bench]
-    fn bench_decrease_key(b: &mut Bencher) {
-        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
-        fheap.insert(1, 1);
-        fheap.insert(4, 4);
-        fheap.insert(0, 0);
-        fheap.insert(5, 5);
-        fheap.insert(2, 2);
-        fheap.insert(6, 6);
-        fheap.insert(3, 3);
-        let ten = fheap.insert(10, 10);
-        fheap.insert(11, 11);
-        fheap.insert(13, 13);
-        fheap.insert(14, 14);
-        fheap.insert(15, 15);
-        fheap.insert(16, 16);
-        fheap.insert(17, 17);
-        fheap.insert(10, 10);
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_fheap_par_heapify() {
+        let items: Vec<(u32, u32)> = (0..10000u32).map(|i| ((i * 7919) % 10000, i)).collect();
+        let mut fheap = FibHeap::par_heapify(items);
+        assert_eq!(fheap.len(), 10000);
+        fheap.par_extend(vec!((20000u32, 0u32), (10000, 1)));
+        let keys: Vec<u32> = (0..10002).map(|_| fheap.delete_min().0).collect();
+        let mut expected: Vec<u32> = (0..10001).collect();
+        expected.push(20000);
+        assert_eq!(keys, expected);
+        assert!(FibHeap::<u32, u32>::par_heapify(Vec::new()).empty());
+    }
 
-        b.iter(|| {
-            fheap.decrease_key(&ten, 1);
-        });
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_fheap_par_consolidate() {
+        let items: Vec<(u32, u32)> = (0..5000u32).map(|i| ((i * 7919) % 5000, i)).collect();
+        let mut fheap = FibHeap::par_heapify(items);
+        assert_eq!(fheap.roots.len(), 5000);
+        fheap.par_consolidate();
+        let mut ranks: Vec<usize> = fheap.roots.iter().map(|r| r.rank()).collect();
+        ranks.sort();
+        ranks.dedup();
+        assert_eq!(ranks.len(), fheap.roots.len());
+        assert_eq!(fheap.len(), 5000);
+        let keys: Vec<u32> = (0..5000).map(|_| fheap.delete_min().0).collect();
+        assert_eq!(keys, (0..5000).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_fheap_macro() {
+        let mut fheap = fibheap!{4 => 'd', 1 => 'a', 3 => 'c', 2 => 'b', 5 => 'e',};
+        assert_eq!(fheap.len(), 5);
+        // Consolidated into a tree of four and the one left over.
+        assert_eq!(fheap.roots.len(), 2);
+        assert_eq!(fheap.delete_min(), (1, 'a'));
+        assert_eq!(fheap.delete_min(), (2, 'b'));
+        let empty: FibHeap<u8, u8> = fibheap!{};
+        assert!(empty.empty());
+    }
+
+    #[test]
+    fn test_fheap_decrease_key_to() {
+        let mut fheap = fibheap!{1 => 'a', 5 => 'b'};
+        let c = fheap.insert(9, 'c');
+        fheap.delete_min();
+        fheap.decrease_key_to(&c, 2).unwrap();
+        assert_eq!(fheap.delete_min(), (2, 'c'));
+        assert_eq!(fheap.decrease_key_to(&c, 0), Err(StaleEntry));
+        assert_eq!(fheap.delete_min(), (5, 'b'));
+    }
+
+    #[test]
+    fn test_fheap_handle_debug() {
+        let mut fheap = fibheap!{1 => 'a'};
+        let b = fheap.insert(2, 'b');
+        fheap.decrease_key(&b, 1).unwrap();
+        assert_eq!(format!("{:?}", b), "FibNode { key: 1, value: 'b', live: true }");
+        fheap.delete(b.clone()).unwrap();
+        assert_eq!(format!("{:?}", b), "FibNode { key: 1, value: 'b', live: false }");
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn test_fheap_handle_hash() {
+        let mut fheap: FibHeap<u8, char> = FibHeap::new();
+        let a = fheap.insert(1, 'a');
+        let b = fheap.insert(1, 'b');
+        assert!(a != b);
+        assert_eq!(a, a.clone());
+        let mut names = HashMap::new();
+        names.insert(a.clone(), "first");
+        names.insert(b.clone(), "second");
+        let (_, v) = fheap.delete_min();
+        let gone = if v == 'a' { &a } else { &b };
+        assert_eq!(names.remove(gone), Some(if v == 'a' { "first" } else { "second" }));
+        assert_eq!(names.len(), 1);
     }
 }
@@ -0,0 +1,24 @@
+use std::cmp::Ordering;
+use std::ops::Sub;
+
+// f64 keys ordered by `partial_cmp`, for bindings whose callers only have
+// floats. NaN must be rejected before a key is built.
+#[allow(clippy::derive_ord_xor_partial_ord)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct FloatKey(pub f64);
+
+impl Eq for FloatKey {}
+
+impl Ord for FloatKey {
+    fn cmp(&self, other: &FloatKey) -> Ordering {
+        self.partial_cmp(other).expect("NaN key in heap")
+    }
+}
+
+impl Sub for FloatKey {
+    type Output = FloatKey;
+
+    fn sub(self, other: FloatKey) -> FloatKey {
+        FloatKey(self.0 - other.0)
+    }
+}
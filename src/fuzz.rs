@@ -0,0 +1,139 @@
+use std::ops::Sub;
+use std::fmt::Debug;
+use arbitrary::{Arbitrary, Unstructured, Result};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete};
+
+/// A single heap operation, as produced by a fuzzer.
+///
+/// `DecreaseKey` and `Delete` carry an index into the entries that are still
+/// live when the operation is applied, so every generated sequence is valid.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op<K, V> {
+    Insert(K, V),
+    FindMin,
+    DeleteMin,
+    DecreaseKey(usize, K),
+    Delete(usize),
+}
+
+impl<'a, K: Arbitrary<'a>, V: Arbitrary<'a>> Arbitrary<'a> for Op<K, V> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Op<K, V>> {
+        let op = match u.int_in_range(0u8..=4)? {
+            0 => Op::Insert(K::arbitrary(u)?, V::arbitrary(u)?),
+            1 => Op::FindMin,
+            2 => Op::DeleteMin,
+            3 => Op::DecreaseKey(usize::arbitrary(u)?, K::arbitrary(u)?),
+            _ => Op::Delete(usize::arbitrary(u)?),
+        };
+        Ok(op)
+    }
+}
+
+impl<'a, K, V> Arbitrary<'a> for FibHeap<K, V>
+where K: Arbitrary<'a> + Ord + Debug + Clone + Sub<K, Output=K>,
+      V: Arbitrary<'a> + Eq + PartialOrd + Debug + Clone {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<FibHeap<K, V>> {
+        let mut fheap = FibHeap::new();
+        for entry in u.arbitrary_iter::<(K, V)>()? {
+            let (k, v) = entry?;
+            fheap.insert(k, v);
+        }
+        Ok(fheap)
+    }
+}
+
+/// Applies `ops` to `heap`, returning every `(key, value)` pair observed by
+/// `FindMin`, `DeleteMin` and `Delete`.
+///
+/// Operations that would be invalid are skipped: popping an empty heap,
+/// addressing an entry when none are live, or decreasing a key by more than
+/// its current value.
+pub fn apply<K, V, H>(heap: &mut H, ops: &[Op<K, V>]) -> Vec<(K, V)>
+where K: Ord + Clone + Sub<K, Output=K>,
+      V: PartialEq + Clone,
+      H: DecreaseKey<K, V> + HeapDelete<K, V, HeapEntry=<H as Heap<K, V>>::HeapEntry> {
+    let mut live: Vec<(<H as Heap<K, V>>::HeapEntry, K, V)> = Vec::new();
+    let mut observed = Vec::new();
+
+    for op in ops.iter() {
+        match *op {
+            Op::Insert(ref k, ref v) => {
+                let entry = heap.insert(k.clone(), v.clone());
+                live.push((entry, k.clone(), v.clone()));
+            }
+            Op::FindMin => {
+                if !heap.empty() {
+                    observed.push(heap.find_min());
+                }
+            }
+            Op::DeleteMin => {
+                if !heap.empty() {
+                    let (k, v) = heap.delete_min();
+                    // Entries with an identical key and value can't be told
+                    // apart, so stop addressing all of them.
+                    live.retain(|(_, lk, lv)| !(*lk == k && *lv == v));
+                    observed.push((k, v));
+                }
+            }
+            Op::DecreaseKey(idx, ref delta) => {
+                if !live.is_empty() {
+                    let idx = idx % live.len();
+                    if *delta <= live[idx].1 {
+                        heap.decrease_key(&live[idx].0, delta.clone()).unwrap();
+                        live[idx].1 = live[idx].1.clone() - delta.clone();
+                    }
+                }
+            }
+            Op::Delete(idx) => {
+                if !live.is_empty() {
+                    let idx = idx % live.len();
+                    let (entry, _, _) = live.swap_remove(idx);
+                    observed.push(heap.delete(entry).unwrap());
+                }
+            }
+        }
+    }
+    observed
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+    use Heap;
+    use fibonacci_heap::{FibHeap};
+    use fuzz::{Op, apply};
+
+    #[test]
+    fn fuzz_arbitrary_heap() {
+        let data = [1u8, 10, 10, 1, 3, 30, 1, 2, 20];
+        let mut u = Unstructured::new(&data);
+        let mut fheap: FibHeap<u8, u8> = FibHeap::arbitrary(&mut u).unwrap();
+        assert_eq!(fheap.find_min(), (2, 20));
+        fheap.delete_min();
+        assert_eq!(fheap.find_min(), (3, 30));
+    }
+
+    #[test]
+    fn fuzz_apply_ops() {
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        let ops = vec!(Op::Insert(5, 5), Op::Insert(3, 3), Op::Insert(8, 8),
+                       Op::FindMin, Op::DecreaseKey(2, 7), Op::DecreaseKey(0, 9),
+                       Op::DeleteMin, Op::Delete(5), Op::DeleteMin,
+                       Op::DeleteMin);
+        let observed = apply(&mut fheap, &ops);
+        // `Delete(5)` picks the entry with key 3 and gets that key back,
+        // not the key lowered to zero that `delete` used to pop.
+        assert_eq!(observed, vec!((3, 3), (1, 8), (3, 3), (5, 5)));
+        assert!(fheap.empty());
+    }
+
+    #[test]
+    fn fuzz_apply_arbitrary_ops() {
+        let data: Vec<u8> = (0..255).collect();
+        let mut u = Unstructured::new(&data);
+        let ops: Vec<Op<u8, u8>> = Arbitrary::arbitrary(&mut u).unwrap();
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        apply(&mut fheap, &ops);
+    }
+}
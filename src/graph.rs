@@ -0,0 +1,847 @@
+use std::ops::{Add, Mul, Neg, Sub};
+use std::slice;
+use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use rev::Rev;
+use {Heap, DecreaseKey};
+
+/// A directed graph stored as adjacency lists. Nodes are numbered from 0.
+#[derive(Clone, Debug)]
+pub struct Graph<W> {
+    edges: Vec<Vec<(usize, W)>>,
+}
+
+impl<W: Clone> Graph<W> {
+    pub fn new(nodes: usize) -> Graph<W> {
+        Graph { edges: (0..nodes).map(|_| Vec::new()).collect() }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Adds a node without edges, returning its index.
+    pub fn add_node(&mut self) -> usize {
+        self.edges.push(Vec::new());
+        self.edges.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, weight: W) {
+        self.edges[from].push((to, weight));
+    }
+
+    /// Adds an edge in both directions.
+    pub fn add_undirected_edge(&mut self, a: usize, b: usize, weight: W) {
+        self.add_edge(a, b, weight.clone());
+        self.add_edge(b, a, weight);
+    }
+
+    /// The `(target, weight)` pairs of the edges leaving `node`.
+    pub fn neighbors(&self, node: usize) -> slice::Iter<'_, (usize, W)> {
+        self.edges[node].iter()
+    }
+}
+
+/// Distances and predecessors computed by a single-source search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShortestPaths<W> {
+    pub distances: Vec<Option<W>>,
+    pub predecessors: Vec<Option<usize>>,
+}
+
+impl<W> ShortestPaths<W> {
+    /// The nodes on the shortest path ending at `target`, starting with the
+    /// source, or `None` if `target` is unreachable.
+    pub fn path_to(&self, target: usize) -> Option<Vec<usize>> {
+        self.distances[target].as_ref()?;
+        let mut path = vec!(target);
+        let mut node = target;
+        while let Some(prev) = self.predecessors[node] {
+            path.push(prev);
+            node = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Dijkstra's algorithm from `start`, using `heap` (which should be empty)
+/// for the frontier and decrease_key when a shorter path is found.
+///
+/// Edge weights must be non-negative; `W::default()` is the zero distance.
+pub fn shortest_path<W, H>(heap: &mut H, graph: &Graph<W>, start: usize) -> ShortestPaths<W>
+where W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize> {
+    let n = graph.node_count();
+    let mut distances: Vec<Option<W>> = vec!(None; n);
+    let mut predecessors = vec!(None; n);
+    let mut entries: Vec<Option<H::HeapEntry>> = (0..n).map(|_| None).collect();
+    let mut visited = vec!(false; n);
+
+    distances[start] = Some(W::default());
+    entries[start] = Some(heap.insert(W::default(), start));
+
+    while !heap.empty() {
+        let (distance, node) = heap.delete_min();
+        visited[node] = true;
+        entries[node] = None;
+        for &(target, ref weight) in graph.neighbors(node) {
+            if visited[target] {
+                continue
+            }
+            let new_dist = distance.clone() + weight.clone();
+            match distances[target].clone() {
+                Some(old_dist) => {
+                    if new_dist < old_dist {
+                        heap.decrease_key(entries[target].as_ref().unwrap(),
+                                          old_dist - new_dist.clone()).unwrap();
+                    } else {
+                        continue
+                    }
+                }
+                None => {
+                    entries[target] = Some(heap.insert(new_dist.clone(), target));
+                }
+            }
+            distances[target] = Some(new_dist);
+            predecessors[target] = Some(node);
+        }
+    }
+
+    ShortestPaths { distances: distances, predecessors: predecessors }
+}
+
+/// Edges of a minimum spanning tree as `(parent, child, weight)`, and their
+/// total weight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanningTree<W> {
+    pub edges: Vec<(usize, usize, W)>,
+    pub weight: W,
+}
+
+/// Prim's algorithm, growing a minimum spanning tree of the component
+/// containing `root`. `heap` should be empty.
+///
+/// The graph is treated as undirected, so every edge should have been added
+/// with `add_undirected_edge`.
+pub fn prim_mst<W, H>(heap: &mut H, graph: &Graph<W>, root: usize) -> SpanningTree<W>
+where W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize> {
+    let n = graph.node_count();
+    // The cheapest known edge connecting each frontier node to the tree.
+    let mut best: Vec<Option<(usize, W)>> = vec!(None; n);
+    let mut entries: Vec<Option<H::HeapEntry>> = (0..n).map(|_| None).collect();
+    let mut in_tree = vec!(false; n);
+    let mut tree = SpanningTree { edges: Vec::new(), weight: W::default() };
+
+    heap.insert(W::default(), root);
+    while !heap.empty() {
+        let (_, node) = heap.delete_min();
+        in_tree[node] = true;
+        entries[node] = None;
+        if let Some((parent, weight)) = best[node].take() {
+            tree.weight = tree.weight + weight.clone();
+            tree.edges.push((parent, node, weight));
+        }
+
+        for &(target, ref weight) in graph.neighbors(node) {
+            if in_tree[target] {
+                continue
+            }
+            match best[target].clone() {
+                Some((_, old)) => {
+                    if *weight < old {
+                        heap.decrease_key(entries[target].as_ref().unwrap(),
+                                          old - weight.clone()).unwrap();
+                    } else {
+                        continue
+                    }
+                }
+                None => {
+                    entries[target] = Some(heap.insert(weight.clone(), target));
+                }
+            }
+            best[target] = Some((node, weight.clone()));
+        }
+    }
+    tree
+}
+
+/// A* search from `start` to `goal` over an implicit graph, returning the
+/// path (including both ends) and its cost, or `None` if `goal` is
+/// unreachable. `heap` should be empty.
+///
+/// `neighbors` yields the `(state, cost)` pairs reachable from a state, and
+/// `heuristic` estimates the remaining cost to `goal`. The heuristic must be
+/// consistent, as expanded states are never reopened. Open states whose cost
+/// improves are updated in place with decrease_key.
+pub fn astar<N, W, H, F, I, E>(heap: &mut H, start: N, goal: N, mut neighbors: F,
+                               mut heuristic: E) -> Option<(Vec<N>, W)>
+where N: Hash + Eq + Clone,
+      W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize>,
+      F: FnMut(&N) -> I,
+      I: IntoIterator<Item=(N, W)>,
+      E: FnMut(&N) -> W {
+    // Every state seen gets an index; the heap holds indices.
+    let mut states = vec!(start.clone());
+    let mut index = HashMap::new();
+    let mut costs = vec!(W::default());
+    let mut parents: Vec<Option<usize>> = vec!(None);
+    let mut entries = vec!(Some(heap.insert(heuristic(&start), 0)));
+    let mut closed = vec!(false);
+    index.insert(start, 0);
+
+    while !heap.empty() {
+        let (_, current) = heap.delete_min();
+        if states[current] == goal {
+            let mut path = vec!(states[current].clone());
+            let mut node = current;
+            while let Some(parent) = parents[node] {
+                path.push(states[parent].clone());
+                node = parent;
+            }
+            path.reverse();
+            return Some((path, costs[current].clone()))
+        }
+        closed[current] = true;
+        entries[current] = None;
+
+        for (next, weight) in neighbors(&states[current]) {
+            let cost = costs[current].clone() + weight;
+            match index.get(&next).cloned() {
+                Some(i) => {
+                    if closed[i] || cost >= costs[i] {
+                        continue
+                    }
+                    heap.decrease_key(entries[i].as_ref().unwrap(), costs[i].clone() - cost.clone()).unwrap();
+                    costs[i] = cost;
+                    parents[i] = Some(current);
+                }
+                None => {
+                    let i = states.len();
+                    let estimate = cost.clone() + heuristic(&next);
+                    entries.push(Some(heap.insert(estimate, i)));
+                    states.push(next.clone());
+                    costs.push(cost);
+                    parents.push(Some(current));
+                    closed.push(false);
+                    index.insert(next, i);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Johnson's algorithm: shortest paths between all pairs of nodes, in a graph
+/// whose edge weights may be negative, as one `ShortestPaths` per source.
+/// Returns `None` if the graph has a negative cycle.
+///
+/// Bellman-Ford computes a potential for each node, which is used to
+/// reweight the edges so they are all non-negative, and then Dijkstra's
+/// algorithm runs from every node using `heap` (which should be empty).
+pub fn johnson<W, H>(heap: &mut H, graph: &Graph<W>) -> Option<Vec<ShortestPaths<W>>>
+where W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize> {
+    let n = graph.node_count();
+    // Distances from a virtual source with a zero-weight edge to every node.
+    let mut potential = vec!(W::default(); n);
+    for round in 0..n + 1 {
+        let mut changed = false;
+        for u in 0..n {
+            for &(v, ref weight) in graph.neighbors(u) {
+                let through = potential[u].clone() + weight.clone();
+                if through < potential[v] {
+                    potential[v] = through;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break
+        }
+        if round == n {
+            return None
+        }
+    }
+
+    let mut reweighted = Graph::new(n);
+    for u in 0..n {
+        for &(v, ref weight) in graph.neighbors(u) {
+            reweighted.add_edge(u, v, weight.clone() + potential[u].clone() - potential[v].clone());
+        }
+    }
+
+    let all = (0..n).map(|source| {
+        let mut paths = shortest_path(heap, &reweighted, source);
+        for (target, distance) in paths.distances.iter_mut().enumerate() {
+            *distance = distance.take().map(|d| {
+                d + potential[target].clone() - potential[source].clone()
+            });
+        }
+        paths
+    }).collect();
+    Some(all)
+}
+
+/// Yen's algorithm: up to `k` loopless paths from `source` to `target` in
+/// order of increasing cost, each as its nodes and total cost. Edge weights
+/// must be non-negative.
+///
+/// Each path after the first is found by running Dijkstra's algorithm, with
+/// `heap` (which should be empty), from every node of the previous path on a
+/// copy of the graph with the edges that would recreate known paths removed.
+pub fn k_shortest_paths<W, H>(heap: &mut H, graph: &Graph<W>, source: usize, target: usize,
+                              k: usize) -> Vec<(Vec<usize>, W)>
+where W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize> {
+    let mut found: Vec<(Vec<usize>, W)> = Vec::new();
+    if k == 0 {
+        return found
+    }
+    let first = shortest_path(heap, graph, source);
+    match first.path_to(target) {
+        Some(path) => found.push((path, first.distances[target].clone().unwrap())),
+        None => return found
+    }
+
+    let mut candidates: FibHeap<Ordered<(W, Vec<usize>)>, ()> = FibHeap::new();
+    let mut seen = HashSet::new();
+    while found.len() < k {
+        let prev = found[found.len() - 1].0.clone();
+        let mut root_cost = W::default();
+        for i in 0..prev.len() - 1 {
+            let spur = prev[i];
+            let root = &prev[..i + 1];
+            let mut spur_graph = Graph::new(graph.node_count());
+            for u in 0..graph.node_count() {
+                if root[..i].contains(&u) {
+                    continue
+                }
+                for &(v, ref weight) in graph.neighbors(u) {
+                    let reused = u == spur && found.iter().any(|(path, _)| {
+                        path.len() > i + 1 && &path[..i + 1] == root && path[i + 1] == v
+                    });
+                    if !reused && !root[..i].contains(&v) {
+                        spur_graph.add_edge(u, v, weight.clone());
+                    }
+                }
+            }
+
+            let spur_paths = shortest_path(heap, &spur_graph, spur);
+            if let Some(spur_path) = spur_paths.path_to(target) {
+                let mut path = root[..i].to_vec();
+                path.extend(spur_path);
+                let cost = root_cost.clone() + spur_paths.distances[target].clone().unwrap();
+                if seen.insert(path.clone()) {
+                    candidates.insert(Ordered((cost, path)), ());
+                }
+            }
+            root_cost = root_cost + edge_weight(graph, spur, prev[i + 1]);
+        }
+
+        if candidates.empty() {
+            break
+        }
+        let (Ordered((cost, path)), _) = candidates.delete_min();
+        found.push((path, cost));
+    }
+    found
+}
+
+// The cheapest edge from `from` to `to`.
+fn edge_weight<W: Ord + Clone>(graph: &Graph<W>, from: usize, to: usize) -> W {
+    graph.neighbors(from).filter(|&&(v, _)| v == to).map(|(_, w)| w.clone())
+        .min().expect("No such edge")
+}
+
+/// A directed graph whose edges have a capacity and a cost per unit of
+/// flow, for `min_cost_flow`.
+///
+/// Residual edges carry negated costs, so the weight type must be signed;
+/// unsigned weights don't compile:
+///
+/// ```compile_fail
+/// use rust_heaps::graph::FlowNetwork;
+/// let mut network = FlowNetwork::new(2);
+/// network.add_edge(0, 1, 1u32, 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FlowNetwork<W> {
+    // Edge `2 * i` is the `i`th edge added and edge `2 * i + 1` its residual
+    // reverse edge, as `(from, to, capacity left, cost)`.
+    edges: Vec<(usize, usize, W, W)>,
+    adjacency: Vec<Vec<usize>>,
+    capacities: Vec<W>,
+}
+
+impl<W: Clone + Default + Sub<W, Output=W> + Neg<Output=W>> FlowNetwork<W> {
+    pub fn new(nodes: usize) -> FlowNetwork<W> {
+        FlowNetwork {
+            edges: Vec::new(),
+            adjacency: (0..nodes).map(|_| Vec::new()).collect(),
+            capacities: Vec::new(),
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Adds an edge, returning its index. Costs must be non-negative.
+    pub fn add_edge(&mut self, from: usize, to: usize, capacity: W, cost: W) -> usize {
+        let index = self.capacities.len();
+        self.adjacency[from].push(self.edges.len());
+        self.edges.push((from, to, capacity.clone(), cost.clone()));
+        self.adjacency[to].push(self.edges.len());
+        self.edges.push((to, from, W::default(), -cost));
+        self.capacities.push(capacity);
+        index
+    }
+
+    /// The flow currently routed along an edge.
+    pub fn flow(&self, edge: usize) -> W {
+        self.capacities[edge].clone() - self.edges[2 * edge].2.clone()
+    }
+}
+
+/// Sends up to `limit` units of flow from `source` to `sink` at minimum
+/// total cost, returning the flow sent and its cost. The flow on each edge
+/// can be read back from `network` afterwards.
+///
+/// Uses successive shortest paths: each augmenting path is found by
+/// Dijkstra's algorithm, with `heap` (which should be empty) and node
+/// potentials keeping the reduced costs of residual edges non-negative.
+pub fn min_cost_flow<W, H>(heap: &mut H, network: &mut FlowNetwork<W>, source: usize, sink: usize,
+                           limit: W) -> (W, W)
+where W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W> + Mul<W, Output=W> + Neg<Output=W>,
+      H: DecreaseKey<W, usize> {
+    let n = network.node_count();
+    let zero = W::default();
+    let mut potential = vec!(zero.clone(); n);
+    let mut flow = zero.clone();
+    let mut cost = zero.clone();
+
+    while flow < limit {
+        let mut distances: Vec<Option<W>> = vec!(None; n);
+        let mut via: Vec<Option<usize>> = vec!(None; n);
+        let mut entries: Vec<Option<H::HeapEntry>> = (0..n).map(|_| None).collect();
+        let mut done = vec!(false; n);
+        distances[source] = Some(zero.clone());
+        entries[source] = Some(heap.insert(zero.clone(), source));
+        while !heap.empty() {
+            let (distance, u) = heap.delete_min();
+            done[u] = true;
+            entries[u] = None;
+            for &e in network.adjacency[u].iter() {
+                let (_, v, ref capacity, ref edge_cost) = network.edges[e];
+                if done[v] || *capacity <= zero {
+                    continue
+                }
+                let reduced = edge_cost.clone() + potential[u].clone() - potential[v].clone();
+                let new_dist = distance.clone() + reduced;
+                match distances[v].clone() {
+                    Some(old) => {
+                        if new_dist >= old {
+                            continue
+                        }
+                        heap.decrease_key(entries[v].as_ref().unwrap(), old - new_dist.clone()).unwrap();
+                    }
+                    None => entries[v] = Some(heap.insert(new_dist.clone(), v))
+                }
+                distances[v] = Some(new_dist);
+                via[v] = Some(e);
+            }
+        }
+        if distances[sink].is_none() {
+            break
+        }
+        for v in 0..n {
+            if let Some(ref d) = distances[v] {
+                potential[v] = potential[v].clone() + d.clone();
+            }
+        }
+
+        let mut push = limit.clone() - flow.clone();
+        let mut node = sink;
+        while let Some(e) = via[node] {
+            if network.edges[e].2 < push {
+                push = network.edges[e].2.clone();
+            }
+            node = network.edges[e].0;
+        }
+        node = sink;
+        while let Some(e) = via[node] {
+            network.edges[e].2 = network.edges[e].2.clone() - push.clone();
+            network.edges[e ^ 1].2 = network.edges[e ^ 1].2.clone() + push.clone();
+            cost = cost + push.clone() * network.edges[e].3.clone();
+            node = network.edges[e].0;
+        }
+        flow = flow + push;
+    }
+    (flow, cost)
+}
+
+/// Kahn's algorithm, always taking the ready node with the smallest
+/// `priority` next, with ties going to the lower-numbered node. Returns
+/// `None` if the graph has a cycle.
+///
+/// With `|n| n` as the priority this gives the lexicographically smallest
+/// topological order.
+pub fn toposort_by_priority<W, P, F>(graph: &Graph<W>, mut priority: F) -> Option<Vec<usize>>
+where W: Clone,
+      P: Ord + Clone,
+      F: FnMut(usize) -> P {
+    let n = graph.node_count();
+    let mut in_degree = vec!(0; n);
+    for u in 0..n {
+        for &(v, _) in graph.neighbors(u) {
+            in_degree[v] += 1;
+        }
+    }
+    let mut ready: FibHeap<Ordered<(P, usize)>, ()> = FibHeap::new();
+    for (u, &degree) in in_degree.iter().enumerate() {
+        if degree == 0 {
+            ready.insert(Ordered((priority(u), u)), ());
+        }
+    }
+
+    let mut order = Vec::with_capacity(n);
+    while !ready.empty() {
+        let (Ordered((_, u)), _) = ready.delete_min();
+        order.push(u);
+        for &(v, _) in graph.neighbors(u) {
+            in_degree[v] -= 1;
+            if in_degree[v] == 0 {
+                ready.insert(Ordered((priority(v), v)), ());
+            }
+        }
+    }
+    if order.len() == n { Some(order) } else { None }
+}
+
+/// Stoer-Wagner: the minimum cut of an undirected graph, as its weight and
+/// the nodes on one side of it. Returns `None` for graphs with fewer than
+/// two nodes.
+///
+/// Every edge should have been added with `add_undirected_edge`. Each phase
+/// orders the nodes by maximum adjacency using `heap` (which should be
+/// empty), raising a node's connectivity with a decrease_key on its `Rev`
+/// key.
+pub fn min_cut_stoer_wagner<W, H>(heap: &mut H, graph: &Graph<W>) -> Option<(W, Vec<usize>)>
+where W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<Rev<W>, usize> {
+    let n = graph.node_count();
+    if n < 2 {
+        return None
+    }
+    let zero = W::default();
+    // Weights between merged groups of nodes, indexed by representative.
+    let mut weights = vec!(vec!(zero.clone(); n); n);
+    for (u, row) in weights.iter_mut().enumerate() {
+        for &(v, ref weight) in graph.neighbors(u) {
+            if u != v {
+                row[v] = row[v].clone() + weight.clone();
+            }
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = (0..n).map(|u| vec!(u)).collect();
+    let mut active: Vec<usize> = (0..n).collect();
+    let mut best: Option<(W, Vec<usize>)> = None;
+
+    while active.len() > 1 {
+        let mut entries: Vec<Option<H::HeapEntry>> = (0..n).map(|_| None).collect();
+        for &u in active.iter() {
+            entries[u] = Some(heap.insert(Rev(zero.clone()), u));
+        }
+        let mut prev = active[0];
+        let mut last = active[0];
+        let mut cut = zero.clone();
+        while !heap.empty() {
+            let (Rev(c), u) = heap.delete_min();
+            entries[u] = None;
+            prev = last;
+            last = u;
+            cut = c;
+            for &v in active.iter() {
+                if entries[v].is_some() && weights[u][v] > zero {
+                    heap.decrease_key(entries[v].as_ref().unwrap(), Rev(weights[u][v].clone())).unwrap();
+                }
+            }
+        }
+
+        if best.as_ref().is_none_or(|(w, _)| cut < *w) {
+            best = Some((cut, groups[last].clone()));
+        }
+        // Merge the last node added into the one before it.
+        let merged = groups[last].clone();
+        groups[prev].extend(merged);
+        for &v in active.iter() {
+            let w = weights[last][v].clone();
+            weights[prev][v] = weights[prev][v].clone() + w.clone();
+            weights[v][prev] = weights[v][prev].clone() + w;
+        }
+        weights[prev][prev] = zero.clone();
+        active.retain(|&v| v != last);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_heap::{FibHeap};
+    use graph::{Graph, FlowNetwork, shortest_path, prim_mst, astar, johnson, k_shortest_paths,
+                min_cost_flow, toposort_by_priority, min_cut_stoer_wagner};
+
+    fn example_graph() -> Graph<u64> {
+        let mut graph = Graph::new(5);
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(0, 2, 5);
+        graph.add_edge(1, 2, 8);
+        graph.add_edge(1, 3, 3);
+        graph.add_edge(2, 3, 2);
+        graph.add_edge(3, 1, 9);
+        graph
+    }
+
+    #[test]
+    fn graph_shortest_path() {
+        let graph = example_graph();
+        let paths = shortest_path(&mut FibHeap::new(), &graph, 0);
+        assert_eq!(paths.distances, vec!(Some(0), Some(1), Some(5), Some(4), None));
+        assert_eq!(paths.predecessors, vec!(None, Some(0), Some(0), Some(1), None));
+        assert_eq!(paths.path_to(3), Some(vec!(0, 1, 3)));
+        assert_eq!(paths.path_to(4), None);
+    }
+
+    #[test]
+    fn graph_shortest_path_decrease_key() {
+        let mut graph = Graph::new(4);
+        graph.add_edge(0, 3, 10u32);
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 3, 1);
+        let paths = shortest_path(&mut FibHeap::new(), &graph, 0);
+        assert_eq!(paths.distances[3], Some(3));
+        assert_eq!(paths.path_to(3), Some(vec!(0, 1, 2, 3)));
+    }
+
+    #[test]
+    fn graph_undirected_edges() {
+        let mut graph = Graph::new(2);
+        let c = graph.add_node();
+        graph.add_undirected_edge(0, 1, 2u8);
+        graph.add_undirected_edge(1, c, 3);
+        let paths = shortest_path(&mut FibHeap::new(), &graph, c);
+        assert_eq!(paths.distances, vec!(Some(5), Some(3), Some(0)));
+        assert_eq!(paths.path_to(0), Some(vec!(2, 1, 0)));
+    }
+
+    #[test]
+    fn graph_prim_mst() {
+        let mut graph = Graph::new(6);
+        graph.add_undirected_edge(0, 1, 4u32);
+        graph.add_undirected_edge(0, 2, 1);
+        graph.add_undirected_edge(1, 2, 2);
+        graph.add_undirected_edge(1, 3, 5);
+        graph.add_undirected_edge(2, 3, 8);
+        graph.add_undirected_edge(3, 4, 3);
+        graph.add_undirected_edge(2, 4, 9);
+
+        let tree = prim_mst(&mut FibHeap::new(), &graph, 0);
+        assert_eq!(tree.weight, 11);
+        assert_eq!(tree.edges, vec!((0, 2, 1), (2, 1, 2), (1, 3, 5), (3, 4, 3)));
+    }
+
+    #[test]
+    fn graph_prim_mst_single_node() {
+        let graph: Graph<u32> = Graph::new(2);
+        let tree = prim_mst(&mut FibHeap::new(), &graph, 1);
+        assert_eq!(tree.weight, 0);
+        assert!(tree.edges.is_empty());
+    }
+
+    fn grid_neighbors(walls: &[(i32, i32)], &(x, y): &(i32, i32)) -> Vec<((i32, i32), u32)> {
+        vec!((x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)).into_iter()
+            .filter(|&(nx, ny)| nx >= 0 && ny >= 0 && nx < 5 && ny < 5)
+            .filter(|p| !walls.contains(p))
+            .map(|p| (p, 1))
+            .collect()
+    }
+
+    #[test]
+    fn graph_astar_grid() {
+        let walls = [(1, 0), (1, 1), (1, 2), (1, 3), (3, 1), (3, 2), (3, 3), (3, 4)];
+        let goal = (4, 0);
+        let manhattan = |&(x, y): &(i32, i32)| ((goal.0 - x).abs() + (goal.1 - y).abs()) as u32;
+        let (path, cost) = astar(&mut FibHeap::new(), (0, 0), goal,
+                                 |p| grid_neighbors(&walls, p), manhattan).unwrap();
+        assert_eq!(cost, 12);
+        assert_eq!(path.len(), 13);
+        assert_eq!(path[0], (0, 0));
+        assert_eq!(path[12], goal);
+    }
+
+    #[test]
+    fn graph_astar_decrease_key() {
+        let graph = {
+            let mut graph = Graph::new(4);
+            graph.add_edge(0, 3, 10u32);
+            graph.add_edge(0, 1, 1);
+            graph.add_edge(1, 2, 1);
+            graph.add_edge(2, 3, 1);
+            graph
+        };
+        let neighbors = |n: &usize| graph.neighbors(*n).cloned().collect::<Vec<_>>();
+        let result = astar(&mut FibHeap::new(), 0, 3, neighbors, |_| 0);
+        assert_eq!(result, Some((vec!(0, 1, 2, 3), 3)));
+    }
+
+    #[test]
+    fn graph_astar_unreachable() {
+        let walls = [(1, 0), (0, 1)];
+        let result = astar(&mut FibHeap::new(), (0, 0), (4, 4),
+                           |p| grid_neighbors(&walls, p), |_| 0u32);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn graph_johnson_negative_weights() {
+        let mut graph = Graph::new(4);
+        graph.add_edge(0, 1, 4i32);
+        graph.add_edge(0, 2, 1);
+        graph.add_edge(2, 1, -2);
+        graph.add_edge(1, 3, 3);
+        graph.add_edge(3, 0, 1);
+
+        let all = johnson(&mut FibHeap::new(), &graph).unwrap();
+        assert_eq!(all[0].distances, vec!(Some(0), Some(-1), Some(1), Some(2)));
+        assert_eq!(all[0].path_to(3), Some(vec!(0, 2, 1, 3)));
+        assert_eq!(all[1].distances, vec!(Some(4), Some(0), Some(5), Some(3)));
+        assert_eq!(all[3].path_to(1), Some(vec!(3, 0, 2, 1)));
+    }
+
+    #[test]
+    fn graph_johnson_unreachable_and_negative_cycle() {
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 1, -1i32);
+        let all = johnson(&mut FibHeap::new(), &graph).unwrap();
+        assert_eq!(all[0].distances, vec!(Some(0), Some(-1), None));
+        assert_eq!(all[1].distances, vec!(None, Some(0), None));
+
+        graph.add_edge(1, 2, 2);
+        graph.add_edge(2, 0, -2);
+        assert!(johnson(&mut FibHeap::new(), &graph).is_none());
+    }
+
+    #[test]
+    fn graph_k_shortest_paths() {
+        // The example graph from Wikipedia's article on Yen's algorithm.
+        let mut graph = Graph::new(6);
+        graph.add_edge(0, 1, 3u32);
+        graph.add_edge(0, 2, 2);
+        graph.add_edge(1, 3, 4);
+        graph.add_edge(2, 1, 1);
+        graph.add_edge(2, 3, 2);
+        graph.add_edge(2, 4, 3);
+        graph.add_edge(3, 4, 2);
+        graph.add_edge(3, 5, 1);
+        graph.add_edge(4, 5, 2);
+
+        let paths = k_shortest_paths(&mut FibHeap::new(), &graph, 0, 5, 3);
+        assert_eq!(paths, vec!((vec!(0, 2, 3, 5), 5),
+                               (vec!(0, 2, 4, 5), 7),
+                               (vec!(0, 1, 3, 5), 8)));
+
+        let all = k_shortest_paths(&mut FibHeap::new(), &graph, 0, 5, 100);
+        assert_eq!(all.len(), 7);
+        assert!(all.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn graph_k_shortest_paths_unreachable() {
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 1, 1u32);
+        assert!(k_shortest_paths(&mut FibHeap::new(), &graph, 0, 2, 3).is_empty());
+        assert_eq!(k_shortest_paths(&mut FibHeap::new(), &graph, 0, 1, 3), vec!((vec!(0, 1), 1)));
+    }
+
+    #[test]
+    fn graph_min_cost_flow() {
+        let mut network = FlowNetwork::new(4);
+        let sa = network.add_edge(0, 1, 2i64, 1);
+        let sb = network.add_edge(0, 2, 2, 2);
+        let ab = network.add_edge(1, 2, 1, 1);
+        let at = network.add_edge(1, 3, 1, 3);
+        let bt = network.add_edge(2, 3, 3, 1);
+
+        let (flow, cost) = min_cost_flow(&mut FibHeap::new(), &mut network.clone(), 0, 3, 1);
+        assert_eq!((flow, cost), (1, 3));
+
+        let (flow, cost) = min_cost_flow(&mut FibHeap::new(), &mut network, 0, 3, 100);
+        assert_eq!((flow, cost), (4, 13));
+        let flows: Vec<i64> = vec!(sa, sb, ab, at, bt).into_iter().map(|e| network.flow(e)).collect();
+        assert_eq!(flows, vec!(2, 2, 1, 1, 3));
+    }
+
+    #[test]
+    fn graph_min_cost_flow_uses_reverse_edges() {
+        // The cheapest first path, 0-1-2-3, blocks both others until flow
+        // is pushed back along 1-2.
+        let mut network = FlowNetwork::new(4);
+        network.add_edge(0, 1, 1i64, 1);
+        network.add_edge(0, 2, 1, 5);
+        network.add_edge(1, 2, 1, 1);
+        network.add_edge(1, 3, 1, 5);
+        let last = network.add_edge(2, 3, 1, 1);
+        let (flow, cost) = min_cost_flow(&mut FibHeap::new(), &mut network, 0, 3, 10);
+        assert_eq!((flow, cost), (2, 12));
+        assert_eq!(network.flow(last), 1);
+    }
+
+    #[test]
+    fn graph_toposort_by_priority() {
+        let mut graph = Graph::new(6);
+        graph.add_edge(5, 2, ());
+        graph.add_edge(5, 0, ());
+        graph.add_edge(4, 0, ());
+        graph.add_edge(4, 1, ());
+        graph.add_edge(2, 3, ());
+        graph.add_edge(3, 1, ());
+        assert_eq!(toposort_by_priority(&graph, |n| n), Some(vec!(4, 5, 0, 2, 3, 1)));
+        assert_eq!(toposort_by_priority(&graph, |n| 6 - n), Some(vec!(5, 4, 2, 3, 1, 0)));
+        // Equal priorities fall back to node order.
+        assert_eq!(toposort_by_priority(&graph, |_| ()), Some(vec!(4, 5, 0, 2, 3, 1)));
+
+        graph.add_edge(1, 5, ());
+        assert_eq!(toposort_by_priority(&graph, |n| n), None);
+    }
+
+    #[test]
+    fn graph_min_cut_stoer_wagner() {
+        // The example from Stoer and Wagner's paper, numbered from 0.
+        let mut graph = Graph::new(8);
+        for &(a, b, w) in [(0, 1, 2u32), (0, 4, 3), (1, 2, 3), (1, 4, 2), (1, 5, 2), (2, 3, 4),
+                           (2, 6, 2), (3, 6, 2), (3, 7, 2), (4, 5, 3), (5, 6, 1), (6, 7, 3)].iter() {
+            graph.add_undirected_edge(a, b, w);
+        }
+        let (weight, mut side) = min_cut_stoer_wagner(&mut FibHeap::new(), &graph).unwrap();
+        assert_eq!(weight, 4);
+        side.sort();
+        assert!(side == vec!(2, 3, 6, 7) || side == vec!(0, 1, 4, 5));
+    }
+
+    #[test]
+    fn graph_min_cut_disconnected() {
+        let mut graph = Graph::new(3);
+        graph.add_undirected_edge(0, 1, 5u32);
+        let (weight, mut side) = min_cut_stoer_wagner(&mut FibHeap::new(), &graph).unwrap();
+        assert_eq!(weight, 0);
+        side.sort();
+        assert!(side == vec!(2) || side == vec!(0, 1));
+        assert!(min_cut_stoer_wagner(&mut FibHeap::new(), &Graph::<u32>::new(1)).is_none());
+    }
+}
@@ -0,0 +1,133 @@
+use core::cell::RefCell;
+use core::ops::Sub;
+use critical_section::{self, Mutex};
+use array_heap::{ArrayEntry, ArrayHeap, Full};
+use {Heap, DecreaseKey, HeapDelete};
+
+/// An `ArrayHeap` that interrupt handlers and the main program can share,
+/// e.g. as the queue of pending timer deadlines.
+///
+/// Every method runs in a critical section, so on a single-core target an
+/// interrupt can't observe the heap half-updated. Nothing allocates and
+/// every method takes `&self`, so the heap can live in a `static`. The
+/// target has to provide a `critical-section` implementation.
+pub struct InterruptHeap<K, V, const N: usize> {
+    heap: Mutex<RefCell<ArrayHeap<K, V, N>>>,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, const N: usize> InterruptHeap<K, V, N> {
+    /// An empty heap, which can be the initializer of a `static`.
+    pub const fn new() -> InterruptHeap<K, V, N> {
+        InterruptHeap { heap: Mutex::new(RefCell::new(ArrayHeap::new())) }
+    }
+
+    /// Runs `f` on the heap inside one critical section, for operations
+    /// that must not be interleaved with an interrupt.
+    pub fn with<R, F: FnOnce(&mut ArrayHeap<K, V, N>) -> R>(&self, f: F) -> R {
+        critical_section::with(|cs| f(&mut self.heap.borrow_ref_mut(cs)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.with(|heap| heap.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.with(|heap| heap.empty())
+    }
+
+    /// Inserts the entry, or returns it if the heap is full.
+    pub fn try_insert(&self, key: K, value: V) -> Result<ArrayEntry, Full<K, V>> {
+        self.with(|heap| heap.try_insert(key, value))
+    }
+
+    pub fn peek(&self) -> Option<(K, V)> {
+        self.with(|heap| if heap.empty() { None } else { Some(heap.find_min()) })
+    }
+
+    pub fn pop(&self) -> Option<(K, V)> {
+        self.with(|heap| if heap.empty() { None } else { Some(heap.delete_min()) })
+    }
+
+    /// Removes the minimum if `f` accepts it, e.g. to take the next timer
+    /// only once its deadline has passed.
+    pub fn pop_if<F: FnOnce(&K, &V) -> bool>(&self, f: F) -> Option<(K, V)> {
+        self.with(|heap| {
+            if heap.empty() {
+                return None
+            }
+            let (k, v) = heap.find_min();
+            if f(&k, &v) { Some(heap.delete_min()) } else { None }
+        })
+    }
+
+    /// Lowers the entry's key by `delta`, returning false if the entry has
+    /// already left the heap.
+    pub fn decrease_key(&self, entry: ArrayEntry, delta: K) -> bool {
+        self.with(|heap| heap.decrease_key(&entry, delta).is_ok())
+    }
+
+    /// Removes the entry, or returns None if it has already left the heap,
+    /// e.g. a timer that fired before it could be cancelled.
+    pub fn delete(&self, entry: ArrayEntry) -> Option<(K, V)> {
+        self.with(|heap| heap.delete(entry).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use interrupt_heap::InterruptHeap;
+
+    static QUEUE: InterruptHeap<u32, char, 4> = InterruptHeap::new();
+
+    #[test]
+    fn interrupt_heap_timers() {
+        let timers: InterruptHeap<u64, &str, 4> = InterruptHeap::new();
+        let blink = timers.try_insert(30, "blink").unwrap();
+        let poll = timers.try_insert(10, "poll").unwrap();
+        timers.try_insert(20, "beep").unwrap();
+        assert_eq!(timers.pop_if(|&deadline, _| deadline <= 5), None);
+        assert_eq!(timers.pop_if(|&deadline, _| deadline <= 15), Some((10, "poll")));
+        assert!(!timers.decrease_key(poll, 1));
+        assert!(timers.decrease_key(blink, 25));
+        assert_eq!(timers.peek(), Some((5, "blink")));
+        assert_eq!(timers.delete(poll), None);
+        assert_eq!(timers.delete(blink), Some((5, "blink")));
+        assert_eq!(timers.len(), 1);
+    }
+
+    #[test]
+    fn interrupt_heap_shared() {
+        let heap: Arc<InterruptHeap<u32, u32, 400>> = Arc::new(InterruptHeap::new());
+        let threads: Vec<_> = (0..4).map(|t| {
+            let heap = heap.clone();
+            thread::spawn(move || {
+                for i in 0..100 {
+                    heap.try_insert(i * 4 + t, t).unwrap();
+                }
+            })
+        }).collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert!(heap.try_insert(0, 0).is_err());
+        let mut last = 0;
+        while let Some((k, _)) = heap.pop() {
+            assert!(k >= last);
+            last = k;
+        }
+        assert_eq!(last, 399);
+    }
+
+    #[test]
+    fn interrupt_heap_static() {
+        thread::spawn(|| {
+            QUEUE.try_insert(2, 'b').unwrap();
+            QUEUE.try_insert(1, 'a').unwrap();
+        }).join().unwrap();
+        assert_eq!(QUEUE.pop(), Some((1, 'a')));
+        assert_eq!(QUEUE.pop(), Some((2, 'b')));
+        assert_eq!(QUEUE.pop(), None);
+    }
+}
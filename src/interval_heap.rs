@@ -0,0 +1,222 @@
+use std::ops::Sub;
+use {Heap, DoubleEndedHeap};
+
+/// An interval heap: an implicit binary tree in a `Vec` whose nodes each
+/// hold two entries, the low end and high end of an interval containing
+/// every interval below it. The low ends form a min-heap and the high ends
+/// a max-heap, so both extremes are at the root.
+///
+/// Nodes are entry pairs at consecutive indices, which makes the tree half
+/// as deep as a `MinMaxHeap` with the same entries.
+pub struct IntervalHeap<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> IntervalHeap<K, V> {
+    pub const fn new() -> IntervalHeap<K, V> {
+        IntervalHeap { entries: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> IntervalHeap<K, V> {
+        IntervalHeap { entries: Vec::with_capacity(capacity) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn key(&self, i: usize) -> &K {
+        &self.entries[i].0
+    }
+
+    // The index of a node's high end. The last node may hold a single
+    // entry, which is both ends.
+    fn high(&self, node: usize) -> usize {
+        (2 * node + 1).min(self.entries.len() - 1)
+    }
+
+    fn bubble_up_low(&mut self, mut node: usize) {
+        while node > 0 {
+            let parent = (node - 1) / 2;
+            if self.key(2 * node) >= self.key(2 * parent) {
+                break
+            }
+            self.entries.swap(2 * node, 2 * parent);
+            node = parent;
+        }
+    }
+
+    fn bubble_up_high(&mut self, mut node: usize) {
+        while node > 0 {
+            let parent = (node - 1) / 2;
+            let (high, parent_high) = (self.high(node), 2 * parent + 1);
+            if self.key(high) <= self.key(parent_high) {
+                break
+            }
+            self.entries.swap(high, parent_high);
+            node = parent;
+        }
+    }
+
+    // Orders the two ends of a node.
+    fn order(&mut self, node: usize) {
+        let high = self.high(node);
+        if self.key(2 * node) > self.key(high) {
+            self.entries.swap(2 * node, high);
+        }
+    }
+
+    // The child of `node` whose `end` is best by `better`.
+    fn best_child<F, B>(&self, node: usize, end: F, better: B) -> Option<usize>
+    where F: Fn(&Self, usize) -> usize, B: Fn(&K, &K) -> bool {
+        let mut best = None;
+        for child in 2 * node + 1..2 * node + 3 {
+            if 2 * child >= self.entries.len() {
+                break
+            }
+            if best.is_none_or(|b| better(self.key(end(self, child)), self.key(end(self, b)))) {
+                best = Some(child);
+            }
+        }
+        best
+    }
+
+    fn trickle_down_low(&mut self, mut node: usize) {
+        loop {
+            self.order(node);
+            let child = match self.best_child(node, |_, c| 2 * c, |a, b| a < b) {
+                Some(c) if self.key(2 * c) < self.key(2 * node) => c,
+                _ => return
+            };
+            self.entries.swap(2 * node, 2 * child);
+            node = child;
+        }
+    }
+
+    fn trickle_down_high(&mut self, mut node: usize) {
+        loop {
+            self.order(node);
+            let high = self.high(node);
+            let child = match self.best_child(node, |h, c| h.high(c), |a, b| a > b) {
+                Some(c) if self.key(self.high(c)) > self.key(high) => c,
+                _ => return
+            };
+            let child_high = self.high(child);
+            self.entries.swap(high, child_high);
+            node = child;
+        }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for IntervalHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        self.entries.first().cloned().expect("IntervalHeap is empty")
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        if self.entries.is_empty() {
+            panic!("IntervalHeap is empty");
+        }
+        let entry = self.entries.swap_remove(0);
+        if !self.entries.is_empty() {
+            self.trickle_down_low(0);
+        }
+        entry
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.entries.push((key, value));
+        let last = self.entries.len() - 1;
+        let node = last / 2;
+        self.order(node);
+        if node == 0 {
+            return
+        }
+        let parent = (node - 1) / 2;
+        if self.key(2 * node) < self.key(2 * parent) {
+            self.bubble_up_low(node);
+        } else {
+            self.bubble_up_high(node);
+        }
+    }
+
+    fn empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DoubleEndedHeap<K, V> for IntervalHeap<K, V> {
+    fn find_max(&self) -> (K, V) {
+        if self.entries.is_empty() {
+            panic!("IntervalHeap is empty");
+        }
+        self.entries[self.high(0)].clone()
+    }
+
+    fn delete_max(&mut self) -> (K, V) {
+        if self.entries.is_empty() {
+            panic!("IntervalHeap is empty");
+        }
+        let high = self.high(0);
+        let entry = self.entries.swap_remove(high);
+        if high < self.entries.len() {
+            self.trickle_down_high(0);
+        }
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use interval_heap::IntervalHeap;
+    use {Heap, DoubleEndedHeap};
+
+    #[test]
+    fn interval_heap_small() {
+        let mut heap = IntervalHeap::new();
+        heap.insert(5u32, 'a');
+        assert_eq!(heap.find_max(), (5, 'a'));
+        assert_eq!(heap.delete_max(), (5, 'a'));
+        assert!(heap.empty());
+
+        heap.insert(5, 'a');
+        heap.insert(3, 'b');
+        assert_eq!(heap.find_min(), (3, 'b'));
+        assert_eq!(heap.find_max(), (5, 'a'));
+        assert_eq!(heap.delete_max(), (5, 'a'));
+        assert_eq!(heap.find_max(), (3, 'b'));
+        assert_eq!(heap.delete_max(), (3, 'b'));
+
+        heap.insert(3, 'b');
+        heap.insert(5, 'a');
+        assert_eq!(heap.delete_both(), ((3, 'b'), Some((5, 'a'))));
+        heap.insert(4, 'c');
+        assert_eq!(heap.delete_both(), ((4, 'c'), None));
+        assert!(heap.empty());
+    }
+
+    #[test]
+    fn interval_heap_single_entry_last_node() {
+        // Five entries leave the last node with one entry, which is both
+        // the low and high end of its interval.
+        for &last in [0u32, 2, 9].iter() {
+            let mut heap = IntervalHeap::new();
+            for &k in [4, 6, 3, 8].iter() {
+                heap.insert(k, k);
+            }
+            heap.insert(last, last);
+            let mut sorted = [3, 4, 6, 8, last];
+            sorted.sort();
+            assert_eq!(heap.find_min().0, sorted[0]);
+            assert_eq!(heap.find_max().0, sorted[4]);
+            // Removing from either end moves the lone entry up.
+            assert_eq!(heap.delete_max().0, sorted[4]);
+            assert_eq!(heap.delete_min().0, sorted[0]);
+            assert_eq!(heap.delete_max().0, sorted[3]);
+            assert_eq!(heap.delete_both(), ((sorted[1], sorted[1]), Some((sorted[2], sorted[2]))));
+            assert!(heap.empty());
+        }
+    }
+}
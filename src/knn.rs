@@ -0,0 +1,90 @@
+use std::cmp::Ordering;
+use rev::Rev;
+use top_k::TopK;
+
+// Orders distances that are only `PartialOrd`, such as floats. Values that
+// don't compare with themselves (NaN) are never stored.
+#[derive(Clone, PartialEq)]
+struct Distance<D>(D);
+
+impl<D: PartialOrd> Eq for Distance<D> {}
+
+impl<D: PartialOrd> PartialOrd for Distance<D> {
+    fn partial_cmp(&self, other: &Distance<D>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<D: PartialOrd> Ord for Distance<D> {
+    fn cmp(&self, other: &Distance<D>) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Keeps the `k` candidates closest to a query out of those offered.
+///
+/// Distances can be any `PartialOrd` type, so `f32` and `f64` work; NaN
+/// distances are rejected.
+pub struct NearestK<D, T> {
+    top: TopK<Rev<Distance<D>>, T>,
+}
+
+impl<D: PartialOrd + Clone, T> NearestK<D, T> {
+    pub fn new(k: usize) -> NearestK<D, T> {
+        NearestK { top: TopK::new(k) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.top.len()
+    }
+
+    /// Offers a candidate, returning whether it is among the `k` closest so
+    /// far.
+    pub fn offer(&mut self, dist: D, item: T) -> bool {
+        if dist.partial_cmp(&dist).is_none() {
+            return false
+        }
+        self.top.insert(Rev(Distance(dist)), item)
+    }
+
+    /// The distance of the farthest candidate kept. Once `k` candidates are
+    /// kept, nothing at this distance or beyond will be.
+    pub fn worst_distance(&self) -> Option<D> {
+        self.top.worst_key().map(|Rev(Distance(d))| d)
+    }
+
+    /// The kept candidates, closest first.
+    pub fn into_sorted_vec(self) -> Vec<(D, T)> {
+        self.top.into_sorted_vec().into_iter().map(|(Rev(Distance(d)), item)| (d, item)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use knn::NearestK;
+
+    #[test]
+    fn knn_keeps_closest() {
+        let points = [(0.0f64, 0.0f64), (3.0, 4.0), (1.0, 1.5), (-2.0, 0.5), (0.5, -0.5), (6.0, 0.0)];
+        let mut nearest = NearestK::new(3);
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let dist = ((x - 1.0) * (x - 1.0) + y * y).sqrt();
+            nearest.offer(dist, i);
+        }
+        assert_eq!(nearest.len(), 3);
+        assert_eq!(nearest.worst_distance(), Some(1.5));
+        let ids: Vec<usize> = nearest.into_sorted_vec().into_iter().map(|(_, i)| i).collect();
+        assert_eq!(ids, vec!(4, 0, 2));
+    }
+
+    #[test]
+    fn knn_rejects_nan() {
+        let mut nearest = NearestK::new(2);
+        assert!(!nearest.offer(f64::NAN, "nan"));
+        assert!(nearest.offer(2.0, "b"));
+        assert!(nearest.offer(1.0, "a"));
+        assert!(!nearest.offer(3.0, "c"));
+        assert_eq!(nearest.into_sorted_vec(), vec!((1.0, "a"), (2.0, "b")));
+    }
+}
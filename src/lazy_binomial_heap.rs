@@ -0,0 +1,339 @@
+use std::cell::RefCell;
+use std::collections::LinkedList;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Sub;
+use std::rc::{Rc, Weak};
+use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
+
+type Link<K, V> = Rc<RefCell<Node<K, V>>>;
+type ItemLink<K, V> = Rc<RefCell<Item<K, V>>>;
+
+// An entry, which moves between nodes as keys are decreased.
+struct Item<K, V> {
+    key: K,
+    value: V,
+    node: Weak<RefCell<Node<K, V>>>,
+    // Set once the entry has been taken out of the heap.
+    removed: bool,
+}
+
+struct Node<K, V> {
+    item: ItemLink<K, V>,
+    parent: Option<Weak<RefCell<Node<K, V>>>>,
+    // A node of rank k has children of ranks 0 to k - 1.
+    children: Vec<Link<K, V>>,
+}
+
+/// Handle to an entry of a `LazyBinomialHeap`. Handles compare and hash by
+/// the entry they refer to, and stay valid when their heap is merged into
+/// another.
+pub struct BinomialEntry<K, V>(ItemLink<K, V>);
+
+impl<K, V> Clone for BinomialEntry<K, V> {
+    fn clone(&self) -> BinomialEntry<K, V> {
+        BinomialEntry(self.0.clone())
+    }
+}
+
+impl<K, V> PartialEq for BinomialEntry<K, V> {
+    fn eq(&self, other: &BinomialEntry<K, V>) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<K, V> Eq for BinomialEntry<K, V> {}
+
+impl<K, V> Hash for BinomialEntry<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&*self.0 as *const RefCell<Item<K, V>>).hash(state)
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for BinomialEntry<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let item = self.0.borrow();
+        f.debug_struct("BinomialEntry")
+            .field("key", &item.key)
+            .field("value", &item.value)
+            .field("live", &!item.removed)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<K: defmt::Format, V: defmt::Format> defmt::Format for BinomialEntry<K, V> {
+    fn format(&self, f: defmt::Formatter) {
+        let item = self.0.borrow();
+        defmt::write!(f, "BinomialEntry {{ key: {}, value: {}, live: {=bool} }}", item.key, item.value, !item.removed)
+    }
+}
+
+/// A lazy binomial heap: binomial trees in a root list that inserting and
+/// merging only append to, in O(1), and that deleting the minimum links
+/// into at most one tree per rank, in O(log n) amortized.
+///
+/// Unlike `FibHeap`, trees keep their binomial shape, since decreasing a
+/// key swaps the entry up its tree instead of cutting it out, so there are
+/// no marks or cascading cuts, at the cost of O(log n) for decrease_key.
+pub struct LazyBinomialHeap<K, V> {
+    roots: LinkedList<Link<K, V>>,
+    min: Option<Link<K, V>>,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> LazyBinomialHeap<K, V> {
+    pub const fn new() -> LazyBinomialHeap<K, V> {
+        LazyBinomialHeap { roots: LinkedList::new(), min: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of trees, which only drops when the minimum is deleted.
+    pub fn root_count(&self) -> usize {
+        self.roots.len()
+    }
+
+    fn less(a: &Link<K, V>, b: &Link<K, V>) -> bool {
+        a.borrow().item.borrow().key < b.borrow().item.borrow().key
+    }
+
+    fn update_min(&mut self, root: &Link<K, V>) {
+        let smaller = match self.min {
+            Some(ref min) => LazyBinomialHeap::less(root, min),
+            None => true
+        };
+        if smaller {
+            self.min = Some(root.clone());
+        }
+    }
+
+    // Makes the root with the larger key the last child of the other,
+    // returning the new root.
+    fn link(a: Link<K, V>, b: Link<K, V>) -> Link<K, V> {
+        let (root, child) = if LazyBinomialHeap::less(&b, &a) { (b, a) } else { (a, b) };
+        child.borrow_mut().parent = Some(Rc::downgrade(&root));
+        root.borrow_mut().children.push(child);
+        root
+    }
+
+    // Links the trees, then the remaining roots, into one tree per rank.
+    fn consolidate<I: Iterator<Item=Link<K, V>>>(&mut self, trees: I) {
+        let mut ranks: Vec<Option<Link<K, V>>> = Vec::new();
+        for tree in trees {
+            let mut tree = tree;
+            loop {
+                let rank = tree.borrow().children.len();
+                if rank >= ranks.len() {
+                    ranks.resize(rank + 1, None);
+                }
+                match ranks[rank].take() {
+                    Some(other) => tree = LazyBinomialHeap::link(tree, other),
+                    None => {
+                        ranks[rank] = Some(tree);
+                        break
+                    }
+                }
+            }
+        }
+        self.min = None;
+        for root in ranks.into_iter().flatten() {
+            self.update_min(&root);
+            self.roots.push_back(root);
+        }
+    }
+
+    // Takes a root out of the heap, consolidating the other roots with its
+    // children.
+    fn remove_root(&mut self, root: Link<K, V>) -> (K, V) {
+        let roots = mem::take(&mut self.roots);
+        let children = mem::take(&mut root.borrow_mut().children);
+        for child in children.iter() {
+            child.borrow_mut().parent = None;
+        }
+        self.consolidate(roots.into_iter().filter(|r| !Rc::ptr_eq(r, &root)).chain(children));
+        self.len -= 1;
+        let node = root.borrow();
+        let mut item = node.item.borrow_mut();
+        item.removed = true;
+        (item.key.clone(), item.value.clone())
+    }
+
+    // Swaps the node's entry up the tree while it's less than its parent's,
+    // or all the way to the root if `always`, returning the node it ends in.
+    fn sift_up(mut node: Link<K, V>, always: bool) -> Link<K, V> {
+        loop {
+            let parent = node.borrow().parent.as_ref().map(|p| p.upgrade().unwrap());
+            let parent = match parent {
+                Some(parent) => parent,
+                None => return node
+            };
+            if !always && !LazyBinomialHeap::less(&node, &parent) {
+                return node
+            }
+            {
+                let (mut n, mut p) = (node.borrow_mut(), parent.borrow_mut());
+                mem::swap(&mut n.item, &mut p.item);
+                n.item.borrow_mut().node = Rc::downgrade(&node);
+                p.item.borrow_mut().node = Rc::downgrade(&parent);
+            }
+            node = parent;
+        }
+    }
+
+    fn node(entry: &BinomialEntry<K, V>) -> Link<K, V> {
+        entry.0.borrow().node.upgrade().unwrap()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for LazyBinomialHeap<K, V> {
+    type HeapEntry = BinomialEntry<K, V>;
+
+    fn find_min(&self) -> (K, V) {
+        match self.min {
+            Some(ref min) => {
+                let node = min.borrow();
+                let item = node.item.borrow();
+                (item.key.clone(), item.value.clone())
+            }
+            None => panic!("LazyBinomialHeap is empty")
+        }
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        match self.min.take() {
+            Some(min) => self.remove_root(min),
+            None => panic!("LazyBinomialHeap is empty")
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) -> BinomialEntry<K, V> {
+        let item = Rc::new(RefCell::new(Item { key: key, value: value, node: Weak::new(), removed: false }));
+        let node = Rc::new(RefCell::new(Node { item: item.clone(), parent: None, children: Vec::new() }));
+        item.borrow_mut().node = Rc::downgrade(&node);
+        self.update_min(&node);
+        self.roots.push_back(node);
+        self.len += 1;
+        BinomialEntry(item)
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for LazyBinomialHeap<K, V> {
+    fn decrease_key(&mut self, entry: &BinomialEntry<K, V>, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        let node = LazyBinomialHeap::node(entry);
+        {
+            let mut item = entry.0.borrow_mut();
+            item.key = item.key.clone() - delta;
+        }
+        let node = LazyBinomialHeap::sift_up(node, false);
+        if node.borrow().parent.is_none() {
+            self.update_min(&node);
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapExt for LazyBinomialHeap<K, V> {
+    fn merge(mut self, mut other: LazyBinomialHeap<K, V>) -> LazyBinomialHeap<K, V> {
+        if let Some(min) = other.min.take() {
+            self.update_min(&min);
+        }
+        self.roots.append(&mut other.roots);
+        self.len += mem::replace(&mut other.len, 0);
+        self
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapDelete<K, V> for LazyBinomialHeap<K, V> {
+    type HeapEntry = BinomialEntry<K, V>;
+
+    fn delete(&mut self, entry: BinomialEntry<K, V>) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        let root = LazyBinomialHeap::sift_up(LazyBinomialHeap::node(&entry), true);
+        self.min = None;
+        Ok(self.remove_root(root))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> AddressableHeap<K, V> for LazyBinomialHeap<K, V> {
+    fn is_valid(&self, entry: &BinomialEntry<K, V>) -> bool {
+        !entry.0.borrow().removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lazy_binomial_heap::LazyBinomialHeap;
+    use test_model;
+    use {Heap, DecreaseKey, HeapExt, HeapDelete, StaleEntry};
+
+    #[test]
+    fn lazy_binomial_heap_against_model() {
+        let mut heap = LazyBinomialHeap::new();
+        test_model::against_model(&mut heap, 20000, |_, _, x| 1000 + x % 5000,
+                                  Some(|heap, entry| heap.delete(entry)), |heap, entries, _| {
+            assert_eq!(heap.len(), entries.len());
+        });
+    }
+
+    #[test]
+    fn lazy_binomial_heap_is_lazy() {
+        let mut heap = LazyBinomialHeap::new();
+        for i in 0..100u32 {
+            heap.insert(i, i);
+        }
+        assert_eq!(heap.root_count(), 100);
+        assert_eq!(heap.delete_min(), (0, 0));
+        // 99 entries, 0b1100011, make trees of ranks 0, 1, 5 and 6.
+        assert_eq!(heap.root_count(), 4);
+        let other = (100..108u32).fold(LazyBinomialHeap::new(), |mut h, i| { h.insert(i, i); h });
+        let mut heap = heap.merge(other);
+        assert_eq!(heap.root_count(), 12);
+        assert_eq!(heap.len(), 107);
+        assert_eq!(heap.delete_min(), (1, 1));
+        assert_eq!(heap.root_count(), 106u32.count_ones() as usize);
+    }
+
+    #[test]
+    fn lazy_binomial_heap_merge_keeps_handles() {
+        let mut a = LazyBinomialHeap::new();
+        let mut b = LazyBinomialHeap::new();
+        a.insert(5, 'a');
+        let c = b.insert(7, 'c');
+        b.insert(6, 'b');
+        b.insert(8, 'd');
+        b.delete_min();
+        let mut merged = a.merge(b);
+        merged.decrease_key(&c, 6).unwrap();
+        assert_eq!(format!("{:?}", c), "BinomialEntry { key: 1, value: 'c', live: true }");
+        assert_eq!(merged.delete_min(), (1, 'c'));
+        assert_eq!(format!("{:?}", c), "BinomialEntry { key: 1, value: 'c', live: false }");
+        assert_eq!(merged.delete_min(), (5, 'a'));
+        assert_eq!(merged.delete_min(), (8, 'd'));
+        assert!(merged.empty());
+    }
+
+    #[test]
+    fn lazy_binomial_heap_stale_handle() {
+        let mut heap = LazyBinomialHeap::new();
+        let a = heap.insert(1, 'a');
+        let b = heap.insert(2, 'b');
+        assert_eq!(heap.delete_min(), (1, 'a'));
+        assert_eq!(heap.decrease_key(&a, 1), Err(StaleEntry));
+        assert_eq!(heap.delete(a), Err(StaleEntry));
+        assert_eq!(heap.delete(b), Ok((2, 'b')));
+        assert!(heap.empty());
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::mem;
+use std::ops::Sub;
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete, AddressableHeap, StaleEntry};
+
+/// Handle to an entry of a `LazyHeap`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LazyEntry(u64);
+
+/// Wraps any of the crate's heaps to change keys by reinserting instead of
+/// calling the inner heap's decrease_key.
+///
+/// Updating or deleting an entry leaves a tombstone in the inner heap, which
+/// is discarded once it reaches the top. Keys can move in either direction,
+/// at the cost of the inner heap holding one extra entry per update until
+/// its tombstone is discarded.
+pub struct LazyHeap<K, V, H = FibHeap<K, u64>> {
+    heap: H,
+    // Each insertion into the inner heap gets a new stamp, mapped to the
+    // entry it belongs to. Stamps missing from `live` are tombstones.
+    live: HashMap<u64, u64>,
+    entries: HashMap<u64, (u64, K, V)>,
+    stale: usize,
+    next_id: u64,
+    next_stamp: u64,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> LazyHeap<K, V> {
+    pub fn new() -> LazyHeap<K, V> {
+        LazyHeap::with_heap(FibHeap::new())
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, H: Heap<K, u64>> LazyHeap<K, V, H> {
+    /// Wraps `heap`, which should be empty.
+    pub fn with_heap(heap: H) -> LazyHeap<K, V, H> {
+        LazyHeap {
+            heap: heap,
+            live: HashMap::new(),
+            entries: HashMap::new(),
+            stale: 0,
+            next_id: 0,
+            next_stamp: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The number of tombstones still in the inner heap.
+    pub fn stale(&self) -> usize {
+        self.stale
+    }
+
+    pub fn contains(&self, entry: LazyEntry) -> bool {
+        self.entries.contains_key(&entry.0)
+    }
+
+    pub fn get(&self, entry: LazyEntry) -> Option<(&K, &V)> {
+        self.entries.get(&entry.0).map(|(_, k, v)| (k, v))
+    }
+
+    /// Sets the key of an entry, which may be larger or smaller than its
+    /// current key. Returns whether the entry was still in the heap.
+    pub fn update(&mut self, entry: LazyEntry, key: K) -> bool {
+        if !self.entries.contains_key(&entry.0) {
+            return false
+        }
+        let stamp = self.push(key.clone(), entry.0);
+        let old = {
+            let e = self.entries.get_mut(&entry.0).unwrap();
+            e.1 = key;
+            mem::replace(&mut e.0, stamp)
+        };
+        self.tombstone(old);
+        true
+    }
+
+    fn push(&mut self, key: K, id: u64) -> u64 {
+        let stamp = self.next_stamp;
+        self.next_stamp += 1;
+        self.heap.insert(key, stamp);
+        self.live.insert(stamp, id);
+        stamp
+    }
+
+    fn tombstone(&mut self, stamp: u64) {
+        self.live.remove(&stamp);
+        self.stale += 1;
+        // Keep a live entry on top so find_min can answer without popping.
+        while !self.heap.empty() {
+            let (_, top) = self.heap.find_min();
+            if self.live.contains_key(&top) {
+                break
+            }
+            self.heap.delete_min();
+            self.stale -= 1;
+        }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, H: Heap<K, u64>> Heap<K, V> for LazyHeap<K, V, H> {
+    type HeapEntry = LazyEntry;
+
+    fn find_min(&self) -> (K, V) {
+        let (_, stamp) = self.heap.find_min();
+        let (_, k, v) = &self.entries[&self.live[&stamp]];
+        (k.clone(), v.clone())
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        let (_, stamp) = self.heap.find_min();
+        let id = self.live[&stamp];
+        self.delete(LazyEntry(id)).unwrap()
+    }
+
+    fn insert(&mut self, key: K, value: V) -> LazyEntry {
+        let id = self.next_id;
+        self.next_id += 1;
+        let stamp = self.push(key.clone(), id);
+        self.entries.insert(id, (stamp, key, value));
+        LazyEntry(id)
+    }
+
+    fn empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, H: Heap<K, u64>> DecreaseKey<K, V> for LazyHeap<K, V, H> {
+    fn decrease_key(&mut self, entry: &LazyEntry, delta: K) -> Result<(), StaleEntry> {
+        let key = match self.entries.get(&entry.0) {
+            Some((_, key, _)) => key.clone() - delta,
+            None => return Err(StaleEntry)
+        };
+        self.update(*entry, key);
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, H: Heap<K, u64>> HeapDelete<K, V> for LazyHeap<K, V, H> {
+    type HeapEntry = LazyEntry;
+
+    fn delete(&mut self, entry: LazyEntry) -> Result<(K, V), StaleEntry> {
+        let (stamp, key, value) = self.entries.remove(&entry.0).ok_or(StaleEntry)?;
+        self.tombstone(stamp);
+        Ok((key, value))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone, H: Heap<K, u64>> AddressableHeap<K, V> for LazyHeap<K, V, H> {
+    fn is_valid(&self, entry: &LazyEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Heap, DecreaseKey, HeapDelete};
+    use fibonacci_heap::{FibHeap};
+    use graph::{Graph, shortest_path};
+    use lazy_heap::LazyHeap;
+
+    #[test]
+    fn lazy_heap_update_both_ways() {
+        let mut heap: LazyHeap<u32, char> = LazyHeap::new();
+        let a = heap.insert(5, 'a');
+        let b = heap.insert(3, 'b');
+        heap.insert(4, 'c');
+        assert!(heap.update(a, 1));
+        assert_eq!(heap.find_min(), (1, 'a'));
+        assert!(heap.update(a, 9));
+        assert_eq!(heap.stale(), 1);
+        heap.decrease_key(&b, 1).unwrap();
+        assert_eq!(heap.get(b), Some((&2, &'b')));
+        assert_eq!(heap.delete_min(), (2, 'b'));
+        assert!(!heap.update(b, 0));
+        assert_eq!(heap.delete_min(), (4, 'c'));
+        assert_eq!(heap.delete_min(), (9, 'a'));
+        assert!(heap.empty());
+        assert_eq!(heap.stale(), 0);
+    }
+
+    #[test]
+    fn lazy_heap_delete() {
+        let mut heap: LazyHeap<u32, u32> = LazyHeap::new();
+        let entries: Vec<_> = (0..6).map(|i| heap.insert(i, i)).collect();
+        assert_eq!(heap.delete(entries[0]), Ok((0, 0)));
+        assert_eq!(heap.delete(entries[3]), Ok((3, 3)));
+        assert!(!heap.contains(entries[3]));
+        assert_eq!(heap.len(), 4);
+        let rest: Vec<u32> = (0..4).map(|_| heap.delete_min().0).collect();
+        assert_eq!(rest, vec!(1, 2, 4, 5));
+    }
+
+    #[test]
+    fn lazy_heap_matches_decrease_key() {
+        let mut graph = Graph::new(5);
+        graph.add_edge(0, 4, 10u32);
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 4, 1);
+        graph.add_edge(0, 3, 7);
+        graph.add_edge(2, 3, 1);
+        let lazy = shortest_path(&mut LazyHeap::with_heap(FibHeap::new()), &graph, 0);
+        let eager = shortest_path(&mut FibHeap::new(), &graph, 0);
+        assert_eq!(lazy, eager);
+    }
+}
@@ -2,15 +2,197 @@
 #![crate_type = "rlib"]
 #![crate_type = "dylib"]
 
-#![feature(alloc)]
-#![feature(collections)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(all(feature = "fallible-alloc", nightly), feature(allocator_api))]
 
-#![feature(test)]
-#[cfg(test)]
-extern crate test;
+// The crate spells out struct fields and calls emptiness `empty()`, after
+// `Heap`, and most heaps take parameters that a `Default` would hide.
+#![allow(clippy::redundant_field_names, clippy::new_without_default, clippy::len_without_is_empty)]
 
+#[cfg(all(feature = "fallible-alloc", not(nightly)))]
+compile_error!("the `fallible-alloc` feature needs a nightly compiler; leave it out of stable builds");
+
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "quickcheck")]
+extern crate quickcheck;
+#[cfg(feature = "petgraph")]
+extern crate petgraph;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "snapshot")]
+extern crate serde;
+#[cfg(feature = "snapshot")]
+extern crate postcard;
+#[cfg(feature = "slotmap")]
+#[macro_use]
+extern crate slotmap;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "epoch")]
+extern crate crossbeam_epoch;
+#[cfg(feature = "tracing")]
+#[macro_use]
+extern crate tracing;
+#[cfg(feature = "interrupt")]
+extern crate critical_section;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "defmt")]
+extern crate defmt;
+// `no_std` builds get `core` at the crate root already; the others declare
+// it, as 2015-edition crates must, for the `core::` paths of the modules
+// that build either way and for the pyo3 and wasm-bindgen macros.
+#[cfg(any(feature = "std", test))]
+extern crate core;
+
+#[macro_use]
+mod macros;
+#[cfg(feature = "std")]
 mod fib_node;
+#[cfg(feature = "std")]
+mod ordered;
+#[cfg(feature = "std")]
+mod owned_heap;
+#[cfg(feature = "std")]
+mod rng;
+#[cfg(any(feature = "array-heap", feature = "binary-heap", feature = "bucket-queue",
+          feature = "strict-fib-heap", feature = "veb-heap", feature = "calendar-queue"))]
+mod slab;
+#[cfg(all(test, any(feature = "strict-fib-heap", feature = "smooth-heap", feature = "veb-heap",
+                    feature = "calendar-queue", feature = "lazy-binomial-heap", feature = "treap")))]
+mod test_model;
+#[cfg(feature = "std")]
 pub mod fibonacci_heap;
+pub mod prelude;
+#[cfg(feature = "send-heap")]
+pub mod send_heap;
+#[cfg(feature = "persistent")]
+pub mod persistent;
+pub mod rev;
+pub mod monotone;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "std")]
+pub mod graph;
+#[cfg(feature = "std")]
+pub mod algorithms;
+#[cfg(feature = "std")]
+pub mod median;
+#[cfg(feature = "std")]
+pub mod top_k;
+#[cfg(feature = "std")]
+pub mod loser_tree;
+#[cfg(feature = "std")]
+pub mod timers;
+#[cfg(feature = "std")]
+pub mod scheduler;
+#[cfg(feature = "std")]
+pub mod sweep;
+#[cfg(feature = "std")]
+pub mod search;
+#[cfg(feature = "lazy-heap")]
+pub mod lazy_heap;
+#[cfg(feature = "array-heap")]
+pub mod array_heap;
+#[cfg(feature = "interrupt")]
+pub mod interrupt_heap;
+#[cfg(feature = "external-heap")]
+pub mod external_heap;
+#[cfg(feature = "pairing-heap")]
+pub mod pairing_heap;
+#[cfg(feature = "binary-heap")]
+pub mod binary_heap;
+#[cfg(feature = "skew-heap")]
+pub mod skew_heap;
+#[cfg(feature = "min-max-heap")]
+pub mod min_max_heap;
+#[cfg(feature = "interval-heap")]
+pub mod interval_heap;
+#[cfg(feature = "soft-heap")]
+pub mod soft_heap;
+#[cfg(feature = "bucket-queue")]
+pub mod bucket_queue;
+#[cfg(feature = "strict-fib-heap")]
+pub mod strict_fib_heap;
+#[cfg(feature = "weak-heap")]
+pub mod weak_heap;
+#[cfg(feature = "smooth-heap")]
+pub mod smooth_heap;
+#[cfg(feature = "b-heap")]
+pub mod b_heap;
+#[cfg(feature = "bounded-heap")]
+pub mod bounded_heap;
+#[cfg(feature = "veb-heap")]
+pub mod veb_heap;
+#[cfg(feature = "calendar-queue")]
+pub mod calendar_queue;
+#[cfg(feature = "lazy-binomial-heap")]
+pub mod lazy_binomial_heap;
+#[cfg(feature = "treap")]
+pub mod treap;
+#[cfg(feature = "std")]
+pub mod mlfq;
+#[cfg(feature = "std")]
+pub mod edf;
+#[cfg(feature = "std")]
+pub mod load_balancer;
+#[cfg(feature = "std")]
+pub mod priority_cache;
+#[cfg(feature = "std")]
+pub mod external_sort;
+#[cfg(feature = "std")]
+pub mod priority_channel;
+#[cfg(feature = "std")]
+pub mod simulation;
+#[cfg(feature = "std")]
+pub mod wfq;
+#[cfg(feature = "std")]
+pub mod space_saving;
+#[cfg(feature = "std")]
+pub mod knn;
+#[cfg(feature = "std")]
+pub mod concurrent;
+#[cfg(feature = "std")]
+pub mod recorder;
+#[cfg(all(feature = "std", feature = "arbitrary"))]
+pub mod fuzz;
+#[cfg(all(feature = "std", feature = "quickcheck"))]
+mod quickcheck_impl;
+#[cfg(feature = "defmt")]
+mod defmt_impl;
+#[cfg(all(feature = "std", feature = "petgraph"))]
+pub mod petgraph_algo;
+#[cfg(any(feature = "python", feature = "wasm"))]
+mod float_key;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(all(feature = "std", feature = "slotmap"))]
+pub mod slot_heap;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "async")]
+pub mod async_heap;
+#[cfg(all(feature = "tokio", feature = "send-heap"))]
+pub mod delay_heap;
+#[cfg(feature = "epoch")]
+pub mod lock_free;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "amortized")]
+pub mod amortized;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 
 pub trait Heap<K, V> {
     type HeapEntry;
@@ -18,16 +200,108 @@ pub trait Heap<K, V> {
     fn find_min(&self) -> (K, V);
     fn delete_min(&mut self) -> (K, V);
     fn insert(&mut self, key: K, value: V) -> Self::HeapEntry;
-    fn decrease_key(&mut self, entry: &Self::HeapEntry, delta: K);
     fn empty(&self) -> bool;
+
+    /// Moves every entry of this heap into `target`, e.g. to switch to a heap
+    /// implementation that better suits the workload.
+    fn convert_into<H: Heap<K, V>>(mut self, mut target: H) -> H where Self: Sized {
+        while !self.empty() {
+            let (k, v) = self.delete_min();
+            target.insert(k, v);
+        }
+        target
+    }
+}
+
+/// A heap whose entries' keys can be lowered through the handles `insert`
+/// returns. Heaps whose entries have no handles, such as `MinMaxHeap`, only
+/// implement `Heap`.
+pub trait DecreaseKey<K, V>: Heap<K, V> {
+    /// Lowers the key of `entry` by `delta`. Fails if the heap can tell that
+    /// `entry` was already removed; see `AddressableHeap`.
+    fn decrease_key(&mut self, entry: &Self::HeapEntry, delta: K) -> Result<(), StaleEntry>;
 }
 
 pub trait HeapExt {
-    fn merge(mut self, mut other: Self) -> Self;
+    fn merge(self, other: Self) -> Self;
 }
 
 pub trait HeapDelete<K, V> {
     type HeapEntry;
 
-    fn delete(&mut self, entry: Self::HeapEntry) -> (K, V);
+    /// Removes `entry` from the heap and returns its key and value. The key
+    /// is the entry's own, as last decreased; entries are unlinked rather
+    /// than decreased to the minimum and popped, so other entries with
+    /// equal or smaller keys are left alone. Fails like
+    /// `DecreaseKey::decrease_key` for a removed entry.
+    fn delete(&mut self, entry: Self::HeapEntry) -> Result<(K, V), StaleEntry>;
+
+    /// Like `Heap::convert_into`, but also returns the handles in `target` of
+    /// the entries referred to by `handles`, in the same order. The given
+    /// handles must be distinct and still in this heap.
+    #[cfg(feature = "std")]
+    fn convert_with_handles<H: Heap<K, V>>(mut self, mut target: H,
+                                          handles: Vec<<Self as HeapDelete<K, V>>::HeapEntry>)
+        -> (H, Vec<H::HeapEntry>) where Self: Heap<K, V> + Sized {
+        let mut remapped = Vec::with_capacity(handles.len());
+        for entry in handles.into_iter() {
+            let (k, v) = self.delete(entry).expect("handle is not in the heap");
+            remapped.push(target.insert(k, v));
+        }
+        (self.convert_into(target), remapped)
+    }
+}
+
+/// A heap that can also find and remove its largest entry, such as
+/// `MinMaxHeap` or `IntervalHeap`. Like their min counterparts, the max
+/// methods panic on an empty heap.
+pub trait DoubleEndedHeap<K, V>: Heap<K, V> {
+    fn find_max(&self) -> (K, V);
+    fn delete_max(&mut self) -> (K, V);
+
+    /// Removes the smallest and the largest entries, or only the smallest
+    /// when it's the last one.
+    fn delete_both(&mut self) -> ((K, V), Option<(K, V)>) {
+        let min = self.delete_min();
+        let max = if self.empty() { None } else { Some(self.delete_max()) };
+        (min, max)
+    }
+}
+
+/// A heap for monotone workloads, such as Dijkstra's algorithm or a discrete
+/// event simulation, where no key goes below the last key deleted. Heaps
+/// that rely on it, such as `BucketQueue` and `CalendarQueue`, can beat
+/// comparison heaps there, and `monotone::Monotone` checks it for any heap.
+pub trait MonotoneHeap<K, V>: Heap<K, V> {
+    /// The key of the last entry taken out by `delete_min`.
+    fn last_deleted(&self) -> Option<K>;
+
+    /// Whether `key` may go in the heap without breaking monotonicity.
+    fn is_monotone(&self, key: &K) -> bool where K: Ord {
+        self.last_deleted().is_none_or(|last| *key >= last)
+    }
+}
+
+/// The error `decrease_key` and `delete` return for a handle whose entry
+/// has already been removed.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaleEntry;
+
+impl ::core::fmt::Display for StaleEntry {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "entry is no longer in the heap")
+    }
+}
+
+/// A heap that can tell whether one of its handles refers to an entry that
+/// has not been removed yet. Its `decrease_key` and `delete` return
+/// `Err(StaleEntry)` for such a handle instead of corrupting the heap, even
+/// once the entry's storage has been reused.
+///
+/// Only removal is tracked: a handle must come from this heap. Most heaps
+/// can't tell a handle of another heap, or of a dropped one, from their own;
+/// `SlotFibHeap`, whose handles carry the id of their heap, is the exception.
+pub trait AddressableHeap<K, V>: DecreaseKey<K, V> {
+    fn is_valid(&self, entry: &Self::HeapEntry) -> bool;
 }
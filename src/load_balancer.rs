@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete};
+
+/// Handle to a backend registered with a `LoadBalancer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BackendHandle(u64);
+
+/// Picks the least-loaded of a set of backends.
+///
+/// Load falling is a decrease_key. Load rising deletes and reinserts the
+/// backend, so reports of finished work are cheaper than reports of new
+/// work.
+pub struct LoadBalancer<I> {
+    heap: FibHeap<i64, u64>,
+    backends: HashMap<u64, (Rc<FibNode<i64, u64>>, I)>,
+    next_id: u64,
+}
+
+impl<I> LoadBalancer<I> {
+    pub fn new() -> LoadBalancer<I> {
+        LoadBalancer { heap: FibHeap::new(), backends: HashMap::new(), next_id: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    pub fn register(&mut self, backend: I, load: i64) -> BackendHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(load, id);
+        self.backends.insert(id, (entry, backend));
+        BackendHandle(id)
+    }
+
+    pub fn unregister(&mut self, handle: BackendHandle) -> Option<I> {
+        self.backends.remove(&handle.0).map(|(entry, backend)| {
+            self.heap.delete(entry).unwrap();
+            backend
+        })
+    }
+
+    /// The backend with the least load. Ties are broken arbitrarily.
+    pub fn pick(&self) -> Option<(BackendHandle, &I)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (_, id) = self.heap.find_min();
+        Some((BackendHandle(id), &self.backends[&id].1))
+    }
+
+    /// Adds `delta`, which may be negative, to a backend's load. Returns the
+    /// new load, or `None` if the backend is not registered.
+    pub fn report(&mut self, handle: BackendHandle, delta: i64) -> Option<i64> {
+        let id = handle.0;
+        let load = match self.backends.get(&id) {
+            Some((entry, _)) => *entry.get_key() + delta,
+            None => return None
+        };
+        if delta <= 0 {
+            self.heap.decrease_key(&self.backends[&id].0, -delta).unwrap();
+        } else {
+            let (entry, backend) = self.backends.remove(&id).unwrap();
+            self.heap.delete(entry).unwrap();
+            let entry = self.heap.insert(load, id);
+            self.backends.insert(id, (entry, backend));
+        }
+        Some(load)
+    }
+
+    pub fn load(&self, handle: BackendHandle) -> Option<i64> {
+        self.backends.get(&handle.0).map(|(entry, _)| *entry.get_key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use load_balancer::LoadBalancer;
+
+    #[test]
+    fn load_balancer_picks_least_loaded() {
+        let mut lb = LoadBalancer::new();
+        let a = lb.register("a", 3);
+        let b = lb.register("b", 1);
+        let c = lb.register("c", 2);
+        assert_eq!(lb.pick(), Some((b, &"b")));
+        assert_eq!(lb.report(b, 5), Some(6));
+        assert_eq!(lb.pick(), Some((c, &"c")));
+        assert_eq!(lb.report(a, -3), Some(0));
+        assert_eq!(lb.pick(), Some((a, &"a")));
+        assert_eq!(lb.load(b), Some(6));
+        assert_eq!(lb.unregister(a), Some("a"));
+        assert_eq!(lb.report(a, 1), None);
+        assert_eq!(lb.pick(), Some((c, &"c")));
+        assert_eq!(lb.len(), 2);
+    }
+
+    #[test]
+    fn load_balancer_spreads_work() {
+        let mut lb = LoadBalancer::new();
+        let handles: Vec<_> = (0..4).map(|i| lb.register(i, 0)).collect();
+        for _ in 0..10 {
+            let (h, _) = lb.pick().unwrap();
+            lb.report(h, 1);
+        }
+        let mut loads: Vec<i64> = handles.iter().map(|&h| lb.load(h).unwrap()).collect();
+        loads.sort();
+        assert_eq!(loads, vec!(2, 2, 3, 3));
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::atomic::Ordering;
+use crossbeam_epoch::{self as epoch, Atomic, Owned, Shared};
+
+pub use crossbeam_epoch::Guard;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // Tagged with 1 once this node has been removed from the queue, after
+    // which the pointer never changes.
+    next: Atomic<Node<K, V>>,
+}
+
+// A link in the list and the node it points to.
+type Position<'g, K, V> = (&'g Atomic<Node<K, V>>, Shared<'g, Node<K, V>>);
+
+/// A lock-free priority queue, kept as a sorted linked list in the style of
+/// Harris and Michael.
+///
+/// `delete_min` claims the first node by marking it, then unlinks it; nodes
+/// are freed through crossbeam-epoch once no thread can still be reading
+/// them. A marked node can't be given back, so `delete_min` is only
+/// linearizable against other deletions, not against concurrent inserts.
+/// Readers can `pin` the queue and look at entries by reference, which
+/// avoids cloning and touches no reference counts. Inserting walks the list,
+/// so this suits queues whose new entries mostly land near the front.
+pub struct LockFreeQueue<K, V> {
+    head: Atomic<Node<K, V>>,
+}
+
+impl<K: Ord + Clone, V: Clone> LockFreeQueue<K, V> {
+    pub fn new() -> LockFreeQueue<K, V> {
+        LockFreeQueue { head: Atomic::null() }
+    }
+
+    /// Pins the current thread, so entries seen through the guard stay
+    /// valid until it is dropped.
+    pub fn pin(&self) -> Guard {
+        epoch::pin()
+    }
+
+    /// Inserts an entry after any others with the same key.
+    pub fn insert(&self, key: K, value: V) {
+        let guard = &epoch::pin();
+        let mut node = Owned::new(Node { key: key, value: value, next: Atomic::null() });
+        loop {
+            let (link, next) = self.find(&node.key, guard);
+            node.next.store(next, Ordering::Relaxed);
+            match link.compare_exchange(next, node, Ordering::Release, Ordering::Relaxed, guard) {
+                Ok(_) => return,
+                Err(err) => node = err.new
+            }
+        }
+    }
+
+    /// Removes an entry with the smallest key at some moment during the
+    /// call. The first node is claimed without checking that it is still
+    /// first, so an entry with a smaller key inserted concurrently may be
+    /// passed over; without concurrent inserts it is the minimum.
+    pub fn delete_min(&self) -> Option<(K, V)> {
+        let guard = &epoch::pin();
+        loop {
+            let first = self.head.load(Ordering::Acquire, guard);
+            let node = unsafe { first.as_ref() }?;
+            let next = node.next.load(Ordering::Acquire, guard);
+            if next.tag() == 0 {
+                if node.next.compare_exchange(next, next.with_tag(1), Ordering::AcqRel,
+                                              Ordering::Acquire, guard).is_err() {
+                    continue
+                }
+                self.unlink(&self.head, first, next, guard);
+                return Some((node.key.clone(), node.value.clone()))
+            }
+            // Removed by another thread but not yet unlinked.
+            self.unlink(&self.head, first, next, guard);
+        }
+    }
+
+    /// The first entry, valid for as long as the guard.
+    pub fn peek<'g>(&self, guard: &'g Guard) -> Option<(&'g K, &'g V)> {
+        self.iter(guard).next()
+    }
+
+    /// Iterates over the entries in key order without removing them. Entries
+    /// inserted or removed concurrently may or may not be seen.
+    pub fn iter<'g>(&self, guard: &'g Guard) -> Iter<'g, K, V> {
+        Iter { next: self.head.load(Ordering::Acquire, guard), guard: guard }
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter(&epoch::pin()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peek(&epoch::pin()).is_none()
+    }
+
+    // Finds the link after which `key` belongs, and the node it points to,
+    // unlinking removed nodes on the way.
+    fn find<'g>(&'g self, key: &K, guard: &'g Guard) -> Position<'g, K, V> {
+        'retry: loop {
+            let mut link = &self.head;
+            let mut curr = link.load(Ordering::Acquire, guard);
+            loop {
+                let node = match unsafe { curr.as_ref() } {
+                    Some(node) => node,
+                    None => return (link, curr)
+                };
+                let next = node.next.load(Ordering::Acquire, guard);
+                if next.tag() == 1 {
+                    if !self.unlink(link, curr, next, guard) {
+                        continue 'retry
+                    }
+                    curr = next.with_tag(0);
+                } else if node.key > *key {
+                    return (link, curr)
+                } else {
+                    link = &node.next;
+                    curr = next;
+                }
+            }
+        }
+    }
+
+    // Swings `link` past a removed node, freeing it once it is safe to.
+    fn unlink<'g>(&self, link: &Atomic<Node<K, V>>, node: Shared<'g, Node<K, V>>,
+                  next: Shared<'g, Node<K, V>>, guard: &'g Guard) -> bool {
+        match link.compare_exchange(node, next.with_tag(0), Ordering::AcqRel, Ordering::Acquire, guard) {
+            Ok(_) => {
+                unsafe { guard.defer_destroy(node) };
+                true
+            }
+            Err(_) => false
+        }
+    }
+}
+
+impl<K, V> Drop for LockFreeQueue<K, V> {
+    fn drop(&mut self) {
+        // Nothing else can reach the queue any more.
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut curr = self.head.load(Ordering::Relaxed, guard);
+            while !curr.is_null() {
+                let next = curr.deref().next.load(Ordering::Relaxed, guard);
+                drop(curr.into_owned());
+                curr = next.with_tag(0);
+            }
+        }
+    }
+}
+
+/// Iterator over the entries of a `LockFreeQueue`, from `LockFreeQueue::iter`.
+pub struct Iter<'g, K: 'g, V: 'g> {
+    next: Shared<'g, Node<K, V>>,
+    guard: &'g Guard,
+}
+
+impl<'g, K, V> Iterator for Iter<'g, K, V> {
+    type Item = (&'g K, &'g V);
+
+    fn next(&mut self) -> Option<(&'g K, &'g V)> {
+        loop {
+            let node = unsafe { self.next.as_ref() }?;
+            let next = node.next.load(Ordering::Acquire, self.guard);
+            self.next = next.with_tag(0);
+            if next.tag() == 0 {
+                return Some((&node.key, &node.value))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use lock_free::LockFreeQueue;
+
+    #[test]
+    fn lock_free_queue_order() {
+        let queue = LockFreeQueue::new();
+        queue.insert(3, 'c');
+        queue.insert(1, 'a');
+        queue.insert(2, 'b');
+        queue.insert(1, 'd');
+        assert_eq!(queue.len(), 4);
+        {
+            let guard = queue.pin();
+            assert_eq!(queue.peek(&guard), Some((&1, &'a')));
+            let keys: Vec<_> = queue.iter(&guard).map(|(k, v)| (*k, *v)).collect();
+            assert_eq!(keys, vec!((1, 'a'), (1, 'd'), (2, 'b'), (3, 'c')));
+        }
+        assert_eq!(queue.delete_min(), Some((1, 'a')));
+        assert_eq!(queue.delete_min(), Some((1, 'd')));
+        assert_eq!(queue.delete_min(), Some((2, 'b')));
+        assert_eq!(queue.delete_min(), Some((3, 'c')));
+        assert_eq!(queue.delete_min(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn lock_free_queue_concurrent() {
+        let queue = Arc::new(LockFreeQueue::new());
+        let producers: Vec<_> = (0..4u32).map(|t| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for i in 0..200u32 {
+                    queue.insert(i * 4 + t, t.to_string());
+                }
+            })
+        }).collect();
+        let consumers: Vec<_> = (0..2).map(|_| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut got = Vec::new();
+                for _ in 0..300 {
+                    if let Some((k, _)) = queue.delete_min() {
+                        got.push(k);
+                    }
+                }
+                got
+            })
+        }).collect();
+        for p in producers.into_iter() {
+            p.join().unwrap();
+        }
+        let mut all: Vec<u32> = Vec::new();
+        for c in consumers.into_iter() {
+            let got = c.join().unwrap();
+            all.extend(got);
+        }
+        while let Some((k, _)) = queue.delete_min() {
+            all.push(k);
+        }
+        all.sort();
+        assert_eq!(all, (0..800).collect::<Vec<u32>>());
+    }
+}
@@ -0,0 +1,181 @@
+use std::cmp::Ordering;
+use std::mem;
+
+/// A tournament tree of losers over a fixed set of k leaves, each holding
+/// an entry or nothing, for merging k sorted sequences.
+///
+/// Each internal node keeps the leaf that lost the match played there, and
+/// the overall winner, the leaf with the smallest key, is kept on the side.
+/// `replace_min` swaps the winner's entry for the next one from its
+/// sequence and replays only the matches on that leaf's path, one
+/// comparison a level, so about log k comparisons where popping and pushing
+/// on a heap takes several times that. Ties go to the leaf with the lower
+/// index, and empty leaves lose to every entry.
+pub struct LoserTree<K, V> {
+    leaves: Vec<Option<(K, V)>>,
+    // The loser of the match at each internal node, with the root at 1 and
+    // leaf i at `leaves.len() + i`. Index 0 is unused.
+    losers: Vec<usize>,
+    winner: usize,
+    len: usize,
+}
+
+impl<K: Ord, V> LoserTree<K, V> {
+    /// Builds a tree over `leaves` with k - 1 comparisons.
+    pub fn new(leaves: Vec<Option<(K, V)>>) -> LoserTree<K, V> {
+        let k = leaves.len();
+        let len = leaves.iter().filter(|leaf| leaf.is_some()).count();
+        let mut tree = LoserTree { leaves: leaves, losers: vec!(0; k), winner: 0, len: len };
+        let mut winners = vec!(0; 2 * k);
+        for i in 0..k {
+            winners[k + i] = i;
+        }
+        for node in (1..k).rev() {
+            let (a, b) = (winners[2 * node], winners[2 * node + 1]);
+            let (winner, loser) = if tree.beats(b, a) { (b, a) } else { (a, b) };
+            winners[node] = winner;
+            tree.losers[node] = loser;
+        }
+        if k > 1 {
+            tree.winner = winners[1];
+        }
+        tree
+    }
+
+    /// The number of leaves holding an entry.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The leaf holding the smallest entry.
+    pub fn winner(&self) -> Option<usize> {
+        if self.len == 0 { None } else { Some(self.winner) }
+    }
+
+    pub fn peek(&self) -> Option<(&K, &V)> {
+        self.leaves.get(self.winner)?.as_ref().map(|(k, v)| (k, v))
+    }
+
+    /// Takes out the smallest entry, putting `entry` in its leaf, or
+    /// leaving the leaf empty if it's None. Panics if every leaf is empty.
+    pub fn replace_min(&mut self, entry: Option<(K, V)>) -> (K, V) {
+        if self.len == 0 {
+            panic!("LoserTree is empty");
+        }
+        if entry.is_none() {
+            self.len -= 1;
+        }
+        let min = mem::replace(&mut self.leaves[self.winner], entry).unwrap();
+        let k = self.leaves.len();
+        let mut winner = self.winner;
+        let mut node = (k + winner) / 2;
+        while node > 0 {
+            if self.beats(self.losers[node], winner) {
+                mem::swap(&mut self.losers[node], &mut winner);
+            }
+            node /= 2;
+        }
+        self.winner = winner;
+        min
+    }
+
+    // Whether leaf `a` wins its match against leaf `b`.
+    fn beats(&self, a: usize, b: usize) -> bool {
+        match (&self.leaves[a], &self.leaves[b]) {
+            (&Some((ref x, _)), &Some((ref y, _))) => match x.cmp(y) {
+                Ordering::Less => true,
+                Ordering::Greater => false,
+                Ordering::Equal => a < b
+            },
+            (&Some(_), &None) => true,
+            (&None, &Some(_)) => false,
+            (&None, &None) => a < b
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use loser_tree::LoserTree;
+
+    thread_local!(static COMPARISONS: Cell<usize> = const { Cell::new(0) });
+
+    // A key counting how often it's compared.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Counted(u32);
+
+    impl PartialOrd for Counted {
+        fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Counted {
+        fn cmp(&self, other: &Counted) -> Ordering {
+            COMPARISONS.with(|c| c.set(c.get() + 1));
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn loser_tree_merges() {
+        for k in 0..12u32 {
+            // Leaf i holds the multiples of k + 1 that leave remainder i,
+            // and some leaves start out empty.
+            let mut runs: Vec<_> = (0..k).map(|i| (0..20).map(move |j| j * (k + 1) + i)).collect();
+            let leaves = runs.iter_mut()
+                .enumerate()
+                .map(|(i, run)| if i % 5 == 3 { None } else { run.next().map(|key| (key, i)) })
+                .collect();
+            let mut tree = LoserTree::new(leaves);
+            let mut merged = Vec::new();
+            while let Some(i) = tree.winner() {
+                assert_eq!(tree.peek().map(|(_, &v)| v), Some(i));
+                let next = runs[i].next().map(|key| (key, i));
+                merged.push(tree.replace_min(next).0);
+            }
+            let mut expected: Vec<u32> = (0..k).filter(|i| i % 5 != 3)
+                .flat_map(|i| (0..20).map(move |j| j * (k + 1) + i))
+                .collect();
+            expected.sort();
+            assert_eq!(merged, expected);
+            assert!(tree.empty());
+            assert_eq!(tree.peek(), None);
+        }
+    }
+
+    #[test]
+    fn loser_tree_ties_go_to_lower_leaf() {
+        let mut tree = LoserTree::new(vec!(Some((2, 'a')), Some((1, 'b')), Some((1, 'c'))));
+        assert_eq!(tree.replace_min(Some((2, 'd'))), (1, 'b'));
+        assert_eq!(tree.replace_min(None), (1, 'c'));
+        assert_eq!(tree.replace_min(None), (2, 'a'));
+        assert_eq!(tree.replace_min(None), (2, 'd'));
+        assert!(tree.empty());
+    }
+
+    #[test]
+    fn loser_tree_comparisons() {
+        let leaves = (0..16).map(|i| Some((Counted(i), ()))).collect();
+        COMPARISONS.with(|c| c.set(0));
+        let mut tree = LoserTree::new(leaves);
+        assert_eq!(COMPARISONS.with(|c| c.get()), 15);
+        for i in 0..1000 {
+            COMPARISONS.with(|c| c.set(0));
+            assert_eq!(tree.replace_min(Some((Counted(16 + i), ()))).0, Counted(i));
+            assert_eq!(COMPARISONS.with(|c| c.get()), 4);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "LoserTree is empty")]
+    fn loser_tree_empty() {
+        LoserTree::<u32, ()>::new(vec!(None, None)).replace_min(None);
+    }
+}
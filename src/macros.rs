@@ -0,0 +1,34 @@
+/// Builds a heap from `key => value` pairs, starting from the heap
+/// `$new` evaluates to.
+///
+/// ```ignore
+/// let heap = heap![ArrayHeap::<_, _, 8>::new(); 3 => 'c', 1 => 'a'];
+/// ```
+#[macro_export]
+macro_rules! heap {
+    ($new:expr; $($k:expr => $v:expr),* $(,)*) => {{
+        // With no pairs, nothing mutates the heap.
+        #[allow(unused_mut)]
+        let mut heap = $new;
+        $($crate::Heap::insert(&mut heap, $k, $v);)*
+        heap
+    }};
+}
+
+/// Builds a `FibHeap` from `key => value` pairs, e.g.
+/// `fibheap!{3 => 'c', 1 => 'a'}`.
+///
+/// The heap is consolidated once every pair is in, as if the minimum had
+/// been deleted and reinserted, so it has trees rather than a flat list of
+/// roots.
+#[macro_export]
+macro_rules! fibheap {
+    ($($k:expr => $v:expr),* $(,)*) => {{
+        let mut heap = $crate::heap![$crate::fibonacci_heap::FibHeap::new(); $($k => $v),*];
+        if !$crate::Heap::empty(&heap) {
+            let (k, v) = $crate::Heap::delete_min(&mut heap);
+            $crate::Heap::insert(&mut heap, k, v);
+        }
+        heap
+    }};
+}
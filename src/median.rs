@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use rev::Rev;
+use {Heap, HeapDelete};
+
+/// Handle to a value inserted into a `RunningMedian`, used to remove it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MedianEntry(u64);
+
+enum Side<T> {
+    Low(Rc<FibNode<Ordered<Rev<T>>, u64>>),
+    High(Rc<FibNode<Ordered<T>, u64>>),
+}
+
+/// Tracks the median of a changing collection of values with a max-heap of
+/// the lower half and a min-heap of the upper half.
+///
+/// The lower half holds as many values as the upper half, or one more.
+pub struct RunningMedian<T> {
+    low: FibHeap<Ordered<Rev<T>>, u64>,
+    high: FibHeap<Ordered<T>, u64>,
+    // Values move between the halves when rebalancing, so entries map to
+    // whichever heap currently holds them.
+    entries: HashMap<u64, Side<T>>,
+    next_id: u64,
+}
+
+impl<T: Ord + Clone> RunningMedian<T> {
+    pub fn new() -> RunningMedian<T> {
+        RunningMedian {
+            low: FibHeap::new(),
+            high: FibHeap::new(),
+            entries: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn insert(&mut self, value: T) -> MedianEntry {
+        let id = self.next_id;
+        self.next_id += 1;
+        let to_low = self.low.empty() || value <= self.low_top();
+        let side = if to_low {
+            Side::Low(self.low.insert(Ordered(Rev(value)), id))
+        } else {
+            Side::High(self.high.insert(Ordered(value), id))
+        };
+        self.entries.insert(id, side);
+        self.rebalance();
+        MedianEntry(id)
+    }
+
+    /// Removes a previously inserted value, or returns `None` if it was
+    /// already removed.
+    pub fn remove(&mut self, entry: MedianEntry) -> Option<T> {
+        let value = match self.entries.remove(&entry.0) {
+            Some(Side::Low(node)) => {
+                let (Ordered(Rev(value)), _) = self.low.delete(node).unwrap();
+                value
+            }
+            Some(Side::High(node)) => {
+                let (Ordered(value), _) = self.high.delete(node).unwrap();
+                value
+            }
+            None => return None
+        };
+        self.rebalance();
+        Some(value)
+    }
+
+    /// The lower median: the middle value, or the smaller of the two middle
+    /// values when there is an even number of them.
+    pub fn median(&self) -> Option<T> {
+        if self.low.empty() {
+            None
+        } else {
+            Some(self.low_top())
+        }
+    }
+
+    /// Both middle values, which are the same value when there is an odd
+    /// number of them.
+    pub fn medians(&self) -> Option<(T, T)> {
+        self.median().map(|low| {
+            if self.low.len() > self.high.len() {
+                (low.clone(), low)
+            } else {
+                let (Ordered(high), _) = self.high.find_min();
+                (low, high)
+            }
+        })
+    }
+
+    fn low_top(&self) -> T {
+        let (Ordered(Rev(value)), _) = self.low.find_min();
+        value
+    }
+
+    fn rebalance(&mut self) {
+        if self.low.len() > self.high.len() + 1 {
+            let (Ordered(Rev(value)), id) = self.low.delete_min();
+            let node = self.high.insert(Ordered(value), id);
+            self.entries.insert(id, Side::High(node));
+        } else if self.high.len() > self.low.len() {
+            let (Ordered(value), id) = self.high.delete_min();
+            let node = self.low.insert(Ordered(Rev(value)), id);
+            self.entries.insert(id, Side::Low(node));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use median::RunningMedian;
+
+    #[test]
+    fn median_insert() {
+        let mut median = RunningMedian::new();
+        assert_eq!(median.median(), None);
+        median.insert(5);
+        assert_eq!(median.median(), Some(5));
+        median.insert(1);
+        assert_eq!(median.medians(), Some((1, 5)));
+        median.insert(9);
+        assert_eq!(median.medians(), Some((5, 5)));
+        median.insert(7);
+        median.insert(8);
+        assert_eq!(median.median(), Some(7));
+        assert_eq!(median.len(), 5);
+    }
+
+    #[test]
+    fn median_remove() {
+        let mut median = RunningMedian::new();
+        let entries: Vec<_> = vec!(4, 8, 1, 6, 3, 9, 2).into_iter().map(|v| median.insert(v)).collect();
+        assert_eq!(median.median(), Some(4));
+        assert_eq!(median.remove(entries[0]), Some(4));
+        assert_eq!(median.remove(entries[0]), None);
+        assert_eq!(median.medians(), Some((3, 6)));
+        assert_eq!(median.remove(entries[5]), Some(9));
+        assert_eq!(median.remove(entries[3]), Some(6));
+        assert_eq!(median.remove(entries[1]), Some(8));
+        assert_eq!(median.medians(), Some((2, 2)));
+        assert_eq!(median.remove(entries[2]), Some(1));
+        assert_eq!(median.remove(entries[6]), Some(2));
+        assert_eq!(median.median(), Some(3));
+        assert_eq!(median.remove(entries[4]), Some(3));
+        assert_eq!(median.median(), None);
+    }
+}
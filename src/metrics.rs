@@ -0,0 +1,30 @@
+use std::ops::Add;
+
+/// Counts of the work done by a `FibHeap`, from `FibHeap::metrics`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Key comparisons between nodes.
+    pub comparisons: u64,
+    /// Roots linked under other roots while consolidating.
+    pub links: u64,
+    /// Nodes cut from their parents, including by cascading cuts.
+    pub cuts: u64,
+    /// Cuts of marked nodes made while cascading up from a cut.
+    pub cascading_cuts: u64,
+    /// Passes over the root list.
+    pub consolidations: u64,
+}
+
+impl Add for Metrics {
+    type Output = Metrics;
+
+    fn add(self, other: Metrics) -> Metrics {
+        Metrics {
+            comparisons: self.comparisons + other.comparisons,
+            links: self.links + other.links,
+            cuts: self.cuts + other.cuts,
+            cascading_cuts: self.cascading_cuts + other.cascading_cuts,
+            consolidations: self.consolidations + other.consolidations,
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::ops::Sub;
+use {Heap, DoubleEndedHeap};
+
+/// A min-max heap: an implicit binary tree in a `Vec` whose even levels are
+/// ordered like a min-heap and odd levels like a max-heap, so the minimum
+/// is at the root and the maximum is one of its children.
+pub struct MinMaxHeap<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+// Whether the entry at `i` is on a min level.
+fn min_level(i: usize) -> bool {
+    (i + 1).ilog2().is_multiple_of(2)
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> MinMaxHeap<K, V> {
+    pub const fn new() -> MinMaxHeap<K, V> {
+        MinMaxHeap { entries: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> MinMaxHeap<K, V> {
+        MinMaxHeap { entries: Vec::with_capacity(capacity) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Whether the entry at `a` belongs above the one at `b` on a min level,
+    // or on a max level if `min` is false.
+    fn before(&self, a: usize, b: usize, min: bool) -> bool {
+        if min {
+            self.entries[a].0 < self.entries[b].0
+        } else {
+            self.entries[a].0 > self.entries[b].0
+        }
+    }
+
+    fn max_position(&self) -> Option<usize> {
+        match self.entries.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => Some(if self.before(2, 1, false) { 2 } else { 1 })
+        }
+    }
+
+    fn bubble_up(&mut self, i: usize) {
+        if i == 0 {
+            return
+        }
+        let parent = (i - 1) / 2;
+        let min = min_level(i);
+        if self.before(parent, i, min) {
+            self.entries.swap(i, parent);
+            self.bubble_up_grandparents(parent, !min);
+        } else {
+            self.bubble_up_grandparents(i, min);
+        }
+    }
+
+    fn bubble_up_grandparents(&mut self, mut i: usize, min: bool) {
+        while i > 2 {
+            let grandparent = ((i - 1) / 2 - 1) / 2;
+            if !self.before(i, grandparent, min) {
+                break
+            }
+            self.entries.swap(i, grandparent);
+            i = grandparent;
+        }
+    }
+
+    fn trickle_down(&mut self, mut i: usize) {
+        let min = min_level(i);
+        let len = self.entries.len();
+        loop {
+            // The best of the children and grandchildren.
+            let first_child = 2 * i + 1;
+            let first_grandchild = 4 * i + 3;
+            let mut best = None;
+            let candidates = (first_child..len.min(first_child + 2))
+                .chain(first_grandchild..len.min(first_grandchild + 4));
+            for c in candidates {
+                if best.is_none_or(|b| self.before(c, b, min)) {
+                    best = Some(c);
+                }
+            }
+            let m = match best {
+                Some(m) if self.before(m, i, min) => m,
+                _ => return
+            };
+            self.entries.swap(i, m);
+            if m < first_grandchild {
+                return
+            }
+            let parent = (m - 1) / 2;
+            if self.before(parent, m, min) {
+                self.entries.swap(m, parent);
+            }
+            i = m;
+        }
+    }
+
+    fn remove(&mut self, i: usize) -> (K, V) {
+        let entry = self.entries.swap_remove(i);
+        if i < self.entries.len() {
+            self.trickle_down(i);
+        }
+        entry
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for MinMaxHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        self.entries.first().cloned().expect("MinMaxHeap is empty")
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        if self.entries.is_empty() {
+            panic!("MinMaxHeap is empty");
+        }
+        self.remove(0)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.entries.push((key, value));
+        let last = self.entries.len() - 1;
+        self.bubble_up(last);
+    }
+
+    fn empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DoubleEndedHeap<K, V> for MinMaxHeap<K, V> {
+    fn find_max(&self) -> (K, V) {
+        let i = self.max_position().expect("MinMaxHeap is empty");
+        self.entries[i].clone()
+    }
+
+    fn delete_max(&mut self) -> (K, V) {
+        let i = self.max_position().expect("MinMaxHeap is empty");
+        self.remove(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use min_max_heap::MinMaxHeap;
+    use {Heap, DoubleEndedHeap};
+
+    #[test]
+    fn min_max_heap_both_ends() {
+        let mut heap = MinMaxHeap::new();
+        for i in 0..500u32 {
+            heap.insert((i * 193) % 500, i);
+        }
+        let mut model: Vec<u32> = (0..500).collect();
+        for round in 0..500 {
+            if round % 3 == 0 {
+                assert_eq!(heap.find_max().0, *model.last().unwrap());
+                assert_eq!(heap.delete_max().0, model.pop().unwrap());
+            } else {
+                assert_eq!(heap.find_min().0, model[0]);
+                assert_eq!(heap.delete_min().0, model.remove(0));
+            }
+            if round % 7 == 0 {
+                let k = (round * 31) % 500;
+                heap.insert(k, 0);
+                let at = model.binary_search(&k).unwrap_or_else(|e| e);
+                model.insert(at, k);
+            }
+            assert_eq!(heap.len(), model.len());
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::mem;
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use Heap;
+
+/// Identifies a job in a `Mlfq`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct JobId(u64);
+
+struct Job<T> {
+    task: T,
+    level: usize,
+    // Time used at the current level, counted against its allotment.
+    used: u64,
+    running: bool,
+}
+
+/// A multi-level feedback queue scheduler.
+///
+/// Level 0 has the highest priority, and jobs within a level run round-robin.
+/// New jobs start at level 0. A job that uses up the allotment of its level,
+/// over however many runs, moves down a level; a job that gives up the CPU
+/// earlier keeps its level. Every `boost_interval` units of time, all jobs
+/// move back to level 0 so long-running jobs don't starve.
+pub struct Mlfq<T> {
+    // Each level is a FIFO: jobs are keyed by the order they were queued.
+    levels: Vec<FibHeap<Ordered<u64>, u64>>,
+    allotments: Vec<u64>,
+    jobs: HashMap<u64, Job<T>>,
+    boost_interval: u64,
+    since_boost: u64,
+    next_id: u64,
+    next_seq: u64,
+}
+
+impl<T> Mlfq<T> {
+    /// A scheduler with one level per allotment, highest priority first.
+    pub fn new(allotments: Vec<u64>, boost_interval: u64) -> Mlfq<T> {
+        assert!(!allotments.is_empty(), "Mlfq needs at least one level");
+        Mlfq {
+            levels: allotments.iter().map(|_| FibHeap::new()).collect(),
+            allotments: allotments,
+            jobs: HashMap::new(),
+            boost_interval: boost_interval,
+            since_boost: 0,
+            next_id: 0,
+            next_seq: 0,
+        }
+    }
+
+    /// The number of jobs, whether waiting or running.
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn spawn(&mut self, task: T) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.insert(id, Job { task: task, level: 0, used: 0, running: false });
+        self.enqueue(id, 0);
+        JobId(id)
+    }
+
+    /// Takes the next job to run from the highest non-empty level, along
+    /// with the time left in its allotment.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<(JobId, u64)> {
+        let level = self.levels.iter().position(|l| !l.empty())?;
+        let (_, id) = self.levels[level].delete_min();
+        let job = self.jobs.get_mut(&id).unwrap();
+        job.running = true;
+        Some((JobId(id), self.allotments[level] - job.used))
+    }
+
+    /// Puts a running job back in the queue after it ran for `used` units of
+    /// time. Returns whether `job` was running.
+    pub fn requeue(&mut self, job: JobId, used: u64) -> bool {
+        let level = {
+            let bottom = self.levels.len() - 1;
+            let allotments = &self.allotments;
+            let job = match self.jobs.get_mut(&job.0) {
+                Some(j) if j.running => j,
+                _ => return false
+            };
+            job.running = false;
+            job.used += used;
+            if job.used >= allotments[job.level] {
+                job.level = if job.level < bottom { job.level + 1 } else { bottom };
+                job.used = 0;
+            }
+            job.level
+        };
+        self.enqueue(job.0, level);
+        self.tick(used);
+        true
+    }
+
+    /// Removes a running job that completed after running for `used` units
+    /// of time, returning its task.
+    pub fn finish(&mut self, job: JobId, used: u64) -> Option<T> {
+        match self.jobs.get(&job.0) {
+            Some(j) if j.running => (),
+            _ => return None
+        }
+        let task = self.jobs.remove(&job.0).unwrap().task;
+        self.tick(used);
+        Some(task)
+    }
+
+    pub fn level(&self, job: JobId) -> Option<usize> {
+        self.jobs.get(&job.0).map(|j| j.level)
+    }
+
+    pub fn task(&self, job: JobId) -> Option<&T> {
+        self.jobs.get(&job.0).map(|j| &j.task)
+    }
+
+    /// Moves every job back to level 0 with a fresh allotment. Waiting jobs
+    /// keep their relative order within each level, with higher levels
+    /// ahead of lower ones.
+    pub fn boost(&mut self) {
+        self.since_boost = 0;
+        for job in self.jobs.values_mut() {
+            job.level = 0;
+            job.used = 0;
+        }
+        for level in 1..self.levels.len() {
+            let mut lower = mem::replace(&mut self.levels[level], FibHeap::new());
+            while !lower.empty() {
+                let (_, id) = lower.delete_min();
+                self.enqueue(id, 0);
+            }
+        }
+    }
+
+    fn enqueue(&mut self, id: u64, level: usize) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.levels[level].insert(Ordered(seq), id);
+    }
+
+    fn tick(&mut self, used: u64) {
+        self.since_boost += used;
+        if self.since_boost >= self.boost_interval {
+            self.boost();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mlfq::Mlfq;
+
+    #[test]
+    fn mlfq_demotes_cpu_bound_jobs() {
+        let mut mlfq = Mlfq::new(vec!(2, 4, 8), 1000);
+        let cpu = mlfq.spawn("cpu");
+        let io = mlfq.spawn("io");
+
+        assert_eq!(mlfq.next(), Some((cpu, 2)));
+        assert!(mlfq.requeue(cpu, 2));
+        assert_eq!(mlfq.level(cpu), Some(1));
+
+        assert_eq!(mlfq.next(), Some((io, 2)));
+        assert!(mlfq.requeue(io, 1));
+        assert_eq!(mlfq.level(io), Some(0));
+        assert_eq!(mlfq.next(), Some((io, 1)));
+        assert!(mlfq.requeue(io, 1));
+        assert_eq!(mlfq.level(io), Some(1));
+
+        // Round-robin within level 1.
+        assert_eq!(mlfq.next(), Some((cpu, 4)));
+        assert!(!mlfq.requeue(io, 1));
+        assert_eq!(mlfq.finish(cpu, 3), Some("cpu"));
+        assert_eq!(mlfq.next(), Some((io, 4)));
+        assert_eq!(mlfq.finish(io, 1), Some("io"));
+        assert_eq!(mlfq.next(), None);
+        assert_eq!(mlfq.len(), 0);
+    }
+
+    #[test]
+    fn mlfq_bottom_level_and_boost() {
+        let mut mlfq = Mlfq::new(vec!(1, 1), 4);
+        let a = mlfq.spawn('a');
+        let b = mlfq.spawn('b');
+        for _ in 0..2 {
+            let (job, slice) = mlfq.next().unwrap();
+            mlfq.requeue(job, slice);
+        }
+        assert_eq!(mlfq.level(a), Some(1));
+        assert_eq!(mlfq.next(), Some((a, 1)));
+        mlfq.requeue(a, 1);
+        assert_eq!(mlfq.level(a), Some(1));
+
+        let c = mlfq.spawn('c');
+        assert_eq!(mlfq.next(), Some((c, 1)));
+        // Total time reaches the boost interval.
+        mlfq.requeue(c, 1);
+        assert_eq!(mlfq.level(c), Some(0));
+        assert_eq!(mlfq.level(b), Some(0));
+        assert_eq!(mlfq.task(b), Some(&'b'));
+        let order: Vec<_> = (0..3).map(|_| mlfq.next().unwrap().0).collect();
+        assert_eq!(order, vec!(b, a, c));
+    }
+}
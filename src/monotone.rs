@@ -0,0 +1,142 @@
+use core::fmt;
+use {Heap, DecreaseKey, HeapDelete, MonotoneHeap, StaleEntry};
+
+/// The entry `Monotone::try_insert` turned away, as its key is below the
+/// last key deleted.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotMonotone<K, V>(pub K, pub V);
+
+impl<K, V> fmt::Display for NotMonotone<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "key is below the last deleted key")
+    }
+}
+
+/// Wraps a heap to hold a workload to monotonicity, remembering the last key
+/// deleted so that the heap is a `MonotoneHeap`.
+///
+/// In debug builds, inserting a key below the last one deleted, or
+/// decreasing a key below it, panics. Release builds skip the checks, which
+/// for decrease_key cost a find_min, but `try_insert` always checks.
+pub struct Monotone<K, H> {
+    heap: H,
+    last: Option<K>,
+}
+
+impl<K, H> Monotone<K, H> {
+    pub fn new(heap: H) -> Monotone<K, H> {
+        Monotone { heap: heap, last: None }
+    }
+
+    pub fn into_inner(self) -> H {
+        self.heap
+    }
+}
+
+impl<K: Ord + Clone, H> Monotone<K, H> {
+    /// Inserts the entry, or returns it if its key is below the last key
+    /// deleted.
+    pub fn try_insert<V>(&mut self, key: K, value: V) -> Result<H::HeapEntry, NotMonotone<K, V>>
+    where H: Heap<K, V> {
+        if !self.is_monotone(&key) {
+            return Err(NotMonotone(key, value))
+        }
+        Ok(self.heap.insert(key, value))
+    }
+}
+
+impl<K: Ord + Clone, V, H: Heap<K, V>> Heap<K, V> for Monotone<K, H> {
+    type HeapEntry = H::HeapEntry;
+
+    fn find_min(&self) -> (K, V) {
+        self.heap.find_min()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        let (key, value) = self.heap.delete_min();
+        self.last = Some(key.clone());
+        (key, value)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> H::HeapEntry {
+        if cfg!(debug_assertions) && !self.is_monotone(&key) {
+            panic!("key inserted below the last deleted key");
+        }
+        self.heap.insert(key, value)
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+}
+
+impl<K: Ord + Clone, V, H: DecreaseKey<K, V>> DecreaseKey<K, V> for Monotone<K, H> {
+    fn decrease_key(&mut self, entry: &H::HeapEntry, delta: K) -> Result<(), StaleEntry> {
+        self.heap.decrease_key(entry, delta)?;
+        if cfg!(debug_assertions) && self.last.is_some() && !self.is_monotone(&self.heap.find_min().0) {
+            panic!("key decreased below the last deleted key");
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone, V, H: Heap<K, V>> MonotoneHeap<K, V> for Monotone<K, H> {
+    fn last_deleted(&self) -> Option<K> {
+        self.last.clone()
+    }
+}
+
+impl<K, V, H: HeapDelete<K, V>> HeapDelete<K, V> for Monotone<K, H> {
+    type HeapEntry = H::HeapEntry;
+
+    fn delete(&mut self, entry: H::HeapEntry) -> Result<(K, V), StaleEntry> {
+        self.heap.delete(entry)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use fibonacci_heap::FibHeap;
+    use monotone::{Monotone, NotMonotone};
+    use {Heap, DecreaseKey, MonotoneHeap};
+
+    #[test]
+    fn monotone_tracks_last_deleted() {
+        let mut heap = Monotone::new(FibHeap::new());
+        heap.insert(5u32, 'a');
+        let b = heap.insert(9, 'b');
+        assert_eq!(heap.last_deleted(), None);
+        assert_eq!(heap.delete_min(), (5, 'a'));
+        assert_eq!(heap.last_deleted(), Some(5));
+        assert!(heap.is_monotone(&5));
+        assert!(!heap.is_monotone(&4));
+        heap.decrease_key(&b, 4).unwrap();
+        assert_eq!(heap.try_insert(3, 'c').unwrap_err(), NotMonotone(3, 'c'));
+        assert!(heap.try_insert(7, 'd').is_ok());
+        assert_eq!(heap.delete_min(), (5, 'b'));
+        assert_eq!(heap.delete_min(), (7, 'd'));
+        assert!(heap.into_inner().empty());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "key inserted below the last deleted key")]
+    fn monotone_insert_below() {
+        let mut heap = Monotone::new(FibHeap::new());
+        heap.insert(5u32, ());
+        heap.delete_min();
+        heap.insert(4, ());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "key decreased below the last deleted key")]
+    fn monotone_decrease_below() {
+        let mut heap = Monotone::new(FibHeap::new());
+        heap.insert(5u32, ());
+        let entry = heap.insert(8, ());
+        heap.delete_min();
+        heap.decrease_key(&entry, 4).unwrap();
+    }
+}
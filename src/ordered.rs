@@ -0,0 +1,22 @@
+use std::fmt;
+use std::ops::Sub;
+
+// Wraps a key that is only ever compared, for users of the crate's heaps that
+// never call decrease_key. The heaps require keys to support `-`, so `Sub` is
+// implemented but must not be used.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ordered<T>(pub T);
+
+impl<T> Sub for Ordered<T> {
+    type Output = Ordered<T>;
+
+    fn sub(self, _: Ordered<T>) -> Ordered<T> {
+        panic!("Ordered keys can't be decreased")
+    }
+}
+
+impl<T> fmt::Debug for Ordered<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Ordered(..)")
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt::Debug;
+use std::ops::Sub;
+use fibonacci_heap::{FibHeap};
+use Heap;
+
+// A FibHeap that never hands out entries, so it can be sent between threads.
+//
+// FibHeap nodes are reference counted with `Rc`, which isn't `Send`. Here
+// every reference to a node is held by the heap itself, since the handles
+// returned by insert are dropped at once, so moving the whole heap to
+// another thread leaves no references behind.
+pub struct OwnedHeap<K, V> {
+    heap: FibHeap<K, V>,
+}
+
+unsafe impl<K: Send, V: Send> Send for OwnedHeap<K, V> {}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> OwnedHeap<K, V> {
+    pub const fn new() -> OwnedHeap<K, V> {
+        OwnedHeap { heap: FibHeap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.heap.insert(key, value);
+    }
+
+    pub fn find_min(&self) -> Option<(K, V)> {
+        if self.heap.empty() { None } else { Some(self.heap.find_min()) }
+    }
+
+    pub fn delete_min(&mut self) -> Option<(K, V)> {
+        if self.heap.empty() { None } else { Some(self.heap.delete_min()) }
+    }
+}
@@ -0,0 +1,333 @@
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Sub;
+use std::rc::{Rc, Weak};
+use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
+
+type Link<K, V> = Rc<RefCell<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // The leftmost child.
+    child: Option<Link<K, V>>,
+    // The sibling to the right.
+    next: Option<Link<K, V>>,
+    // The sibling to the left, or the parent of a leftmost child.
+    prev: Option<Weak<RefCell<Node<K, V>>>>,
+    // Set once the entry has been taken out of the heap.
+    removed: bool,
+}
+
+/// Handle to an entry of a `PairingHeap`. Handles compare and hash by the
+/// entry they refer to, and stay valid when their heap is merged into
+/// another.
+pub struct PairingEntry<K, V>(Link<K, V>);
+
+impl<K, V> Clone for PairingEntry<K, V> {
+    fn clone(&self) -> PairingEntry<K, V> {
+        PairingEntry(self.0.clone())
+    }
+}
+
+impl<K, V> PartialEq for PairingEntry<K, V> {
+    fn eq(&self, other: &PairingEntry<K, V>) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<K, V> Eq for PairingEntry<K, V> {}
+
+impl<K, V> Hash for PairingEntry<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&*self.0 as *const RefCell<Node<K, V>>).hash(state)
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for PairingEntry<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let node = self.0.borrow();
+        f.debug_struct("PairingEntry")
+            .field("key", &node.key)
+            .field("value", &node.value)
+            .field("live", &!node.removed)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<K: defmt::Format, V: defmt::Format> defmt::Format for PairingEntry<K, V> {
+    fn format(&self, f: defmt::Formatter) {
+        let node = self.0.borrow();
+        defmt::write!(f, "PairingEntry {{ key: {}, value: {}, live: {=bool} }}", node.key, node.value, !node.removed)
+    }
+}
+
+/// A pairing heap: a single tree where inserting, melding and decreasing a
+/// key link two trees by comparing their roots, and deleting the minimum
+/// pairs up the root's children from left to right, then links the pairs
+/// from right to left. The bounds are amortized, like `FibHeap`'s, but the
+/// constant factors are usually smaller.
+pub struct PairingHeap<K, V> {
+    root: Option<Link<K, V>>,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> PairingHeap<K, V> {
+    pub const fn new() -> PairingHeap<K, V> {
+        PairingHeap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // Links two detached trees, making the root with the larger key the
+    // leftmost child of the other.
+    fn link(a: Link<K, V>, b: Link<K, V>) -> Link<K, V> {
+        let (parent, child) = if b.borrow().key < a.borrow().key { (b, a) } else { (a, b) };
+        {
+            let mut p = parent.borrow_mut();
+            let mut c = child.borrow_mut();
+            if let Some(ref first) = p.child {
+                first.borrow_mut().prev = Some(Rc::downgrade(&child));
+            }
+            c.next = p.child.take();
+            c.prev = Some(Rc::downgrade(&parent));
+        }
+        parent.borrow_mut().child = Some(child);
+        parent
+    }
+
+    fn meld(&mut self, tree: Link<K, V>) {
+        self.root = Some(match self.root.take() {
+            Some(root) => PairingHeap::link(root, tree),
+            None => tree
+        });
+    }
+
+    // Links a list of siblings into one tree, in two passes.
+    fn pair(first: Option<Link<K, V>>) -> Option<Link<K, V>> {
+        let mut trees = Vec::new();
+        let mut sibling = first;
+        while let Some(node) = sibling {
+            sibling = {
+                let mut n = node.borrow_mut();
+                n.prev = None;
+                n.next.take()
+            };
+            trees.push(node);
+        }
+        let mut pairs = Vec::with_capacity(trees.len().div_ceil(2));
+        let mut trees = trees.into_iter();
+        while let Some(a) = trees.next() {
+            pairs.push(match trees.next() {
+                Some(b) => PairingHeap::link(a, b),
+                None => a
+            });
+        }
+        let mut pairs = pairs.into_iter().rev();
+        pairs.next().map(|last| pairs.fold(last, |tree, pair| PairingHeap::link(pair, tree)))
+    }
+
+    // Cuts the subtree rooted at `node` out of its parent's child list.
+    fn detach(node: &Link<K, V>) {
+        let (prev, next) = {
+            let mut n = node.borrow_mut();
+            (n.prev.take(), n.next.take())
+        };
+        let prev = prev.and_then(|p| p.upgrade()).expect("node is not in a tree");
+        if let Some(ref next) = next {
+            next.borrow_mut().prev = Some(Rc::downgrade(&prev));
+        }
+        let mut p = prev.borrow_mut();
+        let leftmost = match p.child {
+            Some(ref child) => Rc::ptr_eq(child, node),
+            None => false
+        };
+        if leftmost {
+            p.child = next;
+        } else {
+            p.next = next;
+        }
+    }
+
+    // Takes a root that has been cut loose out of the heap.
+    fn take(&mut self, node: Link<K, V>) -> (K, V) {
+        let mut n = node.borrow_mut();
+        n.removed = true;
+        if let Some(tree) = PairingHeap::pair(n.child.take()) {
+            self.meld(tree);
+        }
+        self.len -= 1;
+        (n.key.clone(), n.value.clone())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for PairingHeap<K, V> {
+    type HeapEntry = PairingEntry<K, V>;
+
+    fn find_min(&self) -> (K, V) {
+        match self.root {
+            Some(ref root) => {
+                let root = root.borrow();
+                (root.key.clone(), root.value.clone())
+            }
+            None => panic!("PairingHeap is empty")
+        }
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        match self.root.take() {
+            Some(root) => self.take(root),
+            None => panic!("PairingHeap is empty")
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) -> PairingEntry<K, V> {
+        let node = Rc::new(RefCell::new(Node {
+            key: key, value: value, child: None, next: None, prev: None, removed: false
+        }));
+        self.meld(node.clone());
+        self.len += 1;
+        PairingEntry(node)
+    }
+
+    fn empty(&self) -> bool {
+        self.root.is_none()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for PairingHeap<K, V> {
+    fn decrease_key(&mut self, entry: &PairingEntry<K, V>, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        {
+            let mut n = entry.0.borrow_mut();
+            n.key = n.key.clone() - delta;
+        }
+        if entry.0.borrow().prev.is_some() {
+            PairingHeap::detach(&entry.0);
+            self.meld(entry.0.clone());
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapExt for PairingHeap<K, V> {
+    fn merge(mut self, mut other: PairingHeap<K, V>) -> PairingHeap<K, V> {
+        if let Some(tree) = other.root.take() {
+            self.meld(tree);
+        }
+        self.len += mem::replace(&mut other.len, 0);
+        self
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapDelete<K, V> for PairingHeap<K, V> {
+    type HeapEntry = PairingEntry<K, V>;
+
+    fn delete(&mut self, entry: PairingEntry<K, V>) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        if entry.0.borrow().prev.is_some() {
+            PairingHeap::detach(&entry.0);
+        } else {
+            self.root = None;
+        }
+        Ok(self.take(entry.0))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> AddressableHeap<K, V> for PairingHeap<K, V> {
+    fn is_valid(&self, entry: &PairingEntry<K, V>) -> bool {
+        !entry.0.borrow().removed
+    }
+}
+
+// Dropping the links one at a time, since a chain of siblings as long as
+// the heap would otherwise be dropped recursively and overflow the stack.
+impl<K, V> Drop for PairingHeap<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Link<K, V>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let mut n = node.borrow_mut();
+            n.removed = true;
+            stack.extend(n.child.take());
+            stack.extend(n.next.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use pairing_heap::PairingHeap;
+    use {Heap, DecreaseKey, HeapExt, HeapDelete, StaleEntry};
+
+    #[test]
+    fn pairing_heap_operations() {
+        let mut heap = PairingHeap::new();
+        let entries: Vec<_> = (0..50u32).map(|i| heap.insert(100 + (i * 17) % 50, i)).collect();
+        assert_eq!(heap.len(), 50);
+        heap.delete_min();
+        heap.decrease_key(&entries[10], 90).unwrap();
+        assert_eq!(heap.find_min(), (30, 10));
+        assert_eq!(heap.delete(entries[20].clone()), Ok((140, 20)));
+        heap.decrease_key(&entries[30], 100).unwrap();
+        let mut keys = Vec::new();
+        while !heap.empty() {
+            keys.push(heap.delete_min().0);
+        }
+        let mut expected = vec!(10, 30);
+        expected.extend((101..150).filter(|&k| k != 110 && k != 120 && k != 140));
+        assert_eq!(keys, expected);
+        assert_eq!(heap.len(), 0);
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn pairing_heap_merge_keeps_handles() {
+        let mut a = PairingHeap::new();
+        let mut b = PairingHeap::new();
+        a.insert(5, 'a');
+        let c = b.insert(7, 'c');
+        b.insert(6, 'b');
+        let mut merged = a.merge(b);
+        merged.decrease_key(&c, 6).unwrap();
+        assert_eq!(format!("{:?}", c), "PairingEntry { key: 1, value: 'c', live: true }");
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.delete_min(), (1, 'c'));
+        assert_eq!(format!("{:?}", c), "PairingEntry { key: 1, value: 'c', live: false }");
+        let handles: HashSet<_> = ['x', 'y', 'z'].iter().map(|&v| merged.insert(0, v)).collect();
+        assert_eq!(handles.len(), 3);
+    }
+
+    #[test]
+    fn pairing_heap_stale_handle() {
+        let mut heap = PairingHeap::new();
+        let a = heap.insert(1, 'a');
+        let b = heap.insert(2, 'b');
+        assert_eq!(heap.delete_min(), (1, 'a'));
+        assert_eq!(heap.decrease_key(&a, 1), Err(StaleEntry));
+        assert_eq!(heap.delete(a), Err(StaleEntry));
+        assert_eq!(heap.delete(b), Ok((2, 'b')));
+        assert!(heap.empty());
+    }
+
+    #[test]
+    fn pairing_heap_long_chains() {
+        // Every entry ends up a child of the first, in one long sibling list.
+        let mut heap = PairingHeap::new();
+        for i in 0..200000u32 {
+            heap.insert(i, ());
+        }
+        assert_eq!(heap.delete_min(), (0, ()));
+        assert_eq!(heap.delete_min(), (1, ()));
+        drop(heap);
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::VecDeque;
+use std::iter::FromIterator;
+use std::mem;
+use std::sync::Arc;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // Length of the shortest path to a missing child.
+    rank: usize,
+    left: Option<Arc<Node<K, V>>>,
+    right: Option<Arc<Node<K, V>>>,
+}
+
+// Dropping recursively could overflow the stack on a long left spine, so
+// children whose last reference is going away are freed from a list.
+impl<K, V> Drop for Node<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Arc<Node<K, V>>> = self.left.take().into_iter()
+            .chain(self.right.take()).collect();
+        while let Some(child) = stack.pop() {
+            if let Ok(mut node) = Arc::try_unwrap(child) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+/// An immutable leftist heap. Updates return a new heap that shares most of
+/// its nodes with the old one, which stays valid and unchanged.
+///
+/// `insert`, `delete_min` and `meld` each take O(log n) and copy only the
+/// O(log n) nodes they pass through. A Fibonacci heap's amortized bounds
+/// don't survive persistence, since an expensive version can be operated on
+/// again and again, so this uses a structure whose bounds are worst-case.
+///
+/// Nodes are reference counted with `Arc`, so cloning a heap is O(1) and
+/// versions can be shared between threads.
+pub struct LeftistHeap<K, V> {
+    root: Option<Arc<Node<K, V>>>,
+    len: usize,
+}
+
+impl<K, V> Clone for LeftistHeap<K, V> {
+    fn clone(&self) -> LeftistHeap<K, V> {
+        LeftistHeap { root: self.root.clone(), len: self.len }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> LeftistHeap<K, V> {
+    pub const fn new() -> LeftistHeap<K, V> {
+        LeftistHeap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn find_min(&self) -> Option<(&K, &V)> {
+        self.root.as_ref().map(|n| (&n.key, &n.value))
+    }
+
+    /// A new heap with the entry added.
+    pub fn insert(&self, key: K, value: V) -> LeftistHeap<K, V> {
+        let node = Arc::new(Node { key: key, value: value, rank: 1, left: None, right: None });
+        LeftistHeap { root: merge(self.root.clone(), Some(node)), len: self.len + 1 }
+    }
+
+    /// A new heap with the entries of both.
+    pub fn meld(&self, other: &LeftistHeap<K, V>) -> LeftistHeap<K, V> {
+        LeftistHeap { root: merge(self.root.clone(), other.root.clone()), len: self.len + other.len }
+    }
+
+    /// The minimum entry and a new heap without it.
+    pub fn delete_min(&self) -> Option<((K, V), LeftistHeap<K, V>)> {
+        self.root.as_ref().map(|n| {
+            let rest = LeftistHeap { root: merge(n.left.clone(), n.right.clone()), len: self.len - 1 };
+            ((n.key.clone(), n.value.clone()), rest)
+        })
+    }
+
+    /// Iterates over the entries in no particular order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { stack: self.root.iter().map(|n| &**n).collect() }
+    }
+
+    /// Copies of the entries, smallest key first.
+    pub fn to_sorted_vec(&self) -> Vec<(K, V)> {
+        let mut sorted = Vec::with_capacity(self.len);
+        let mut heap = self.clone();
+        while let Some((min, rest)) = heap.delete_min() {
+            sorted.push(min);
+            heap = rest;
+        }
+        sorted
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> FromIterator<(K, V)> for LeftistHeap<K, V> {
+    // Melds the entries pairwise, which builds the heap in O(n).
+    fn from_iter<I: IntoIterator<Item=(K, V)>>(iter: I) -> LeftistHeap<K, V> {
+        let mut queue: VecDeque<LeftistHeap<K, V>> = iter.into_iter()
+            .map(|(k, v)| LeftistHeap::new().insert(k, v))
+            .collect();
+        while queue.len() > 1 {
+            let a = queue.pop_front().unwrap();
+            let b = queue.pop_front().unwrap();
+            queue.push_back(a.meld(&b));
+        }
+        queue.pop_front().unwrap_or_else(LeftistHeap::new)
+    }
+}
+
+fn rank<K, V>(node: &Option<Arc<Node<K, V>>>) -> usize {
+    node.as_ref().map_or(0, |n| n.rank)
+}
+
+// Merges along the right spines, copying the nodes on them.
+fn merge<K: Ord + Clone, V: Clone>(a: Option<Arc<Node<K, V>>>, b: Option<Arc<Node<K, V>>>)
+    -> Option<Arc<Node<K, V>>> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(a), Some(b)) => {
+            let (top, other) = if b.key < a.key { (b, a) } else { (a, b) };
+            let left = top.left.clone();
+            let right = merge(top.right.clone(), Some(other));
+            let (left, right) = if rank(&left) < rank(&right) { (right, left) } else { (left, right) };
+            Some(Arc::new(Node {
+                key: top.key.clone(),
+                value: top.value.clone(),
+                rank: rank(&right) + 1,
+                left: left,
+                right: right,
+            }))
+        }
+    }
+}
+
+/// Iterator over the entries of a `LeftistHeap`, from `LeftistHeap::iter`.
+pub struct Iter<'a, K: 'a, V: 'a> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.stack.pop().map(|n| {
+            self.stack.extend(n.left.iter().map(|c| &**c));
+            self.stack.extend(n.right.iter().map(|c| &**c));
+            (&n.key, &n.value)
+        })
+    }
+}
+
+// A persistent singly linked list, whose tails are shared between versions.
+struct Cons<T> {
+    head: T,
+    tail: List<T>,
+}
+
+type List<T> = Option<Arc<Cons<T>>>;
+
+fn push<T>(head: T, tail: List<T>) -> List<T> {
+    Some(Arc::new(Cons { head: head, tail: tail }))
+}
+
+fn items<T>(list: &List<T>) -> Items<'_, T> {
+    Items { next: list }
+}
+
+struct Items<'a, T: 'a> {
+    next: &'a List<T>,
+}
+
+impl<'a, T> Iterator for Items<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.as_ref().map(|cons| {
+            self.next = &cons.tail;
+            &cons.head
+        })
+    }
+}
+
+struct Tree<K, V> {
+    rank: usize,
+    key: K,
+    value: V,
+    // Entries a skew link put in the tree, none with a key below `key`.
+    extras: List<(K, V)>,
+    // Ranks decrease along the list.
+    children: List<Arc<Tree<K, V>>>,
+}
+
+// Links two trees of equal rank, the one with the larger root becoming the
+// first child of the other.
+fn link<K: Ord + Clone, V: Clone>(a: &Arc<Tree<K, V>>, b: &Arc<Tree<K, V>>) -> Tree<K, V> {
+    let (top, other) = if b.key < a.key { (b, a) } else { (a, b) };
+    Tree {
+        rank: top.rank + 1,
+        key: top.key.clone(),
+        value: top.value.clone(),
+        extras: top.extras.clone(),
+        children: push(other.clone(), top.children.clone()),
+    }
+}
+
+// Links two trees of equal rank with a new entry, which takes the root if
+// it's smallest and otherwise goes with the extras.
+fn skew_link<K: Ord + Clone, V: Clone>(key: K, value: V, a: &Arc<Tree<K, V>>, b: &Arc<Tree<K, V>>)
+    -> Arc<Tree<K, V>> {
+    let mut tree = link(a, b);
+    if key <= tree.key {
+        let root = (mem::replace(&mut tree.key, key), mem::replace(&mut tree.value, value));
+        tree.extras = push(root, tree.extras);
+    } else {
+        tree.extras = push((key, value), tree.extras);
+    }
+    Arc::new(tree)
+}
+
+// Links trees until no two have the same rank, returning them by
+// increasing rank.
+fn consolidate<K: Ord + Clone, V: Clone, I>(trees: I) -> List<Arc<Tree<K, V>>>
+where I: Iterator<Item=Arc<Tree<K, V>>> {
+    let mut ranks: Vec<Option<Arc<Tree<K, V>>>> = Vec::new();
+    for tree in trees {
+        let mut tree = tree;
+        loop {
+            let rank = tree.rank;
+            if rank >= ranks.len() {
+                ranks.resize(rank + 1, None);
+            }
+            match ranks[rank].take() {
+                Some(other) => tree = Arc::new(link(&tree, &other)),
+                None => {
+                    ranks[rank] = Some(tree);
+                    break
+                }
+            }
+        }
+    }
+    ranks.into_iter().rev().flatten().fold(None, |list, tree| push(tree, list))
+}
+
+/// An immutable skew binomial heap (Brodal and Okasaki). Like
+/// `LeftistHeap`, updates return a new heap sharing most of its nodes with
+/// the old one, which stays valid and unchanged.
+///
+/// `insert` takes O(1) in the worst case, as it links at most the two
+/// smallest trees, so it suits workloads that insert far more often than
+/// they delete. `delete_min` and `meld` take O(log n), and so does
+/// `find_min`, which looks at the root of each of the O(log n) trees.
+pub struct SkewBinomialHeap<K, V> {
+    // By increasing rank, except that the first two may have equal ranks.
+    trees: List<Arc<Tree<K, V>>>,
+    len: usize,
+}
+
+impl<K, V> Clone for SkewBinomialHeap<K, V> {
+    fn clone(&self) -> SkewBinomialHeap<K, V> {
+        SkewBinomialHeap { trees: self.trees.clone(), len: self.len }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> SkewBinomialHeap<K, V> {
+    pub const fn new() -> SkewBinomialHeap<K, V> {
+        SkewBinomialHeap { trees: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn find_min(&self) -> Option<(&K, &V)> {
+        self.min_tree().map(|t| (&t.key, &t.value))
+    }
+
+    fn min_tree(&self) -> Option<&Arc<Tree<K, V>>> {
+        items(&self.trees).fold(None, |min: Option<&Arc<Tree<K, V>>>, t| match min {
+            Some(m) if m.key <= t.key => Some(m),
+            _ => Some(t)
+        })
+    }
+
+    /// A new heap with the entry added.
+    pub fn insert(&self, key: K, value: V) -> SkewBinomialHeap<K, V> {
+        let pair = self.trees.as_ref().and_then(|first| match first.tail {
+            Some(ref second) if first.head.rank == second.head.rank => Some((first, second)),
+            _ => None
+        });
+        let trees = match pair {
+            Some((first, second)) => {
+                push(skew_link(key, value, &first.head, &second.head), second.tail.clone())
+            }
+            None => {
+                let leaf = Tree { rank: 0, key: key, value: value, extras: None, children: None };
+                push(Arc::new(leaf), self.trees.clone())
+            }
+        };
+        SkewBinomialHeap { trees: trees, len: self.len + 1 }
+    }
+
+    /// A new heap with the entries of both.
+    pub fn meld(&self, other: &SkewBinomialHeap<K, V>) -> SkewBinomialHeap<K, V> {
+        let trees = items(&self.trees).chain(items(&other.trees)).cloned();
+        SkewBinomialHeap { trees: consolidate(trees), len: self.len + other.len }
+    }
+
+    /// The minimum entry and a new heap without it.
+    pub fn delete_min(&self) -> Option<((K, V), SkewBinomialHeap<K, V>)> {
+        let min = self.min_tree()?;
+        let rest = items(&self.trees).filter(|t| !Arc::ptr_eq(t, min));
+        let trees = consolidate(rest.chain(items(&min.children)).cloned());
+        let mut heap = SkewBinomialHeap { trees: trees, len: self.len - 1 - items(&min.extras).count() };
+        for (key, value) in items(&min.extras) {
+            heap = heap.insert(key.clone(), value.clone());
+        }
+        Some(((min.key.clone(), min.value.clone()), heap))
+    }
+
+    /// Iterates over the entries in no particular order.
+    pub fn iter(&self) -> SkewIter<'_, K, V> {
+        SkewIter { trees: items(&self.trees).map(|t| &**t).collect(), extras: Vec::new() }
+    }
+
+    /// Copies of the entries, smallest key first.
+    pub fn to_sorted_vec(&self) -> Vec<(K, V)> {
+        let mut sorted = Vec::with_capacity(self.len);
+        let mut heap = self.clone();
+        while let Some((min, rest)) = heap.delete_min() {
+            sorted.push(min);
+            heap = rest;
+        }
+        sorted
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> FromIterator<(K, V)> for SkewBinomialHeap<K, V> {
+    fn from_iter<I: IntoIterator<Item=(K, V)>>(iter: I) -> SkewBinomialHeap<K, V> {
+        iter.into_iter().fold(SkewBinomialHeap::new(), |heap, (k, v)| heap.insert(k, v))
+    }
+}
+
+/// Iterator over the entries of a `SkewBinomialHeap`, from
+/// `SkewBinomialHeap::iter`.
+pub struct SkewIter<'a, K: 'a, V: 'a> {
+    trees: Vec<&'a Tree<K, V>>,
+    extras: Vec<&'a (K, V)>,
+}
+
+impl<'a, K, V> Iterator for SkewIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if let Some((k, v)) = self.extras.pop() {
+            return Some((k, v))
+        }
+        self.trees.pop().map(|t| {
+            self.trees.extend(items(&t.children).map(|c| &**c));
+            self.extras.extend(items(&t.extras));
+            (&t.key, &t.value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use persistent::{LeftistHeap, SkewBinomialHeap};
+
+    #[test]
+    fn leftist_heap_versions() {
+        let empty = LeftistHeap::new();
+        let a = empty.insert(3, 'c').insert(1, 'a');
+        let b = a.insert(2, 'b');
+        assert_eq!(a.len(), 2);
+        assert_eq!(b.len(), 3);
+        let ((k, v), rest) = b.delete_min().unwrap();
+        assert_eq!((k, v), (1, 'a'));
+        assert_eq!(rest.find_min(), Some((&2, &'b')));
+        // The older versions are unchanged.
+        assert_eq!(a.find_min(), Some((&1, &'a')));
+        assert_eq!(b.find_min(), Some((&1, &'a')));
+        assert!(empty.is_empty());
+        let mut keys: Vec<i32> = b.iter().map(|(k, _)| *k).collect();
+        keys.sort();
+        assert_eq!(keys, vec!(1, 2, 3));
+    }
+
+    #[test]
+    fn leftist_heap_long_spine() {
+        let mut heap = LeftistHeap::new();
+        for i in (0..100000u32).rev() {
+            heap = heap.insert(i, ());
+        }
+        let ((k, _), rest) = heap.delete_min().unwrap();
+        assert_eq!(k, 0);
+        assert_eq!(rest.find_min(), Some((&1, &())));
+    }
+
+    #[test]
+    fn leftist_heap_meld_and_fork() {
+        let base: LeftistHeap<u32, &str> = vec!((5, "e"), (1, "a"), (4, "d")).into_iter().collect();
+        let other: LeftistHeap<u32, &str> = vec!((2, "b"), (3, "c")).into_iter().collect();
+        let melded = base.meld(&other);
+        assert_eq!(melded.len(), 5);
+        assert_eq!(melded.to_sorted_vec(), vec!((1, "a"), (2, "b"), (3, "c"), (4, "d"), (5, "e")));
+        // Two branches forked from the same state don't see each other.
+        let left = melded.delete_min().unwrap().1.insert(0, "left");
+        let right = melded.insert(9, "right");
+        assert_eq!(left.find_min(), Some((&0, &"left")));
+        assert_eq!(right.len(), 6);
+        assert_eq!(right.find_min(), Some((&1, &"a")));
+        assert_eq!(base.to_sorted_vec(), vec!((1, "a"), (4, "d"), (5, "e")));
+    }
+
+    #[test]
+    fn skew_binomial_heap_versions() {
+        let empty = SkewBinomialHeap::new();
+        let a = empty.insert(3, 'c').insert(1, 'a');
+        let b = a.insert(2, 'b');
+        let ((k, v), rest) = b.delete_min().unwrap();
+        assert_eq!((k, v), (1, 'a'));
+        assert_eq!(rest.find_min(), Some((&2, &'b')));
+        assert_eq!(rest.len(), 2);
+        assert_eq!(a.find_min(), Some((&1, &'a')));
+        assert_eq!(b.to_sorted_vec(), vec!((1, 'a'), (2, 'b'), (3, 'c')));
+        assert!(empty.is_empty());
+        let mut keys: Vec<i32> = b.iter().map(|(k, _)| *k).collect();
+        keys.sort();
+        assert_eq!(keys, vec!(1, 2, 3));
+    }
+
+    #[test]
+    fn skew_binomial_heap_sorts() {
+        let heap: SkewBinomialHeap<u32, u32> = (0..2000u32).map(|i| ((i * 7919) % 1000, i)).collect();
+        assert_eq!(heap.len(), 2000);
+        assert_eq!(heap.iter().count(), 2000);
+        // Deleting from one version and inserting into another.
+        let mut forks = vec!(heap.clone());
+        let mut expected: Vec<u32> = (0..2000).map(|i| (i * 7919) % 1000).collect();
+        expected.sort();
+        let mut current = heap;
+        for (i, &k) in expected.iter().enumerate() {
+            let ((key, _), rest) = current.delete_min().unwrap();
+            assert_eq!(key, k);
+            assert_eq!(rest.len(), 1999 - i);
+            if i % 500 == 0 {
+                forks.push(rest.insert(0, 0));
+            }
+            current = rest;
+        }
+        assert!(current.delete_min().is_none());
+        assert_eq!(forks[0].len(), 2000);
+        assert_eq!(forks[2].find_min(), Some((&0, &0)));
+        assert_eq!(forks[2].len(), 1500);
+    }
+
+    #[test]
+    fn skew_binomial_heap_meld() {
+        let base: SkewBinomialHeap<u32, &str> = vec!((5, "e"), (1, "a"), (4, "d")).into_iter().collect();
+        let other: SkewBinomialHeap<u32, &str> = vec!((2, "b"), (3, "c"), (0, "z")).into_iter().collect();
+        let (_, other) = other.delete_min().unwrap();
+        let melded = base.meld(&other).insert(6, "f");
+        assert_eq!(melded.len(), 6);
+        assert_eq!(melded.to_sorted_vec(), vec!((1, "a"), (2, "b"), (3, "c"), (4, "d"), (5, "e"), (6, "f")));
+        assert_eq!(base.to_sorted_vec(), vec!((1, "a"), (4, "d"), (5, "e")));
+    }
+}
@@ -0,0 +1,150 @@
+use std::ops::Sub;
+use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
+use petgraph::algo::Measure;
+use petgraph::visit::{EdgeRef, IntoEdges, IntoNodeIdentifiers, NodeIndexable};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey};
+
+/// Computes the length of the shortest path from `start` to every reachable
+/// node, using a Fibonacci heap with decrease_key for the frontier.
+///
+/// `edge_cost` must return non-negative costs.
+pub fn dijkstra_fib<G, F, K>(graph: G, start: G::NodeId, mut edge_cost: F)
+    -> HashMap<G::NodeId, K>
+where G: IntoEdges + NodeIndexable,
+      G::NodeId: Eq + Hash,
+      F: FnMut(G::EdgeRef) -> K,
+      K: Measure + Ord + Sub<K, Output=K> {
+    let mut fheap = FibHeap::new();
+    let mut scores = HashMap::new();
+    let mut entries = HashMap::new();
+    let mut visited = HashSet::new();
+
+    scores.insert(start, K::default());
+    entries.insert(start, fheap.insert(K::default(), graph.to_index(start)));
+
+    while !fheap.empty() {
+        let (score, idx) = fheap.delete_min();
+        let node = graph.from_index(idx);
+        visited.insert(node);
+        for edge in graph.edges(node) {
+            let next = edge.target();
+            if visited.contains(&next) {
+                continue
+            }
+            let next_score = score.clone() + edge_cost(edge);
+            match scores.get(&next).cloned() {
+                Some(old) => {
+                    if next_score < old {
+                        fheap.decrease_key(&entries[&next], old - next_score.clone()).unwrap();
+                        scores.insert(next, next_score);
+                    }
+                }
+                None => {
+                    let entry = fheap.insert(next_score.clone(), graph.to_index(next));
+                    entries.insert(next, entry);
+                    scores.insert(next, next_score);
+                }
+            }
+        }
+    }
+    scores
+}
+
+/// Computes a minimum spanning forest of an undirected graph with Prim's
+/// algorithm, returning its edges as `(source, target, weight)`.
+pub fn prim_fib<G, F, K>(graph: G, mut edge_cost: F) -> Vec<(G::NodeId, G::NodeId, K)>
+where G: IntoEdges + IntoNodeIdentifiers + NodeIndexable,
+      G::NodeId: Eq + Hash,
+      F: FnMut(G::EdgeRef) -> K,
+      K: Measure + Ord + Sub<K, Output=K> {
+    let mut fheap = FibHeap::new();
+    let mut entries = HashMap::new();
+    // The cheapest known edge connecting a frontier node to the tree.
+    let mut best: HashMap<G::NodeId, (G::NodeId, K)> = HashMap::new();
+    let mut in_tree = HashSet::new();
+    let mut mst = Vec::new();
+
+    for root in graph.node_identifiers() {
+        if in_tree.contains(&root) {
+            continue
+        }
+        fheap.insert(K::default(), graph.to_index(root));
+
+        while !fheap.empty() {
+            let (_, idx) = fheap.delete_min();
+            let node = graph.from_index(idx);
+            in_tree.insert(node);
+            if let Some((parent, weight)) = best.remove(&node) {
+                mst.push((parent, node, weight));
+            }
+
+            for edge in graph.edges(node) {
+                let next = edge.target();
+                if in_tree.contains(&next) {
+                    continue
+                }
+                let weight = edge_cost(edge);
+                match best.get(&next).map(|(_, w)| w.clone()) {
+                    Some(old) => {
+                        if weight < old {
+                            fheap.decrease_key(&entries[&next], old - weight.clone()).unwrap();
+                            best.insert(next, (node, weight));
+                        }
+                    }
+                    None => {
+                        let entry = fheap.insert(weight.clone(), graph.to_index(next));
+                        entries.insert(next, entry);
+                        best.insert(next, (node, weight));
+                    }
+                }
+            }
+        }
+    }
+    mst
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::Graph;
+    use petgraph::graph::UnGraph;
+    use petgraph_algo::{dijkstra_fib, prim_fib};
+
+    #[test]
+    fn petgraph_dijkstra() {
+        let mut graph = Graph::new();
+        let a = graph.add_node(1);
+        let b = graph.add_node(2);
+        let c = graph.add_node(3);
+        let d = graph.add_node(4);
+        let e = graph.add_node(5);
+        graph.extend_with_edges([(a, b, 1u64), (a, c, 5), (b, c, 8), (b, d, 3),
+                                  (c, d, 2), (d, b, 9)]);
+
+        let scores = dijkstra_fib(&graph, a, |e| *e.weight());
+        assert_eq!(scores[&a], 0);
+        assert_eq!(scores[&b], 1);
+        assert_eq!(scores[&c], 5);
+        assert_eq!(scores[&d], 4);
+        assert!(!scores.contains_key(&e));
+    }
+
+    #[test]
+    fn petgraph_prim() {
+        let mut graph = UnGraph::new_undirected();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        let d = graph.add_node(());
+        let e = graph.add_node(());
+        let f = graph.add_node(());
+        graph.extend_with_edges([(a, b, 4u32), (a, c, 1), (b, c, 2), (b, d, 5),
+                                  (c, d, 8), (e, f, 3)]);
+
+        let mst = prim_fib(&graph, |e| *e.weight());
+        assert_eq!(mst.len(), 4);
+        let total: u32 = mst.iter().map(|&(_, _, w)| w).sum();
+        assert_eq!(total, 11);
+    }
+}
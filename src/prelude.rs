@@ -0,0 +1,72 @@
+//! The heap traits and the main heap types, for glob importing:
+//!
+//! ```ignore
+//! use rust_heaps::prelude::*;
+//! ```
+//!
+//! Types behind features are only exported when their feature is on.
+
+pub use {Heap, DecreaseKey, HeapExt, HeapDelete, DoubleEndedHeap, MonotoneHeap, AddressableHeap, StaleEntry};
+#[cfg(feature = "std")]
+pub use fibonacci_heap::FibHeap;
+pub use rev::Rev;
+#[cfg(feature = "send-heap")]
+pub use send_heap::SendFibHeap;
+#[cfg(feature = "persistent")]
+pub use persistent::{LeftistHeap, SkewBinomialHeap};
+#[cfg(feature = "lazy-heap")]
+pub use lazy_heap::LazyHeap;
+#[cfg(feature = "array-heap")]
+pub use array_heap::{ArrayHeap, SliceHeap};
+#[cfg(feature = "interrupt")]
+pub use interrupt_heap::InterruptHeap;
+#[cfg(feature = "external-heap")]
+pub use external_heap::ExternalHeap;
+#[cfg(feature = "pairing-heap")]
+pub use pairing_heap::PairingHeap;
+#[cfg(feature = "binary-heap")]
+pub use binary_heap::{BinaryHeap, DaryHeap};
+#[cfg(feature = "skew-heap")]
+pub use skew_heap::SkewHeap;
+#[cfg(feature = "min-max-heap")]
+pub use min_max_heap::MinMaxHeap;
+#[cfg(feature = "interval-heap")]
+pub use interval_heap::IntervalHeap;
+#[cfg(feature = "soft-heap")]
+pub use soft_heap::SoftHeap;
+#[cfg(feature = "bucket-queue")]
+pub use bucket_queue::BucketQueue;
+#[cfg(feature = "strict-fib-heap")]
+pub use strict_fib_heap::StrictFibHeap;
+#[cfg(feature = "weak-heap")]
+pub use weak_heap::WeakHeap;
+#[cfg(feature = "smooth-heap")]
+pub use smooth_heap::SmoothHeap;
+#[cfg(feature = "b-heap")]
+pub use b_heap::BHeap;
+#[cfg(feature = "bounded-heap")]
+pub use bounded_heap::BoundedHeap;
+#[cfg(feature = "veb-heap")]
+pub use veb_heap::VebHeap;
+#[cfg(feature = "calendar-queue")]
+pub use calendar_queue::CalendarQueue;
+#[cfg(feature = "lazy-binomial-heap")]
+pub use lazy_binomial_heap::LazyBinomialHeap;
+#[cfg(feature = "treap")]
+pub use treap::Treap;
+#[cfg(feature = "slotmap")]
+pub use slot_heap::SlotFibHeap;
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use prelude::*;
+
+    #[test]
+    fn prelude_imports() {
+        let mut fheap: FibHeap<Rev<u8>, u8> = FibHeap::new();
+        let one = fheap.insert(Rev(1), 1);
+        fheap.insert(Rev(2), 2);
+        fheap.delete(one).unwrap();
+        assert_eq!(fheap.merge(FibHeap::new()).delete_min(), (Rev(2), 2));
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete};
+
+struct Slot<P, V> {
+    entry: Rc<FibNode<P, u64>>,
+    value: V,
+    cost: P,
+}
+
+/// A bounded cache that evicts the entry with the lowest priority, using the
+/// GreedyDual policy.
+///
+/// Each entry has a cost, e.g. the work needed to fetch it again. An entry's
+/// priority is set to `inflation + cost` when it is inserted or used, where
+/// `inflation` is the priority of the last evicted entry. Expensive entries
+/// are kept longer, and entries that go unused fall behind as inflation
+/// rises. With equal costs it approximates LRU.
+pub struct PriorityCache<K, V, P> {
+    heap: FibHeap<P, u64>,
+    slots: HashMap<K, Slot<P, V>>,
+    keys: HashMap<u64, K>,
+    capacity: usize,
+    inflation: P,
+    next_id: u64,
+}
+
+impl<K, V, P> PriorityCache<K, V, P>
+where K: Hash + Eq + Clone,
+      P: Ord + Debug + Clone + Default + Add<P, Output=P> + Sub<P, Output=P> {
+    pub fn new(capacity: usize) -> PriorityCache<K, V, P> {
+        PriorityCache {
+            heap: FibHeap::new(),
+            slots: HashMap::new(),
+            keys: HashMap::new(),
+            capacity: capacity,
+            inflation: P::default(),
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Inserts an entry, replacing any entry with the same key. If the cache
+    /// was full, the entry with the lowest priority is evicted and returned.
+    pub fn insert(&mut self, key: K, value: V, cost: P) -> Option<(K, V)> {
+        if self.capacity == 0 {
+            return Some((key, value))
+        }
+        if self.slots.contains_key(&key) {
+            {
+                let slot = self.slots.get_mut(&key).unwrap();
+                slot.value = value;
+                slot.cost = cost;
+            }
+            self.touch(&key);
+            return None
+        }
+        let evicted = if self.slots.len() == self.capacity { self.evict() } else { None };
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(self.inflation.clone() + cost.clone(), id);
+        self.keys.insert(id, key.clone());
+        self.slots.insert(key, Slot { entry: entry, value: value, cost: cost });
+        evicted
+    }
+
+    /// Looks up an entry and marks it as used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.touch(key) {
+            self.slots.get(key).map(|slot| &slot.value)
+        } else {
+            None
+        }
+    }
+
+    /// Looks up an entry without marking it as used.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.slots.get(key).map(|slot| &slot.value)
+    }
+
+    /// Marks an entry as used, restoring its priority to `inflation + cost`.
+    /// Returns whether the entry is cached.
+    pub fn touch(&mut self, key: &K) -> bool {
+        let priority = match self.slots.get(key) {
+            Some(slot) => self.inflation.clone() + slot.cost.clone(),
+            None => return false
+        };
+        self.set_priority(key, priority)
+    }
+
+    pub fn priority(&self, key: &K) -> Option<P> {
+        self.slots.get(key).map(|slot| slot.entry.get_key().clone())
+    }
+
+    /// Overrides the priority of a cached entry until it is next used.
+    /// Lowering a priority is a decrease_key; raising one deletes and
+    /// reinserts the entry. Returns whether the entry is cached.
+    pub fn set_priority(&mut self, key: &K, priority: P) -> bool {
+        let slot = match self.slots.get_mut(key) {
+            Some(slot) => slot,
+            None => return false
+        };
+        let old = slot.entry.get_key().clone();
+        if priority <= old {
+            self.heap.decrease_key(&slot.entry, old - priority).unwrap();
+        } else {
+            let (_, id) = self.heap.delete(slot.entry.clone()).unwrap();
+            slot.entry = self.heap.insert(priority, id);
+        }
+        true
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.slots.remove(key).map(|slot| {
+            let (_, id) = self.heap.delete(slot.entry).unwrap();
+            self.keys.remove(&id);
+            slot.value
+        })
+    }
+
+    fn evict(&mut self) -> Option<(K, V)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (priority, id) = self.heap.delete_min();
+        self.inflation = priority;
+        let key = self.keys.remove(&id).unwrap();
+        let slot = self.slots.remove(&key).unwrap();
+        Some((key, slot.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use priority_cache::PriorityCache;
+
+    #[test]
+    fn priority_cache_touch() {
+        let mut cache = PriorityCache::new(2);
+        assert_eq!(cache.insert("a", 1, 2u32), None);
+        assert_eq!(cache.insert("b", 2, 5), None);
+        assert_eq!(cache.insert("c", 3, 4), Some(("a", 1)));
+        assert_eq!(cache.priority(&"c"), Some(6));
+        assert_eq!(cache.get(&"b"), Some(&2));
+        assert_eq!(cache.priority(&"b"), Some(7));
+        assert_eq!(cache.insert("d", 4, 1), Some(("c", 3)));
+        assert_eq!(cache.peek(&"c"), None);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn priority_cache_keeps_expensive_entries() {
+        let mut cache = PriorityCache::new(2);
+        cache.insert(0, "cheap", 1u32);
+        cache.insert(1, "costly", 10);
+        assert_eq!(cache.insert(2, "x", 1), Some((0, "cheap")));
+        assert_eq!(cache.priority(&2), Some(2));
+        assert_eq!(cache.insert(3, "y", 1), Some((2, "x")));
+        // Unused, the costly entry eventually ages out.
+        for i in 4..14 {
+            cache.insert(i, "z", 1);
+        }
+        assert_eq!(cache.peek(&1), None);
+    }
+
+    #[test]
+    fn priority_cache_set_priority_and_remove() {
+        let mut cache = PriorityCache::new(3);
+        cache.insert('a', 'a', 5u32);
+        cache.insert('b', 'b', 3);
+        cache.insert('c', 'c', 4);
+        assert!(cache.set_priority(&'a', 1));
+        assert!(cache.set_priority(&'b', 9));
+        assert_eq!(cache.remove(&'c'), Some('c'));
+        assert!(!cache.touch(&'c'));
+        cache.insert('d', 'd', 2);
+        assert_eq!(cache.insert('e', 'e', 2), Some(('a', 'a')));
+        assert_eq!(cache.insert('f', 'f', 2), Some(('d', 'd')));
+        assert_eq!(cache.priority(&'f'), Some(4));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::mpsc::{RecvError, SendError, TryRecvError};
+use ordered::Ordered;
+use owned_heap::OwnedHeap;
+use rev::Rev;
+
+/// Creates a channel whose receiver always gets the pending message with
+/// the highest priority, and among those the one sent first.
+///
+/// Like `std::sync::mpsc::channel`, the sender can be cloned and sending
+/// never blocks. `recv` blocks until a message is pending or every sender
+/// is gone.
+pub fn priority_channel<P: Ord + Clone, T>() -> (Sender<P, T>, Receiver<P, T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: OwnedHeap::new(),
+            messages: HashMap::new(),
+            next_seq: 0,
+            senders: 1,
+            receiver: true,
+        }),
+        ready: Condvar::new(),
+    });
+    (Sender { shared: shared.clone() }, Receiver { shared: shared })
+}
+
+struct State<P, T> {
+    // Messages are keyed by priority, highest first, then by send order.
+    queue: OwnedHeap<Ordered<(Rev<P>, u64)>, u64>,
+    messages: HashMap<u64, T>,
+    next_seq: u64,
+    senders: usize,
+    receiver: bool,
+}
+
+struct Shared<P, T> {
+    state: Mutex<State<P, T>>,
+    ready: Condvar,
+}
+
+pub struct Sender<P, T> {
+    shared: Arc<Shared<P, T>>,
+}
+
+impl<P: Ord + Clone, T> Sender<P, T> {
+    /// Queues `msg`, or gives it back if the receiver is gone.
+    pub fn send(&self, priority: P, msg: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.receiver {
+            return Err(SendError(msg))
+        }
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.queue.insert(Ordered((Rev(priority), seq)), seq);
+        state.messages.insert(seq, msg);
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+}
+
+impl<P, T> Clone for Sender<P, T> {
+    fn clone(&self) -> Sender<P, T> {
+        self.shared.state.lock().unwrap().senders += 1;
+        Sender { shared: self.shared.clone() }
+    }
+}
+
+impl<P, T> Drop for Sender<P, T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            self.shared.ready.notify_all();
+        }
+    }
+}
+
+pub struct Receiver<P, T> {
+    shared: Arc<Shared<P, T>>,
+}
+
+impl<P: Ord + Clone, T> Receiver<P, T> {
+    /// Waits for the highest-priority pending message. Fails once no
+    /// messages are pending and every sender is gone.
+    pub fn recv(&self) -> Result<(P, T), RecvError> {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(item) = pop(&mut state) {
+                return Ok(item)
+            }
+            if state.senders == 0 {
+                return Err(RecvError)
+            }
+            state = self.shared.ready.wait(state).unwrap();
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<(P, T), TryRecvError> {
+        let mut state = self.shared.state.lock().unwrap();
+        match pop(&mut state) {
+            Some(item) => Ok(item),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty)
+        }
+    }
+
+    /// The number of pending messages.
+    pub fn len(&self) -> usize {
+        self.shared.state.lock().unwrap().messages.len()
+    }
+}
+
+impl<P, T> Drop for Receiver<P, T> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().receiver = false;
+    }
+}
+
+fn pop<P: Ord + Clone, T>(state: &mut State<P, T>) -> Option<(P, T)> {
+    state.queue.delete_min().map(|(Ordered((Rev(priority), _)), seq)| {
+        (priority, state.messages.remove(&seq).unwrap())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{RecvError, SendError, TryRecvError};
+    use std::thread;
+    use priority_channel::priority_channel;
+
+    #[test]
+    fn priority_channel_order() {
+        let (tx, rx) = priority_channel();
+        tx.send(1, "low").unwrap();
+        tx.send(5, "high").unwrap();
+        tx.send(3, "mid").unwrap();
+        tx.send(5, "high again").unwrap();
+        assert_eq!(rx.len(), 4);
+        assert_eq!(rx.recv(), Ok((5, "high")));
+        assert_eq!(rx.recv(), Ok((5, "high again")));
+        assert_eq!(rx.try_recv(), Ok((3, "mid")));
+        assert_eq!(rx.recv(), Ok((1, "low")));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        drop(tx);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(rx.recv(), Err(RecvError));
+    }
+
+    #[test]
+    fn priority_channel_receiver_dropped() {
+        let (tx, rx) = priority_channel::<u8, u8>();
+        drop(rx);
+        assert_eq!(tx.send(1, 7), Err(SendError(7)));
+    }
+
+    #[test]
+    fn priority_channel_threads() {
+        let (tx, rx) = priority_channel();
+        let workers: Vec<_> = (0..4u32).map(|t| {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for i in 0..100u32 {
+                    tx.send(i % 10, (t, i)).unwrap();
+                }
+            })
+        }).collect();
+        drop(tx);
+        for w in workers.into_iter() {
+            w.join().unwrap();
+        }
+        let mut received = Vec::new();
+        while let Ok((priority, _)) = rx.recv() {
+            received.push(priority);
+        }
+        assert_eq!(received.len(), 400);
+        assert!(received.windows(2).all(|w| w[0] >= w[1]));
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use fibonacci_heap::{FibHeap};
+use float_key::FloatKey;
+use Heap;
+
+fn to_key(k: f64) -> PyResult<FloatKey> {
+    if k.is_nan() {
+        Err(PyValueError::new_err("heap keys must not be NaN"))
+    } else {
+        Ok(FloatKey(k))
+    }
+}
+
+/// Handle returned by `FibHeap.push`, used to decrease the entry's key.
+#[pyclass(name = "FibEntry", unsendable)]
+pub struct PyFibEntry {
+    entry: <FibHeap<FloatKey, u64> as Heap<FloatKey, u64>>::HeapEntry,
+    id: u64,
+}
+
+#[pymethods]
+impl PyFibEntry {
+    #[getter]
+    fn key(&self) -> f64 {
+        self.entry.get_key().0
+    }
+}
+
+/// A Fibonacci heap keyed by floats, holding arbitrary Python objects.
+///
+/// Python objects are kept in a side table keyed by an id stored in the
+/// heap, which also tells us whether an entry is still in the heap.
+#[pyclass(name = "FibHeap", unsendable)]
+pub struct PyFibHeap {
+    heap: FibHeap<FloatKey, u64>,
+    objects: HashMap<u64, Py<PyAny>>,
+    next_id: u64,
+}
+
+#[pymethods]
+impl PyFibHeap {
+    #[new]
+    fn new() -> PyFibHeap {
+        PyFibHeap { heap: FibHeap::new(), objects: HashMap::new(), next_id: 0 }
+    }
+
+    fn push(&mut self, key: f64, obj: Py<PyAny>) -> PyResult<PyFibEntry> {
+        let k = to_key(key)?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.objects.insert(id, obj);
+        Ok(PyFibEntry { entry: self.heap.insert(k, id), id: id })
+    }
+
+    fn peek(&self, py: Python) -> PyResult<(f64, Py<PyAny>)> {
+        if self.heap.empty() {
+            return Err(PyIndexError::new_err("peek from an empty heap"))
+        }
+        let (k, id) = self.heap.find_min();
+        Ok((k.0, self.objects[&id].clone_ref(py)))
+    }
+
+    fn pop(&mut self) -> PyResult<(f64, Py<PyAny>)> {
+        if self.heap.empty() {
+            return Err(PyIndexError::new_err("pop from an empty heap"))
+        }
+        let (k, id) = self.heap.delete_min();
+        Ok((k.0, self.objects.remove(&id).unwrap()))
+    }
+
+    /// Lowers the key of `entry` to `new_key`.
+    fn decrease_key(&mut self, entry: &PyFibEntry, new_key: f64) -> PyResult<()> {
+        let new_key = to_key(new_key)?;
+        if !self.objects.contains_key(&entry.id) {
+            return Err(PyValueError::new_err("entry is no longer in the heap"))
+        }
+        let old_key = *entry.entry.get_key();
+        if new_key > old_key {
+            return Err(PyValueError::new_err("new key is greater than the current key"))
+        }
+        self.heap.decrease_key_to(&entry.entry, new_key).unwrap();
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.objects.len()
+    }
+}
+
+#[pymodule]
+fn rust_heaps(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<PyFibHeap>()?;
+    m.add_class::<PyFibEntry>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+    use python::PyFibHeap;
+
+    #[test]
+    fn python_push_pop() {
+        Python::initialize();
+        Python::attach(|py| {
+            let mut fheap = PyFibHeap::new();
+            let a = fheap.push(3.5, py.None()).unwrap();
+            fheap.push(1.0, 7i32.into_pyobject(py).unwrap().into_any().unbind()).unwrap();
+            fheap.push(2.0, py.None()).unwrap();
+            assert_eq!(fheap.__len__(), 3);
+            assert!(fheap.push(f64::NAN, py.None()).is_err());
+
+            let (k, obj) = fheap.pop().unwrap();
+            assert_eq!(k, 1.0);
+            assert_eq!(obj.extract::<i32>(py).unwrap(), 7);
+
+            assert!(fheap.decrease_key(&a, 5.0).is_err());
+            fheap.decrease_key(&a, 0.5).unwrap();
+            assert_eq!(a.key(), 0.5);
+            assert_eq!(fheap.peek(py).unwrap().0, 0.5);
+            fheap.pop().unwrap();
+            assert!(fheap.decrease_key(&a, 0.0).is_err());
+            fheap.pop().unwrap();
+            assert!(fheap.pop().is_err());
+        });
+    }
+
+    #[test]
+    fn python_decrease_key_keeps_exact_key() {
+        Python::initialize();
+        Python::attach(|py| {
+            let mut fheap = PyFibHeap::new();
+            let a = fheap.push(f64::INFINITY, py.None()).unwrap();
+            let b = fheap.push(1e17, py.None()).unwrap();
+            fheap.push(2.0, py.None()).unwrap();
+            fheap.decrease_key(&a, 3.0).unwrap();
+            assert_eq!(a.key(), 3.0);
+            fheap.decrease_key(&b, 1.0).unwrap();
+            assert_eq!(b.key(), 1.0);
+            assert_eq!(fheap.pop().unwrap().0, 1.0);
+            assert_eq!(fheap.pop().unwrap().0, 2.0);
+            assert_eq!(fheap.pop().unwrap().0, 3.0);
+        });
+    }
+}
@@ -0,0 +1,65 @@
+use std::ops::Sub;
+use std::fmt::Debug;
+use quickcheck::{Arbitrary, Gen};
+use fibonacci_heap::{FibHeap};
+use Heap;
+
+fn from_entries<K, V>(entries: Vec<(K, V)>) -> FibHeap<K, V>
+where K: Ord + Debug + Clone + Sub<K, Output=K>,
+      V: Eq + PartialOrd + Debug + Clone {
+    let mut fheap = FibHeap::new();
+    for (k, v) in entries.into_iter() {
+        fheap.insert(k, v);
+    }
+    if !fheap.empty() {
+        // Popping and reinserting the minimum consolidates the root list, so
+        // generated heaps contain real trees rather than a flat list of roots.
+        let (k, v) = fheap.delete_min();
+        fheap.insert(k, v);
+    }
+    fheap
+}
+
+impl<K, V> Arbitrary for FibHeap<K, V>
+where K: Arbitrary + Ord + Debug + Clone + Sub<K, Output=K>,
+      V: Arbitrary + Eq + PartialOrd + Debug + Clone {
+    fn arbitrary(g: &mut Gen) -> FibHeap<K, V> {
+        from_entries(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item=FibHeap<K, V>>> {
+        let entries: Vec<(K, V)> = self.iter().collect();
+        Box::new(entries.shrink().map(from_entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::{quickcheck, Arbitrary, Gen};
+    use Heap;
+    use fibonacci_heap::{FibHeap};
+
+    #[test]
+    fn quickcheck_delete_min_sorted() {
+        fn prop(mut fheap: FibHeap<u32, u8>) -> bool {
+            let mut expected: Vec<u32> = fheap.iter().map(|(k, _)| k).collect();
+            expected.sort();
+            let mut popped = Vec::new();
+            while !fheap.empty() {
+                popped.push(fheap.delete_min().0);
+            }
+            popped == expected
+        }
+        quickcheck(prop as fn(FibHeap<u32, u8>) -> bool);
+    }
+
+    #[test]
+    fn quickcheck_shrink_smaller() {
+        let mut g = Gen::new(20);
+        let fheap: FibHeap<u32, u8> = Arbitrary::arbitrary(&mut g);
+        let len = fheap.iter().count();
+        for smaller in fheap.shrink().take(10) {
+            assert!(smaller.iter().count() <= len);
+        }
+    }
+}
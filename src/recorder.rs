@@ -0,0 +1,178 @@
+use std::fmt::Debug;
+use std::fs;
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::ops::Sub;
+use std::path::Path;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete, StaleEntry};
+
+/// The heap as it was after one operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The operation, e.g. `insert 5`.
+    pub label: String,
+    /// A Graphviz digraph of the heap, captioned with the label.
+    pub dot: String,
+}
+
+/// A `FibHeap` that records a Graphviz frame of its structure after every
+/// operation, for stepping through how the heap changes.
+///
+/// Each frame is a `FibHeap::to_dot` graph captioned with the operation. The
+/// frames can be written to numbered files, or joined into one file that
+/// `dot -Tpng -O` renders as one image per frame.
+pub struct Recorder<K, V> {
+    heap: FibHeap<K, V>,
+    frames: Vec<Frame>,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> Recorder<K, V> {
+    pub fn new() -> Recorder<K, V> {
+        Recorder { heap: FibHeap::new(), frames: Vec::new() }
+    }
+
+    /// Records an existing heap, starting with a frame of it as it is.
+    pub fn from_heap(heap: FibHeap<K, V>) -> Recorder<K, V> {
+        let mut recorder = Recorder { heap: heap, frames: Vec::new() };
+        recorder.mark("start");
+        recorder
+    }
+
+    pub fn heap(&self) -> &FibHeap<K, V> {
+        &self.heap
+    }
+
+    pub fn into_heap(self) -> FibHeap<K, V> {
+        self.heap
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn clear_frames(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Records a frame of the heap as it is now, e.g. to caption a step in
+    /// a walkthrough.
+    pub fn mark(&mut self, label: &str) {
+        let dot = self.heap.to_dot();
+        let caption = format!("    label=\"{}\";\n    labelloc=t;\n",
+                              label.replace('\\', "\\\\").replace('"', "\\\""));
+        // `to_dot` puts the opening line first, so the caption goes after it.
+        let split = dot.find('\n').unwrap() + 1;
+        self.frames.push(Frame {
+            label: label.to_string(),
+            dot: format!("{}{}{}", &dot[..split], caption, &dot[split..]),
+        });
+    }
+
+    /// Every frame in one file of consecutive digraphs.
+    pub fn to_dot_sequence(&self) -> String {
+        self.frames.iter().map(|f| &f.dot[..]).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Writes the frames to `frame-000.dot`, `frame-001.dot` and so on in
+    /// `dir`, which must exist.
+    pub fn write_frames(&self, dir: &Path) -> io::Result<()> {
+        for (i, frame) in self.frames.iter().enumerate() {
+            let mut file = fs::File::create(dir.join(format!("frame-{:03}.dot", i)))?;
+            file.write_all(frame.dot.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> Heap<K, V>
+for Recorder<K, V> {
+    type HeapEntry = Rc<FibNode<K, V>>;
+
+    fn find_min(&self) -> (K, V) {
+        self.heap.find_min()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        let (k, v) = self.heap.delete_min();
+        self.mark(&format!("delete_min {:?}", k));
+        (k, v)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Rc<FibNode<K, V>> {
+        let label = format!("insert {:?}", key);
+        let entry = self.heap.insert(key, value);
+        self.mark(&label);
+        entry
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone> DecreaseKey<K, V>
+for Recorder<K, V> {
+    fn decrease_key(&mut self, entry: &Rc<FibNode<K, V>>, delta: K) -> Result<(), StaleEntry> {
+        let old = entry.get_key().clone();
+        self.heap.decrease_key(entry, delta)?;
+        self.mark(&format!("decrease_key {:?} to {:?}", old, entry.get_key()));
+        Ok(())
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Hash + Clone> HeapDelete<K, V>
+for Recorder<K, V> {
+    type HeapEntry = Rc<FibNode<K, V>>;
+
+    fn delete(&mut self, entry: Rc<FibNode<K, V>>) -> Result<(K, V), StaleEntry> {
+        let (k, v) = self.heap.delete(entry)?;
+        self.mark(&format!("delete {:?}", k));
+        Ok((k, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use recorder::Recorder;
+    use {Heap, DecreaseKey, HeapDelete};
+
+    #[test]
+    fn recorder_frames() {
+        let mut heap = Recorder::new();
+        for &k in [3u32, 1, 4, 5, 9].iter() {
+            heap.insert(k, ());
+        }
+        let two = heap.insert(2, ());
+        heap.delete_min();
+        heap.decrease_key(&two, 2).unwrap();
+        let labels: Vec<&str> = heap.frames().iter().map(|f| &f.label[..]).collect();
+        assert_eq!(labels, vec!("insert 3", "insert 1", "insert 4", "insert 5", "insert 9",
+                                "insert 2", "delete_min 1", "decrease_key 2 to 0"));
+        let consolidated = &heap.frames()[6].dot;
+        assert!(consolidated.starts_with("digraph {\n    label=\"delete_min 1\";"));
+        // Five nodes left after deleting the minimum link into two trees.
+        assert_eq!(consolidated.matches(" -> ").count() - 1, 3);
+        assert_eq!(heap.to_dot_sequence().matches("digraph").count(), 8);
+    }
+
+    #[test]
+    fn recorder_writes_frames() {
+        let dir = env::temp_dir().join("rust_heaps_recorder_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        let mut heap = Recorder::new();
+        let a = heap.insert(2i32, "a");
+        heap.insert(1, "b");
+        heap.delete(a).unwrap();
+        heap.write_frames(&dir).unwrap();
+        let last = fs::read_to_string(dir.join("frame-002.dot")).unwrap();
+        assert_eq!(last, heap.frames()[2].dot);
+        assert!(last.contains("label=\"1\", peripheries=2"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt::{self, Debug, Display};
+use std::ops::Sub;
+use std::str::FromStr;
+use send_heap::{EntryId, SendFibHeap};
+use {Heap, DecreaseKey, HeapDelete, StaleEntry};
+
+/// An operation that changed a `LoggedHeap`. Entries are numbered in the
+/// order they were inserted, starting from 0, and the other operations refer
+/// to them by that number.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op<K, V> {
+    Insert(K, V),
+    DeleteMin,
+    DecreaseKey(u64, K),
+    Delete(u64),
+}
+
+/// The operations applied to a heap, in order.
+///
+/// Written with `Display`, a log has one operation per line: `i <key>
+/// <value>`, `d`, `k <entry> <delta>` or `x <entry>`. Keys must print without
+/// spaces; values take the rest of their line. `Log::parse` reads it back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Log<K, V> {
+    ops: Vec<Op<K, V>>,
+}
+
+/// The line of a log that `Log::parse` could not read, counting from 1.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+}
+
+impl<K, V> Log<K, V> {
+    pub fn new() -> Log<K, V> {
+        Log { ops: Vec::new() }
+    }
+
+    pub fn ops(&self) -> &[Op<K, V>] {
+        &self.ops
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn push(&mut self, op: Op<K, V>) {
+        self.ops.push(op);
+    }
+}
+
+impl<K: FromStr, V: FromStr> Log<K, V> {
+    /// Reads a log in the format written by `Display`. Blank lines are
+    /// skipped.
+    pub fn parse(text: &str) -> Result<Log<K, V>, ParseError> {
+        let mut log = Log::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue
+            }
+            match parse_op(line) {
+                Some(op) => log.push(op),
+                None => return Err(ParseError { line: i + 1 })
+            }
+        }
+        Ok(log)
+    }
+}
+
+fn parse_op<K: FromStr, V: FromStr>(line: &str) -> Option<Op<K, V>> {
+    let mut parts = line.splitn(3, ' ');
+    let op = match (parts.next(), parts.next(), parts.next()) {
+        (Some("i"), Some(k), Some(v)) => Op::Insert(k.parse().ok()?, v.parse().ok()?),
+        (Some("d"), None, None) => Op::DeleteMin,
+        (Some("k"), Some(id), Some(delta)) => Op::DecreaseKey(id.parse().ok()?, delta.parse().ok()?),
+        (Some("x"), Some(id), None) => Op::Delete(id.parse().ok()?),
+        _ => return None
+    };
+    Some(op)
+}
+
+impl<K: Display, V: Display> Display for Log<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for op in self.ops.iter() {
+            match *op {
+                Op::Insert(ref k, ref v) => writeln!(f, "i {} {}", k, v)?,
+                Op::DeleteMin => writeln!(f, "d")?,
+                Op::DecreaseKey(id, ref delta) => writeln!(f, "k {} {}", id, delta)?,
+                Op::Delete(id) => writeln!(f, "x {}", id)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A Fibonacci heap that logs every operation that changes it, so that
+/// `replay` can rebuild the same heap, down to the shape of its trees.
+///
+/// Handles are `EntryId`s numbered from 0 in insertion order, which is how
+/// the log refers to entries. Each operation is logged before it runs, so
+/// one that panics is the last in the log; one that returns `Err` is not
+/// logged.
+pub struct LoggedHeap<K, V> {
+    heap: SendFibHeap<K, V>,
+    log: Log<K, V>,
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> LoggedHeap<K, V> {
+    pub fn new() -> LoggedHeap<K, V> {
+        LoggedHeap { heap: SendFibHeap::new(), log: Log::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn heap(&self) -> &SendFibHeap<K, V> {
+        &self.heap
+    }
+
+    pub fn log(&self) -> &Log<K, V> {
+        &self.log
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for LoggedHeap<K, V> {
+    type HeapEntry = EntryId;
+
+    fn find_min(&self) -> (K, V) {
+        self.heap.find_min()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        self.log.push(Op::DeleteMin);
+        self.heap.delete_min()
+    }
+
+    fn insert(&mut self, key: K, value: V) -> EntryId {
+        self.log.push(Op::Insert(key.clone(), value.clone()));
+        self.heap.insert(key, value)
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for LoggedHeap<K, V> {
+    fn decrease_key(&mut self, entry: &EntryId, delta: K) -> Result<(), StaleEntry> {
+        // Logged first, so the log shows an operation that panics.
+        self.log.push(Op::DecreaseKey(entry.id(), delta.clone()));
+        let decreased = self.heap.decrease_key(entry, delta);
+        if decreased.is_err() {
+            self.log.ops.pop();
+        }
+        decreased
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> HeapDelete<K, V> for LoggedHeap<K, V> {
+    type HeapEntry = EntryId;
+
+    fn delete(&mut self, entry: EntryId) -> Result<(K, V), StaleEntry> {
+        self.log.push(Op::Delete(entry.id()));
+        let deleted = self.heap.delete(entry);
+        if deleted.is_err() {
+            self.log.ops.pop();
+        }
+        deleted
+    }
+}
+
+/// Applies the operations of `log` to a new heap, which ends up in the same
+/// state as the heap that wrote the log and keeps logging from there.
+/// Operations that returned `Err` aren't logged, so this panics on
+/// operations that refer to entries that aren't in the heap, and on one
+/// that panicked when it was logged.
+pub fn replay<K, V>(log: &Log<K, V>) -> LoggedHeap<K, V>
+where K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone {
+    let mut heap = LoggedHeap::new();
+    let mut entries = Vec::new();
+    for op in log.ops().iter() {
+        match *op {
+            Op::Insert(ref k, ref v) => entries.push(heap.insert(k.clone(), v.clone())),
+            Op::DeleteMin => { heap.delete_min(); }
+            Op::DecreaseKey(id, ref delta) => {
+                heap.decrease_key(&entries[id as usize], delta.clone()).expect("entry is not in the heap");
+            }
+            Op::Delete(id) => { heap.delete(entries[id as usize]).expect("entry is not in the heap"); }
+        }
+    }
+    heap
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use replay::{Log, LoggedHeap, Op, ParseError, replay};
+    use {Heap, DecreaseKey, HeapDelete};
+
+    #[test]
+    fn replay_rebuilds_heap() {
+        let mut heap = LoggedHeap::new();
+        let entries: Vec<_> = (0..20u32).map(|i| heap.insert(100 + (i * 7) % 20, i)).collect();
+        heap.delete_min();
+        heap.decrease_key(&entries[13], 50).unwrap();
+        heap.delete(entries[4]).unwrap();
+        heap.delete_min();
+        let mut copy = replay(heap.log());
+        assert_eq!(copy.log(), heap.log());
+        assert_eq!(copy.len(), heap.len());
+        copy.decrease_key(&entries[9], 90).unwrap();
+        heap.decrease_key(&entries[9], 90).unwrap();
+        while !heap.empty() {
+            assert_eq!(copy.delete_min(), heap.delete_min());
+        }
+        assert!(copy.empty());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn replay_logs_panicking_op() {
+        let mut heap = LoggedHeap::new();
+        let a = heap.insert(5u32, 'a');
+        heap.delete(a).unwrap();
+        assert!(heap.delete(a).is_err());
+        let b = heap.insert(3, 'b');
+        // Decreasing 3 by 4 overflows.
+        let decreased = panic::catch_unwind(AssertUnwindSafe(|| heap.decrease_key(&b, 4)));
+        assert!(decreased.is_err());
+        assert_eq!(heap.log().ops(), &[Op::Insert(5, 'a'), Op::Delete(0), Op::Insert(3, 'b'),
+                                       Op::DecreaseKey(1, 4)][..]);
+    }
+
+    #[test]
+    fn replay_log_text() {
+        let mut heap = LoggedHeap::new();
+        let a = heap.insert(5i32, "first entry".to_string());
+        heap.insert(3, "b".to_string());
+        heap.decrease_key(&a, 4).unwrap();
+        heap.delete_min();
+        let text = heap.log().to_string();
+        assert_eq!(text, "i 5 first entry\ni 3 b\nk 0 4\nd\n");
+        let log: Log<i32, String> = Log::parse(&text).unwrap();
+        assert_eq!(log.ops()[0], Op::Insert(5, "first entry".to_string()));
+        assert_eq!(replay(&log).find_min(), (3, "b".to_string()));
+        assert_eq!(Log::<i32, String>::parse("i 1 a\n\nk x 2"), Err(ParseError { line: 3 }));
+    }
+}
@@ -0,0 +1,52 @@
+use core::cmp::Ordering;
+use core::ops::{Add, Sub};
+
+/// Reverses the ordering of a key, turning the crate's min-heaps into
+/// max-heaps.
+///
+/// Decreasing a `Rev` key moves it towards the top of the heap, so
+/// subtraction raises the wrapped value: `Rev(a) - Rev(b) == Rev(a + b)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rev<K>(pub K);
+
+impl<K: Ord> Ord for Rev<K> {
+    fn cmp(&self, other: &Rev<K>) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl<K: PartialOrd> PartialOrd for Rev<K> {
+    fn partial_cmp(&self, other: &Rev<K>) -> Option<Ordering> {
+        other.0.partial_cmp(&self.0)
+    }
+}
+
+impl<K: Add<K, Output=K>> Sub for Rev<K> {
+    type Output = Rev<K>;
+
+    // Lowering a reversed key raises the key it wraps.
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, other: Rev<K>) -> Rev<K> {
+        Rev(self.0 + other.0)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {Heap, DecreaseKey};
+    use fibonacci_heap::{FibHeap};
+    use rev::Rev;
+
+    #[test]
+    fn rev_max_heap() {
+        let mut fheap: FibHeap<Rev<u8>, u8> = FibHeap::new();
+        fheap.insert(Rev(1), 1);
+        let two = fheap.insert(Rev(2), 2);
+        fheap.insert(Rev(5), 5);
+        assert_eq!(fheap.find_min(), (Rev(5), 5));
+        fheap.decrease_key(&two, Rev(4)).unwrap();
+        assert_eq!(fheap.delete_min(), (Rev(6), 2));
+        assert_eq!(fheap.delete_min(), (Rev(5), 5));
+        assert_eq!(fheap.delete_min(), (Rev(1), 1));
+    }
+}
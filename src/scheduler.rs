@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::Sub;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey};
+
+/// Handle to a task waiting in a `Scheduler`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TaskHandle(u64);
+
+struct Aging<P> {
+    interval: usize,
+    step: P,
+    floor: P,
+}
+
+/// A run-queue that hands out the waiting task with the smallest priority
+/// value first.
+///
+/// With aging enabled, every waiting task's priority is periodically lowered
+/// so that tasks which keep losing to newcomers eventually run.
+pub struct Scheduler<P, T> {
+    heap: FibHeap<P, u64>,
+    tasks: HashMap<u64, (Rc<FibNode<P, u64>>, T)>,
+    next_id: u64,
+    aging: Option<Aging<P>>,
+    since_aging: usize,
+}
+
+impl<P: Ord + Debug + Clone + Sub<P, Output=P>, T> Scheduler<P, T> {
+    pub fn new() -> Scheduler<P, T> {
+        Scheduler {
+            heap: FibHeap::new(),
+            tasks: HashMap::new(),
+            next_id: 0,
+            aging: None,
+            since_aging: 0,
+        }
+    }
+
+    /// A scheduler that calls `age` after every `interval` calls to `next`,
+    /// lowering each waiting task's priority by `step` but not below `floor`.
+    pub fn with_aging(interval: usize, step: P, floor: P) -> Scheduler<P, T> {
+        let mut scheduler = Scheduler::new();
+        scheduler.aging = Some(Aging { interval: interval, step: step, floor: floor });
+        scheduler
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn spawn(&mut self, priority: P, task: T) -> TaskHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(priority, id);
+        self.tasks.insert(id, (entry, task));
+        TaskHandle(id)
+    }
+
+    /// Removes and returns the task to run next.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<T> {
+        if self.heap.empty() {
+            return None
+        }
+        let (_, id) = self.heap.delete_min();
+        let (_, task) = self.tasks.remove(&id).unwrap();
+        let due = match self.aging {
+            Some(ref aging) => {
+                self.since_aging += 1;
+                self.since_aging >= aging.interval
+            }
+            None => false
+        };
+        if due {
+            self.age();
+        }
+        Some(task)
+    }
+
+    /// Lowers a waiting task's priority value by `amount`, but not below
+    /// zero; a priority already below zero stays put. Returns whether the
+    /// task was still waiting.
+    pub fn boost(&mut self, handle: TaskHandle, amount: P) -> bool {
+        match self.tasks.get(&handle.0) {
+            Some((entry, _)) => {
+                let key = entry.get_key().clone();
+                let zero = key.clone() - key.clone();
+                let amount = if key <= zero { zero } else if amount > key { key } else { amount };
+                self.heap.decrease_key(entry, amount).unwrap();
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn priority(&self, handle: TaskHandle) -> Option<P> {
+        self.tasks.get(&handle.0).map(|(entry, _)| entry.get_key().clone())
+    }
+
+    /// Runs an aging sweep now. Does nothing if aging is not enabled.
+    pub fn age(&mut self) {
+        self.since_aging = 0;
+        let aging = match self.aging {
+            Some(ref aging) => aging,
+            None => return
+        };
+        for (entry, _) in self.tasks.values() {
+            let key = entry.get_key().clone();
+            if key <= aging.floor {
+                continue
+            }
+            let headroom = key - aging.floor.clone();
+            let delta = if headroom < aging.step { headroom } else { aging.step.clone() };
+            self.heap.decrease_key(entry, delta).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scheduler::Scheduler;
+
+    #[test]
+    fn scheduler_runs_by_priority() {
+        let mut scheduler = Scheduler::new();
+        scheduler.spawn(5u32, "low");
+        let mid = scheduler.spawn(3, "mid");
+        scheduler.spawn(1, "high");
+        assert!(scheduler.boost(mid, 3));
+        assert_eq!(scheduler.priority(mid), Some(0));
+        assert_eq!(scheduler.next(), Some("mid"));
+        assert!(!scheduler.boost(mid, 1));
+        assert_eq!(scheduler.next(), Some("high"));
+        assert_eq!(scheduler.next(), Some("low"));
+        assert_eq!(scheduler.next(), None);
+    }
+
+    #[test]
+    fn scheduler_boost_stops_at_zero() {
+        let mut scheduler = Scheduler::new();
+        let a = scheduler.spawn(3u32, "a");
+        scheduler.spawn(1, "b");
+        assert!(scheduler.boost(a, 5));
+        assert_eq!(scheduler.priority(a), Some(0));
+        assert_eq!(scheduler.next(), Some("a"));
+
+        let mut scheduler = Scheduler::new();
+        let c = scheduler.spawn(2i32, "c");
+        let d = scheduler.spawn(-2, "d");
+        assert!(scheduler.boost(c, 5));
+        assert!(scheduler.boost(d, 1));
+        assert_eq!(scheduler.priority(c), Some(0));
+        assert_eq!(scheduler.priority(d), Some(-2));
+    }
+
+    #[test]
+    fn scheduler_aging_prevents_starvation() {
+        let mut scheduler = Scheduler::with_aging(1, 2u32, 0);
+        let starved = scheduler.spawn(9, "starved");
+        let mut ran = Vec::new();
+        for _ in 0..6 {
+            scheduler.spawn(4, "new");
+            ran.push(scheduler.next().unwrap());
+        }
+        assert_eq!(scheduler.priority(starved), None);
+        assert!(ran.contains(&"starved"));
+        assert_eq!(scheduler.len(), 1);
+    }
+
+    #[test]
+    fn scheduler_aging_stops_at_floor() {
+        let mut scheduler = Scheduler::with_aging(10, 3u32, 2);
+        let a = scheduler.spawn(7, "a");
+        let b = scheduler.spawn(1, "b");
+        scheduler.age();
+        assert_eq!(scheduler.priority(a), Some(4));
+        assert_eq!(scheduler.priority(b), Some(1));
+        scheduler.age();
+        assert_eq!(scheduler.priority(a), Some(2));
+    }
+}
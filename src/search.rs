@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use {Heap, DecreaseKey, HeapDelete};
+
+type Entry<C> = Rc<FibNode<Ordered<C>, u64>>;
+
+/// Best-first search from `start` to the first state satisfying `goal`,
+/// returning the path found and its cost.
+///
+/// `expand` gives the successors of a state with the cost of reaching each
+/// one. States are explored in order of `priority(state, cost)`, where
+/// `cost` is that of the cheapest path to the state found so far. When a
+/// cheaper path to a waiting state is found, its entry is updated with
+/// decrease_key. Expanded states are never reopened, so the path is only
+/// guaranteed to be cheapest when `priority` orders states like uniform-cost
+/// search or A* with a consistent heuristic.
+pub fn best_first<N, W, H, X, I, G, P>(heap: &mut H, start: N, mut expand: X, mut goal: G,
+                                       mut priority: P) -> Option<(Vec<N>, W)>
+where N: Hash + Eq + Clone,
+      W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize>,
+      X: FnMut(&N) -> I,
+      I: IntoIterator<Item=(N, W)>,
+      G: FnMut(&N) -> bool,
+      P: FnMut(&N, &W) -> W {
+    // Every state seen gets an index; the heap holds indices.
+    let first = priority(&start, &W::default());
+    let mut states = vec!(start.clone());
+    let mut index = HashMap::new();
+    let mut costs = vec!(W::default());
+    let mut keys = vec!(first.clone());
+    let mut parents: Vec<Option<usize>> = vec!(None);
+    let mut entries = vec!(Some(heap.insert(first, 0)));
+    index.insert(start, 0);
+
+    while !heap.empty() {
+        let (_, current) = heap.delete_min();
+        entries[current] = None;
+        if goal(&states[current]) {
+            let mut path = vec!(states[current].clone());
+            let mut node = current;
+            while let Some(parent) = parents[node] {
+                path.push(states[parent].clone());
+                node = parent;
+            }
+            path.reverse();
+            return Some((path, costs[current].clone()))
+        }
+
+        for (next, weight) in expand(&states[current]) {
+            let cost = costs[current].clone() + weight;
+            match index.get(&next).cloned() {
+                Some(i) => {
+                    // Closed states have no entry left in the heap.
+                    if entries[i].is_none() || cost >= costs[i] {
+                        continue
+                    }
+                    let key = priority(&next, &cost);
+                    if key < keys[i] {
+                        heap.decrease_key(entries[i].as_ref().unwrap(), keys[i].clone() - key.clone()).unwrap();
+                        keys[i] = key;
+                    }
+                    costs[i] = cost;
+                    parents[i] = Some(current);
+                }
+                None => {
+                    let i = states.len();
+                    let key = priority(&next, &cost);
+                    entries.push(Some(heap.insert(key.clone(), i)));
+                    states.push(next.clone());
+                    costs.push(cost);
+                    keys.push(key);
+                    parents.push(Some(current));
+                    index.insert(next, i);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Uniform-cost search, i.e. Dijkstra's algorithm stopped at the first goal
+/// state. The path returned is a cheapest one.
+pub fn uniform_cost<N, W, H, X, I, G>(heap: &mut H, start: N, expand: X, goal: G)
+    -> Option<(Vec<N>, W)>
+where N: Hash + Eq + Clone,
+      W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize>,
+      X: FnMut(&N) -> I,
+      I: IntoIterator<Item=(N, W)>,
+      G: FnMut(&N) -> bool {
+    best_first(heap, start, expand, goal, |_, cost| cost.clone())
+}
+
+/// Greedy best-first search, which always expands the state `heuristic`
+/// rates closest to a goal. Usually fast, but the path returned need not be
+/// a cheapest one.
+pub fn greedy<N, W, H, X, I, G, E>(heap: &mut H, start: N, expand: X, goal: G,
+                                   mut heuristic: E) -> Option<(Vec<N>, W)>
+where N: Hash + Eq + Clone,
+      W: Ord + Clone + Default + Add<W, Output=W> + Sub<W, Output=W>,
+      H: DecreaseKey<W, usize>,
+      X: FnMut(&N) -> I,
+      I: IntoIterator<Item=(N, W)>,
+      G: FnMut(&N) -> bool,
+      E: FnMut(&N) -> W {
+    best_first(heap, start, expand, goal, |state, _| heuristic(state))
+}
+
+/// What branching on a node of a branch-and-bound search produced.
+pub enum Branch<N, C> {
+    /// A subproblem to explore later.
+    Node(N),
+    /// A complete solution and its cost.
+    Solution(N, C),
+}
+
+/// Minimizing branch-and-bound search from `root`, as an iterator over
+/// successively cheaper solutions. The last one is optimal.
+///
+/// `bound` gives a lower bound on the cost of every solution under a node,
+/// and the frontier is explored in order of it. Whenever a cheaper solution
+/// is found, every node on the frontier whose bound is no better is deleted
+/// from the heap.
+pub fn branch_and_bound<N, C, B, F, I>(root: N, bound: B, branch: F) -> BranchAndBound<N, C, B, F>
+where C: Ord + Clone,
+      B: FnMut(&N) -> C,
+      F: FnMut(N) -> I,
+      I: IntoIterator<Item=Branch<N, C>> {
+    let mut search = BranchAndBound {
+        heap: FibHeap::new(),
+        nodes: HashMap::new(),
+        incumbent: None,
+        pruned: 0,
+        next_id: 0,
+        bound: bound,
+        branch: branch,
+    };
+    search.push(root);
+    search
+}
+
+pub struct BranchAndBound<N, C, B, F> {
+    heap: FibHeap<Ordered<C>, u64>,
+    nodes: HashMap<u64, (Entry<C>, N)>,
+    incumbent: Option<C>,
+    pruned: usize,
+    next_id: u64,
+    bound: B,
+    branch: F,
+}
+
+impl<N, C, B, F, I> BranchAndBound<N, C, B, F>
+where C: Ord + Clone,
+      B: FnMut(&N) -> C,
+      F: FnMut(N) -> I,
+      I: IntoIterator<Item=Branch<N, C>> {
+    /// The cost of the best solution found so far.
+    pub fn incumbent(&self) -> Option<&C> {
+        self.incumbent.as_ref()
+    }
+
+    /// The number of nodes discarded because their bound could not beat the
+    /// incumbent.
+    pub fn pruned(&self) -> usize {
+        self.pruned
+    }
+
+    fn push(&mut self, node: N) {
+        let bound = (self.bound)(&node);
+        if self.incumbent.as_ref().is_some_and(|best| bound >= *best) {
+            self.pruned += 1;
+            return
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(Ordered(bound), id);
+        self.nodes.insert(id, (entry, node));
+    }
+
+    fn prune(&mut self, best: &C) {
+        let dominated: Vec<u64> = self.nodes.iter()
+            .filter(|&(_, (entry, _))| entry.get_key().0 >= *best)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in dominated.into_iter() {
+            let (entry, _) = self.nodes.remove(&id).unwrap();
+            self.heap.delete(entry).unwrap();
+            self.pruned += 1;
+        }
+    }
+}
+
+impl<N, C, B, F, I> Iterator for BranchAndBound<N, C, B, F>
+where C: Ord + Clone,
+      B: FnMut(&N) -> C,
+      F: FnMut(N) -> I,
+      I: IntoIterator<Item=Branch<N, C>> {
+    type Item = (N, C);
+
+    fn next(&mut self) -> Option<(N, C)> {
+        while !self.heap.empty() {
+            let (_, id) = self.heap.delete_min();
+            let (_, node) = self.nodes.remove(&id).unwrap();
+            let mut improved = None;
+            for step in (self.branch)(node) {
+                match step {
+                    Branch::Node(child) => self.push(child),
+                    Branch::Solution(solution, cost) => {
+                        if self.incumbent.as_ref().is_none_or(|best| cost < *best) {
+                            self.incumbent = Some(cost.clone());
+                            improved = Some((solution, cost));
+                        }
+                    }
+                }
+            }
+            if let Some((solution, cost)) = improved {
+                self.prune(&cost);
+                return Some((solution, cost))
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_heap::{FibHeap};
+    use search::{best_first, uniform_cost, greedy, branch_and_bound, Branch};
+
+    // A 1-D line of states where stepping right costs 1 and a shortcut from
+    // 0 straight to 10 costs 20.
+    fn line(n: &u32) -> Vec<(u32, u32)> {
+        let mut next = vec!();
+        if *n < 10 {
+            next.push((n + 1, 1));
+        }
+        if *n == 0 {
+            next.push((10, 20));
+        }
+        next
+    }
+
+    #[test]
+    fn search_uniform_cost() {
+        let mut heap = FibHeap::new();
+        let (path, cost) = uniform_cost(&mut heap, 0u32, line, |n| *n == 10).unwrap();
+        assert_eq!(cost, 10);
+        assert_eq!(path, (0..11).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn search_greedy() {
+        let mut heap = FibHeap::new();
+        let (path, cost) = greedy(&mut heap, 0u32, line, |n| *n == 10, |n| 10 - *n).unwrap();
+        assert_eq!(cost, 20);
+        assert_eq!(path, vec!(0, 10));
+    }
+
+    #[test]
+    fn search_rediscovery_decreases_key() {
+        // Diamond where the direct edge to 3 is found first but the path
+        // through 1 and 2 is cheaper.
+        let graph = [vec!((1u32, 1u32), (3, 10)), vec!((2, 1)), vec!((3, 1)), vec!()];
+        let mut heap = FibHeap::new();
+        let result = best_first(&mut heap, 0u32, |n| graph[*n as usize].clone(),
+                                |n| *n == 3, |_, cost| *cost);
+        assert_eq!(result, Some((vec!(0, 1, 2, 3), 3)));
+
+        let mut heap = FibHeap::new();
+        assert_eq!(uniform_cost(&mut heap, 0u32, line, |n| *n == 11), None);
+    }
+
+    // Picks one of each pair so the chosen values sum to at least `need`,
+    // minimizing the sum. Nodes are the choices made so far.
+    fn pick_pairs(pairs: &[(u32, u32)], need: u32) -> Vec<(Vec<u32>, u32)> {
+        let bound = |chosen: &Vec<u32>| chosen.iter().sum::<u32>()
+            + pairs[chosen.len()..].iter().map(|&(a, b)| a.min(b)).sum::<u32>();
+        let search = branch_and_bound(vec!(), bound, |chosen: Vec<u32>| {
+            if chosen.len() == pairs.len() {
+                let sum = chosen.iter().sum();
+                return if sum >= need { vec!(Branch::Solution(chosen, sum)) } else { vec!() }
+            }
+            let (a, b) = pairs[chosen.len()];
+            let mut left = chosen.clone();
+            left.push(a);
+            let mut right = chosen;
+            right.push(b);
+            vec!(Branch::Node(left), Branch::Node(right))
+        });
+        search.collect()
+    }
+
+    #[test]
+    fn search_branch_and_bound() {
+        let solutions = pick_pairs(&[(5, 1), (2, 6), (4, 3), (7, 2)], 15);
+        let &(ref best, cost) = solutions.last().unwrap();
+        assert_eq!(cost, 16);
+        assert_eq!(best, &vec!(5, 6, 3, 2));
+        assert!(solutions.windows(2).all(|w| w[1].1 < w[0].1));
+        assert!(pick_pairs(&[(1, 2)], 3).is_empty());
+    }
+
+    #[test]
+    fn search_branch_and_bound_prunes() {
+        // The first solution found costs 2, which no other node's bound can
+        // beat.
+        let mut search = branch_and_bound(0u32, |n: &u32| *n, |n: u32| {
+            if n == 0 {
+                vec!(Branch::Node(1), Branch::Node(2), Branch::Node(3))
+            } else {
+                vec!(Branch::Solution(n, n + 1), Branch::Node(n + 10))
+            }
+        });
+        assert_eq!(search.next(), Some((1, 2)));
+        assert_eq!(search.incumbent(), Some(&2));
+        assert_eq!(search.pruned(), 3);
+        assert_eq!(search.next(), None);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::Sub;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete, AddressableHeap, StaleEntry};
+
+/// Handle to an entry of a `SendFibHeap`. Handles are plain ids, so they
+/// can be sent to another thread along with the heap.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntryId(u64);
+
+impl EntryId {
+    /// The entry's number. Entries are numbered from 0 in the order they
+    /// were inserted.
+    pub fn id(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A `FibHeap` whose entry handles are ids rather than `Rc`s, so the heap
+/// and its handles can be built on one thread and moved to another.
+///
+/// The heap keeps the `Rc` handles itself, in a table indexed by id. With
+/// the `slotmap` feature, `slot_heap::SlotFibHeap` is also `Send` and avoids
+/// the table lookup.
+pub struct SendFibHeap<K, V> {
+    heap: FibHeap<K, u64>,
+    entries: HashMap<u64, (Rc<FibNode<K, u64>>, V)>,
+    next_id: u64,
+}
+
+// Every `Rc` pointing into the heap is held by the heap or the table, and
+// neither is shared, so moving them all to another thread at once is safe.
+unsafe impl<K: Send, V: Send> Send for SendFibHeap<K, V> {}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for SendFibHeap<K, V> {
+    type HeapEntry = EntryId;
+
+    fn find_min(&self) -> (K, V) {
+        let (k, id) = self.heap.find_min();
+        (k, self.entries[&id].1.clone())
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        let (k, id) = self.heap.delete_min();
+        (k, self.entries.remove(&id).unwrap().1)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> EntryId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let node = self.heap.insert(key, id);
+        self.entries.insert(id, (node, value));
+        EntryId(id)
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for SendFibHeap<K, V> {
+    fn decrease_key(&mut self, entry: &EntryId, delta: K) -> Result<(), StaleEntry> {
+        let node = match self.entries.get(&entry.0) {
+            Some((node, _)) => node.clone(),
+            None => return Err(StaleEntry)
+        };
+        self.heap.decrease_key(&node, delta)
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> HeapDelete<K, V> for SendFibHeap<K, V> {
+    type HeapEntry = EntryId;
+
+    fn delete(&mut self, entry: EntryId) -> Result<(K, V), StaleEntry> {
+        let (node, value) = self.entries.remove(&entry.0).ok_or(StaleEntry)?;
+        let (k, _) = self.heap.delete(node)?;
+        Ok((k, value))
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> AddressableHeap<K, V> for SendFibHeap<K, V> {
+    fn is_valid(&self, entry: &EntryId) -> bool {
+        self.contains(*entry)
+    }
+}
+
+impl<K: Ord + Debug + Clone + Sub<K, Output=K>, V: Clone> SendFibHeap<K, V> {
+    pub fn new() -> SendFibHeap<K, V> {
+        SendFibHeap { heap: FibHeap::new(), entries: HashMap::new(), next_id: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn contains(&self, entry: EntryId) -> bool {
+        self.entries.contains_key(&entry.0)
+    }
+
+    pub fn get(&self, entry: EntryId) -> Option<(&K, &V)> {
+        self.entries.get(&entry.0).map(|(node, value)| (node.get_key(), value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use send_heap::SendFibHeap;
+    use {Heap, DecreaseKey, HeapDelete};
+
+    #[test]
+    fn send_heap_operations() {
+        let mut heap = SendFibHeap::new();
+        let a = heap.insert(5u32, "a");
+        let b = heap.insert(3, "b");
+        let c = heap.insert(8, "c");
+        heap.decrease_key(&c, 7).unwrap();
+        assert_eq!(heap.get(c), Some((&1, &"c")));
+        assert_eq!(heap.find_min(), (1, "c"));
+        assert_eq!(heap.delete(b), Ok((3, "b")));
+        assert_eq!(heap.delete_min(), (1, "c"));
+        assert!(!heap.contains(c));
+        assert!(heap.contains(a));
+        assert_eq!(heap.len(), 1);
+    }
+
+    #[test]
+    fn send_heap_moves_with_handles() {
+        let mut heap = SendFibHeap::new();
+        let handles: Vec<_> = (0..10u32).map(|i| heap.insert(100 + i, i)).collect();
+        let moved = thread::spawn(move || {
+            heap.decrease_key(&handles[7], 100).unwrap();
+            heap.delete(handles[0]).unwrap();
+            (0..9).map(|_| heap.delete_min()).collect::<Vec<_>>()
+        }).join().unwrap();
+        assert_eq!(moved[0], (7, 7));
+        assert_eq!(moved[1], (101, 1));
+        assert_eq!(moved.len(), 9);
+    }
+}
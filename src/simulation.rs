@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::ops::Add;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use {Heap, HeapDelete};
+
+/// Identifies a scheduled event, for cancelling it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EventId(u64);
+
+/// Returned with the event when scheduling it before the current time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InPast<E>(pub E);
+
+type Key<T> = Ordered<(T, u64)>;
+type Entry<T> = Rc<FibNode<Key<T>, u64>>;
+
+/// The clock and future-event list of a discrete-event simulation.
+///
+/// Time only moves forward, to the time of each event as it is processed,
+/// and events can't be scheduled before the current time. Events scheduled
+/// for the same time are processed in the order they were scheduled.
+pub struct Clock<T, E> {
+    now: T,
+    heap: FibHeap<Key<T>, u64>,
+    events: HashMap<u64, (Entry<T>, E)>,
+    next_id: u64,
+}
+
+impl<T: Ord + Clone, E> Clock<T, E> {
+    pub fn new(start: T) -> Clock<T, E> {
+        Clock { now: start, heap: FibHeap::new(), events: HashMap::new(), next_id: 0 }
+    }
+
+    pub fn now(&self) -> T {
+        self.now.clone()
+    }
+
+    /// The number of pending events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn schedule(&mut self, at: T, event: E) -> Result<EventId, InPast<E>> {
+        if at < self.now {
+            return Err(InPast(event))
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(Ordered((at, id)), id);
+        self.events.insert(id, (entry, event));
+        Ok(EventId(id))
+    }
+
+    /// Removes a pending event, or returns `None` if it was already
+    /// processed or cancelled.
+    pub fn cancel(&mut self, id: EventId) -> Option<E> {
+        self.events.remove(&id.0).map(|(entry, event)| {
+            self.heap.delete(entry).unwrap();
+            event
+        })
+    }
+
+    pub fn peek_next_time(&self) -> Option<T> {
+        if self.heap.empty() {
+            None
+        } else {
+            let (Ordered((at, _)), _) = self.heap.find_min();
+            Some(at)
+        }
+    }
+
+    /// Advances the clock to the next event and removes it.
+    pub fn step(&mut self) -> Option<(T, E)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (Ordered((at, _)), id) = self.heap.delete_min();
+        let (_, event) = self.events.remove(&id).unwrap();
+        self.now = at.clone();
+        Some((at, event))
+    }
+
+    /// Processes every event up to and including time `until` with
+    /// `handler`, which may schedule further events, then advances the clock
+    /// to `until`. Returns the number of events processed.
+    pub fn run_until<F>(&mut self, until: T, mut handler: F) -> usize
+    where F: FnMut(&mut Clock<T, E>, E) {
+        let mut processed = 0;
+        while self.peek_next_time().is_some_and(|at| at <= until) {
+            let (_, event) = self.step().unwrap();
+            handler(self, event);
+            processed += 1;
+        }
+        if until > self.now {
+            self.now = until;
+        }
+        processed
+    }
+}
+
+impl<T: Ord + Clone + Add<T, Output=T>, E> Clock<T, E> {
+    /// Schedules an event `delay` after the current time.
+    pub fn schedule_in(&mut self, delay: T, event: E) -> EventId {
+        let at = self.now.clone() + delay;
+        match self.schedule(at, event) {
+            Ok(id) => id,
+            Err(_) => panic!("Delay must not be negative")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simulation::{Clock, InPast};
+
+    #[test]
+    fn simulation_rejects_past_events() {
+        let mut clock = Clock::new(5u64);
+        assert_eq!(clock.schedule(4, "late"), Err(InPast("late")));
+        let id = clock.schedule(5, "now").unwrap();
+        clock.schedule_in(3, "later");
+        assert_eq!(clock.peek_next_time(), Some(5));
+        assert_eq!(clock.cancel(id), Some("now"));
+        assert_eq!(clock.cancel(id), None);
+        assert_eq!(clock.step(), Some((8, "later")));
+        assert_eq!(clock.now(), 8);
+        assert_eq!(clock.schedule(7, "past"), Err(InPast("past")));
+        assert_eq!(clock.step(), None);
+    }
+
+    #[test]
+    fn simulation_run_until() {
+        // Each arrival schedules the next one 4 ticks later, and a departure
+        // 3 ticks later.
+        let mut clock = Clock::new(0u64);
+        clock.schedule(1, ("arrive", 0)).unwrap();
+        let mut log = Vec::new();
+        let processed = clock.run_until(10, |clock, (kind, n)| {
+            log.push((clock.now(), kind, n));
+            if kind == "arrive" {
+                clock.schedule_in(4, ("arrive", n + 1));
+                clock.schedule_in(3, ("depart", n));
+            }
+        });
+        assert_eq!(processed, 5);
+        assert_eq!(log, vec!((1, "arrive", 0), (4, "depart", 0), (5, "arrive", 1),
+                             (8, "depart", 1), (9, "arrive", 2)));
+        assert_eq!(clock.now(), 10);
+        assert_eq!(clock.peek_next_time(), Some(12));
+        assert_eq!(clock.len(), 2);
+    }
+}
@@ -0,0 +1,141 @@
+use std::mem;
+use std::ops::Sub;
+use {Heap, HeapExt};
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    left: Option<Box<Node<K, V>>>,
+    right: Option<Box<Node<K, V>>>,
+}
+
+/// A skew heap: a binary tree that merges along the right spines of both
+/// heaps and swaps the children of every node it passes, which keeps merges
+/// cheap on average without storing any balance information.
+///
+/// Use it where only inserting, deleting the minimum and merging are
+/// needed.
+pub struct SkewHeap<K, V> {
+    root: Option<Box<Node<K, V>>>,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> SkewHeap<K, V> {
+    pub const fn new() -> SkewHeap<K, V> {
+        SkewHeap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // Merges two trees top down, without recursing, since the right spines
+    // can be as long as the heap.
+    fn meld(mut a: Option<Box<Node<K, V>>>, mut b: Option<Box<Node<K, V>>>) -> Option<Box<Node<K, V>>> {
+        let mut root = None;
+        let mut hole = &mut root;
+        loop {
+            let (x, y) = match (a, b) {
+                (Some(x), Some(y)) => (x, y),
+                (rest, None) | (None, rest) => {
+                    *hole = rest;
+                    return root
+                }
+            };
+            let (mut least, other) = if y.key < x.key { (y, x) } else { (x, y) };
+            a = least.right.take();
+            b = Some(other);
+            least.right = least.left.take();
+            hole = &mut hole.get_or_insert(least).left;
+        }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for SkewHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        match self.root {
+            Some(ref root) => (root.key.clone(), root.value.clone()),
+            None => panic!("SkewHeap is empty")
+        }
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        match self.root.take() {
+            Some(root) => {
+                let root = *root;
+                self.root = SkewHeap::meld(root.left, root.right);
+                self.len -= 1;
+                (root.key, root.value)
+            }
+            None => panic!("SkewHeap is empty")
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        let node = Box::new(Node { key: key, value: value, left: None, right: None });
+        self.root = SkewHeap::meld(self.root.take(), Some(node));
+        self.len += 1;
+    }
+
+    fn empty(&self) -> bool {
+        self.root.is_none()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapExt for SkewHeap<K, V> {
+    fn merge(mut self, mut other: SkewHeap<K, V>) -> SkewHeap<K, V> {
+        self.root = SkewHeap::meld(self.root.take(), other.root.take());
+        self.len += mem::replace(&mut other.len, 0);
+        self
+    }
+}
+
+// Dropping the nodes one at a time, since the tree can be as deep as the
+// heap is large.
+impl<K, V> Drop for SkewHeap<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use skew_heap::SkewHeap;
+    use {Heap, HeapExt};
+
+    #[test]
+    fn skew_heap_merge() {
+        let mut a = SkewHeap::new();
+        let mut b = SkewHeap::new();
+        for i in 0..100u32 {
+            a.insert((i * 37) % 100, 'a');
+            b.insert((i * 13) % 100 + 50, 'b');
+        }
+        let mut heap = a.merge(b);
+        assert_eq!(heap.len(), 200);
+        assert_eq!(heap.find_min(), (0, 'a'));
+        let mut keys = Vec::new();
+        while !heap.empty() {
+            keys.push(heap.delete_min().0);
+        }
+        let mut expected: Vec<_> = (0..100).chain(50..150).collect();
+        expected.sort();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn skew_heap_deep_trees() {
+        let mut heap = SkewHeap::new();
+        for i in (0..200000u32).rev() {
+            heap.insert(i, ());
+        }
+        assert_eq!(heap.delete_min(), (0, ()));
+        drop(heap);
+    }
+}
@@ -0,0 +1,121 @@
+// Slot storage for the heaps whose handles name a slot by index. Each slot
+// has a generation, bumped whenever its entry is removed, so a handle to a
+// removed entry is spotted even once its slot holds another entry.
+
+/// How many times a slot's entry has been removed.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Generation(u32);
+
+impl Generation {
+    pub const fn new() -> Generation {
+        Generation(0)
+    }
+
+    // Called whenever the slot's entry is removed.
+    pub fn bump(&mut self) {
+        self.0 = self.0.wrapping_add(1);
+    }
+}
+
+/// A slot's index and its generation when the entry went in.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SlotKey {
+    index: usize,
+    generation: Generation,
+}
+
+impl SlotKey {
+    pub fn new(index: usize, generation: Generation) -> SlotKey {
+        SlotKey { index: index, generation: generation }
+    }
+
+    pub fn index(self) -> usize {
+        self.index
+    }
+
+    // Whether the key names the entry of a slot now at `generation`.
+    pub fn is_current(self, generation: Generation) -> bool {
+        self.generation == generation
+    }
+}
+
+// The heaps with a growable vector of slots; `ArrayHeap` keeps its own.
+#[cfg(any(feature = "binary-heap", feature = "bucket-queue", feature = "strict-fib-heap",
+          feature = "veb-heap", feature = "calendar-queue"))]
+pub use self::growable::Slab;
+
+#[cfg(any(feature = "binary-heap", feature = "bucket-queue", feature = "strict-fib-heap",
+          feature = "veb-heap", feature = "calendar-queue"))]
+mod growable {
+    use std::ops::{Index, IndexMut};
+    use slab::{Generation, SlotKey};
+
+    struct Slot<T> {
+        item: Option<T>,
+        generation: Generation,
+    }
+
+    // A vector of slots, reusing freed ones. Indexing a free slot panics.
+    pub struct Slab<T> {
+        slots: Vec<Slot<T>>,
+        free: Vec<usize>,
+    }
+
+    impl<T> Slab<T> {
+        pub const fn new() -> Slab<T> {
+            Slab { slots: Vec::new(), free: Vec::new() }
+        }
+
+        #[cfg(any(feature = "binary-heap", feature = "strict-fib-heap"))]
+        pub fn reserve(&mut self, additional: usize) {
+            self.slots.reserve(additional);
+            self.free.reserve(additional);
+        }
+
+        pub fn insert(&mut self, item: T) -> SlotKey {
+            let index = match self.free.pop() {
+                Some(index) => {
+                    self.slots[index].item = Some(item);
+                    index
+                }
+                None => {
+                    self.slots.push(Slot { item: Some(item), generation: Generation::new() });
+                    self.slots.len() - 1
+                }
+            };
+            SlotKey::new(index, self.slots[index].generation)
+        }
+
+        // Frees the slot, so that its handles go stale.
+        pub fn remove(&mut self, index: usize) -> T {
+            let slot = &mut self.slots[index];
+            let item = slot.item.take().expect("slot is already free");
+            slot.generation.bump();
+            self.free.push(index);
+            item
+        }
+
+        pub fn contains(&self, key: SlotKey) -> bool {
+            match self.slots.get(key.index) {
+                Some(slot) => slot.item.is_some() && key.is_current(slot.generation),
+                None => false
+            }
+        }
+    }
+
+    impl<T> Index<usize> for Slab<T> {
+        type Output = T;
+
+        fn index(&self, index: usize) -> &T {
+            self.slots[index].item.as_ref().expect("slot is free")
+        }
+    }
+
+    impl<T> IndexMut<usize> for Slab<T> {
+        fn index_mut(&mut self, index: usize) -> &mut T {
+            self.slots[index].item.as_mut().expect("slot is free")
+        }
+    }
+}
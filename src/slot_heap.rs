@@ -0,0 +1,521 @@
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::ops::Sub;
+use std::sync::atomic::{AtomicU64, Ordering};
+use slotmap::SlotMap;
+#[cfg(feature = "defmt")]
+use slotmap::Key;
+use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
+
+new_key_type! {
+    struct NodeKey;
+}
+
+// Numbers heaps, so that handles of one are never taken for another's.
+static NEXT_HEAP: AtomicU64 = AtomicU64::new(0);
+
+/// Handle to an entry of a `SlotFibHeap`. Handles are `Copy` and carry the
+/// slot's generation and the heap's id, so a handle to a removed entry, or
+/// to an entry of another heap, is never mistaken for a live one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SlotEntry {
+    key: NodeKey,
+    heap: u64,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for SlotEntry {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "SlotEntry {{ key: {=u64}, heap: {=u64} }}", self.key.data().as_ffi(), self.heap)
+    }
+}
+
+struct Node<K, V> {
+    parent: Option<NodeKey>,
+    children: Vec<NodeKey>,
+    marked: bool,
+    key: K,
+    value: V,
+}
+
+/// How a `SlotFibHeap` grows its node storage when an insert finds it full.
+///
+/// The storage is one vector, so growing copies every node. `SlotMap` has no
+/// exact reserve, so the vector may grow by more than the policy asks, but
+/// the heap's capacity is exactly what the policy gave it. To keep inserts
+/// free of reallocation, create the heap `with_capacity` and cap it there.
+#[derive(Clone, Copy, Debug)]
+pub enum Growth {
+    /// Doubles the storage. This is the default.
+    Doubling,
+    /// Doubles the storage, but inserts fail once the heap holds this many
+    /// entries.
+    Capped(usize),
+    /// Called with the current capacity, returns the capacity to grow to,
+    /// or None to make the insert fail.
+    Custom(fn(usize) -> Option<usize>),
+}
+
+/// The entry `SlotFibHeap::try_insert` had no room for.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Full<K, V>(pub K, pub V);
+
+impl<K, V> fmt::Display for Full<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SlotFibHeap can't grow")
+    }
+}
+
+/// A Fibonacci heap whose nodes live in a `SlotMap`, with slotmap keys as
+/// entry handles.
+pub struct SlotFibHeap<K, V> {
+    nodes: SlotMap<NodeKey, Node<K, V>>,
+    roots: Vec<NodeKey>,
+    min: Option<NodeKey>,
+    growth: Growth,
+    // The capacity the policy has granted; the storage may have room for more.
+    capacity: usize,
+    id: u64,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for SlotFibHeap<K, V> {
+    type HeapEntry = SlotEntry;
+
+    fn find_min(&self) -> (K, V) {
+        match self.min {
+            Some(min) => {
+                let node = &self.nodes[min];
+                (node.key.clone(), node.value.clone())
+            }
+            None => panic!("Fibonacci heap is empty")
+        }
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        match self.min {
+            Some(min) => self.remove_root(min),
+            None => panic!("Fibonacci heap is empty")
+        }
+    }
+
+    // Panics if the growth policy refuses to grow the heap.
+    fn insert(&mut self, key: K, value: V) -> SlotEntry {
+        match self.try_insert(key, value) {
+            Ok(entry) => entry,
+            Err(full) => panic!("{}", full)
+        }
+    }
+
+    fn empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for SlotFibHeap<K, V> {
+    fn decrease_key(&mut self, entry: &SlotEntry, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        let entry = entry.key;
+        let parent = {
+            let node = &mut self.nodes[entry];
+            node.key = node.key.clone() - delta;
+            node.parent
+        };
+        if let Some(p) = parent {
+            if self.nodes[entry].key < self.nodes[p].key {
+                self.cut(entry);
+                self.cascading_cut(p);
+            }
+        }
+        self.update_min(entry);
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapExt for SlotFibHeap<K, V> {
+    // Entries of the smaller heap are moved into the larger one, so handles
+    // from the smaller heap are no longer valid afterwards, and `contains`
+    // says so. The larger heap's growth policy is kept but not applied to
+    // the moved entries.
+    fn merge(mut self, mut other: SlotFibHeap<K, V>) -> SlotFibHeap<K, V> {
+        if other.nodes.len() > self.nodes.len() {
+            mem::swap(&mut self, &mut other);
+        }
+        for root in std::mem::take(&mut other.roots) {
+            let moved = self.adopt(&mut other.nodes, root, None);
+            self.roots.push(moved);
+            self.update_min(moved);
+        }
+        self.capacity = cmp::max(self.capacity, self.nodes.len());
+        self
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapDelete<K, V> for SlotFibHeap<K, V> {
+    type HeapEntry = SlotEntry;
+
+    fn delete(&mut self, entry: SlotEntry) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        let entry = entry.key;
+        if let Some(p) = self.nodes[entry].parent {
+            self.cut(entry);
+            self.cascading_cut(p);
+        }
+        Ok(self.remove_root(entry))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> AddressableHeap<K, V> for SlotFibHeap<K, V> {
+    fn is_valid(&self, entry: &SlotEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> SlotFibHeap<K, V> {
+    pub fn new() -> SlotFibHeap<K, V> {
+        SlotFibHeap::with_capacity(0)
+    }
+
+    /// A heap with room for `capacity` entries before it has to grow.
+    pub fn with_capacity(capacity: usize) -> SlotFibHeap<K, V> {
+        SlotFibHeap {
+            nodes: SlotMap::with_capacity_and_key(capacity),
+            roots: Vec::new(),
+            min: None,
+            growth: Growth::Doubling,
+            capacity: capacity,
+            id: NEXT_HEAP.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Sets how the heap grows once it's full.
+    pub fn with_growth(mut self, growth: Growth) -> SlotFibHeap<K, V> {
+        self.growth = growth;
+        self
+    }
+
+    /// How many entries fit before the heap has to grow.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Inserts the entry, or returns it if the heap is full and its growth
+    /// policy, or the allocator, won't let it grow.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<SlotEntry, Full<K, V>> {
+        let len = self.nodes.len();
+        let allowed = match self.growth {
+            Growth::Capped(max) => len < max,
+            _ => true
+        };
+        if !allowed || (len == self.capacity() && !self.grow()) {
+            return Err(Full(key, value))
+        }
+        let entry = self.nodes.insert(Node {
+            parent: None,
+            children: Vec::new(),
+            marked: false,
+            key: key,
+            value: value,
+        });
+        self.roots.push(entry);
+        self.update_min(entry);
+        Ok(SlotEntry { key: entry, heap: self.id })
+    }
+
+    // Grows the full storage as the policy says, returning whether it did.
+    fn grow(&mut self) -> bool {
+        let capacity = self.capacity();
+        let target = match self.growth {
+            Growth::Doubling => Some(cmp::max(2 * capacity, 1)),
+            Growth::Capped(max) => Some(cmp::min(cmp::max(2 * capacity, 1), max)),
+            Growth::Custom(f) => f(capacity),
+        };
+        match target {
+            Some(target) if target > capacity => {
+                if self.nodes.try_reserve(target - self.nodes.len()).is_err() {
+                    return false
+                }
+                self.capacity = target;
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether `entry` still refers to an entry in this heap.
+    pub fn contains(&self, entry: SlotEntry) -> bool {
+        entry.heap == self.id && self.nodes.contains_key(entry.key)
+    }
+
+    pub fn get(&self, entry: SlotEntry) -> Option<(&K, &V)> {
+        if entry.heap != self.id {
+            return None
+        }
+        self.nodes.get(entry.key).map(|node| (&node.key, &node.value))
+    }
+
+    // The node of an entry of this heap. Panics if the entry is from another.
+    fn update_min(&mut self, entry: NodeKey) {
+        let smaller = match self.min {
+            Some(min) => self.nodes[entry].key < self.nodes[min].key,
+            None => true
+        };
+        if smaller {
+            self.min = Some(entry);
+        }
+    }
+
+    // Removes a root from the heap, promoting its children to roots.
+    fn remove_root(&mut self, entry: NodeKey) -> (K, V) {
+        let pos = self.roots.iter().position(|&r| r == entry).expect("Entry is not a root");
+        self.roots.swap_remove(pos);
+        let node = self.nodes.remove(entry).unwrap();
+        for c in node.children.into_iter() {
+            self.nodes[c].parent = None;
+            self.roots.push(c);
+        }
+        if self.min == Some(entry) {
+            self.consolidate();
+        }
+        (node.key, node.value)
+    }
+
+    fn cut(&mut self, entry: NodeKey) {
+        let parent = self.nodes[entry].parent.take().expect("Entry has no parent");
+        {
+            let siblings = &mut self.nodes[parent].children;
+            let pos = siblings.iter().position(|&c| c == entry).unwrap();
+            siblings.swap_remove(pos);
+        }
+        self.nodes[entry].marked = false;
+        self.roots.push(entry);
+    }
+
+    fn cascading_cut(&mut self, entry: NodeKey) {
+        if let Some(parent) = self.nodes[entry].parent {
+            if self.nodes[entry].marked {
+                self.cut(entry);
+                self.cascading_cut(parent);
+            } else {
+                self.nodes[entry].marked = true;
+            }
+        }
+    }
+
+    fn consolidate(&mut self) {
+        let mut rank_vec: Vec<Option<NodeKey>> = Vec::new();
+        for root in std::mem::take(&mut self.roots) {
+            let mut node = root;
+            loop {
+                let rank = self.nodes[node].children.len();
+                if rank >= rank_vec.len() {
+                    rank_vec.resize(rank + 1, None);
+                }
+                match rank_vec[rank].take() {
+                    Some(other) => node = self.link(node, other),
+                    None => {
+                        rank_vec[rank] = Some(node);
+                        break
+                    }
+                }
+            }
+        }
+        self.min = None;
+        for n in rank_vec.into_iter().flatten() {
+            self.roots.push(n);
+            self.update_min(n);
+        }
+    }
+
+    // Makes the root with the larger key a child of the other, returning the
+    // new root.
+    fn link(&mut self, a: NodeKey, b: NodeKey) -> NodeKey {
+        let (root, child) = if self.nodes[b].key < self.nodes[a].key { (b, a) } else { (a, b) };
+        {
+            let c = &mut self.nodes[child];
+            c.parent = Some(root);
+            c.marked = false;
+        }
+        self.nodes[root].children.push(child);
+        root
+    }
+
+    // Moves the subtree rooted at `entry` out of `from` and into this heap.
+    fn adopt(&mut self, from: &mut SlotMap<NodeKey, Node<K, V>>, entry: NodeKey,
+             parent: Option<NodeKey>) -> NodeKey {
+        let mut node = from.remove(entry).unwrap();
+        let children = std::mem::take(&mut node.children);
+        node.parent = parent;
+        let moved = self.nodes.insert(node);
+        for c in children.into_iter() {
+            let child = self.adopt(from, c, Some(moved));
+            self.nodes[moved].children.push(child);
+        }
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
+    use fibonacci_heap::{FibHeap};
+    use slot_heap::{Full, Growth, SlotFibHeap};
+
+    #[test]
+    fn slot_heap_delete_min() {
+        let mut heap: SlotFibHeap<u8, u8> = SlotFibHeap::new();
+        for i in [3u8, 1, 4, 0, 5, 2].iter() {
+            heap.insert(*i, *i);
+        }
+        assert_eq!(heap.find_min(), (0, 0));
+        for i in 0..6 {
+            assert_eq!(heap.delete_min(), (i, i));
+        }
+        assert!(heap.empty());
+    }
+
+    #[test]
+    fn slot_heap_decrease_key() {
+        let mut heap: SlotFibHeap<u8, u8> = SlotFibHeap::new();
+        let entries: Vec<_> = (0..12u8).map(|i| heap.insert(i + 10, i)).collect();
+        heap.delete_min();
+        heap.decrease_key(&entries[7], 15).unwrap();
+        assert_eq!(heap.find_min(), (2, 7));
+        heap.decrease_key(&entries[9], 18).unwrap();
+        heap.decrease_key(&entries[11], 21).unwrap();
+        assert_eq!(heap.delete_min(), (0, 11));
+        assert_eq!(heap.delete_min(), (1, 9));
+        assert_eq!(heap.delete_min(), (2, 7));
+        assert_eq!(heap.delete_min(), (11, 1));
+        assert_eq!(heap.len(), 7);
+    }
+
+    #[test]
+    fn slot_heap_delete() {
+        let mut heap: SlotFibHeap<u8, u8> = SlotFibHeap::new();
+        let entries: Vec<_> = (0..8u8).map(|i| heap.insert(i, i)).collect();
+        heap.delete_min();
+        assert_eq!(heap.delete(entries[5]), Ok((5, 5)));
+        assert_eq!(heap.delete(entries[1]), Ok((1, 1)));
+        assert!(!heap.contains(entries[1]));
+        assert!(heap.get(entries[1]).is_none());
+        assert_eq!(heap.get(entries[2]), Some((&2, &2)));
+        let rest: Vec<u8> = (0..5).map(|_| heap.delete_min().0).collect();
+        assert_eq!(rest, vec!(2, 3, 4, 6, 7));
+    }
+
+    #[test]
+    fn slot_heap_stale_handle() {
+        let mut heap: SlotFibHeap<u8, u8> = SlotFibHeap::new();
+        let one = heap.insert(1, 1);
+        heap.delete_min();
+        let two = heap.insert(2, 2);
+        assert!(!heap.contains(one));
+        assert!(heap.contains(two));
+        assert!(one != two);
+    }
+
+    #[test]
+    fn slot_heap_convert_from_fib_heap() {
+        let mut fheap: FibHeap<u8, u8> = FibHeap::new();
+        fheap.insert(0, 0);
+        let four = fheap.insert(4, 4);
+        fheap.insert(2, 2);
+        fheap.delete_min();
+        let (mut heap, handles) = fheap.convert_with_handles(SlotFibHeap::new(), vec!(four));
+        assert_eq!(heap.get(handles[0]), Some((&4, &4)));
+        heap.decrease_key(&handles[0], 3).unwrap();
+        assert_eq!(heap.delete_min(), (1, 4));
+        assert_eq!(heap.delete_min(), (2, 2));
+        assert!(heap.empty());
+    }
+
+    #[test]
+    fn slot_heap_merge() {
+        let mut heap: SlotFibHeap<u8, u8> = SlotFibHeap::new();
+        heap.insert(1, 1);
+        heap.insert(4, 4);
+        heap.insert(0, 0);
+        heap.delete_min();
+        let mut other: SlotFibHeap<u8, u8> = SlotFibHeap::new();
+        other.insert(3, 3);
+        other.insert(0, 0);
+
+        let mut heap = heap.merge(other);
+        assert_eq!(heap.len(), 4);
+        let all: Vec<u8> = (0..4).map(|_| heap.delete_min().0).collect();
+        assert_eq!(all, vec!(0, 1, 3, 4));
+    }
+
+    #[test]
+    fn slot_heap_merge_stale_handles() {
+        let mut big: SlotFibHeap<u8, char> = SlotFibHeap::new();
+        let a = big.insert(5, 'a');
+        big.insert(6, 'b');
+        let mut small: SlotFibHeap<u8, char> = SlotFibHeap::new();
+        // Takes the same slot in its own storage as `a` does in `big`'s.
+        let c = small.insert(7, 'c');
+        assert!(!big.contains(c) && !small.contains(a));
+
+        let mut heap = big.merge(small);
+        assert!(heap.contains(a));
+        assert!(!heap.contains(c));
+        assert!(!heap.is_valid(&c));
+        assert_eq!(heap.get(c), None);
+        assert_eq!(heap.decrease_key(&c, 7), Err(StaleEntry));
+        assert_eq!(heap.delete_min(), (5, 'a'));
+        assert_eq!(heap.delete_min(), (6, 'b'));
+        assert_eq!(heap.delete_min(), (7, 'c'));
+    }
+
+    #[test]
+    fn slot_heap_foreign_handle() {
+        let mut heap: SlotFibHeap<u8, ()> = SlotFibHeap::new();
+        let mut other: SlotFibHeap<u8, ()> = SlotFibHeap::new();
+        heap.insert(1, ());
+        let entry = other.insert(2, ());
+        assert_eq!(heap.decrease_key(&entry, 1), Err(StaleEntry));
+        assert_eq!(heap.delete(entry), Err(StaleEntry));
+        assert_eq!(heap.find_min(), (1, ()));
+        assert_eq!(other.delete(entry), Ok((2, ())));
+    }
+
+    #[test]
+    fn slot_heap_growth() {
+        let mut heap = SlotFibHeap::with_capacity(4).with_growth(Growth::Capped(4));
+        let capacity = heap.capacity();
+        for i in 0..4u32 {
+            heap.try_insert(i, i).unwrap();
+        }
+        assert_eq!(heap.try_insert(9, 9), Err(Full(9, 9)));
+        assert_eq!(heap.capacity(), capacity);
+        heap.delete_min();
+        assert!(heap.try_insert(9, 9).is_ok());
+
+        // Grows in steps of 8, up to 24 entries.
+        fn steps(capacity: usize) -> Option<usize> {
+            if capacity < 24 { Some(capacity + 8) } else { None }
+        }
+        let mut heap = SlotFibHeap::new().with_growth(Growth::Custom(steps));
+        let mut inserted = 0u32;
+        let mut capacities = vec![heap.capacity()];
+        while heap.try_insert(inserted, ()).is_ok() {
+            inserted += 1;
+            if heap.capacity() != *capacities.last().unwrap() {
+                capacities.push(heap.capacity());
+            }
+        }
+        assert_eq!(capacities, [0, 8, 16, 24]);
+        assert_eq!(inserted, 24);
+        assert_eq!(heap.delete_min(), (0, ()));
+    }
+}
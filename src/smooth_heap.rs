@@ -0,0 +1,393 @@
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Sub;
+use std::rc::{Rc, Weak};
+use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
+
+type Link<K, V> = Rc<RefCell<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    first: Option<Link<K, V>>,
+    last: Option<Weak<RefCell<Node<K, V>>>>,
+    // The siblings to the right and to the left.
+    next: Option<Link<K, V>>,
+    prev: Option<Weak<RefCell<Node<K, V>>>>,
+    parent: Option<Weak<RefCell<Node<K, V>>>>,
+    // Set once the entry has been taken out of the heap.
+    removed: bool,
+}
+
+/// Handle to an entry of a `SmoothHeap`. Handles compare and hash by the
+/// entry they refer to, and stay valid when their heap is merged into
+/// another.
+pub struct SmoothEntry<K, V>(Link<K, V>);
+
+impl<K, V> Clone for SmoothEntry<K, V> {
+    fn clone(&self) -> SmoothEntry<K, V> {
+        SmoothEntry(self.0.clone())
+    }
+}
+
+impl<K, V> PartialEq for SmoothEntry<K, V> {
+    fn eq(&self, other: &SmoothEntry<K, V>) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<K, V> Eq for SmoothEntry<K, V> {}
+
+impl<K, V> Hash for SmoothEntry<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&*self.0 as *const RefCell<Node<K, V>>).hash(state)
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for SmoothEntry<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let node = self.0.borrow();
+        f.debug_struct("SmoothEntry")
+            .field("key", &node.key)
+            .field("value", &node.value)
+            .field("live", &!node.removed)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<K: defmt::Format, V: defmt::Format> defmt::Format for SmoothEntry<K, V> {
+    fn format(&self, f: defmt::Formatter) {
+        let node = self.0.borrow();
+        defmt::write!(f, "SmoothEntry {{ key: {}, value: {}, live: {=bool} }}", node.key, node.value, !node.removed)
+    }
+}
+
+/// A smooth heap (Kozma and Saranurak): a pairing heap whose `delete_min`
+/// links each local maximum among the root's children to the larger of its
+/// neighbours, and whose links keep trees in their left-to-right order.
+///
+/// It's a heap counterpart of greedy binary search trees, and adapts to its
+/// input: runs of sorted keys cost little, so sorting nearly sorted data
+/// takes close to linear time. The bounds are amortized, O(log n) for
+/// `delete_min` and O(1) for `insert`.
+pub struct SmoothHeap<K, V> {
+    root: Option<Link<K, V>>,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> SmoothHeap<K, V> {
+    pub const fn new() -> SmoothHeap<K, V> {
+        SmoothHeap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn push_front(parent: &Link<K, V>, child: Link<K, V>) {
+        let mut p = parent.borrow_mut();
+        {
+            let mut c = child.borrow_mut();
+            c.parent = Some(Rc::downgrade(parent));
+            c.prev = None;
+            match p.first {
+                Some(ref first) => first.borrow_mut().prev = Some(Rc::downgrade(&child)),
+                None => p.last = Some(Rc::downgrade(&child))
+            }
+            c.next = p.first.take();
+        }
+        p.first = Some(child);
+    }
+
+    fn push_back(parent: &Link<K, V>, child: Link<K, V>) {
+        let mut p = parent.borrow_mut();
+        let last = p.last.take().and_then(|l| l.upgrade());
+        p.last = Some(Rc::downgrade(&child));
+        {
+            let mut c = child.borrow_mut();
+            c.parent = Some(Rc::downgrade(parent));
+            c.prev = last.as_ref().map(Rc::downgrade);
+            c.next = None;
+        }
+        match last {
+            Some(last) => last.borrow_mut().next = Some(child),
+            None => p.first = Some(child)
+        }
+    }
+
+    // Links two detached trees, `left` being to the left of `right`. The
+    // root with the larger key becomes the other's child on that side.
+    fn link(left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+        if right.borrow().key < left.borrow().key {
+            SmoothHeap::push_front(&right, left);
+            right
+        } else {
+            SmoothHeap::push_back(&left, right);
+            left
+        }
+    }
+
+    // Adds a detached tree to the right of the root.
+    fn meld(&mut self, tree: Link<K, V>) {
+        self.root = Some(match self.root.take() {
+            Some(root) => SmoothHeap::link(root, tree),
+            None => tree
+        });
+    }
+
+    // Links a list of siblings into one tree. Going from left to right,
+    // each local maximum is linked to the larger of its neighbours, which
+    // leaves the trees in increasing order to be linked from right to left.
+    fn restructure(first: Option<Link<K, V>>) -> Option<Link<K, V>> {
+        let mut rising: Vec<Link<K, V>> = Vec::new();
+        let mut sibling = first;
+        while let Some(node) = sibling {
+            sibling = {
+                let mut n = node.borrow_mut();
+                n.prev = None;
+                n.parent = None;
+                n.next.take()
+            };
+            while rising.last().is_some_and(|top| node.borrow().key < top.borrow().key) {
+                let top = rising.pop().unwrap();
+                match rising.last() {
+                    Some(left) if node.borrow().key < left.borrow().key => {
+                        SmoothHeap::push_back(left, top)
+                    }
+                    _ => SmoothHeap::push_front(&node, top)
+                }
+            }
+            rising.push(node);
+        }
+        let mut tree = rising.pop();
+        while let Some(left) = rising.pop() {
+            SmoothHeap::push_back(&left, tree.unwrap());
+            tree = Some(left);
+        }
+        tree
+    }
+
+    // Cuts the subtree rooted at `node` out of its parent's child list.
+    fn detach(node: &Link<K, V>) {
+        let (prev, next, parent) = {
+            let mut n = node.borrow_mut();
+            (n.prev.take(), n.next.take(), n.parent.take())
+        };
+        let parent = parent.and_then(|p| p.upgrade()).expect("node is not in a tree");
+        let mut p = parent.borrow_mut();
+        match next {
+            Some(ref next) => next.borrow_mut().prev = prev.clone(),
+            None => p.last = prev.clone()
+        }
+        match prev.and_then(|p| p.upgrade()) {
+            Some(prev) => prev.borrow_mut().next = next,
+            None => p.first = next
+        }
+    }
+
+    // Takes a root that has been cut loose out of the heap.
+    fn take(&mut self, node: Link<K, V>) -> (K, V) {
+        let mut n = node.borrow_mut();
+        n.removed = true;
+        n.last = None;
+        if let Some(tree) = SmoothHeap::restructure(n.first.take()) {
+            self.meld(tree);
+        }
+        self.len -= 1;
+        (n.key.clone(), n.value.clone())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for SmoothHeap<K, V> {
+    type HeapEntry = SmoothEntry<K, V>;
+
+    fn find_min(&self) -> (K, V) {
+        match self.root {
+            Some(ref root) => {
+                let root = root.borrow();
+                (root.key.clone(), root.value.clone())
+            }
+            None => panic!("SmoothHeap is empty")
+        }
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        match self.root.take() {
+            Some(root) => self.take(root),
+            None => panic!("SmoothHeap is empty")
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) -> SmoothEntry<K, V> {
+        let node = Rc::new(RefCell::new(Node {
+            key: key, value: value, first: None, last: None, next: None, prev: None, parent: None,
+            removed: false
+        }));
+        self.meld(node.clone());
+        self.len += 1;
+        SmoothEntry(node)
+    }
+
+    fn empty(&self) -> bool {
+        self.root.is_none()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for SmoothHeap<K, V> {
+    fn decrease_key(&mut self, entry: &SmoothEntry<K, V>, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        {
+            let mut n = entry.0.borrow_mut();
+            n.key = n.key.clone() - delta;
+        }
+        if entry.0.borrow().parent.is_some() {
+            SmoothHeap::detach(&entry.0);
+            self.meld(entry.0.clone());
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapExt for SmoothHeap<K, V> {
+    fn merge(mut self, mut other: SmoothHeap<K, V>) -> SmoothHeap<K, V> {
+        if let Some(tree) = other.root.take() {
+            self.meld(tree);
+        }
+        self.len += mem::replace(&mut other.len, 0);
+        self
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapDelete<K, V> for SmoothHeap<K, V> {
+    type HeapEntry = SmoothEntry<K, V>;
+
+    fn delete(&mut self, entry: SmoothEntry<K, V>) -> Result<(K, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        if entry.0.borrow().parent.is_some() {
+            SmoothHeap::detach(&entry.0);
+        } else {
+            self.root = None;
+        }
+        Ok(self.take(entry.0))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> AddressableHeap<K, V> for SmoothHeap<K, V> {
+    fn is_valid(&self, entry: &SmoothEntry<K, V>) -> bool {
+        !entry.0.borrow().removed
+    }
+}
+
+// Dropping the links one at a time, since a path or sibling list as long as
+// the heap would otherwise be dropped recursively and overflow the stack.
+impl<K, V> Drop for SmoothHeap<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Link<K, V>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let mut n = node.borrow_mut();
+            n.removed = true;
+            stack.extend(n.first.take());
+            stack.extend(n.next.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::ops::Sub;
+    use smooth_heap::SmoothHeap;
+    use test_model;
+    use {Heap, HeapDelete};
+
+    thread_local!(static COMPARISONS: Cell<usize> = const { Cell::new(0) });
+
+    // A key counting how often it's compared.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Counted(u32);
+
+    impl PartialOrd for Counted {
+        fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Counted {
+        fn cmp(&self, other: &Counted) -> Ordering {
+            COMPARISONS.with(|c| c.set(c.get() + 1));
+            self.0.cmp(&other.0)
+        }
+    }
+
+    impl Sub for Counted {
+        type Output = Counted;
+        fn sub(self, other: Counted) -> Counted {
+            Counted(self.0 - other.0)
+        }
+    }
+
+    #[test]
+    fn smooth_heap_against_model() {
+        let mut heap = SmoothHeap::new();
+        test_model::against_model(&mut heap, 20000, |_, _, x| 1000 + x % 5000,
+                                  Some(|heap, entry| heap.delete(entry)), |heap, entries, _| {
+            assert_eq!(heap.len(), entries.len());
+        });
+    }
+
+    // Inserts `keys` and deletes them all, returning how many comparisons
+    // that took.
+    fn sort_comparisons(keys: &[u32]) -> usize {
+        COMPARISONS.with(|c| c.set(0));
+        let mut heap = SmoothHeap::new();
+        for &k in keys {
+            heap.insert(Counted(k), ());
+        }
+        let mut sorted = keys.to_vec();
+        sorted.sort();
+        for &k in sorted.iter() {
+            assert_eq!(heap.delete_min().0, Counted(k));
+        }
+        COMPARISONS.with(|c| c.get())
+    }
+
+    #[test]
+    fn smooth_heap_adapts_to_presorted_input() {
+        let n = 1 << 12;
+        let sorted: Vec<u32> = (0..n).collect();
+        let reversed: Vec<u32> = (0..n).rev().collect();
+        // Sorted but for one swapped pair in every 16 entries.
+        let mut nearly = sorted.clone();
+        for i in (0..n as usize - 1).step_by(16) {
+            nearly.swap(i, i + 1);
+        }
+        let shuffled: Vec<u32> = (0..n).map(|i| (i * 7919) % n).collect();
+        // Sorting presorted input takes a linear number of comparisons,
+        // while shuffled input needs about n log n.
+        let n = n as usize;
+        assert!(sort_comparisons(&sorted) <= 2 * n);
+        assert!(sort_comparisons(&reversed) <= 2 * n);
+        assert!(sort_comparisons(&nearly) <= 3 * n);
+        assert!(sort_comparisons(&shuffled) >= 6 * n);
+    }
+
+    #[test]
+    fn smooth_heap_long_paths() {
+        // Sorted input leaves a path as long as the heap.
+        let mut heap = SmoothHeap::new();
+        for i in 0..200000u32 {
+            heap.insert(i, ());
+        }
+        assert_eq!(heap.delete_min(), (0, ()));
+        assert_eq!(heap.delete_min(), (1, ()));
+        drop(heap);
+    }
+}
@@ -0,0 +1,213 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use core::marker::PhantomData;
+use core::ops::Sub;
+use serde::ser::{Serialize, Serializer, SerializeSeq};
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, Visitor, SeqAccess};
+use postcard;
+#[cfg(feature = "array-heap")]
+use array_heap::{ArrayHeap, FixedHeap, Slot};
+#[cfg(feature = "std")]
+use fibonacci_heap::{FibHeap};
+#[cfg(feature = "std")]
+use Heap;
+
+// A heap serializes as the sequence of its (key, value) pairs. Tree shape and
+// entry handles are not preserved; restoring reinserts every pair.
+#[cfg(feature = "std")]
+impl<K, V> Serialize for FibHeap<K, V>
+where K: Serialize + Ord + Debug + Clone + Sub<K, Output=K>,
+      V: Serialize + Eq + PartialOrd + Debug + Clone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for entry in self.iter() {
+            seq.serialize_element(&entry)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "std")]
+struct HeapVisitor<K, V>(PhantomData<(K, V)>);
+
+#[cfg(feature = "std")]
+impl<'de, K, V> Visitor<'de> for HeapVisitor<K, V>
+where K: Deserialize<'de> + Ord + Debug + Clone + Sub<K, Output=K>,
+      V: Deserialize<'de> + Eq + PartialOrd + Debug + Clone {
+    type Value = FibHeap<K, V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of (key, value) pairs")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FibHeap<K, V>, A::Error> {
+        let mut fheap = FibHeap::new();
+        while let Some((k, v)) = seq.next_element()? {
+            fheap.insert(k, v);
+        }
+        Ok(fheap)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, K, V> Deserialize<'de> for FibHeap<K, V>
+where K: Deserialize<'de> + Ord + Debug + Clone + Sub<K, Output=K>,
+      V: Deserialize<'de> + Eq + PartialOrd + Debug + Clone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<FibHeap<K, V>, D::Error> {
+        deserializer.deserialize_seq(HeapVisitor(PhantomData))
+    }
+}
+
+// Fixed heaps serialize the same way, and need neither std nor an
+// allocator, so a device can keep them across a reset.
+#[cfg(feature = "array-heap")]
+impl<K, V, S> Serialize for FixedHeap<K, V, S>
+where K: Serialize + Ord + Clone + Sub<K, Output=K>, V: Serialize + Clone,
+      S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for entry in self.entries() {
+            seq.serialize_element(entry)?;
+        }
+        seq.end()
+    }
+}
+
+// Inserts the pairs of a sequence into a heap that already has storage.
+#[cfg(feature = "array-heap")]
+struct Fill<'h, K: 'h, V: 'h, S: 'h>(&'h mut FixedHeap<K, V, S>);
+
+#[cfg(feature = "array-heap")]
+impl<'de, 'h, K, V, S> Visitor<'de> for Fill<'h, K, V, S>
+where K: Deserialize<'de> + Ord + Clone + Sub<K, Output=K>, V: Deserialize<'de> + Clone,
+      S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at most {} (key, value) pairs", self.0.capacity() - self.0.len())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some((k, v)) = seq.next_element()? {
+            if self.0.try_insert(k, v).is_err() {
+                return Err(de::Error::invalid_length(self.0.capacity() + 1, &self))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "array-heap")]
+impl<'de, 'h, K, V, S> DeserializeSeed<'de> for Fill<'h, K, V, S>
+where K: Deserialize<'de> + Ord + Clone + Sub<K, Output=K>, V: Deserialize<'de> + Clone,
+      S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+#[cfg(feature = "array-heap")]
+impl<'de, K, V, const N: usize> Deserialize<'de> for ArrayHeap<K, V, N>
+where K: Deserialize<'de> + Ord + Clone + Sub<K, Output=K>, V: Deserialize<'de> + Clone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ArrayHeap<K, V, N>, D::Error> {
+        let mut heap = ArrayHeap::new();
+        Fill(&mut heap).deserialize(deserializer)?;
+        Ok(heap)
+    }
+}
+
+/// Writes the heap's contents into `buf` using postcard, returning the used
+/// part of the buffer.
+pub fn save<'a, H: Serialize>(heap: &H, buf: &'a mut [u8]) -> postcard::Result<&'a mut [u8]> {
+    postcard::to_slice(heap, buf)
+}
+
+/// Rebuilds a heap from bytes written by `save`.
+pub fn restore<'a, H: Deserialize<'a>>(bytes: &'a [u8]) -> postcard::Result<H> {
+    postcard::from_bytes(bytes)
+}
+
+/// Adds the entries in bytes written by `save` to `heap`, such as a
+/// `SliceHeap` over static storage. Fails if they don't all fit, leaving
+/// the ones read so far in the heap.
+#[cfg(feature = "array-heap")]
+pub fn restore_into<'a, K, V, S>(heap: &mut FixedHeap<K, V, S>, bytes: &'a [u8]) -> postcard::Result<()>
+where K: Deserialize<'a> + Ord + Clone + Sub<K, Output=K>, V: Deserialize<'a> + Clone,
+      S: AsRef<[Slot<K, V>]> + AsMut<[Slot<K, V>]> {
+    Fill(heap).deserialize(&mut postcard::Deserializer::from_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use postcard;
+    use Heap;
+    #[cfg(feature = "array-heap")]
+    use array_heap::{ArrayHeap, Slot, SliceHeap};
+    #[cfg(feature = "std")]
+    use fibonacci_heap::{FibHeap};
+    use snapshot::{save, restore};
+    #[cfg(feature = "array-heap")]
+    use snapshot::restore_into;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn snapshot_round_trip() {
+        let mut fheap: FibHeap<u32, u8> = FibHeap::new();
+        fheap.insert(300, 3);
+        fheap.insert(100, 1);
+        fheap.insert(0, 0);
+        fheap.insert(200, 2);
+        fheap.delete_min();
+
+        let mut buf = [0u8; 32];
+        let used = save(&fheap, &mut buf).unwrap().len();
+        let mut restored: FibHeap<u32, u8> = restore(&buf[..used]).unwrap();
+        assert_eq!(restored.len(), 3);
+        assert_eq!(restored.delete_min(), (100, 1));
+        assert_eq!(restored.delete_min(), (200, 2));
+        assert_eq!(restored.delete_min(), (300, 3));
+        assert!(restored.empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn snapshot_buffer_too_small() {
+        let mut fheap: FibHeap<u32, u8> = FibHeap::new();
+        fheap.insert(1000, 1);
+        fheap.insert(2000, 2);
+        let mut buf = [0u8; 4];
+        assert_eq!(save(&fheap, &mut buf), Err(postcard::Error::SerializeBufferFull));
+    }
+
+    #[cfg(feature = "array-heap")]
+    #[test]
+    fn snapshot_fixed_heaps() {
+        // Pending timers, as a device would save them before deep sleep.
+        let mut timers: ArrayHeap<u32, u8, 4> = ArrayHeap::new();
+        timers.insert(300, 3);
+        timers.insert(100, 1);
+        timers.insert(200, 2);
+        let mut buf = [0u8; 16];
+        let used = save(&timers, &mut buf).unwrap().len();
+
+        let mut restored: ArrayHeap<u32, u8, 4> = restore(&buf[..used]).unwrap();
+        assert_eq!(restored.delete_min(), (100, 1));
+
+        let mut storage: [Slot<u32, u8>; 2] = [const { Slot::new() }; 2];
+        let mut small = SliceHeap::with_storage(&mut storage[..]);
+        assert!(restore_into(&mut small, &buf[..used]).is_err());
+        assert_eq!(small.len(), 2);
+        let mut storage: [Slot<u32, u8>; 3] = [const { Slot::new() }; 3];
+        let mut heap = SliceHeap::with_storage(&mut storage[..]);
+        restore_into(&mut heap, &buf[..used]).unwrap();
+        assert_eq!(heap.delete_min(), (100, 1));
+        assert_eq!(heap.delete_min(), (200, 2));
+        assert_eq!(heap.delete_min(), (300, 3));
+        assert!(restore::<ArrayHeap<u32, u8, 2>>(&buf[..used]).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::LinkedList;
+use std::mem;
+use std::ops::Sub;
+use {Heap, HeapExt};
+
+struct Node<K, V> {
+    // Every entry in `list` is ordered by this key, which is at least its own.
+    ckey: K,
+    list: LinkedList<(K, V)>,
+    rank: usize,
+    // How many entries the node takes from its children before it stops
+    // refilling itself.
+    size: usize,
+    left: Option<Box<Node<K, V>>>,
+    right: Option<Box<Node<K, V>>>,
+}
+
+impl<K: Ord + Clone, V> Node<K, V> {
+    fn is_leaf(&self) -> bool {
+        self.left.is_none() && self.right.is_none()
+    }
+
+    // Moves entries up from the child with the smaller key until the node
+    // holds `size` of them, taking on the child's key each time.
+    fn sift(&mut self) {
+        while self.list.len() < self.size && !self.is_leaf() {
+            let swap = match (self.left.as_ref(), self.right.as_ref()) {
+                (None, _) => true,
+                (Some(l), Some(r)) => l.ckey > r.ckey,
+                _ => false
+            };
+            if swap {
+                mem::swap(&mut self.left, &mut self.right);
+            }
+            let empty = {
+                let left = self.left.as_mut().unwrap();
+                self.list.append(&mut left.list);
+                self.ckey = left.ckey.clone();
+                if !left.is_leaf() {
+                    left.sift();
+                }
+                left.list.is_empty()
+            };
+            if empty {
+                self.left = None;
+            }
+        }
+    }
+}
+
+/// A soft heap (Chazelle, in Kaplan and Zwick's simplified form), which
+/// trades exactness for speed: inserting takes amortized O(log 1/ε) time
+/// and deleting the minimum amortized O(1), but entries can be
+/// *corrupted*, raised to a larger key than their own, and are then
+/// returned later than their key says.
+///
+/// After `n` insertions at most `ε·n` of the entries in the heap are
+/// corrupted. Entries keep their own key; `pop_with_ckey` also returns the
+/// key the heap ordered the entry by. That's enough for linear-time
+/// selection and Chazelle's minimum spanning tree algorithm.
+pub struct SoftHeap<K, V> {
+    // The tree of each rank, as in a binomial heap.
+    trees: Vec<Option<Box<Node<K, V>>>>,
+    // The rank of the tree with the smallest key among those of at least
+    // each rank.
+    suffix_min: Vec<Option<usize>>,
+    // Nodes above this rank start corrupting entries.
+    r: usize,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> SoftHeap<K, V> {
+    /// An empty heap corrupting at most `epsilon` of its insertions.
+    /// Panics unless `epsilon` is in (0, 1/2].
+    pub fn new(epsilon: f64) -> SoftHeap<K, V> {
+        if !(epsilon > 0.0 && epsilon <= 0.5) {
+            panic!("the SoftHeap error rate must be in (0, 1/2], not {}", epsilon);
+        }
+        let r = 2 + 2 * (1.0 / epsilon).log2().ceil() as usize;
+        SoftHeap { trees: Vec::new(), suffix_min: Vec::new(), r: r, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Deletes an entry with the smallest key the heap ordered entries by,
+    /// returning it and that key, which is larger than the entry's own if
+    /// the entry was corrupted.
+    pub fn pop_with_ckey(&mut self) -> Option<(K, V, K)> {
+        let rank = (*self.suffix_min.first()?)?;
+        let (entry, ckey) = {
+            let tree = self.trees[rank].as_mut().unwrap();
+            let entry = tree.list.pop_front().unwrap();
+            let ckey = tree.ckey.clone();
+            if 2 * tree.list.len() <= tree.size && !tree.is_leaf() {
+                tree.sift();
+            }
+            (entry, ckey)
+        };
+        if self.trees[rank].as_ref().unwrap().list.is_empty() {
+            self.trees[rank] = None;
+        }
+        self.len -= 1;
+        self.update_suffix_min(rank);
+        Some((entry.0, entry.1, ckey))
+    }
+
+    // Links two trees of the same rank under a new root.
+    fn combine(&self, x: Box<Node<K, V>>, y: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        let rank = x.rank + 1;
+        let size = if rank <= self.r { 1 } else { (3 * x.size).div_ceil(2) };
+        let mut z = Box::new(Node {
+            ckey: x.ckey.clone(), list: LinkedList::new(), rank: rank, size: size,
+            left: Some(x), right: Some(y)
+        });
+        z.sift();
+        z
+    }
+
+    // Adds a tree of rank `rank`, combining with the trees already there
+    // as in binary addition. Returns the highest rank changed.
+    fn add_tree(&mut self, mut tree: Box<Node<K, V>>) -> usize {
+        let mut rank = tree.rank;
+        loop {
+            if rank >= self.trees.len() {
+                self.trees.resize_with(rank + 1, || None);
+                self.suffix_min.resize(rank + 1, None);
+            }
+            match self.trees[rank].take() {
+                Some(other) => {
+                    tree = self.combine(other, tree);
+                    rank += 1;
+                }
+                None => {
+                    self.trees[rank] = Some(tree);
+                    return rank
+                }
+            }
+        }
+    }
+
+    // Recomputes the suffix minimums at `rank` and below.
+    fn update_suffix_min(&mut self, rank: usize) {
+        for i in (0..rank + 1).rev() {
+            let after = self.suffix_min.get(i + 1).cloned().unwrap_or(None);
+            self.suffix_min[i] = match (self.trees[i].as_ref(), after) {
+                (Some(tree), Some(j)) => {
+                    if tree.ckey <= self.trees[j].as_ref().unwrap().ckey { Some(i) } else { Some(j) }
+                }
+                (Some(_), None) => Some(i),
+                (None, after) => after
+            };
+        }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for SoftHeap<K, V> {
+    type HeapEntry = ();
+
+    /// The entry `delete_min` would return, which may be corrupted.
+    fn find_min(&self) -> (K, V) {
+        match self.suffix_min.first() {
+            Some(&Some(rank)) => self.trees[rank].as_ref().unwrap().list.front().cloned().unwrap(),
+            _ => panic!("SoftHeap is empty")
+        }
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        match self.pop_with_ckey() {
+            Some((k, v, _)) => (k, v),
+            None => panic!("SoftHeap is empty")
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        let mut list = LinkedList::new();
+        list.push_back((key.clone(), value));
+        let node = Box::new(Node { ckey: key, list: list, rank: 0, size: 1, left: None, right: None });
+        let rank = self.add_tree(node);
+        self.len += 1;
+        self.update_suffix_min(rank);
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Merges `other` into this heap, keeping this heap's error rate.
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapExt for SoftHeap<K, V> {
+    fn merge(mut self, mut other: SoftHeap<K, V>) -> SoftHeap<K, V> {
+        for tree in other.trees.drain(..).flatten() {
+            self.add_tree(tree);
+        }
+        self.len += other.len;
+        let top = self.trees.len();
+        if top > 0 {
+            self.update_suffix_min(top - 1);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use soft_heap::{Node, SoftHeap};
+    use {Heap, HeapExt};
+
+    fn corrupted(node: &Node<u32, u32>) -> usize {
+        node.list.iter().filter(|e| e.0 < node.ckey).count()
+            + node.left.as_ref().map_or(0, |n| corrupted(n))
+            + node.right.as_ref().map_or(0, |n| corrupted(n))
+    }
+
+    fn keys(n: u32) -> Vec<u32> {
+        (0..n).map(|i| (i * 7919) % n).collect()
+    }
+
+    #[test]
+    fn soft_heap_corruption_bound() {
+        let mut heap = SoftHeap::new(0.1);
+        for k in keys(10000) {
+            heap.insert(k, k);
+        }
+        for round in 0..5000 {
+            heap.delete_min();
+            if round % 500 == 0 {
+                let bad: usize = heap.trees.iter().flatten().map(|t| corrupted(t)).sum();
+                assert!(bad <= 1000, "{} corrupted entries", bad);
+            }
+        }
+        let mut last = 0;
+        while let Some((k, v, ckey)) = heap.pop_with_ckey() {
+            assert_eq!(k, v);
+            assert!(k <= ckey);
+            assert!(last <= ckey);
+            last = ckey;
+        }
+        assert!(heap.empty());
+    }
+
+    #[test]
+    fn soft_heap_small_epsilon_is_exact() {
+        let a = keys(1000).into_iter().fold(SoftHeap::new(1e-6), |mut h, k| { h.insert(k, ()); h });
+        let mut b = SoftHeap::new(1e-6);
+        b.insert(1000, ());
+        let mut heap = a.merge(b);
+        assert_eq!(heap.len(), 1001);
+        for k in 0..1001 {
+            assert_eq!(heap.find_min().0, k);
+            assert_eq!(heap.delete_min().0, k);
+        }
+    }
+}
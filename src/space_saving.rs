@@ -0,0 +1,117 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, HeapDelete};
+
+/// The Space-Saving sketch of Metwally et al., which finds the most frequent
+/// items of a stream using `k` counters.
+///
+/// Each monitored item has a count, which overestimates its true frequency
+/// by at most its error. When an unmonitored item arrives and all counters
+/// are in use, it takes over the counter with the smallest count. Any item
+/// occurring more than `total / k` times is guaranteed to be monitored.
+pub struct SpaceSaving<I> {
+    k: usize,
+    heap: FibHeap<u64, u64>,
+    items: HashMap<I, (Rc<FibNode<u64, u64>>, u64)>,
+    ids: HashMap<u64, I>,
+    total: u64,
+    next_id: u64,
+}
+
+impl<I: Hash + Eq + Clone> SpaceSaving<I> {
+    pub fn new(k: usize) -> SpaceSaving<I> {
+        assert!(k > 0, "SpaceSaving needs at least one counter");
+        SpaceSaving {
+            k: k,
+            heap: FibHeap::new(),
+            items: HashMap::new(),
+            ids: HashMap::new(),
+            total: 0,
+            next_id: 0,
+        }
+    }
+
+    /// The number of items offered so far.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn offer(&mut self, item: I) {
+        self.total += 1;
+        if self.items.contains_key(&item) {
+            // Counts only go up, and the heap has no increase_key, so the
+            // counter is reinserted.
+            let (entry, error) = self.items.remove(&item).unwrap();
+            let (count, id) = self.heap.delete(entry).unwrap();
+            let entry = self.heap.insert(count + 1, id);
+            self.items.insert(item, (entry, error));
+        } else if self.items.len() < self.k {
+            let id = self.next_id;
+            self.next_id += 1;
+            let entry = self.heap.insert(1, id);
+            self.ids.insert(id, item.clone());
+            self.items.insert(item, (entry, 0));
+        } else {
+            let (min, id) = self.heap.delete_min();
+            let evicted = self.ids.insert(id, item.clone()).unwrap();
+            self.items.remove(&evicted);
+            let entry = self.heap.insert(min + 1, id);
+            self.items.insert(item, (entry, min));
+        }
+    }
+
+    /// The estimated count of a monitored item and the most it may
+    /// overestimate by.
+    pub fn estimate(&self, item: &I) -> Option<(u64, u64)> {
+        self.items.get(item).map(|&(ref entry, error)| (*entry.get_key(), error))
+    }
+
+    /// The monitored items with their estimated counts and errors, most
+    /// frequent first.
+    pub fn top(&self) -> Vec<(I, u64, u64)> {
+        let mut top: Vec<(I, u64, u64)> = self.items.iter()
+            .map(|(item, &(ref entry, error))| (item.clone(), *entry.get_key(), error))
+            .collect();
+        top.sort_by_key(|&(_, count, _)| Reverse(count));
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use space_saving::SpaceSaving;
+
+    #[test]
+    fn space_saving_exact_when_few_items() {
+        let mut sketch = SpaceSaving::new(3);
+        for &c in ['a', 'b', 'a', 'c', 'a', 'b'].iter() {
+            sketch.offer(c);
+        }
+        assert_eq!(sketch.estimate(&'a'), Some((3, 0)));
+        assert_eq!(sketch.estimate(&'b'), Some((2, 0)));
+        assert_eq!(sketch.top()[0], ('a', 3, 0));
+        assert_eq!(sketch.total(), 6);
+    }
+
+    #[test]
+    fn space_saving_finds_heavy_hitters() {
+        let mut sketch = SpaceSaving::new(4);
+        // 0 makes up a third of the stream, and the rest are all distinct.
+        for i in 0..300u32 {
+            if i % 3 == 0 {
+                sketch.offer(0);
+            } else {
+                sketch.offer(i);
+            }
+        }
+        let (count, error) = sketch.estimate(&0).unwrap();
+        assert!(count >= 100 && count - error <= 100);
+        assert_eq!(sketch.top()[0].0, 0);
+        assert_eq!(sketch.top().len(), 4);
+        assert_eq!(sketch.top().iter().map(|t| t.1).sum::<u64>(), 300);
+    }
+}
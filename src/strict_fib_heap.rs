@@ -0,0 +1,757 @@
+use std::ops::Sub;
+use slab::{Slab, SlotKey};
+use {Heap, DecreaseKey, AddressableHeap, StaleEntry};
+
+const NONE: usize = usize::MAX;
+
+/// Handle to an entry of a `StrictFibHeap`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StrictEntry(SlotKey);
+
+struct Item<K, V> {
+    entry: (K, V),
+    // The node holding the item. Decreasing a key can swap the items of a
+    // node and the root, so handles name items rather than nodes.
+    node: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Filed {
+    Nowhere,
+    ActiveRoot(usize),
+    LossOne(usize),
+    LossTwo,
+}
+
+#[derive(Clone, Copy)]
+struct List {
+    head: usize,
+    tail: usize,
+}
+
+const EMPTY: List = List { head: NONE, tail: NONE };
+
+// Which pair of link fields a list runs through.
+#[derive(Clone, Copy)]
+enum Chain {
+    Sibling,
+    Queue,
+    Fix,
+}
+
+struct Node {
+    item: usize,
+    parent: usize,
+    active: bool,
+    // The number of active children, for active nodes.
+    rank: usize,
+    // Active children lost since the node last became an active root.
+    loss: usize,
+    // The active children, then the passive ones.
+    children: [List; 2],
+    prev: usize,
+    next: usize,
+    q_prev: usize,
+    q_next: usize,
+    fix_prev: usize,
+    fix_next: usize,
+    filed: Filed,
+}
+
+impl Node {
+    fn new(item: usize) -> Node {
+        Node {
+            item: item, parent: NONE, active: false, rank: 0, loss: 0, children: [EMPTY, EMPTY],
+            prev: NONE, next: NONE, q_prev: NONE, q_next: NONE, fix_prev: NONE, fix_next: NONE,
+            filed: Filed::Nowhere,
+        }
+    }
+
+    fn links(&mut self, chain: Chain) -> (&mut usize, &mut usize) {
+        match chain {
+            Chain::Sibling => (&mut self.prev, &mut self.next),
+            Chain::Queue => (&mut self.q_prev, &mut self.q_next),
+            Chain::Fix => (&mut self.fix_prev, &mut self.fix_next),
+        }
+    }
+
+    // A passive node with no active children.
+    fn linkable(&self) -> bool {
+        self.children[0].head == NONE
+    }
+}
+
+fn push_front(nodes: &mut [Node], list: &mut List, n: usize, chain: Chain) {
+    {
+        let (prev, next) = nodes[n].links(chain);
+        *prev = NONE;
+        *next = list.head;
+    }
+    match list.head {
+        NONE => list.tail = n,
+        head => *nodes[head].links(chain).0 = n,
+    }
+    list.head = n;
+}
+
+fn push_back(nodes: &mut [Node], list: &mut List, n: usize, chain: Chain) {
+    {
+        let (prev, next) = nodes[n].links(chain);
+        *prev = list.tail;
+        *next = NONE;
+    }
+    match list.tail {
+        NONE => list.head = n,
+        tail => *nodes[tail].links(chain).1 = n,
+    }
+    list.tail = n;
+}
+
+fn unlink(nodes: &mut [Node], list: &mut List, n: usize, chain: Chain) {
+    let (prev, next) = {
+        let (prev, next) = nodes[n].links(chain);
+        (*prev, *next)
+    };
+    match prev {
+        NONE => list.head = next,
+        prev => *nodes[prev].links(chain).1 = next,
+    }
+    match next {
+        NONE => list.tail = prev,
+        next => *nodes[next].links(chain).0 = prev,
+    }
+}
+
+// Nodes grouped by rank, finding two of the same rank in constant time.
+struct RankLists {
+    lists: Vec<List>,
+    counts: Vec<usize>,
+    // The ranks with two or more nodes, and each rank's index in it.
+    pairs: Vec<usize>,
+    pair_index: Vec<usize>,
+}
+
+impl RankLists {
+    fn new() -> RankLists {
+        RankLists { lists: Vec::new(), counts: Vec::new(), pairs: Vec::new(), pair_index: Vec::new() }
+    }
+
+    fn reserve(&mut self, ranks: usize) {
+        if ranks > self.lists.len() {
+            self.lists.resize(ranks, EMPTY);
+            self.counts.resize(ranks, 0);
+            self.pair_index.resize(ranks, NONE);
+        }
+    }
+
+    fn add(&mut self, nodes: &mut [Node], rank: usize, n: usize) {
+        self.reserve(rank + 1);
+        push_front(nodes, &mut self.lists[rank], n, Chain::Fix);
+        self.counts[rank] += 1;
+        if self.counts[rank] == 2 {
+            self.pair_index[rank] = self.pairs.len();
+            self.pairs.push(rank);
+        }
+    }
+
+    fn remove(&mut self, nodes: &mut [Node], rank: usize, n: usize) {
+        unlink(nodes, &mut self.lists[rank], n, Chain::Fix);
+        self.counts[rank] -= 1;
+        if self.counts[rank] == 1 {
+            let i = self.pair_index[rank];
+            self.pairs.swap_remove(i);
+            if i < self.pairs.len() {
+                self.pair_index[self.pairs[i]] = i;
+            }
+            self.pair_index[rank] = NONE;
+        }
+    }
+
+    fn pair(&self, nodes: &[Node]) -> Option<(usize, usize)> {
+        let rank = *self.pairs.last()?;
+        let a = self.lists[rank].head;
+        Some((a, nodes[a].fix_next))
+    }
+}
+
+/// A strict Fibonacci heap (Brodal, Lagogiannis and Tarjan), with the
+/// bounds of a Fibonacci heap in the worst case rather than amortized:
+/// `insert`, `decrease_key` and `find_min` take O(1) time, and
+/// `delete_min` O(log n).
+///
+/// Each operation does a bounded amount of restructuring, so no single
+/// call stalls to pay for earlier ones, which suits real-time code. The
+/// constant factors are larger than `FibHeap`'s. Nodes live in vectors
+/// that grow like any `Vec`; `with_capacity` allocates them up front so
+/// that the bounds hold from the first insertion.
+pub struct StrictFibHeap<K, V> {
+    items: Slab<Item<K, V>>,
+    nodes: Vec<Node>,
+    free_nodes: Vec<usize>,
+    root: usize,
+    // Every node but the root, visited in turn by `delete_min` to keep
+    // degrees down.
+    queue: List,
+    active_roots: RankLists,
+    loss_one: RankLists,
+    loss_two: List,
+    len: usize,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> StrictFibHeap<K, V> {
+    pub fn new() -> StrictFibHeap<K, V> {
+        StrictFibHeap {
+            items: Slab::new(), nodes: Vec::new(), free_nodes: Vec::new(),
+            root: NONE, queue: EMPTY, active_roots: RankLists::new(), loss_one: RankLists::new(),
+            loss_two: EMPTY, len: 0,
+        }
+    }
+
+    /// An empty heap with room for `capacity` entries before anything
+    /// reallocates.
+    pub fn with_capacity(capacity: usize) -> StrictFibHeap<K, V> {
+        let mut heap = StrictFibHeap::new();
+        heap.items.reserve(capacity);
+        heap.nodes.reserve(capacity);
+        heap.free_nodes.reserve(capacity);
+        let ranks = StrictFibHeap::<K, V>::bound(capacity) + 1;
+        heap.active_roots.reserve(ranks);
+        heap.loss_one.reserve(ranks);
+        heap
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn contains(&self, entry: StrictEntry) -> bool {
+        self.items.contains(entry.0)
+    }
+
+    // R in the paper: the bound on active roots and total loss, which also
+    // bounds ranks.
+    fn bound(n: usize) -> usize {
+        2 * (usize::BITS - n.leading_zeros()) as usize + 6
+    }
+
+    // Orders nodes by their items' keys, then by item, so that no two
+    // nodes tie and linking can't make a node its own ancestor.
+    fn less(&self, a: usize, b: usize) -> bool {
+        let (ia, ib) = (self.nodes[a].item, self.nodes[b].item);
+        let (ka, kb) = (&self.items[ia].entry.0, &self.items[ib].entry.0);
+        ka < kb || (ka == kb && ia < ib)
+    }
+
+    fn parent_active(&self, n: usize) -> bool {
+        let parent = self.nodes[n].parent;
+        parent != NONE && self.nodes[parent].active
+    }
+
+    fn file(&mut self, n: usize) {
+        let node = &self.nodes[n];
+        let filed = if !node.active {
+            Filed::Nowhere
+        } else if !self.parent_active(n) {
+            Filed::ActiveRoot(node.rank)
+        } else {
+            match node.loss {
+                0 => Filed::Nowhere,
+                1 => Filed::LossOne(node.rank),
+                _ => Filed::LossTwo
+            }
+        };
+        match filed {
+            Filed::Nowhere => {}
+            Filed::ActiveRoot(rank) => self.active_roots.add(&mut self.nodes, rank, n),
+            Filed::LossOne(rank) => self.loss_one.add(&mut self.nodes, rank, n),
+            Filed::LossTwo => push_front(&mut self.nodes, &mut self.loss_two, n, Chain::Fix),
+        }
+        self.nodes[n].filed = filed;
+    }
+
+    fn unfile(&mut self, n: usize) {
+        match self.nodes[n].filed {
+            Filed::Nowhere => {}
+            Filed::ActiveRoot(rank) => self.active_roots.remove(&mut self.nodes, rank, n),
+            Filed::LossOne(rank) => self.loss_one.remove(&mut self.nodes, rank, n),
+            Filed::LossTwo => unlink(&mut self.nodes, &mut self.loss_two, n, Chain::Fix),
+        }
+        self.nodes[n].filed = Filed::Nowhere;
+    }
+
+    fn cut(&mut self, n: usize) {
+        let parent = self.nodes[n].parent;
+        let side = if self.nodes[n].active { 0 } else { 1 };
+        let mut list = self.nodes[parent].children[side];
+        unlink(&mut self.nodes, &mut list, n, Chain::Sibling);
+        self.nodes[parent].children[side] = list;
+        self.nodes[n].parent = NONE;
+    }
+
+    // Active children go to the left and passive ones to the right, except
+    // that the root keeps its linkable passive children rightmost.
+    fn link(&mut self, child: usize, parent: usize) {
+        self.nodes[child].parent = parent;
+        let mut list;
+        if self.nodes[child].active {
+            list = self.nodes[parent].children[0];
+            push_front(&mut self.nodes, &mut list, child, Chain::Sibling);
+            self.nodes[parent].children[0] = list;
+        } else {
+            list = self.nodes[parent].children[1];
+            if parent == self.root && !self.nodes[child].linkable() {
+                push_front(&mut self.nodes, &mut list, child, Chain::Sibling);
+            } else {
+                push_back(&mut self.nodes, &mut list, child, Chain::Sibling);
+            }
+            self.nodes[parent].children[1] = list;
+        }
+    }
+
+    // Moves a passive child of the root that has become linkable to the
+    // right of the root's children.
+    fn lost_active_child(&mut self, parent: usize) {
+        let node = &self.nodes[parent];
+        if !node.active && parent != self.root && node.parent == self.root && node.linkable() {
+            self.cut(parent);
+            let root = self.root;
+            self.link(parent, root);
+        }
+    }
+
+    // Links the larger of two active roots of the same rank to the other.
+    fn active_root_reduction(&mut self) -> bool {
+        let (a, b) = match self.active_roots.pair(&self.nodes) {
+            Some(pair) => pair,
+            None => return false
+        };
+        let (x, y) = if self.less(a, b) { (a, b) } else { (b, a) };
+        self.unfile(x);
+        self.unfile(y);
+        let parent = self.nodes[y].parent;
+        self.cut(y);
+        self.link(y, x);
+        self.nodes[x].rank += 1;
+        self.file(x);
+        self.file(y);
+        self.lost_active_child(parent);
+        let z = self.nodes[x].children[1].tail;
+        if z != NONE {
+            self.cut(z);
+            let root = self.root;
+            self.link(z, root);
+        }
+        true
+    }
+
+    // Turns the root's three rightmost linkable children into an active
+    // root with one active child.
+    fn root_degree_reduction(&mut self) -> bool {
+        let root = self.root;
+        let c = self.nodes[root].children[1].tail;
+        let b = if c == NONE { NONE } else { self.nodes[c].prev };
+        let a = if b == NONE { NONE } else { self.nodes[b].prev };
+        if a == NONE || !self.nodes[a].linkable() {
+            return false
+        }
+        let mut three = [a, b, c];
+        for &n in three.iter() {
+            self.cut(n);
+        }
+        three.sort_by(|&p, &q| if self.less(p, q) { ::std::cmp::Ordering::Less } else { ::std::cmp::Ordering::Greater });
+        let [x, y, z] = three;
+        self.link(z, y);
+        for &(n, rank) in [(y, 0), (x, 1)].iter() {
+            let node = &mut self.nodes[n];
+            node.active = true;
+            node.rank = rank;
+            node.loss = 0;
+        }
+        self.link(y, x);
+        self.link(x, root);
+        self.file(y);
+        self.file(x);
+        true
+    }
+
+    // Lowers the total loss, by moving a node that has lost two children
+    // to the root or by linking two nodes that have lost one each.
+    fn loss_reduction(&mut self) -> bool {
+        let x = self.loss_two.head;
+        if x != NONE {
+            let parent = self.nodes[x].parent;
+            self.unfile(x);
+            self.unfile(parent);
+            self.cut(x);
+            self.lose_child(parent);
+            self.file(parent);
+            self.nodes[x].loss = 0;
+            let root = self.root;
+            self.link(x, root);
+            self.file(x);
+            return true
+        }
+        let (a, b) = match self.loss_one.pair(&self.nodes) {
+            Some(pair) => pair,
+            None => return false
+        };
+        let (x, y) = if self.less(a, b) { (a, b) } else { (b, a) };
+        let parent = self.nodes[y].parent;
+        self.unfile(x);
+        self.unfile(y);
+        self.unfile(parent);
+        self.cut(y);
+        self.link(y, x);
+        self.nodes[x].rank += 1;
+        self.nodes[x].loss = 0;
+        self.nodes[y].loss = 0;
+        // After resetting the losses, as `parent` may be `x`.
+        self.lose_child(parent);
+        self.file(parent);
+        if parent != x {
+            self.file(x);
+        }
+        self.file(y);
+        true
+    }
+
+    // Accounts for an active node losing an active child.
+    fn lose_child(&mut self, n: usize) {
+        self.nodes[n].rank -= 1;
+        if self.parent_active(n) {
+            self.nodes[n].loss += 1;
+        }
+    }
+
+    /// Checks the heap's structure and the strict Fibonacci heap
+    /// invariants, describing the first problem found.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.root == NONE {
+            return if self.len == 0 { Ok(()) } else { Err(format!("no root but {} entries", self.len)) }
+        }
+        let bound = StrictFibHeap::<K, V>::bound(self.len);
+        let (mut count, mut active_roots, mut loss) = (0, 0, 0);
+        let mut stack = vec!(self.root);
+        if self.nodes[self.root].active {
+            return Err(String::from("the root is active"))
+        }
+        while let Some(n) = stack.pop() {
+            count += 1;
+            let node = &self.nodes[n];
+            if self.items[node.item].node != n {
+                return Err(format!("node {} and its item disagree", n))
+            }
+            if node.active && !self.parent_active(n) {
+                active_roots += 1;
+            } else if node.active {
+                loss += node.loss;
+            }
+            let mut active = 0;
+            let mut seen_linkable = false;
+            for side in 0..2 {
+                let mut c = node.children[side].head;
+                while c != NONE {
+                    let child = &self.nodes[c];
+                    if child.parent != n {
+                        return Err(format!("child {} of {} doesn't link back to it", c, n))
+                    }
+                    if child.active != (side == 0) {
+                        return Err(format!("child {} of {} is in the wrong list", c, n))
+                    }
+                    if self.less(c, n) {
+                        return Err(format!("child {} is less than its parent {}", c, n))
+                    }
+                    if n == self.root && side == 1 && !child.linkable() && seen_linkable {
+                        return Err(format!("passive node {} with active children is misplaced", c))
+                    }
+                    seen_linkable |= side == 1 && child.linkable();
+                    if side == 0 {
+                        active += 1;
+                    }
+                    stack.push(c);
+                    c = child.next;
+                }
+            }
+            if n == self.root {
+                let mut degree = 0;
+                for side in 0..2 {
+                    let mut c = node.children[side].head;
+                    while c != NONE {
+                        degree += 1;
+                        c = self.nodes[c].next;
+                    }
+                }
+                if degree > bound + 3 {
+                    return Err(format!("the root's degree {} exceeds {}", degree, bound + 3))
+                }
+            }
+            if node.active && node.rank != active {
+                return Err(format!("node {} has rank {} but {} active children", n, node.rank, active))
+            }
+            // The i-th active child from the right has rank plus loss at
+            // least i - 1.
+            let (mut c, mut i) = (node.children[0].tail, 0);
+            while node.active && c != NONE {
+                if self.nodes[c].rank + self.nodes[c].loss < i {
+                    return Err(format!("active child {} of {} has too low a rank and loss", c, n))
+                }
+                i += 1;
+                c = self.nodes[c].prev;
+            }
+        }
+        if count != self.len {
+            return Err(format!("the heap holds {} nodes but counts {}", count, self.len))
+        }
+        let mut queued = 0;
+        let mut q = self.queue.head;
+        while q != NONE {
+            queued += 1;
+            q = self.nodes[q].q_next;
+        }
+        if queued != self.len - 1 {
+            return Err(format!("the queue holds {} nodes, not {}", queued, self.len - 1))
+        }
+        if active_roots > bound + 1 {
+            return Err(format!("{} active roots exceed {}", active_roots, bound + 1))
+        }
+        if loss > bound + 1 {
+            return Err(format!("total loss {} exceeds {}", loss, bound + 1))
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for StrictFibHeap<K, V> {
+    type HeapEntry = StrictEntry;
+
+    fn find_min(&self) -> (K, V) {
+        if self.root == NONE {
+            panic!("StrictFibHeap is empty");
+        }
+        self.items[self.nodes[self.root].item].entry.clone()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        if self.root == NONE {
+            panic!("StrictFibHeap is empty");
+        }
+        let old = self.root;
+        let item = self.nodes[old].item;
+        let entry = self.items.remove(item).entry;
+        self.free_nodes.push(old);
+        self.len -= 1;
+
+        // The smallest child becomes the root, taking the others as
+        // children.
+        let mut x = NONE;
+        for side in 0..2 {
+            let mut c = self.nodes[old].children[side].head;
+            while c != NONE {
+                if x == NONE || self.less(c, x) {
+                    x = c;
+                }
+                c = self.nodes[c].next;
+            }
+        }
+        if x == NONE {
+            self.root = NONE;
+            return entry
+        }
+        unlink(&mut self.nodes, &mut self.queue, x, Chain::Queue);
+        self.unfile(x);
+        self.cut(x);
+        self.root = x;
+        if self.nodes[x].active {
+            self.nodes[x].active = false;
+            self.nodes[x].loss = 0;
+            let mut c = self.nodes[x].children[0].head;
+            while c != NONE {
+                self.unfile(c);
+                self.nodes[c].loss = 0;
+                self.file(c);
+                c = self.nodes[c].next;
+            }
+        }
+        // The new root's passive children with active children go to the
+        // left of the others.
+        let mut c = self.nodes[x].children[1].head;
+        while c != NONE {
+            let next = self.nodes[c].next;
+            if !self.nodes[c].linkable() {
+                self.cut(c);
+                self.link(c, x);
+            }
+            c = next;
+        }
+        for side in 0..2 {
+            while self.nodes[old].children[side].head != NONE {
+                let c = self.nodes[old].children[side].head;
+                self.cut(c);
+                self.link(c, x);
+            }
+        }
+
+        // Links the passive children of the next two nodes in the queue to
+        // the root.
+        for _ in 0..2 {
+            let y = self.queue.head;
+            if y == NONE {
+                break
+            }
+            unlink(&mut self.nodes, &mut self.queue, y, Chain::Queue);
+            push_back(&mut self.nodes, &mut self.queue, y, Chain::Queue);
+            for _ in 0..2 {
+                let c = self.nodes[y].children[1].tail;
+                if c == NONE {
+                    break
+                }
+                self.cut(c);
+                self.link(c, x);
+            }
+        }
+
+        while self.loss_reduction() {}
+        while self.active_root_reduction() || self.root_degree_reduction() {}
+        entry
+    }
+
+    fn insert(&mut self, key: K, value: V) -> StrictEntry {
+        let slot = self.items.insert(Item { entry: (key, value), node: NONE });
+        let item = slot.index();
+        let n = match self.free_nodes.pop() {
+            Some(n) => {
+                self.nodes[n] = Node::new(item);
+                n
+            }
+            None => {
+                self.nodes.push(Node::new(item));
+                self.nodes.len() - 1
+            }
+        };
+        self.items[item].node = n;
+        self.len += 1;
+        if self.root == NONE {
+            self.root = n;
+        } else {
+            let child = if self.less(n, self.root) {
+                let old = self.root;
+                self.root = n;
+                old
+            } else {
+                n
+            };
+            let root = self.root;
+            self.link(child, root);
+            push_back(&mut self.nodes, &mut self.queue, child, Chain::Queue);
+            self.active_root_reduction();
+            self.root_degree_reduction();
+        }
+        StrictEntry(slot)
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for StrictFibHeap<K, V> {
+    fn decrease_key(&mut self, entry: &StrictEntry, delta: K) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        {
+            let key = &mut self.items[entry.0.index()].entry.0;
+            *key = key.clone() - delta;
+        }
+        let x = self.items[entry.0.index()].node;
+        let root = self.root;
+        if x == root {
+            return Ok(())
+        }
+        let parent = self.nodes[x].parent;
+        if self.nodes[x].active {
+            self.unfile(x);
+            self.unfile(parent);
+            self.cut(x);
+            if self.nodes[parent].active {
+                self.lose_child(parent);
+                self.file(parent);
+            } else {
+                self.lost_active_child(parent);
+            }
+            self.nodes[x].loss = 0;
+            self.link(x, root);
+            self.file(x);
+        } else {
+            self.cut(x);
+            self.link(x, root);
+        }
+        if self.less(x, root) {
+            let (a, b) = (self.nodes[x].item, self.nodes[root].item);
+            self.nodes[x].item = b;
+            self.nodes[root].item = a;
+            self.items[a].node = root;
+            self.items[b].node = x;
+        }
+        self.loss_reduction();
+        for _ in 0..6 {
+            self.active_root_reduction();
+        }
+        for _ in 0..4 {
+            self.root_degree_reduction();
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> AddressableHeap<K, V> for StrictFibHeap<K, V> {
+    fn is_valid(&self, entry: &StrictEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strict_fib_heap::StrictFibHeap;
+    use test_model;
+    use {Heap, DecreaseKey, StaleEntry};
+
+    #[test]
+    fn strict_fib_heap_against_model() {
+        let mut heap = StrictFibHeap::with_capacity(64);
+        test_model::against_model(&mut heap, 20000, |_, _, x| 1000 + x % 100000, None, |heap, entries, step| {
+            assert_eq!(heap.len(), entries.len());
+            if step % 97 == 0 {
+                heap.check_invariants().unwrap();
+            }
+        });
+        while !heap.empty() {
+            heap.delete_min();
+            heap.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn strict_fib_heap_sorts() {
+        let mut heap = StrictFibHeap::new();
+        for i in 0..5000u32 {
+            heap.insert((i * 7919) % 5000, i);
+        }
+        heap.check_invariants().unwrap();
+        for k in 0..5000 {
+            assert_eq!(heap.find_min().0, k);
+            assert_eq!(heap.delete_min().0, k);
+        }
+    }
+
+    #[test]
+    fn strict_fib_heap_stale_handle() {
+        let mut heap = StrictFibHeap::new();
+        let a = heap.insert(1, 'a');
+        heap.delete_min();
+        heap.insert(2, 'b');
+        assert_eq!(heap.decrease_key(&a, 1), Err(StaleEntry));
+        assert_eq!(heap.find_min(), (2, 'b'));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use {Heap, HeapDelete};
+
+/// Handle to a pending event in an `EventQueue`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EventHandle(u64);
+
+type Key<C, E> = Ordered<(C, E, u64)>;
+type Entry<C, E> = Rc<FibNode<Key<C, E>, u64>>;
+
+/// The event queue of a sweep-line algorithm such as Bentley-Ottmann.
+///
+/// Events are processed by coordinate, then by event type, so an `E` whose
+/// ordering puts e.g. segment ends before intersections before segment
+/// starts controls the order at a shared point. Events with the same
+/// coordinate and type come out in insertion order.
+pub struct EventQueue<C, E, V> {
+    heap: FibHeap<Key<C, E>, u64>,
+    events: HashMap<u64, (Entry<C, E>, V)>,
+    next_id: u64,
+}
+
+impl<C: Ord + Clone, E: Ord + Clone, V> EventQueue<C, E, V> {
+    pub fn new() -> EventQueue<C, E, V> {
+        EventQueue { heap: FibHeap::new(), events: HashMap::new(), next_id: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn insert(&mut self, coord: C, kind: E, payload: V) -> EventHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(Ordered((coord, kind, id)), id);
+        self.events.insert(id, (entry, payload));
+        EventHandle(id)
+    }
+
+    /// Removes an event that is no longer valid, such as an intersection
+    /// between segments that stopped being neighbours. Returns `None` if the
+    /// event was already processed or removed.
+    pub fn remove(&mut self, handle: EventHandle) -> Option<(C, E, V)> {
+        self.events.remove(&handle.0).map(|(entry, payload)| {
+            let (Ordered((coord, kind, _)), _) = self.heap.delete(entry).unwrap();
+            (coord, kind, payload)
+        })
+    }
+
+    /// The coordinate and type of the next event.
+    pub fn peek(&self) -> Option<(C, E)> {
+        if self.heap.empty() {
+            None
+        } else {
+            let (Ordered((coord, kind, _)), _) = self.heap.find_min();
+            Some((coord, kind))
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<(C, E, V)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (Ordered((coord, kind, _)), id) = self.heap.delete_min();
+        let (_, payload) = self.events.remove(&id).unwrap();
+        Some((coord, kind, payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sweep::EventQueue;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    enum Kind {
+        End,
+        Cross,
+        Start,
+    }
+
+    #[test]
+    fn sweep_orders_by_point_then_kind() {
+        let mut queue = EventQueue::new();
+        queue.insert((2, 0), Kind::Start, "s1");
+        queue.insert((1, 5), Kind::Start, "s0");
+        queue.insert((2, 0), Kind::End, "e0");
+        queue.insert((2, 0), Kind::Cross, "x0");
+        queue.insert((2, 0), Kind::End, "e1");
+        assert_eq!(queue.peek(), Some(((1, 5), Kind::Start)));
+        let order: Vec<_> = (0..5).map(|_| queue.pop().unwrap().2).collect();
+        assert_eq!(order, vec!("s0", "e0", "e1", "x0", "s1"));
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn sweep_remove_invalidated() {
+        let mut queue = EventQueue::new();
+        queue.insert(1, Kind::Start, 'a');
+        let cross = queue.insert(3, Kind::Cross, 'x');
+        queue.insert(5, Kind::End, 'a');
+        assert_eq!(queue.remove(cross), Some((3, Kind::Cross, 'x')));
+        assert_eq!(queue.remove(cross), None);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some((1, Kind::Start, 'a')));
+        assert_eq!(queue.pop(), Some((5, Kind::End, 'a')));
+        assert!(queue.is_empty());
+    }
+}
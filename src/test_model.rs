@@ -0,0 +1,60 @@
+// The model test shared by the addressable heaps: random inserts, decreases,
+// deletes and delete_mins, each answer checked against a list of the live
+// entries.
+
+use std::cmp;
+use std::fmt::Debug;
+use std::ops::Sub;
+use rng::Rng;
+use {Heap, AddressableHeap, StaleEntry};
+
+// A live entry: its handle, key and value. Values are the step that inserted
+// the entry, so they tell entries with equal keys apart.
+pub type Entry<H, K> = (<H as Heap<K, usize>>::HeapEntry, K, usize);
+
+// How a heap without `HeapDelete` is tested: its deletes become delete_mins.
+pub type Delete<H, K> = Option<fn(&mut H, <H as Heap<K, usize>>::HeapEntry) -> Result<(K, usize), StaleEntry>>;
+
+// Runs `steps` random operations on `heap`. `key` picks a key from the step
+// and a random number, both for inserts and as the key a decrease lowers an
+// entry to, if it's smaller. `check` runs after every step, for what only
+// the heap at hand has.
+pub fn against_model<K, H, F, C>(heap: &mut H, steps: usize, mut key: F, delete: Delete<H, K>, mut check: C)
+where K: Copy + Ord + Debug + Sub<K, Output=K>, H: AddressableHeap<K, usize>,
+      F: FnMut(&H, usize, u64) -> K, C: FnMut(&mut H, &[Entry<H, K>], usize) {
+    let mut rng = Rng::new(1);
+    let mut entries: Vec<Entry<H, K>> = Vec::new();
+    for step in 0..steps {
+        let x = rng.next_u64();
+        let k = key(heap, step, x);
+        match (x % 6, delete) {
+            (0..=2, _) => {
+                let entry = heap.insert(k, step);
+                entries.push((entry, k, step));
+            }
+            (3, _) if !entries.is_empty() => {
+                let i = (x >> 40) as usize % entries.len();
+                let old = entries[i].1;
+                let new = cmp::min(k, old);
+                heap.decrease_key(&entries[i].0, old - new).unwrap();
+                entries[i].1 = new;
+            }
+            (4, Some(delete)) if !entries.is_empty() => {
+                let (entry, k, v) = entries.swap_remove((x >> 40) as usize % entries.len());
+                assert_eq!(delete(heap, entry), Ok((k, v)));
+            }
+            _ if !entries.is_empty() => {
+                let min = heap.find_min();
+                assert_eq!(heap.delete_min(), min);
+                assert_eq!(Some(min.0), entries.iter().map(|e| e.1).min());
+                let i = entries.iter().position(|e| e.2 == min.1).unwrap();
+                let (entry, k, _) = entries.swap_remove(i);
+                assert_eq!(k, min.0);
+                assert!(!heap.is_valid(&entry));
+            }
+            _ => {}
+        }
+        assert_eq!(heap.empty(), entries.is_empty());
+        check(heap, &entries, step);
+    }
+}
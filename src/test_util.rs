@@ -0,0 +1,360 @@
+//! Reproducible random graphs and a harness that runs Dijkstra workloads
+//! through any of the crate's heaps, counting the heap operations.
+
+use std::cell::Cell;
+use std::env;
+use std::fmt::Debug;
+use std::fs;
+use std::ops::Sub;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use fibonacci_heap::{FibHeap, Tree};
+use graph::{Graph, shortest_path};
+use {Heap, DecreaseKey, StaleEntry};
+
+pub use rng::Rng;
+
+/// A directed G(n, p) graph: every ordered pair of distinct nodes gets an
+/// edge with probability `p`, weighted uniformly in `1..=max_weight`.
+pub fn erdos_renyi(n: usize, p: f64, max_weight: u64, seed: u64) -> Graph<u64> {
+    let mut rng = Rng::new(seed);
+    let mut graph = Graph::new(n);
+    for u in 0..n {
+        for v in 0..n {
+            if u != v && rng.unit() < p {
+                let weight = 1 + rng.below(max_weight);
+                graph.add_edge(u, v, weight);
+            }
+        }
+    }
+    graph
+}
+
+/// A `width` by `height` grid with undirected edges between horizontal and
+/// vertical neighbours, weighted uniformly in `1..=max_weight`. Node
+/// `y * width + x` is at `(x, y)`.
+pub fn grid(width: usize, height: usize, max_weight: u64, seed: u64) -> Graph<u64> {
+    let mut rng = Rng::new(seed);
+    let mut graph = Graph::new(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let node = y * width + x;
+            if x + 1 < width {
+                let weight = 1 + rng.below(max_weight);
+                graph.add_undirected_edge(node, node + 1, weight);
+            }
+            if y + 1 < height {
+                let weight = 1 + rng.below(max_weight);
+                graph.add_undirected_edge(node, node + width, weight);
+            }
+        }
+    }
+    graph
+}
+
+/// A Barabási-Albert graph with a power-law degree distribution: nodes are
+/// added one at a time, each with undirected edges to `m` existing nodes
+/// chosen with probability proportional to their degree. Weights are
+/// uniform in `1..=max_weight`.
+pub fn power_law(n: usize, m: usize, max_weight: u64, seed: u64) -> Graph<u64> {
+    let mut rng = Rng::new(seed);
+    let mut graph = Graph::new(n);
+    // Every edge endpoint, so a uniform pick is a degree-weighted pick.
+    let mut endpoints: Vec<usize> = Vec::new();
+    for node in 0..n {
+        let mut targets: Vec<usize> = Vec::new();
+        if node <= m {
+            targets.extend(0..node);
+        } else {
+            while targets.len() < m {
+                let t = endpoints[rng.below(endpoints.len() as u64) as usize];
+                if !targets.contains(&t) {
+                    targets.push(t);
+                }
+            }
+        }
+        for t in targets.into_iter() {
+            let weight = 1 + rng.below(max_weight);
+            graph.add_undirected_edge(node, t, weight);
+            endpoints.push(node);
+            endpoints.push(t);
+        }
+    }
+    graph
+}
+
+/// The number of each heap operation performed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpCounts {
+    pub inserts: u64,
+    pub find_mins: u64,
+    pub delete_mins: u64,
+    pub decrease_keys: u64,
+}
+
+/// Wraps a heap and counts the operations performed on it.
+pub struct CountingHeap<H> {
+    heap: H,
+    find_mins: Cell<u64>,
+    counts: OpCounts,
+}
+
+impl<H> CountingHeap<H> {
+    pub fn new(heap: H) -> CountingHeap<H> {
+        CountingHeap { heap: heap, find_mins: Cell::new(0), counts: OpCounts::default() }
+    }
+
+    pub fn counts(&self) -> OpCounts {
+        OpCounts { find_mins: self.find_mins.get(), ..self.counts }
+    }
+
+    pub fn into_inner(self) -> H {
+        self.heap
+    }
+}
+
+impl<K, V, H: Heap<K, V>> Heap<K, V> for CountingHeap<H> {
+    type HeapEntry = H::HeapEntry;
+
+    fn find_min(&self) -> (K, V) {
+        self.find_mins.set(self.find_mins.get() + 1);
+        self.heap.find_min()
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        self.counts.delete_mins += 1;
+        self.heap.delete_min()
+    }
+
+    fn insert(&mut self, key: K, value: V) -> H::HeapEntry {
+        self.counts.inserts += 1;
+        self.heap.insert(key, value)
+    }
+
+    fn empty(&self) -> bool {
+        self.heap.empty()
+    }
+}
+
+impl<K, V, H: DecreaseKey<K, V>> DecreaseKey<K, V> for CountingHeap<H> {
+    fn decrease_key(&mut self, entry: &H::HeapEntry, delta: K) -> Result<(), StaleEntry> {
+        self.counts.decrease_keys += 1;
+        self.heap.decrease_key(entry, delta)
+    }
+}
+
+/// The result of running a Dijkstra workload.
+#[derive(Clone, Copy, Debug)]
+pub struct DijkstraReport {
+    pub counts: OpCounts,
+    pub elapsed: Duration,
+    /// The sum of all finite distances found, to check heaps agree.
+    pub checksum: u64,
+}
+
+/// Runs Dijkstra's algorithm on `graph` from each of `sources`, with a fresh
+/// heap from `new_heap` for each run, and totals the heap operations.
+pub fn dijkstra_workload<H, F>(graph: &Graph<u64>, sources: &[usize], mut new_heap: F)
+    -> DijkstraReport
+where H: DecreaseKey<u64, usize>,
+      F: FnMut() -> H {
+    let mut counts = OpCounts::default();
+    let mut checksum = 0;
+    let mut elapsed = Duration::new(0, 0);
+    for &source in sources.iter() {
+        let mut heap = CountingHeap::new(new_heap());
+        let start = Instant::now();
+        let paths = shortest_path(&mut heap, graph, source);
+        elapsed += start.elapsed();
+        checksum += paths.distances.iter().filter_map(|d| *d).sum::<u64>();
+        let run = heap.counts();
+        counts.inserts += run.inserts;
+        counts.find_mins += run.find_mins;
+        counts.delete_mins += run.delete_mins;
+        counts.decrease_keys += run.decrease_keys;
+    }
+    DijkstraReport { counts: counts, elapsed: elapsed, checksum: checksum }
+}
+
+/// Panics unless the two heaps have the same trees, in the same root list
+/// order, with the same keys, ranks and marks. Values are not compared.
+///
+/// The message names the first difference by its path from the root list,
+/// e.g. `root 1, child 0`.
+pub fn assert_same_structure<K, V>(a: &FibHeap<K, V>, b: &FibHeap<K, V>)
+where K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone {
+    let (a, b) = (a.trees(), b.trees());
+    if a.len() != b.len() {
+        panic!("heaps have {} and {} roots", a.len(), b.len());
+    }
+    for (i, (x, y)) in a.iter().zip(b.iter()).enumerate() {
+        if let Some(diff) = tree_difference(x, y, &mut vec!(format!("root {}", i))) {
+            panic!("heaps differ at {}", diff);
+        }
+    }
+}
+
+fn tree_difference<K: PartialEq + Debug>(a: &Tree<K>, b: &Tree<K>, path: &mut Vec<String>)
+    -> Option<String> {
+    if a.key != b.key {
+        return Some(format!("{}: key {:?} != {:?}", path.join(", "), a.key, b.key))
+    }
+    if a.rank() != b.rank() {
+        return Some(format!("{}: rank {} != {}", path.join(", "), a.rank(), b.rank()))
+    }
+    if a.marked != b.marked {
+        return Some(format!("{}: marked {} != {}", path.join(", "), a.marked, b.marked))
+    }
+    for (i, (x, y)) in a.children.iter().zip(b.children.iter()).enumerate() {
+        path.push(format!("child {}", i));
+        let diff = tree_difference(x, y, path);
+        path.pop();
+        if diff.is_some() {
+            return diff
+        }
+    }
+    None
+}
+
+/// The heap's trees as text, one node per line in root list order with
+/// children indented under their parent. Keys are written with `Debug` and
+/// marked nodes are followed by `*`:
+///
+/// ```text
+/// 0
+///   1
+///   4*
+///     5
+/// 2
+/// ```
+pub fn structure_text<K, V>(heap: &FibHeap<K, V>) -> String
+where K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone {
+    let mut out = String::new();
+    let mut stack: Vec<(usize, Tree<K>)> = heap.trees().into_iter().rev().map(|t| (0, t)).collect();
+    while let Some((depth, tree)) = stack.pop() {
+        out.push_str(&format!("{}{:?}{}\n", "  ".repeat(depth), tree.key, if tree.marked { "*" } else { "" }));
+        stack.extend(tree.children.into_iter().rev().map(|c| (depth + 1, c)));
+    }
+    out
+}
+
+/// Panics unless `structure_text` of the heap matches the golden file at
+/// `path`. With `UPDATE_GOLDEN` set in the environment, writes the file
+/// instead, so golden files can be created and updated by rerunning the
+/// tests.
+pub fn assert_golden<K, V>(heap: &FibHeap<K, V>, path: &Path)
+where K: Ord + Debug + Clone + Sub<K, Output=K>, V: Eq + PartialOrd + Debug + Clone {
+    let actual = structure_text(heap);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(path, &actual).unwrap();
+        return
+    }
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(err) => panic!("can't read {}: {}; set UPDATE_GOLDEN=1 to create it", path.display(), err)
+    };
+    if actual != expected {
+        panic!("heap structure doesn't match {}; set UPDATE_GOLDEN=1 to update it\n\
+                expected:\n{}\nactual:\n{}", path.display(), expected, actual);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_heap::{FibHeap};
+    use graph::Graph;
+    #[cfg(feature = "lazy-heap")]
+    use lazy_heap::LazyHeap;
+    use std::path::Path;
+    use test_util::{erdos_renyi, grid, power_law, dijkstra_workload, assert_same_structure,
+                    assert_golden, structure_text};
+    use {Heap, DecreaseKey};
+
+    fn build(decrease: bool) -> FibHeap<u32, u32> {
+        let mut fheap = FibHeap::new();
+        let entries: Vec<_> = (0..9u32).map(|i| fheap.insert(10 + i, i)).collect();
+        fheap.delete_min();
+        if decrease {
+            fheap.decrease_key(&entries[8], 1).unwrap();
+        }
+        fheap
+    }
+
+    #[test]
+    fn test_util_graphs_are_reproducible() {
+        let a = erdos_renyi(30, 0.1, 100, 7);
+        let b = erdos_renyi(30, 0.1, 100, 7);
+        let c = erdos_renyi(30, 0.1, 100, 8);
+        let edges = |g: &Graph<u64>| {
+            (0..g.node_count()).map(|n| g.neighbors(n).cloned().collect::<Vec<_>>()).collect::<Vec<_>>()
+        };
+        assert_eq!(edges(&a), edges(&b));
+        assert!(edges(&a) != edges(&c));
+
+        let g = grid(4, 3, 10, 1);
+        assert_eq!(g.node_count(), 12);
+        assert_eq!((0..12).map(|n| g.neighbors(n).count()).sum::<usize>(), 2 * 17);
+
+        let g = power_law(50, 2, 10, 3);
+        assert_eq!((0..50).map(|n| g.neighbors(n).count()).sum::<usize>(), 2 * (1 + 2 * 48));
+    }
+
+    #[test]
+    fn test_util_dijkstra_workload() {
+        let graph = grid(10, 10, 20, 42);
+        let sources = [0, 55, 99];
+        let fib = dijkstra_workload(&graph, &sources, FibHeap::new);
+        #[cfg(feature = "lazy-heap")]
+        {
+            let lazy = dijkstra_workload(&graph, &sources, LazyHeap::new);
+            assert_eq!(fib.checksum, lazy.checksum);
+        }
+        assert_eq!(fib.counts.inserts, 300);
+        assert_eq!(fib.counts.delete_mins, 300);
+        assert!(fib.counts.decrease_keys > 0);
+    }
+
+    #[test]
+    fn test_util_same_structure() {
+        assert_same_structure(&build(true), &build(true));
+        // The same entries inserted in another order link differently.
+        let mut a = build(false);
+        let mut b = FibHeap::new();
+        for i in (0..9u32).map(|i| i * 4 % 9) {
+            b.insert(10 + i, i);
+        }
+        b.delete_min();
+        assert!(a.trees() != b.trees());
+        while !a.empty() {
+            assert_eq!(a.delete_min(), b.delete_min());
+        }
+        assert!(b.empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "heaps differ at root 0, child 2, child 1, child 0: key 17 != 18")]
+    fn test_util_structure_differs() {
+        assert_same_structure(&build(true), &build(false));
+    }
+
+    #[test]
+    fn test_util_structure_text() {
+        let mut fheap = FibHeap::new();
+        let entries: Vec<_> = (0..9u32).map(|i| fheap.insert(10 + i, i)).collect();
+        fheap.delete_min();
+        assert_eq!(structure_text(&fheap), "11\n  12\n  13\n    14\n  15\n    16\n    17\n      18\n");
+        // Cutting 17 marks its parent, 15.
+        fheap.decrease_key(&entries[7], 10).unwrap();
+        assert_eq!(structure_text(&fheap), "7\n  18\n11\n  12\n  13\n    14\n  15*\n    16\n");
+    }
+
+    #[test]
+    fn test_util_golden() {
+        // Inserts 10 to 18, deletes the minimum, decreases 18 to 17 and
+        // deletes the minimum again.
+        let mut fheap = build(true);
+        fheap.delete_min();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden/fheap_delete_min.txt");
+        assert_golden(&fheap, &path);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use {Heap, DecreaseKey, HeapDelete};
+
+type Entry<D> = Rc<FibNode<D, u64>>;
+
+/// Handle to a timer in a `TimerQueue` or `CoalescingTimerQueue`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerHandle(u64);
+
+/// A set of pending timers ordered by deadline.
+///
+/// Deadlines can be any ordered type supporting `-`, such as ticks or
+/// milliseconds since some epoch.
+pub struct TimerQueue<D, T> {
+    heap: FibHeap<D, u64>,
+    timers: HashMap<u64, (Entry<D>, T)>,
+    next_id: u64,
+}
+
+impl<D: Ord + Debug + Clone + Sub<D, Output=D>, T> TimerQueue<D, T> {
+    pub fn new() -> TimerQueue<D, T> {
+        TimerQueue { heap: FibHeap::new(), timers: HashMap::new(), next_id: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn add(&mut self, deadline: D, payload: T) -> TimerHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = self.heap.insert(deadline, id);
+        self.timers.insert(id, (entry, payload));
+        TimerHandle(id)
+    }
+
+    /// Removes a pending timer, returning its payload, or `None` if it has
+    /// already fired or been cancelled.
+    pub fn cancel(&mut self, handle: TimerHandle) -> Option<T> {
+        self.timers.remove(&handle.0).map(|(entry, payload)| {
+            self.heap.delete(entry).unwrap();
+            payload
+        })
+    }
+
+    /// Moves a pending timer to `new_deadline`, which may be earlier or later
+    /// than its current one. Returns whether the timer was still pending.
+    pub fn postpone(&mut self, handle: TimerHandle, new_deadline: D) -> bool {
+        let id = handle.0;
+        let old = match self.timers.get(&id) {
+            Some((entry, _)) => entry.get_key().clone(),
+            None => return false
+        };
+        if new_deadline <= old {
+            self.heap.decrease_key(&self.timers[&id].0, old - new_deadline).unwrap();
+        } else {
+            let (entry, payload) = self.timers.remove(&id).unwrap();
+            self.heap.delete(entry).unwrap();
+            let entry = self.heap.insert(new_deadline, id);
+            self.timers.insert(id, (entry, payload));
+        }
+        true
+    }
+
+    /// The earliest pending deadline.
+    pub fn next_deadline(&self) -> Option<D> {
+        if self.heap.empty() {
+            None
+        } else {
+            Some(self.heap.find_min().0)
+        }
+    }
+
+    /// Removes every timer whose deadline is at or before `now`, returning
+    /// them in deadline order.
+    pub fn expired(&mut self, now: D) -> Vec<(D, T)> {
+        let mut due = Vec::new();
+        while !self.heap.empty() && self.heap.find_min().0 <= now {
+            let (deadline, id) = self.heap.delete_min();
+            let (_, payload) = self.timers.remove(&id).unwrap();
+            due.push((deadline, payload));
+        }
+        due
+    }
+}
+
+/// A timer queue that batches timers to reduce wakeups.
+///
+/// Each timer may fire anywhere from its deadline to its deadline plus its
+/// tolerance. The queue wakes at the earliest time some timer can't be
+/// delayed any longer, and then fires every timer whose deadline has passed.
+pub struct CoalescingTimerQueue<D, T> {
+    // The same timers keyed by deadline and by latest firing time.
+    earliest: FibHeap<D, u64>,
+    latest: FibHeap<D, u64>,
+    timers: HashMap<u64, (Entry<D>, Entry<D>, T)>,
+    next_id: u64,
+}
+
+impl<D: Ord + Debug + Clone + Add<D, Output=D> + Sub<D, Output=D>, T> CoalescingTimerQueue<D, T> {
+    pub fn new() -> CoalescingTimerQueue<D, T> {
+        CoalescingTimerQueue {
+            earliest: FibHeap::new(),
+            latest: FibHeap::new(),
+            timers: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn add(&mut self, deadline: D, tolerance: D, payload: T) -> TimerHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let early = self.earliest.insert(deadline.clone(), id);
+        let late = self.latest.insert(deadline + tolerance, id);
+        self.timers.insert(id, (early, late, payload));
+        TimerHandle(id)
+    }
+
+    pub fn cancel(&mut self, handle: TimerHandle) -> Option<T> {
+        self.timers.remove(&handle.0).map(|(early, late, payload)| {
+            self.earliest.delete(early).unwrap();
+            self.latest.delete(late).unwrap();
+            payload
+        })
+    }
+
+    /// When the next batch is due: the earliest deadline plus tolerance.
+    pub fn next_wakeup(&self) -> Option<D> {
+        if self.latest.empty() {
+            None
+        } else {
+            Some(self.latest.find_min().0)
+        }
+    }
+
+    /// If a batch is due by `now`, removes every timer whose deadline is at
+    /// or before `now` and returns them in deadline order. Otherwise fires
+    /// nothing, even if some deadlines have passed.
+    pub fn fire(&mut self, now: D) -> Vec<(D, T)> {
+        let mut batch = Vec::new();
+        match self.next_wakeup() {
+            Some(ref wakeup) if *wakeup <= now => (),
+            _ => return batch
+        }
+        while !self.earliest.empty() && self.earliest.find_min().0 <= now {
+            let (deadline, id) = self.earliest.delete_min();
+            let (_, late, payload) = self.timers.remove(&id).unwrap();
+            self.latest.delete(late).unwrap();
+            batch.push((deadline, payload));
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use timers::{TimerQueue, CoalescingTimerQueue};
+
+    #[test]
+    fn timers_expire_in_order() {
+        let mut timers = TimerQueue::new();
+        timers.add(30u64, "c");
+        timers.add(10, "a");
+        timers.add(20, "b");
+        assert_eq!(timers.next_deadline(), Some(10));
+        assert_eq!(timers.expired(5), vec!());
+        assert_eq!(timers.expired(20), vec!((10, "a"), (20, "b")));
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers.expired(100), vec!((30, "c")));
+        assert_eq!(timers.next_deadline(), None);
+    }
+
+    #[test]
+    fn timers_cancel_and_postpone() {
+        let mut timers = TimerQueue::new();
+        let a = timers.add(10u64, "a");
+        let b = timers.add(20, "b");
+        let c = timers.add(30, "c");
+        let d = timers.add(40, "d");
+        assert_eq!(timers.cancel(b), Some("b"));
+        assert_eq!(timers.cancel(b), None);
+        assert!(timers.postpone(a, 35));
+        assert!(timers.postpone(d, 5));
+        assert_eq!(timers.expired(30), vec!((5, "d"), (30, "c")));
+        assert!(!timers.postpone(c, 50));
+        assert_eq!(timers.expired(50), vec!((35, "a")));
+    }
+
+    #[test]
+    fn timers_coalesce_within_tolerance() {
+        let mut timers = CoalescingTimerQueue::new();
+        timers.add(10u64, 5, "a");
+        let b = timers.add(12, 10, "b");
+        timers.add(14, 0, "c");
+        timers.add(30, 2, "d");
+        assert_eq!(timers.next_wakeup(), Some(14));
+        assert_eq!(timers.fire(13), vec!());
+        assert_eq!(timers.fire(14), vec!((10, "a"), (12, "b"), (14, "c")));
+        assert_eq!(timers.cancel(b), None);
+        assert_eq!(timers.next_wakeup(), Some(32));
+
+        let e = timers.add(31, 0, "e");
+        assert_eq!(timers.next_wakeup(), Some(31));
+        assert_eq!(timers.cancel(e), Some("e"));
+        assert_eq!(timers.fire(31), vec!());
+        assert_eq!(timers.fire(40), vec!((30, "d")));
+        assert_eq!(timers.len(), 0);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use Heap;
+
+/// Keeps the `k` entries with the largest keys seen so far.
+///
+/// The kept entries sit in a min-heap, so the worst of them is the one
+/// evicted when a better entry arrives.
+pub struct TopK<K, V> {
+    k: usize,
+    heap: FibHeap<Ordered<K>, u64>,
+    values: HashMap<u64, V>,
+    next_id: u64,
+}
+
+impl<K: Ord + Clone, V> TopK<K, V> {
+    pub fn new(k: usize) -> TopK<K, V> {
+        TopK { k: k, heap: FibHeap::new(), values: HashMap::new(), next_id: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Offers an entry, returning whether it was kept. Once `k` entries are
+    /// held, an entry is only kept if its key beats the current worst, which
+    /// is then evicted.
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        if self.k == 0 {
+            return false
+        }
+        if self.values.len() == self.k {
+            let (Ordered(worst), _) = self.heap.find_min();
+            if key <= worst {
+                return false
+            }
+            let (_, id) = self.heap.delete_min();
+            self.values.remove(&id);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.heap.insert(Ordered(key), id);
+        self.values.insert(id, value);
+        true
+    }
+
+    /// The smallest key still kept, which an entry must beat once `k`
+    /// entries are held.
+    pub fn worst_key(&self) -> Option<K> {
+        if self.heap.empty() {
+            None
+        } else {
+            Some((self.heap.find_min().0).0)
+        }
+    }
+
+    /// The kept entries, best first.
+    pub fn into_sorted_vec(mut self) -> Vec<(K, V)> {
+        let mut entries = Vec::with_capacity(self.values.len());
+        while !self.heap.empty() {
+            let (Ordered(key), id) = self.heap.delete_min();
+            entries.push((key, self.values.remove(&id).unwrap()));
+        }
+        entries.reverse();
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use top_k::TopK;
+
+    #[test]
+    fn top_k_keeps_largest() {
+        let mut top = TopK::new(3);
+        for &(k, v) in [(5, "e"), (1, "a"), (9, "i"), (3, "c"), (7, "g"), (2, "b")].iter() {
+            top.insert(k, v);
+        }
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.worst_key(), Some(5));
+        assert!(!top.insert(5, "x"));
+        assert!(top.insert(6, "f"));
+        assert_eq!(top.into_sorted_vec(), vec!((9, "i"), (7, "g"), (6, "f")));
+    }
+
+    #[test]
+    fn top_k_zero() {
+        let mut top: TopK<u8, ()> = TopK::new(0);
+        assert!(!top.insert(1, ()));
+        assert_eq!(top.worst_key(), None);
+        assert!(top.into_sorted_vec().is_empty());
+    }
+}
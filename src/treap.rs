@@ -0,0 +1,457 @@
+use std::cell::RefCell;
+use std::collections::Bound;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::ops::{RangeBounds, Sub};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use {Heap, DecreaseKey, HeapExt, HeapDelete, AddressableHeap, StaleEntry};
+
+// Ids break ties between equal keys, and are unique across treaps so that
+// treaps can always be merged.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+type Tree<K, V> = Option<Box<Node<K, V>>>;
+type ItemLink<K, V> = Rc<RefCell<Item<K, V>>>;
+
+struct Item<K, V> {
+    key: K,
+    value: V,
+    id: u64,
+    // Set once the entry has been taken out of its treap.
+    removed: bool,
+}
+
+struct Node<K, V> {
+    item: ItemLink<K, V>,
+    priority: u64,
+    // Entries in the subtree.
+    size: usize,
+    left: Tree<K, V>,
+    right: Tree<K, V>,
+}
+
+/// Handle to an entry of a `Treap`. Handles compare and hash by the entry
+/// they refer to, and follow it when its treap is split or merged.
+pub struct TreapEntry<K, V>(ItemLink<K, V>);
+
+impl<K, V> Clone for TreapEntry<K, V> {
+    fn clone(&self) -> TreapEntry<K, V> {
+        TreapEntry(self.0.clone())
+    }
+}
+
+impl<K, V> PartialEq for TreapEntry<K, V> {
+    fn eq(&self, other: &TreapEntry<K, V>) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<K, V> Eq for TreapEntry<K, V> {}
+
+impl<K, V> Hash for TreapEntry<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&*self.0 as *const RefCell<Item<K, V>>).hash(state)
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for TreapEntry<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let item = self.0.borrow();
+        f.debug_struct("TreapEntry")
+            .field("key", &item.key)
+            .field("value", &item.value)
+            .field("live", &!item.removed)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<K: defmt::Format, V: defmt::Format> defmt::Format for TreapEntry<K, V> {
+    fn format(&self, f: defmt::Formatter) {
+        let item = self.0.borrow();
+        defmt::write!(f, "TreapEntry {{ key: {}, value: {}, live: {=bool} }}", item.key, item.value, !item.removed)
+    }
+}
+
+// The splitmix64 finalizer, to turn ids into priorities.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
+fn size<K, V>(tree: &Tree<K, V>) -> usize {
+    tree.as_ref().map_or(0, |node| node.size)
+}
+
+impl<K: Ord, V> Node<K, V> {
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+
+    // Whether the node's entry sorts before the entry with `key` and `id`.
+    fn before(&self, key: &K, id: u64) -> bool {
+        let item = self.item.borrow();
+        item.key < *key || (item.key == *key && item.id < id)
+    }
+}
+
+// Splits a tree into the entries before `key` and `id`, and the rest.
+fn split<K: Ord, V>(tree: Tree<K, V>, key: &K, id: u64) -> (Tree<K, V>, Tree<K, V>) {
+    match tree {
+        None => (None, None),
+        Some(mut node) => {
+            if node.before(key, id) {
+                let (left, right) = split(node.right.take(), key, id);
+                node.right = left;
+                node.update();
+                (Some(node), right)
+            } else {
+                let (left, right) = split(node.left.take(), key, id);
+                node.left = right;
+                node.update();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+// Joins two trees, every entry of `a` sorting before every entry of `b`.
+fn join<K: Ord, V>(a: Tree<K, V>, b: Tree<K, V>) -> Tree<K, V> {
+    match (a, b) {
+        (None, tree) | (tree, None) => tree,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority > b.priority {
+                a.right = join(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.left = join(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+// Merges two trees whose entries may interleave.
+fn union<K: Ord + Clone, V>(a: Tree<K, V>, b: Tree<K, V>) -> Tree<K, V> {
+    match (a, b) {
+        (None, tree) | (tree, None) => tree,
+        (Some(a), Some(b)) => {
+            let (mut root, other) = if a.priority > b.priority { (a, b) } else { (b, a) };
+            let (key, id) = {
+                let item = root.item.borrow();
+                (item.key.clone(), item.id)
+            };
+            let (left, right) = split(Some(other), &key, id);
+            root.left = union(root.left.take(), left);
+            root.right = union(root.right.take(), right);
+            root.update();
+            Some(root)
+        }
+    }
+}
+
+/// A treap: a binary search tree on the keys that is also a heap on random
+/// priorities, which keeps it balanced in expectation.
+///
+/// Besides the heap operations, each in O(log n) expected time, entries can
+/// be iterated in key order or over a range of keys, `split_off` divides a
+/// treap at a key and `HeapExt::merge` unions two treaps whose keys
+/// interleave. Entries with equal keys are kept in the order they went in.
+pub struct Treap<K, V> {
+    root: Tree<K, V>,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Treap<K, V> {
+    pub const fn new() -> Treap<K, V> {
+        Treap { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Whether `entry` is in this treap, rather than removed or in another.
+    pub fn contains(&self, entry: &TreapEntry<K, V>) -> bool {
+        let item = entry.0.borrow();
+        let mut tree = &self.root;
+        while let Some(ref node) = *tree {
+            if Rc::ptr_eq(&node.item, &entry.0) {
+                return true
+            }
+            tree = if node.before(&item.key, item.id) { &node.right } else { &node.left };
+        }
+        false
+    }
+
+    /// Moves the entries with keys of at least `key` into a new treap.
+    pub fn split_off(&mut self, key: &K) -> Treap<K, V> {
+        let (left, right) = split(self.root.take(), key, 0);
+        self.root = left;
+        Treap { root: right }
+    }
+
+    /// The entries in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.range(..)
+    }
+
+    /// The entries with keys in `range`, in key order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, V> {
+        let mut stack = Vec::new();
+        let mut tree = &self.root;
+        while let Some(ref node) = *tree {
+            let above = match range.start_bound() {
+                Bound::Included(start) => node.item.borrow().key >= *start,
+                Bound::Excluded(start) => node.item.borrow().key > *start,
+                Bound::Unbounded => true
+            };
+            if above {
+                stack.push(&**node);
+                tree = &node.left;
+            } else {
+                tree = &node.right;
+            }
+        }
+        Iter { stack: stack, end: range.end_bound().cloned() }
+    }
+
+    // Takes the entry's node out of the tree, or returns None if it's not
+    // in this treap.
+    fn take(&mut self, entry: &TreapEntry<K, V>) -> Option<Box<Node<K, V>>> {
+        let (key, id) = {
+            let item = entry.0.borrow();
+            if item.removed {
+                return None
+            }
+            (item.key.clone(), item.id)
+        };
+        let (left, rest) = split(self.root.take(), &key, id);
+        let (middle, right) = split(rest, &key, id + 1);
+        match middle {
+            Some(ref node) if Rc::ptr_eq(&node.item, &entry.0) => {
+                self.root = join(left, right);
+                middle
+            }
+            _ => {
+                self.root = join(join(left, middle), right);
+                None
+            }
+        }
+    }
+
+    fn put(&mut self, mut node: Box<Node<K, V>>) {
+        let (key, id) = {
+            let item = node.item.borrow();
+            (item.key.clone(), item.id)
+        };
+        node.left = None;
+        node.right = None;
+        node.size = 1;
+        let (left, right) = split(self.root.take(), &key, id);
+        self.root = join(join(left, Some(node)), right);
+    }
+
+    fn first(&self) -> &Node<K, V> {
+        let mut node = self.root.as_ref().expect("Treap is empty");
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        node
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for Treap<K, V> {
+    type HeapEntry = TreapEntry<K, V>;
+
+    fn find_min(&self) -> (K, V) {
+        let item = self.first().item.borrow();
+        (item.key.clone(), item.value.clone())
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        let entry = TreapEntry(self.first().item.clone());
+        self.delete(entry).unwrap()
+    }
+
+    fn insert(&mut self, key: K, value: V) -> TreapEntry<K, V> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let item = Rc::new(RefCell::new(Item { key: key, value: value, id: id, removed: false }));
+        self.put(Box::new(Node { item: item.clone(), priority: mix(id), size: 1, left: None, right: None }));
+        TreapEntry(item)
+    }
+
+    fn empty(&self) -> bool {
+        self.root.is_none()
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> DecreaseKey<K, V> for Treap<K, V> {
+    // The entry goes after any others with its new key.
+    fn decrease_key(&mut self, entry: &TreapEntry<K, V>, delta: K) -> Result<(), StaleEntry> {
+        let node = self.take(entry).ok_or(StaleEntry)?;
+        {
+            let mut item = entry.0.borrow_mut();
+            item.key = item.key.clone() - delta;
+            item.id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        }
+        self.put(node);
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapExt for Treap<K, V> {
+    fn merge(mut self, mut other: Treap<K, V>) -> Treap<K, V> {
+        self.root = union(self.root.take(), other.root.take());
+        self
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> HeapDelete<K, V> for Treap<K, V> {
+    type HeapEntry = TreapEntry<K, V>;
+
+    fn delete(&mut self, entry: TreapEntry<K, V>) -> Result<(K, V), StaleEntry> {
+        self.take(&entry).ok_or(StaleEntry)?;
+        let mut item = entry.0.borrow_mut();
+        item.removed = true;
+        Ok((item.key.clone(), item.value.clone()))
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> AddressableHeap<K, V> for Treap<K, V> {
+    fn is_valid(&self, entry: &TreapEntry<K, V>) -> bool {
+        self.contains(entry)
+    }
+}
+
+impl<K, V> Drop for Treap<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            node.item.borrow_mut().removed = true;
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+/// An iterator over the entries of a `Treap` in key order, from
+/// `Treap::iter` or `Treap::range`.
+pub struct Iter<'a, K: 'a, V: 'a> {
+    // The nodes still to visit whose left subtrees are done, last first.
+    stack: Vec<&'a Node<K, V>>,
+    end: Bound<K>,
+}
+
+impl<'a, K: Ord + Clone, V: Clone> Iterator for Iter<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let node = self.stack.pop()?;
+        let (key, value) = {
+            let item = node.item.borrow();
+            (item.key.clone(), item.value.clone())
+        };
+        let within = match self.end {
+            Bound::Included(ref end) => key <= *end,
+            Bound::Excluded(ref end) => key < *end,
+            Bound::Unbounded => true
+        };
+        if !within {
+            self.stack.clear();
+            return None
+        }
+        let mut tree = &node.right;
+        while let Some(ref node) = *tree {
+            self.stack.push(node);
+            tree = &node.left;
+        }
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+    use test_model;
+    use treap::Treap;
+    use {Heap, DecreaseKey, HeapExt, HeapDelete, StaleEntry};
+
+    #[test]
+    fn treap_against_model() {
+        let mut treap = Treap::new();
+        test_model::against_model(&mut treap, 10000, |_, _, x| 1000 + x % 3000,
+                                  Some(|treap, entry| treap.delete(entry)), |treap, entries, step| {
+            assert_eq!(treap.len(), entries.len());
+            if step % 7 == 5 {
+                // Splits off the upper part and merges it back.
+                let key = 1000 + step as u64 * 7919 % 3000;
+                let upper = treap.split_off(&key);
+                assert!(treap.iter().all(|(k, _)| k < key));
+                assert!(upper.iter().all(|(k, _)| k >= key));
+                assert_eq!(treap.len() + upper.len(), entries.len());
+                let lower = mem::replace(treap, Treap::new());
+                *treap = if step % 2 == 0 { lower.merge(upper) } else { upper.merge(lower) };
+            }
+            if step % 1000 == 999 {
+                let mut sorted: Vec<_> = entries.iter().map(|e| (e.1, e.2)).collect();
+                sorted.sort();
+                let mut all: Vec<_> = treap.iter().collect();
+                assert!(all.windows(2).all(|w| w[0].0 <= w[1].0));
+                all.sort();
+                assert_eq!(all, sorted);
+            }
+        });
+    }
+
+    #[test]
+    fn treap_range() {
+        let mut treap = Treap::new();
+        for i in 0..100u32 {
+            treap.insert((i * 37) % 100, i);
+        }
+        let keys = |treap: &Treap<u32, u32>, r: ::std::ops::Range<u32>| -> Vec<u32> {
+            treap.range(r).map(|(k, _)| k).collect()
+        };
+        assert_eq!(keys(&treap, 20..25), vec!(20, 21, 22, 23, 24));
+        assert_eq!(treap.range(95..).map(|(k, _)| k).collect::<Vec<_>>(), vec!(95, 96, 97, 98, 99));
+        assert_eq!(treap.range(..=2).map(|(k, _)| k).collect::<Vec<_>>(), vec!(0, 1, 2));
+        assert_eq!(keys(&treap, 50..50), Vec::<u32>::new());
+        assert_eq!(treap.iter().count(), 100);
+    }
+
+    #[test]
+    fn treap_handles_follow_splits() {
+        let mut treap = Treap::new();
+        let entries: Vec<_> = (0..10u32).map(|i| treap.insert(i * 10, i)).collect();
+        let mut upper = treap.split_off(&50);
+        assert!(treap.contains(&entries[4]));
+        assert!(!treap.contains(&entries[5]));
+        upper.decrease_key(&entries[7], 45).unwrap();
+        assert_eq!(upper.find_min(), (25, 7));
+        let mut all = treap.merge(upper);
+        all.decrease_key(&entries[9], 90).unwrap();
+        assert_eq!(format!("{:?}", entries[9]), "TreapEntry { key: 0, value: 9, live: true }");
+        // Equal keys come out in the order they got them.
+        assert_eq!(all.delete_min(), (0, 0));
+        assert_eq!(all.delete_min(), (0, 9));
+        assert_eq!(all.delete(entries[7].clone()), Ok((25, 7)));
+        assert!(!all.contains(&entries[7]));
+        assert_eq!(all.len(), 7);
+        drop(all);
+        assert_eq!(format!("{:?}", entries[3]), "TreapEntry { key: 30, value: 3, live: false }");
+    }
+
+    #[test]
+    fn treap_entry_of_other_treap() {
+        let mut treap = Treap::new();
+        let entry = treap.insert(1, 'a');
+        let mut upper = treap.split_off(&0);
+        assert_eq!(treap.decrease_key(&entry, 1), Err(StaleEntry));
+        assert_eq!(upper.decrease_key(&entry, 1), Ok(()));
+        assert_eq!(upper.find_min(), (0, 'a'));
+    }
+}
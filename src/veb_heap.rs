@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use slab::{Slab, SlotKey};
+use {Heap, DecreaseKey, HeapDelete, AddressableHeap, StaleEntry};
+
+const NONE: usize = usize::MAX;
+
+// Trees over this many bits or fewer are a single bitmap.
+const LEAF_BITS: u32 = 6;
+
+/// Handle to an entry of a `VebHeap`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VebEntry(SlotKey);
+
+struct Slot<V> {
+    value: V,
+    key: u32,
+    // Neighbours in the key's list.
+    prev: usize,
+    next: usize,
+}
+
+// A van Emde Boas tree holding a non-empty set of keys below 2^bits, with
+// the number of bits passed down alongside it.
+enum Veb {
+    Leaf(u64),
+    Branch(Box<Branch>),
+}
+
+// The minimum is kept here only, not in a cluster, which is what makes
+// inserting into an empty cluster cheap.
+struct Branch {
+    min: u32,
+    max: u32,
+    // The high halves of the keys with a cluster.
+    summary: Option<Veb>,
+    clusters: HashMap<u32, Veb>,
+}
+
+fn split(bits: u32, key: u32) -> (u32, u32) {
+    let low = bits / 2;
+    (key >> low, key & ((1 << low) - 1))
+}
+
+fn join(bits: u32, high: u32, low: u32) -> u32 {
+    (high << (bits / 2)) | low
+}
+
+impl Veb {
+    fn new(bits: u32, key: u32) -> Veb {
+        if bits <= LEAF_BITS {
+            Veb::Leaf(1 << key)
+        } else {
+            Veb::Branch(Box::new(Branch { min: key, max: key, summary: None, clusters: HashMap::new() }))
+        }
+    }
+
+    fn min(&self) -> u32 {
+        match *self {
+            Veb::Leaf(set) => set.trailing_zeros(),
+            Veb::Branch(ref b) => b.min,
+        }
+    }
+
+    fn max(&self) -> u32 {
+        match *self {
+            Veb::Leaf(set) => 63 - set.leading_zeros(),
+            Veb::Branch(ref b) => b.max,
+        }
+    }
+
+    // Adds a key that isn't in the tree.
+    fn insert(&mut self, bits: u32, mut key: u32) {
+        match *self {
+            Veb::Leaf(ref mut set) => *set |= 1 << key,
+            Veb::Branch(ref mut b) => {
+                if key < b.min {
+                    key = ::std::mem::replace(&mut b.min, key);
+                }
+                if key > b.max {
+                    b.max = key;
+                }
+                let (high, low) = split(bits, key);
+                let low_bits = bits / 2;
+                match b.clusters.get_mut(&high) {
+                    Some(cluster) => cluster.insert(low_bits, low),
+                    None => {
+                        b.clusters.insert(high, Veb::new(low_bits, low));
+                        match b.summary {
+                            Some(ref mut summary) => summary.insert(bits - low_bits, high),
+                            None => b.summary = Some(Veb::new(bits - low_bits, high)),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Removes a key in the tree, returning whether the tree is now empty.
+    fn delete(&mut self, bits: u32, mut key: u32) -> bool {
+        match *self {
+            Veb::Leaf(ref mut set) => {
+                *set &= !(1 << key);
+                *set == 0
+            }
+            Veb::Branch(ref mut b) => {
+                let low_bits = bits / 2;
+                let summary = match b.summary {
+                    Some(ref mut summary) => summary,
+                    None => return true,
+                };
+                // The smallest key in a cluster takes the place of the
+                // minimum, and is deleted from its cluster instead.
+                if key == b.min {
+                    let high = summary.min();
+                    key = join(bits, high, b.clusters[&high].min());
+                    b.min = key;
+                }
+                let (high, low) = split(bits, key);
+                if b.clusters.get_mut(&high).unwrap().delete(low_bits, low) {
+                    b.clusters.remove(&high);
+                    if summary.delete(bits - low_bits, high) {
+                        b.summary = None;
+                    }
+                }
+                if key == b.max {
+                    b.max = match b.summary {
+                        Some(ref summary) => {
+                            let high = summary.max();
+                            join(bits, high, b.clusters[&high].max())
+                        }
+                        None => b.min,
+                    };
+                }
+                false
+            }
+        }
+    }
+
+    // The smallest key in the tree above `key`.
+    fn successor(&self, bits: u32, key: u32) -> Option<u32> {
+        match *self {
+            Veb::Leaf(set) => {
+                let above = if key >= 63 { 0 } else { set & (!0 << (key + 1)) };
+                if above == 0 { None } else { Some(above.trailing_zeros()) }
+            }
+            Veb::Branch(ref b) => {
+                if key < b.min {
+                    return Some(b.min)
+                }
+                let (high, low) = split(bits, key);
+                let low_bits = bits / 2;
+                if let Some(cluster) = b.clusters.get(&high) {
+                    if low < cluster.max() {
+                        return cluster.successor(low_bits, low).map(|l| join(bits, high, l))
+                    }
+                }
+                let high = b.summary.as_ref()?.successor(bits - low_bits, high)?;
+                Some(join(bits, high, b.clusters[&high].min()))
+            }
+        }
+    }
+}
+
+/// A priority queue for `u32` keys on a van Emde Boas tree, where
+/// inserting, deleting, decreasing a key and finding the next key take
+/// O(log log U) time, a handful of steps for 32-bit keys, whatever the
+/// number of entries.
+///
+/// Clusters of the tree are made as keys arrive, in hash maps, so memory
+/// grows with the number of distinct keys rather than with 2^32. Entries
+/// with equal keys come out in the order they went in.
+pub struct VebHeap<V> {
+    tree: Option<Veb>,
+    // The first and last slot of each key's list.
+    lists: HashMap<u32, (usize, usize)>,
+    slots: Slab<Slot<V>>,
+    len: usize,
+}
+
+impl<V: Clone> VebHeap<V> {
+    pub fn new() -> VebHeap<V> {
+        VebHeap { tree: None, lists: HashMap::new(), slots: Slab::new(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn contains(&self, entry: VebEntry) -> bool {
+        self.slots.contains(entry.0)
+    }
+
+    /// The smallest key in the heap greater than `key`.
+    pub fn successor(&self, key: u32) -> Option<u32> {
+        self.tree.as_ref()?.successor(32, key)
+    }
+
+    // Appends the slot to its key's list, adding the key to the tree if
+    // it's new.
+    fn link(&mut self, slot: usize) {
+        let key = self.slots[slot].key;
+        self.slots[slot].next = NONE;
+        match self.lists.get_mut(&key) {
+            Some(&mut (_, ref mut tail)) => {
+                self.slots[slot].prev = *tail;
+                self.slots[*tail].next = slot;
+                *tail = slot;
+            }
+            None => {
+                self.slots[slot].prev = NONE;
+                self.lists.insert(key, (slot, slot));
+                match self.tree {
+                    Some(ref mut tree) => tree.insert(32, key),
+                    None => self.tree = Some(Veb::new(32, key)),
+                }
+            }
+        }
+    }
+
+    // Takes the slot out of its key's list, and the key out of the tree if
+    // that empties the list.
+    fn unlink(&mut self, slot: usize) {
+        let (key, prev, next) = {
+            let s = &self.slots[slot];
+            (s.key, s.prev, s.next)
+        };
+        if prev != NONE {
+            self.slots[prev].next = next;
+        }
+        if next != NONE {
+            self.slots[next].prev = prev;
+        }
+        let emptied = {
+            let list = self.lists.get_mut(&key).unwrap();
+            if prev == NONE {
+                list.0 = next;
+            }
+            if next == NONE {
+                list.1 = prev;
+            }
+            list.0 == NONE
+        };
+        if emptied {
+            self.lists.remove(&key);
+            if self.tree.as_mut().unwrap().delete(32, key) {
+                self.tree = None;
+            }
+        }
+    }
+
+    fn remove(&mut self, slot: usize) -> (u32, V) {
+        self.unlink(slot);
+        self.len -= 1;
+        let s = self.slots.remove(slot);
+        (s.key, s.value)
+    }
+
+    fn head(&self) -> usize {
+        match self.tree {
+            Some(ref tree) => self.lists[&tree.min()].0,
+            None => panic!("VebHeap is empty")
+        }
+    }
+}
+
+impl<V: Clone> Heap<u32, V> for VebHeap<V> {
+    type HeapEntry = VebEntry;
+
+    fn find_min(&self) -> (u32, V) {
+        let slot = &self.slots[self.head()];
+        (slot.key, slot.value.clone())
+    }
+
+    fn delete_min(&mut self) -> (u32, V) {
+        let head = self.head();
+        self.remove(head)
+    }
+
+    fn insert(&mut self, key: u32, value: V) -> VebEntry {
+        let slot = self.slots.insert(Slot { value: value, key: key, prev: NONE, next: NONE });
+        self.link(slot.index());
+        self.len += 1;
+        VebEntry(slot)
+    }
+
+    fn empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<V: Clone> DecreaseKey<u32, V> for VebHeap<V> {
+    // The entry goes after any others with its new key.
+    fn decrease_key(&mut self, entry: &VebEntry, delta: u32) -> Result<(), StaleEntry> {
+        if !self.is_valid(entry) {
+            return Err(StaleEntry)
+        }
+        let key = self.slots[entry.0.index()].key.checked_sub(delta).expect("key decreased below zero");
+        self.unlink(entry.0.index());
+        self.slots[entry.0.index()].key = key;
+        self.link(entry.0.index());
+        Ok(())
+    }
+}
+
+impl<V: Clone> HeapDelete<u32, V> for VebHeap<V> {
+    type HeapEntry = VebEntry;
+
+    fn delete(&mut self, entry: VebEntry) -> Result<(u32, V), StaleEntry> {
+        if !self.is_valid(&entry) {
+            return Err(StaleEntry)
+        }
+        Ok(self.remove(entry.0.index()))
+    }
+}
+
+impl<V: Clone> AddressableHeap<u32, V> for VebHeap<V> {
+    fn is_valid(&self, entry: &VebEntry) -> bool {
+        self.contains(*entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_model;
+    use veb_heap::VebHeap;
+    use {Heap, DecreaseKey, HeapDelete};
+
+    #[test]
+    fn veb_heap_against_model() {
+        let mut heap = VebHeap::new();
+        // Keys both spread out and bunched together, with duplicates.
+        let key = |_: &VebHeap<usize>, step, x| {
+            if step % 2 == 0 { x as u32 } else { u32::MAX - 500 + (x % 300) as u32 }
+        };
+        test_model::against_model(&mut heap, 20000, key, Some(|heap, entry| heap.delete(entry)),
+                                  |heap, entries, step| {
+            assert_eq!(heap.len(), entries.len());
+            let probe = if entries.is_empty() { step as u32 } else { entries[step % entries.len()].1 };
+            let next = entries.iter().map(|e| e.1).filter(|&k| k > probe).min();
+            assert_eq!(heap.successor(probe), next);
+        });
+    }
+
+    #[test]
+    fn veb_heap_ties_and_successor() {
+        let mut heap = VebHeap::new();
+        let a = heap.insert(7, 'a');
+        heap.insert(7, 'b');
+        heap.insert(u32::MAX, 'z');
+        heap.insert(0, 'o');
+        assert_eq!(heap.successor(0), Some(7));
+        assert_eq!(heap.successor(7), Some(u32::MAX));
+        assert_eq!(heap.successor(u32::MAX), None);
+        assert_eq!(heap.delete_min(), (0, 'o'));
+        heap.decrease_key(&a, 0).unwrap();
+        assert_eq!(heap.delete_min(), (7, 'b'));
+        assert_eq!(heap.delete_min(), (7, 'a'));
+        assert!(!heap.contains(a));
+        assert_eq!(heap.delete_min(), (u32::MAX, 'z'));
+        assert!(heap.empty());
+        assert_eq!(heap.successor(0), None);
+    }
+}
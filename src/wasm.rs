@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use fibonacci_heap::{FibHeap};
+use float_key::FloatKey;
+use Heap;
+
+/// Handle returned by `push`, used to decrease the entry's key.
+#[wasm_bindgen]
+pub struct FibEntry {
+    entry: <FibHeap<FloatKey, u64> as Heap<FloatKey, u64>>::HeapEntry,
+    id: u64,
+}
+
+#[wasm_bindgen]
+impl FibEntry {
+    #[wasm_bindgen(getter)]
+    pub fn key(&self) -> f64 {
+        self.entry.get_key().0
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeapItem {
+    pub key: f64,
+    pub value: u32,
+}
+
+/// A Fibonacci heap keyed by floats holding `u32` values, typically node ids
+/// in a pathfinding demo.
+#[wasm_bindgen]
+pub struct WasmFibHeap {
+    heap: FibHeap<FloatKey, u64>,
+    // Values of the entries still in the heap, by entry id.
+    values: HashMap<u64, u32>,
+    next_id: u64,
+}
+
+#[wasm_bindgen]
+impl WasmFibHeap {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmFibHeap {
+        WasmFibHeap { heap: FibHeap::new(), values: HashMap::new(), next_id: 0 }
+    }
+
+    /// Returns `None` if `key` is NaN.
+    pub fn push(&mut self, key: f64, value: u32) -> Option<FibEntry> {
+        if key.is_nan() {
+            return None
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.values.insert(id, value);
+        Some(FibEntry { entry: self.heap.insert(FloatKey(key), id), id: id })
+    }
+
+    pub fn peek(&self) -> Option<HeapItem> {
+        if self.heap.empty() {
+            return None
+        }
+        let (k, id) = self.heap.find_min();
+        Some(HeapItem { key: k.0, value: self.values[&id] })
+    }
+
+    pub fn pop(&mut self) -> Option<HeapItem> {
+        if self.heap.empty() {
+            return None
+        }
+        let (k, id) = self.heap.delete_min();
+        Some(HeapItem { key: k.0, value: self.values.remove(&id).unwrap() })
+    }
+
+    /// Lowers the key of `entry` to `new_key`, returning false if the entry is
+    /// no longer in the heap or `new_key` is NaN or larger than the current key.
+    pub fn decrease_key(&mut self, entry: &FibEntry, new_key: f64) -> bool {
+        let old_key = *entry.entry.get_key();
+        if !self.values.contains_key(&entry.id) || new_key.is_nan() || new_key > old_key.0 {
+            return false
+        }
+        self.heap.decrease_key_to(&entry.entry, FloatKey(new_key)).unwrap();
+        true
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.values.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm::{HeapItem, WasmFibHeap};
+
+    #[test]
+    fn wasm_push_pop() {
+        let mut fheap = WasmFibHeap::new();
+        let a = fheap.push(3.5, 1).unwrap();
+        fheap.push(1.0, 2).unwrap();
+        fheap.push(2.0, 3).unwrap();
+        assert!(fheap.push(f64::NAN, 4).is_none());
+        assert_eq!(fheap.length(), 3);
+
+        assert_eq!(fheap.pop(), Some(HeapItem { key: 1.0, value: 2 }));
+        assert!(!fheap.decrease_key(&a, 5.0));
+        assert!(fheap.decrease_key(&a, 0.5));
+        assert_eq!(a.key(), 0.5);
+        assert_eq!(fheap.peek(), Some(HeapItem { key: 0.5, value: 1 }));
+        fheap.pop();
+        assert!(!fheap.decrease_key(&a, 0.0));
+        fheap.pop();
+        assert_eq!(fheap.pop(), None);
+    }
+
+    #[test]
+    fn wasm_decrease_key_keeps_exact_key() {
+        let mut fheap = WasmFibHeap::new();
+        let a = fheap.push(f64::INFINITY, 1).unwrap();
+        let b = fheap.push(1e17, 2).unwrap();
+        fheap.push(2.0, 3).unwrap();
+        assert!(fheap.decrease_key(&a, 3.0));
+        assert_eq!(a.key(), 3.0);
+        assert!(fheap.decrease_key(&b, 1.0));
+        assert_eq!(b.key(), 1.0);
+        assert_eq!(fheap.pop(), Some(HeapItem { key: 1.0, value: 2 }));
+        assert_eq!(fheap.pop(), Some(HeapItem { key: 2.0, value: 3 }));
+        assert_eq!(fheap.pop(), Some(HeapItem { key: 3.0, value: 1 }));
+    }
+}
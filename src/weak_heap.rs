@@ -0,0 +1,200 @@
+use std::ops::Sub;
+use Heap;
+
+/// A weak heap (Dutton): an implicit binary tree in a `Vec` where each
+/// entry is only ordered against its right subtree, with a reverse bit per
+/// node that swaps its children instead of moving entries.
+///
+/// The weaker order is cheaper to restore. Deleting the minimum takes at
+/// most ⌈log n⌉ key comparisons, against about 2 log n for a binary heap,
+/// and `from_vec` builds a heap in n - 1, so it suits keys that are
+/// expensive to compare.
+pub struct WeakHeap<K, V> {
+    entries: Vec<(K, V)>,
+    // Whether each node's children are swapped.
+    reverse: Vec<bool>,
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> WeakHeap<K, V> {
+    pub const fn new() -> WeakHeap<K, V> {
+        WeakHeap { entries: Vec::new(), reverse: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> WeakHeap<K, V> {
+        WeakHeap { entries: Vec::with_capacity(capacity), reverse: Vec::with_capacity(capacity) }
+    }
+
+    /// Builds a heap of `entries` with n - 1 key comparisons.
+    pub fn from_vec(entries: Vec<(K, V)>) -> WeakHeap<K, V> {
+        let mut heap = WeakHeap { reverse: vec!(false; entries.len()), entries: entries };
+        for j in (1..heap.entries.len()).rev() {
+            let i = heap.ancestor(j);
+            heap.join(i, j);
+        }
+        heap
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // The left child of `i`, taking its reverse bit into account.
+    fn left(&self, i: usize) -> usize {
+        2 * i + self.reverse[i] as usize
+    }
+
+    // The nearest ancestor whose right subtree holds `j`, which is the one
+    // `j` must not be less than.
+    fn ancestor(&self, mut j: usize) -> usize {
+        while (j & 1 == 1) == self.reverse[j / 2] {
+            j /= 2;
+        }
+        j / 2
+    }
+
+    // Restores the order between `i` and its descendant `j`, swapping them
+    // and flipping `j`'s children if needed. Returns whether it swapped.
+    fn join(&mut self, i: usize, j: usize) -> bool {
+        if self.entries[j].0 < self.entries[i].0 {
+            self.entries.swap(i, j);
+            self.reverse[j] = !self.reverse[j];
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<K: Ord + Clone + Sub<K, Output=K>, V: Clone> Heap<K, V> for WeakHeap<K, V> {
+    type HeapEntry = ();
+
+    fn find_min(&self) -> (K, V) {
+        self.entries.first().cloned().expect("WeakHeap is empty")
+    }
+
+    fn delete_min(&mut self) -> (K, V) {
+        if self.entries.is_empty() {
+            panic!("WeakHeap is empty");
+        }
+        let entry = self.entries.swap_remove(0);
+        self.reverse.pop();
+        let len = self.entries.len();
+        if len > 1 {
+            // Joins the root with each node on the leftmost path of its
+            // right subtree, from the bottom up.
+            let mut j = 1;
+            while self.left(j) < len {
+                j = self.left(j);
+            }
+            while j > 0 {
+                self.join(0, j);
+                j /= 2;
+            }
+        }
+        entry
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        let mut j = self.entries.len();
+        self.entries.push((key, value));
+        self.reverse.push(false);
+        // A new node at an even index is its parent's only child, which
+        // must be the left one.
+        if j & 1 == 0 {
+            self.reverse[j / 2] = false;
+        }
+        while j > 0 {
+            let i = self.ancestor(j);
+            if !self.join(i, j) {
+                break
+            }
+            j = i;
+        }
+    }
+
+    fn empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::ops::Sub;
+    use weak_heap::WeakHeap;
+    use Heap;
+
+    thread_local!(static COMPARISONS: Cell<usize> = const { Cell::new(0) });
+
+    // A key counting how often it's compared.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Counted(u32);
+
+    impl PartialOrd for Counted {
+        fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Counted {
+        fn cmp(&self, other: &Counted) -> Ordering {
+            COMPARISONS.with(|c| c.set(c.get() + 1));
+            self.0.cmp(&other.0)
+        }
+    }
+
+    impl Sub for Counted {
+        type Output = Counted;
+        fn sub(self, other: Counted) -> Counted {
+            Counted(self.0 - other.0)
+        }
+    }
+
+    #[test]
+    fn weak_heap_small() {
+        for n in 0..9u32 {
+            let mut heap = WeakHeap::from_vec((0..n).rev().map(|k| (k, k)).collect());
+            heap.insert(n, n);
+            for k in 0..n + 1 {
+                assert_eq!(heap.delete_min(), (k, k));
+            }
+            assert!(heap.empty());
+        }
+    }
+
+    #[test]
+    fn weak_heap_insert_after_from_vec() {
+        // Building and deleting leave reverse bits set all over the tree,
+        // which inserts must then follow to find each new node's ancestor.
+        let entries = (0..500u32).map(|i| ((i * 7919) % 500 * 2, i)).collect();
+        let mut heap = WeakHeap::from_vec(entries);
+        for k in 0..100 {
+            assert_eq!(heap.delete_min().0, 2 * k);
+        }
+        for i in 0..500u32 {
+            heap.insert((i * 7919) % 500 * 2 + 1, i);
+        }
+        let mut expected: Vec<u32> = (200..1000).step_by(2).chain((1..1000).step_by(2)).collect();
+        expected.sort();
+        for k in expected {
+            assert_eq!(heap.delete_min().0, k);
+        }
+        assert!(heap.empty());
+    }
+
+    #[test]
+    fn weak_heap_comparisons() {
+        let n = 1 << 12;
+        let entries = (0..n).map(|i| (Counted((i * 7919) % n), ())).collect();
+        COMPARISONS.with(|c| c.set(0));
+        let mut heap = WeakHeap::from_vec(entries);
+        assert_eq!(COMPARISONS.with(|c| c.get()), n as usize - 1);
+        COMPARISONS.with(|c| c.set(0));
+        for k in 0..n {
+            assert_eq!(heap.delete_min().0, Counted(k));
+        }
+        // At most ⌈log n⌉ comparisons per deletion.
+        assert!(COMPARISONS.with(|c| c.get()) <= 12 * n as usize);
+    }
+}
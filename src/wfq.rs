@@ -0,0 +1,186 @@
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use fib_node::{FibNode};
+use fibonacci_heap::{FibHeap};
+use ordered::Ordered;
+use {Heap, HeapDelete};
+
+/// Identifies a flow in a `Scheduler`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FlowId(u64);
+
+// Virtual time advances by `SCALE / weight` per byte, so small weights keep
+// some precision in integer arithmetic.
+const SCALE: u128 = 1 << 32;
+
+type Key = Ordered<(u128, u64)>;
+
+struct Flow<T> {
+    weight: u64,
+    // Packets with their virtual finish times.
+    packets: VecDeque<(u128, T)>,
+    last_finish: u128,
+    // Present while the flow has packets queued.
+    entry: Option<Rc<FibNode<Key, u64>>>,
+}
+
+/// A weighted fair queuing packet scheduler.
+///
+/// Each flow gets a share of the link in proportion to its weight. Packets
+/// are stamped with a virtual finish time when they arrive, and the flow
+/// whose head packet finishes first is served next. The heap holds one entry
+/// per backlogged flow; a flow that runs out of packets leaves it and
+/// rejoins when a packet arrives. This is self-clocked fair queuing: the
+/// virtual time is the finish time of the last packet sent.
+pub struct Scheduler<T> {
+    heap: FibHeap<Key, u64>,
+    flows: HashMap<u64, Flow<T>>,
+    virtual_time: u128,
+    queued: usize,
+    next_id: u64,
+}
+
+impl<T> Scheduler<T> {
+    pub fn new() -> Scheduler<T> {
+        Scheduler {
+            heap: FibHeap::new(),
+            flows: HashMap::new(),
+            virtual_time: 0,
+            queued: 0,
+            next_id: 0,
+        }
+    }
+
+    /// The number of queued packets across all flows.
+    pub fn len(&self) -> usize {
+        self.queued
+    }
+
+    pub fn add_flow(&mut self, weight: u64) -> FlowId {
+        assert!(weight > 0, "Flow weight must be positive");
+        let id = self.next_id;
+        self.next_id += 1;
+        self.flows.insert(id, Flow {
+            weight: weight,
+            packets: VecDeque::new(),
+            last_finish: 0,
+            entry: None,
+        });
+        FlowId(id)
+    }
+
+    /// Removes a flow, returning its queued packets.
+    pub fn remove_flow(&mut self, flow: FlowId) -> Vec<T> {
+        match self.flows.remove(&flow.0) {
+            Some(f) => {
+                if let Some(entry) = f.entry {
+                    self.heap.delete(entry).unwrap();
+                }
+                self.queued -= f.packets.len();
+                f.packets.into_iter().map(|(_, p)| p).collect()
+            }
+            None => Vec::new()
+        }
+    }
+
+    /// Changes a flow's weight, which applies to packets queued from now on.
+    pub fn set_weight(&mut self, flow: FlowId, weight: u64) -> bool {
+        assert!(weight > 0, "Flow weight must be positive");
+        match self.flows.get_mut(&flow.0) {
+            Some(f) => {
+                f.weight = weight;
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Queues a packet of `size` bytes on a flow. Returns the packet if the
+    /// flow doesn't exist.
+    pub fn enqueue(&mut self, flow: FlowId, size: u64, packet: T) -> Result<(), T> {
+        let virtual_time = self.virtual_time;
+        let f = match self.flows.get_mut(&flow.0) {
+            Some(f) => f,
+            None => return Err(packet)
+        };
+        let start = if f.last_finish > virtual_time { f.last_finish } else { virtual_time };
+        let finish = start + size as u128 * SCALE / f.weight as u128;
+        f.last_finish = finish;
+        f.packets.push_back((finish, packet));
+        if f.entry.is_none() {
+            f.entry = Some(self.heap.insert(Ordered((finish, flow.0)), flow.0));
+        }
+        self.queued += 1;
+        Ok(())
+    }
+
+    /// Removes the next packet to send.
+    pub fn dequeue(&mut self) -> Option<(FlowId, T)> {
+        if self.heap.empty() {
+            return None
+        }
+        let (Ordered((finish, _)), id) = self.heap.delete_min();
+        self.virtual_time = finish;
+        self.queued -= 1;
+        let f = self.flows.get_mut(&id).unwrap();
+        let (_, packet) = f.packets.pop_front().unwrap();
+        f.entry = match f.packets.front() {
+            Some(&(next, _)) => Some(self.heap.insert(Ordered((next, id)), id)),
+            None => None
+        };
+        Some((FlowId(id), packet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wfq::Scheduler;
+
+    #[test]
+    fn wfq_shares_by_weight() {
+        let mut wfq = Scheduler::new();
+        let heavy = wfq.add_flow(3);
+        let light = wfq.add_flow(1);
+        for i in 0..30 {
+            wfq.enqueue(heavy, 100, ('h', i)).unwrap();
+            wfq.enqueue(light, 100, ('l', i)).unwrap();
+        }
+        let first: Vec<char> = (0..20).map(|_| wfq.dequeue().unwrap().1 .0).collect();
+        assert_eq!(first.iter().filter(|&&c| c == 'h').count(), 15);
+        assert_eq!(wfq.len(), 40);
+    }
+
+    #[test]
+    fn wfq_idle_flow_does_not_bank_credit() {
+        let mut wfq = Scheduler::new();
+        let a = wfq.add_flow(1);
+        let b = wfq.add_flow(1);
+        for i in 0..4 {
+            wfq.enqueue(a, 10, i).unwrap();
+        }
+        assert_eq!(wfq.dequeue(), Some((a, 0)));
+        assert_eq!(wfq.dequeue(), Some((a, 1)));
+        // b starts at the current virtual time, so it alternates with a
+        // instead of getting a burst for the time it was idle.
+        wfq.enqueue(b, 10, 10).unwrap();
+        wfq.enqueue(b, 10, 11).unwrap();
+        let rest: Vec<_> = (0..4).map(|_| wfq.dequeue().unwrap()).collect();
+        assert_eq!(rest, vec!((a, 2), (b, 10), (a, 3), (b, 11)));
+        assert_eq!(wfq.dequeue(), None);
+    }
+
+    #[test]
+    fn wfq_remove_flow() {
+        let mut wfq = Scheduler::new();
+        let a = wfq.add_flow(1);
+        let b = wfq.add_flow(2);
+        wfq.enqueue(a, 10, 'a').unwrap();
+        wfq.enqueue(b, 10, 'b').unwrap();
+        wfq.enqueue(b, 10, 'c').unwrap();
+        assert_eq!(wfq.remove_flow(b), vec!('b', 'c'));
+        assert_eq!(wfq.enqueue(b, 10, 'd'), Err('d'));
+        assert!(wfq.set_weight(a, 5));
+        assert_eq!(wfq.len(), 1);
+        assert_eq!(wfq.dequeue(), Some((a, 'a')));
+    }
+}